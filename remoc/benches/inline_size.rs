@@ -0,0 +1,59 @@
+//! Round-trip latency of small items over a base channel, with the default
+//! and with a lowered inline (de)serialization size limit.
+//!
+//! Run with `cargo bench -p remoc --bench inline_size`.
+
+use std::time::{Duration, Instant};
+
+use remoc::{codec, Cfg, Connect};
+
+/// Number of round trips per measurement.
+const ROUND_TRIPS: u32 = 10_000;
+
+/// Size of each item in bytes.
+const ITEM_SIZE: usize = 64;
+
+/// Measures the mean round-trip time of small items.
+async fn round_trip(max_inline_size: Option<usize>) -> Duration {
+    let ((a_conn, mut a_tx, mut a_rx), (b_conn, mut b_tx, mut b_rx)) =
+        Connect::loopback::<Vec<u8>, Vec<u8>, codec::Default>(Cfg::default(), 16).await.unwrap();
+    tokio::spawn(a_conn);
+    tokio::spawn(b_conn);
+
+    if let Some(max_inline_size) = max_inline_size {
+        a_tx.set_max_inline_size(max_inline_size);
+        a_rx.set_max_inline_size(max_inline_size);
+        b_tx.set_max_inline_size(max_inline_size);
+        b_rx.set_max_inline_size(max_inline_size);
+    }
+
+    let echo = tokio::spawn(async move {
+        while let Some(item) = b_rx.recv().await.unwrap() {
+            b_tx.send(item).await.unwrap();
+        }
+    });
+
+    let item = vec![0; ITEM_SIZE];
+    let start = Instant::now();
+    for _ in 0..ROUND_TRIPS {
+        a_tx.send(item.clone()).await.unwrap();
+        a_rx.recv().await.unwrap().unwrap();
+    }
+    let elapsed = start.elapsed();
+
+    drop(a_tx);
+    echo.await.unwrap();
+
+    elapsed / ROUND_TRIPS
+}
+
+#[tokio::main]
+async fn main() {
+    // Warm up.
+    round_trip(None).await;
+
+    for (name, max_inline_size) in [("default", None), ("1 KiB", Some(1_024))] {
+        let latency = round_trip(max_inline_size).await;
+        println!("max_inline_size {name:>7}: {latency:?} per round trip of {ITEM_SIZE} byte item");
+    }
+}
@@ -15,6 +15,34 @@ pub enum PortsExhausted {
     Wait(Option<Duration>),
 }
 
+/// Strategy for allocating local port numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PortAllocation {
+    /// Released port numbers are reused before unused port numbers are assigned.
+    ///
+    /// Unused port numbers are assigned sequentially, starting from the beginning of the port range.
+    /// Allocation takes constant time, independent of how many ports are in use.
+    /// This is recommended when many ports are open concurrently.
+    Compact,
+    /// Port numbers are chosen randomly from the port range.
+    ///
+    /// This makes port numbers hard to predict by the remote endpoint.
+    /// Allocation probes for an unused port number starting from a random position
+    /// and thus slows down when a large fraction of the port range is in use.
+    /// The random number generator can be seeded using
+    /// [Cfg::port_allocation_seed].
+    /// This is the default.
+    Random,
+    /// Port numbers are assigned sequentially, starting from the beginning of the port range.
+    ///
+    /// Port numbers that are in use are skipped.
+    /// After the end of the port range has been reached, allocation restarts from its beginning.
+    /// This makes port assignment reproducible, which is useful for testing and debugging.
+    Sequential,
+}
+
 /// Channel multiplexer configuration.
 ///
 /// In most cases the default configuration ([Cfg::default]) is recommended, since it
@@ -29,12 +57,78 @@ pub struct Cfg {
     ///
     /// Pings are send automatically when this is enabled and no data is transmitted.
     /// By default this is 60 seconds.
+    ///
+    /// A connection that has silently failed, for example a half-open TCP connection,
+    /// is detected after this time and all ports are failed.
     pub connection_timeout: Option<Duration>,
+    /// Maximum interval between messages sent to the remote endpoint.
+    ///
+    /// Pings are sent to the remote endpoint at least half as often as its
+    /// [connection timeout](Self::connection_timeout) requires.
+    /// This allows sending them more often, for example to keep network address
+    /// translation (NAT) mappings of a long-lived connection alive.
+    ///
+    /// By default this is disabled.
+    pub ping_interval: Option<Duration>,
+    /// Time to wait for the remote endpoint to respond to a ping.
+    ///
+    /// If the remote endpoint does not respond within this time, the connection
+    /// is considered failed, all ports are failed and the multiplexer terminates
+    /// with [ChMuxError::Timeout](super::ChMuxError::Timeout).
+    /// This detects a silently failed connection even while data is being sent.
+    ///
+    /// When enabled, pings are sent at the [ping interval](Self::ping_interval)
+    /// regardless of other data being sent.
+    /// Pings are only sent if a ping interval is configured or the remote endpoint
+    /// has a [connection timeout](Self::connection_timeout).
+    /// This has no effect if the remote endpoint does not support responding to pings,
+    /// see [Negotiated::ping_timeout](super::Negotiated::ping_timeout).
+    ///
+    /// By default this is disabled.
+    pub ping_timeout: Option<Duration>,
+    /// Time after which a port is closed when no data is transmitted over it.
+    ///
+    /// A port is idle when no data and no ports have been sent or received over it
+    /// in either direction.
+    /// When it has been idle for this time, the port is closed and its local
+    /// [Sender](super::Sender) and [Receiver](super::Receiver) as well as the ones of the
+    /// remote endpoint fail with an idle timeout error.
+    /// Ports whose local sender and receiver have both been dropped are not affected.
+    ///
+    /// The remote endpoint is only notified if it supports this feature, see
+    /// [Negotiated::port_idle_timeout](super::Negotiated::port_idle_timeout).
+    ///
+    /// By default this is disabled.
+    pub idle_timeout: Option<Duration>,
     /// Maximum number of open ports.
     ///
     /// This must not exceed 2^31 = 2147483648.
     /// By default this is 16384.
     pub max_ports: u32,
+    /// Lowest local port number that is allocated.
+    ///
+    /// By default this is 0.
+    pub port_range_start: u32,
+    /// Highest local port number that is allocated.
+    ///
+    /// If the range between [port_range_start](Self::port_range_start) and this
+    /// value (inclusive) contains less port numbers than [max_ports](Self::max_ports),
+    /// the number of open ports is limited by the size of the range.
+    ///
+    /// By default this is [u32::MAX].
+    /// This must not be smaller than [port_range_start](Self::port_range_start).
+    pub port_range_end: u32,
+    /// Strategy for allocating local port numbers.
+    ///
+    /// By default port numbers are allocated [randomly](PortAllocation::Random).
+    pub port_allocation: PortAllocation,
+    /// Seed of the random number generator used for [random](PortAllocation::Random)
+    /// port number allocation.
+    ///
+    /// Providing a seed makes random allocation reproducible, for example for testing.
+    /// By default this is [None] and the thread-local random number generator
+    /// of the [rand] crate is used.
+    pub port_allocation_seed: Option<u64>,
     /// Default behavior when ports are exhausted and a connect is requested.
     ///
     /// This can be overridden on a per-request basis.
@@ -62,7 +156,26 @@ pub struct Cfg {
     /// This can be configured on a per-receiver basis.
     /// By default this is 128.
     pub max_received_ports: usize,
-    /// Size of a chunk of data in bytes.
+    /// Maximum size of a chunk of data in bytes.
+    ///
+    /// Data sent over a port is split into chunks, each of which is transmitted as a
+    /// separate message over the transport.
+    /// This value is announced to the remote endpoint during connection establishment
+    /// and limits the size of the chunks it sends to this endpoint.
+    ///
+    /// Chunks of different ports are interleaved on the transport.
+    /// A smaller chunk size thus reduces the latency that other ports experience while a
+    /// large item is being transmitted, whereas a larger chunk size reduces the per-message
+    /// overhead on links with a high bandwidth-delay product.
+    ///
+    /// A chunk is also limited by the flow-control credits available to the sender,
+    /// i.e. it never exceeds the [receive buffer](Self::receive_buffer) of a port and
+    /// is smaller while data is in flight.
+    /// Therefore, the receive buffer should be a multiple of the chunk size.
+    /// The maximum frame length accepted from the transport is derived from this value,
+    /// see [max_frame_length](Self::max_frame_length).
+    /// When using [Connect::io_buffered](crate::Connect::io_buffered), a buffer size of
+    /// at least the chunk size avoids splitting chunks across multiple IO operations.
     ///
     /// By default this is 16 kB.
     /// This must be at least 4 bytes.
@@ -79,6 +192,42 @@ pub struct Cfg {
     /// By default this is 512 kB.
     /// This must be at least 4 bytes.
     pub receive_buffer: u32,
+    /// Maximum amount of in-flight data of all ports in bytes.
+    ///
+    /// In-flight data is data sent but not yet consumed by the remote endpoint,
+    /// i.e. data on the transport plus data buffered by the remote endpoint.
+    /// When this limit is reached, sending on any port waits until the remote endpoint
+    /// has consumed data.
+    /// This bounds the memory used when many ports are open, each of which may
+    /// otherwise have up to the [receive buffer](Self::receive_buffer) of data in flight.
+    ///
+    /// Waiting ports are served in FIFO order and obtain at most one [chunk](Self::chunk_size)
+    /// at a time, so that concurrently sending ports interleave.
+    /// A single port never uses more than half of this limit, so that other ports
+    /// can make progress even if the remote endpoint stops reading from one port.
+    /// Consequently, [Sender::try_send](super::Sender::try_send) cannot send more data
+    /// than half of this limit at once.
+    ///
+    /// If the remote endpoint uses an older protocol version, the limit is raised to at least
+    /// its receive buffer size, since it withholds flow control credits of a port until half
+    /// of its receive buffer has been consumed.
+    ///
+    /// By default this is unlimited.
+    /// This must be at least 8 bytes.
+    pub max_connection_buffer: Option<usize>,
+    /// Amount of received data in bytes that must be consumed from the receive buffer
+    /// of a port before flow control credits are returned to the remote endpoint.
+    ///
+    /// Credits of consumed data are accumulated and returned in a single message once
+    /// this threshold is reached.
+    /// Raising this reduces the number of messages sent in the reverse direction of
+    /// a port, while lowering it lets the remote endpoint resume sending earlier.
+    /// If the remote endpoint [limits its connection buffer](Self::max_connection_buffer),
+    /// credits are additionally returned when all received data of a port has been consumed.
+    ///
+    /// By default (`None`) this is half of the [receive buffer](Self::receive_buffer).
+    /// If set, this must not be zero and must not exceed the receive buffer size minus 4 bytes.
+    pub credit_return_threshold: Option<u32>,
     /// Length of global send queue.
     /// Each element holds a chunk.
     ///
@@ -117,6 +266,20 @@ pub struct Cfg {
     ///
     /// By default this is 20 milliseconds.
     pub flush_delay: Duration,
+    /// Whether to protect transmitted data by checksums.
+    ///
+    /// If enabled, a CRC32C checksum is appended to each frame sent over the transport
+    /// and verified by the receiving endpoint.
+    /// When corrupted data is received, the connection fails with
+    /// [ChMuxError::Corrupted](super::ChMuxError::Corrupted) instead of processing it.
+    /// This is useful for transports that do not guarantee data integrity themselves,
+    /// for example serial links.
+    ///
+    /// Checksums are used if either endpoint enables them and both endpoints support them,
+    /// see [Negotiated::checksum](super::Negotiated::checksum).
+    ///
+    /// By default this is disabled.
+    pub checksum: bool,
     #[doc(hidden)]
     pub _non_exhaustive: (),
 }
@@ -127,17 +290,27 @@ impl Default for Cfg {
     fn default() -> Self {
         Self {
             connection_timeout: Some(Duration::from_secs(60)),
+            ping_interval: None,
+            ping_timeout: None,
+            idle_timeout: None,
             max_ports: 16_384,
+            port_range_start: 0,
+            port_range_end: u32::MAX,
+            port_allocation: PortAllocation::Random,
+            port_allocation_seed: None,
             ports_exhausted: PortsExhausted::Wait(Some(Duration::from_secs(60))),
             max_data_size: 524_288,
             max_received_ports: 128,
             chunk_size: 16_384,
             receive_buffer: 524_288,
+            max_connection_buffer: None,
+            credit_return_threshold: None,
             shared_send_queue: 16,
             transport_send_queue: 16,
             transport_receive_queue: 16,
             connect_queue: 128,
             flush_delay: Duration::from_millis(20),
+            checksum: false,
             _non_exhaustive: (),
         }
     }
@@ -153,6 +326,22 @@ impl Cfg {
             panic!("maximum ports must not exceed 2^31");
         }
 
+        if self.port_range_start > self.port_range_end {
+            panic!("port range start must not exceed port range end");
+        }
+
+        if self.ping_interval == Some(Duration::ZERO) {
+            panic!("ping interval must not be zero");
+        }
+
+        if self.ping_timeout == Some(Duration::ZERO) {
+            panic!("ping timeout must not be zero");
+        }
+
+        if self.idle_timeout == Some(Duration::ZERO) {
+            panic!("idle timeout must not be zero");
+        }
+
         if self.chunk_size < 4 {
             panic!("chunk size must be at least 4");
         }
@@ -161,6 +350,17 @@ impl Cfg {
             panic!("receive buffer must be at least 4 bytes");
         }
 
+        if matches!(self.max_connection_buffer, Some(limit) if limit < 8) {
+            panic!("maximum connection buffer must be at least 8 bytes");
+        }
+
+        if matches!(self.credit_return_threshold, Some(threshold) if threshold == 0 || threshold > self.receive_buffer - 4)
+        {
+            panic!(
+                "credit return threshold must not be zero and must not exceed receive buffer size minus 4 bytes"
+            );
+        }
+
         if self.shared_send_queue == 0 {
             panic!("shared send queue length must not be zero");
         }
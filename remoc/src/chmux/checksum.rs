@@ -0,0 +1,74 @@
+//! CRC32C (Castagnoli) checksum of transport frames.
+
+use bytes::{BufMut, Bytes, BytesMut};
+
+/// Size of the checksum appended to a frame in bytes.
+pub(crate) const CHECKSUM_LEN: usize = 4;
+
+/// Reversed Castagnoli polynomial.
+const POLY: u32 = 0x82f6_3b78;
+
+/// Lookup table for bytewise calculation.
+const TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ POLY } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// Calculates the CRC32C of the specified data.
+pub(crate) fn crc32c(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc = TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+/// Appends the checksum to a frame.
+pub(crate) fn append(frame: &[u8]) -> Bytes {
+    let mut buf = BytesMut::with_capacity(frame.len() + CHECKSUM_LEN);
+    buf.extend_from_slice(frame);
+    buf.put_u32_le(crc32c(frame));
+    buf.freeze()
+}
+
+/// Verifies and removes the checksum of a frame.
+///
+/// Returns [None] if the checksum does not match.
+pub(crate) fn verify(mut frame: Bytes) -> Option<Bytes> {
+    let len = frame.len().checked_sub(CHECKSUM_LEN)?;
+    let expected = u32::from_le_bytes(frame[len..].try_into().unwrap());
+    frame.truncate(len);
+    (crc32c(&frame) == expected).then_some(frame)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_value() {
+        assert_eq!(crc32c(b"123456789"), 0xe306_9283);
+    }
+
+    #[test]
+    fn append_verify() {
+        let frame = append(b"frame");
+        assert_eq!(verify(frame.clone()).unwrap(), &b"frame"[..]);
+
+        let mut corrupted = frame.to_vec();
+        corrupted[0] ^= 1;
+        assert!(verify(corrupted.into()).is_none());
+        assert!(verify(Bytes::from_static(b"abc")).is_none());
+    }
+}
@@ -9,6 +9,7 @@ use std::{
         Arc, Mutex,
     },
     task::{Context, Poll},
+    time::Duration,
 };
 use tokio::{
     sync::{mpsc, oneshot},
@@ -16,9 +17,12 @@ use tokio::{
 };
 
 use super::{
+    negotiated::Negotiated,
     port_allocator::{PortAllocator, PortNumber},
     receiver::Receiver,
     sender::Sender,
+    shutdown::ShutdownHandle,
+    stats::{Stats, StatsCounters},
     PortReq,
 };
 
@@ -34,6 +38,9 @@ pub enum ConnectError {
     TooManyPendingConnectionRequests,
     /// Connection has been rejected by server.
     Rejected,
+    /// Connection has been rejected by server, because no
+    /// [route](super::Router) matches the id of the request.
+    NoRoute,
     /// A multiplexer error has occurred or it has been terminated.
     ChMux,
 }
@@ -45,6 +52,7 @@ impl fmt::Display for ConnectError {
             Self::RemotePortsExhausted => write!(f, "all remote ports are in use"),
             Self::TooManyPendingConnectionRequests => write!(f, "too many connection requests are pending"),
             Self::Rejected => write!(f, "connection has been rejected by server"),
+            Self::NoRoute => write!(f, "connection has been rejected by server due to no matching route"),
             Self::ChMux => write!(f, "multiplexer error"),
         }
     }
@@ -60,6 +68,7 @@ impl From<ConnectError> for std::io::Error {
             ConnectError::RemotePortsExhausted => Self::new(ErrorKind::AddrInUse, err.to_string()),
             ConnectError::TooManyPendingConnectionRequests => Self::new(ErrorKind::AddrInUse, err.to_string()),
             ConnectError::Rejected => Self::new(ErrorKind::ConnectionRefused, err.to_string()),
+            ConnectError::NoRoute => Self::new(ErrorKind::ConnectionRefused, err.to_string()),
             ConnectError::ChMux => Self::new(ErrorKind::ConnectionReset, err.to_string()),
         }
     }
@@ -143,6 +152,8 @@ pub(crate) struct ConnectRequest {
     pub local_port: PortNumber,
     /// Port id.
     pub id: u32,
+    /// Port metadata.
+    pub metadata: Vec<u8>,
     /// Notification that request has been queued for sending.
     pub sent_tx: mpsc::Sender<()>,
     /// Response channel sender.
@@ -161,6 +172,8 @@ pub(crate) enum ConnectResponse {
     Rejected {
         /// Remote endpoint had not ports available.
         no_ports: bool,
+        /// Remote endpoint had no route for the id of the request.
+        no_route: bool,
     },
 }
 
@@ -204,7 +217,9 @@ pub struct Client {
     crediter: ConntectRequestCrediter,
     port_allocator: PortAllocator,
     listener_dropped: Arc<AtomicBool>,
-    terminate_tx: mpsc::UnboundedSender<()>,
+    shutdown: ShutdownHandle,
+    stats: Arc<StatsCounters>,
+    negotiated: Arc<Negotiated>,
 }
 
 impl fmt::Debug for Client {
@@ -216,14 +231,17 @@ impl fmt::Debug for Client {
 impl Client {
     pub(crate) fn new(
         tx: mpsc::UnboundedSender<ConnectRequest>, limit: u16, port_allocator: PortAllocator,
-        listener_dropped: Arc<AtomicBool>, terminate_tx: mpsc::UnboundedSender<()>,
+        listener_dropped: Arc<AtomicBool>, shutdown: ShutdownHandle, stats: Arc<StatsCounters>,
+        negotiated: Negotiated,
     ) -> Client {
         Client {
             tx,
             crediter: ConntectRequestCrediter::new(limit),
             port_allocator,
             listener_dropped,
-            terminate_tx,
+            shutdown,
+            stats,
+            negotiated: Arc::new(negotiated),
         }
     }
 
@@ -232,6 +250,16 @@ impl Client {
         self.port_allocator.clone()
     }
 
+    /// Returns a snapshot of the connection statistics.
+    pub fn stats(&self) -> Stats {
+        self.stats.snapshot()
+    }
+
+    /// Returns the connection parameters negotiated with the remote endpoint.
+    pub fn negotiated(&self) -> &Negotiated {
+        &self.negotiated
+    }
+
     /// Connects to a newly allocated remote port from a newly allocated local port.
     ///
     /// This function waits until a local and remote port become available.
@@ -276,8 +304,8 @@ impl Client {
         // Build and send request.
         let (sent_tx, sent_rx) = mpsc::channel(1);
         let (response_tx, response_rx) = oneshot::channel();
-        let PortReq { port: local_port, id } = local_port;
-        let req = ConnectRequest { local_port, id, sent_tx, response_tx, wait };
+        let PortReq { port: local_port, id, metadata } = local_port;
+        let req = ConnectRequest { local_port, id, metadata, sent_tx, response_tx, wait };
         let _ = self.tx.send(req);
 
         let listener_dropped = self.listener_dropped.clone();
@@ -288,9 +316,11 @@ impl Client {
             // Process response.
             match response_rx.await {
                 Ok(ConnectResponse::Accepted(sender, receiver)) => Ok((sender, receiver)),
-                Ok(ConnectResponse::Rejected { no_ports }) => {
+                Ok(ConnectResponse::Rejected { no_ports, no_route }) => {
                     if no_ports {
                         Err(ConnectError::RemotePortsExhausted)
+                    } else if no_route {
+                        Err(ConnectError::NoRoute)
                     } else {
                         Err(ConnectError::Rejected)
                     }
@@ -310,6 +340,18 @@ impl Client {
 
     /// Terminates the multiplexer, forcibly closing all open ports.
     pub fn terminate(&self) {
-        let _ = self.terminate_tx.send(());
+        self.shutdown.terminate()
+    }
+
+    /// Gracefully shuts down the multiplexer and waits for it to terminate.
+    ///
+    /// See [ShutdownHandle::shutdown] for details.
+    pub async fn shutdown(&self, timeout: Duration) {
+        self.shutdown.shutdown(timeout).await
+    }
+
+    /// Returns a handle for terminating or gracefully shutting down the multiplexer.
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        self.shutdown.clone()
     }
 }
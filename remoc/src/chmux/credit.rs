@@ -1,11 +1,18 @@
-use futures::{future::BoxFuture, FutureExt};
+use futures::{future::BoxFuture, pin_mut, FutureExt};
 use std::{
     mem,
-    sync::{Arc, Mutex, Weak},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, Weak,
+    },
+    time::Duration,
 };
-use tokio::sync::{
-    mpsc::{self, error::TrySendError},
-    oneshot,
+use tokio::{
+    sync::{
+        mpsc::{self, error::TrySendError},
+        oneshot, Semaphore,
+    },
+    time::Instant,
 };
 
 use super::{mux::PortEvt, ChMuxError, SendError};
@@ -58,16 +65,107 @@ impl Drop for AssignedCredits {
             if let Some(port) = self.port_inner.upgrade() {
                 let mut port = port.lock().unwrap();
                 port.credits += self.port;
+                port.release_connection(self.port);
             }
         }
     }
 }
 
+/// Budget for sending data shared by all ports of a connection.
+///
+/// Credits are granted in FIFO order and a single blocking request is granted
+/// at most one chunk, so that concurrently sending ports interleave.
+/// A single port never holds more than half of the budget.
+#[derive(Debug)]
+pub(crate) struct ConnectionCredits {
+    semaphore: Semaphore,
+    /// Maximum credits held by a single port.
+    port_share: u32,
+    /// Maximum credits granted by a single blocking request.
+    grant: u32,
+}
+
+impl ConnectionCredits {
+    /// Creates a connection budget of the specified size in bytes.
+    ///
+    /// The budget is raised so that the share of a single port is at least `min_port_share`.
+    pub fn new(limit: usize, chunk_size: u32, min_port_share: u32) -> Arc<Self> {
+        let limit = limit.max(2 * min_port_share as usize).min(Semaphore::MAX_PERMITS);
+        Arc::new(Self {
+            semaphore: Semaphore::new(limit),
+            port_share: (limit / 2).min(u32::MAX as usize) as u32,
+            grant: chunk_size,
+        })
+    }
+
+    /// Takes up to `max` credits if at least `min` are available without waiting.
+    fn try_acquire(&self, max: u32, min: u32) -> Option<u32> {
+        let available = self.semaphore.available_permits().min(max as usize) as u32;
+        if available < min {
+            return None;
+        }
+
+        match self.semaphore.try_acquire_many(available) {
+            Ok(permit) => {
+                permit.forget();
+                Some(available)
+            }
+            Err(_) => None,
+        }
+    }
+
+    /// Takes the specified number of credits, waiting in FIFO order.
+    async fn acquire(&self, credits: u32) {
+        if let Ok(permit) = self.semaphore.acquire_many(credits).await {
+            permit.forget();
+        }
+    }
+
+    /// Returns credits to the budget.
+    fn release(&self, credits: u32) {
+        if credits > 0 {
+            self.semaphore.add_permits(credits as usize);
+        }
+    }
+}
+
 #[derive(Debug)]
 struct ChannelCreditsInner {
     credits: u32,
+    limit: u32,
     closed: Option<bool>,
+    idle_timeout: bool,
     notify: Vec<oneshot::Sender<()>>,
+    blocked: Duration,
+    /// Budget shared by all ports of the connection, if limited.
+    connection: Option<Arc<ConnectionCredits>>,
+    /// Credits taken from the connection budget by this port.
+    connection_used: u32,
+}
+
+impl ChannelCreditsInner {
+    /// Credits that can be taken, considering the share of the connection budget.
+    fn allowance(&self) -> u32 {
+        match &self.connection {
+            Some(connection) => self.credits.min(connection.port_share.saturating_sub(self.connection_used)),
+            None => self.credits,
+        }
+    }
+
+    /// Returns credits of this port to the connection budget.
+    fn release_connection(&mut self, credits: u32) {
+        let credits = credits.min(self.connection_used);
+        self.connection_used -= credits;
+        if let Some(connection) = &self.connection {
+            connection.release(credits);
+        }
+    }
+}
+
+impl Drop for ChannelCreditsInner {
+    fn drop(&mut self) {
+        self.release_connection(self.connection_used);
+    }
 }
 
 /// Provides credits for sending over a channel.
@@ -86,6 +184,7 @@ impl CreditProvider {
                 Some(new_credits) => inner.credits = new_credits,
                 None => return Err(ChMuxError::Protocol("credits overflow".to_string())),
             };
+            inner.release_connection(credits);
 
             mem::take(&mut inner.notify)
         };
@@ -111,16 +210,55 @@ impl CreditProvider {
             let _ = tx.send(());
         }
     }
+
+    /// Closes the channel due to idle timeout.
+    pub fn idle_timeout(&self) {
+        let notify = {
+            let mut inner = self.0.lock().unwrap();
+
+            inner.idle_timeout = true;
+
+            mem::take(&mut inner.notify)
+        };
+
+        for tx in notify {
+            let _ = tx.send(());
+        }
+    }
 }
 
 /// Requests and consumes credits for sending over a channel.
 pub(crate) struct CreditUser {
     channel: Weak<Mutex<ChannelCreditsInner>>,
+    /// Whether the connection has been shut down.
+    shutdown: Arc<AtomicBool>,
     /// Whether data is sent anyway, when remote endpoint closed channel gracefully.
     pub(crate) override_graceful_close: bool,
 }
 
 impl CreditUser {
+    /// Error when the channel is not available anymore.
+    fn terminated(&self) -> SendError {
+        if self.shutdown.load(Ordering::SeqCst) {
+            SendError::Shutdown
+        } else {
+            SendError::ChMux
+        }
+    }
+
+    /// Error when the channel has been closed.
+    fn closed(&self, channel: &ChannelCreditsInner) -> Result<(), SendError> {
+        if channel.idle_timeout {
+            return Err(SendError::IdleTimeout);
+        }
+        if let Some(gracefully) = channel.closed {
+            if !self.override_graceful_close || !gracefully {
+                return Err(SendError::Closed { gracefully });
+            }
+        }
+        Ok(())
+    }
+
     /// Requests credits for sending.
     /// Blocks until at least `min_req` credits become available.
     pub async fn request(&self, req: u32, min_req: u32) -> Result<AssignedCredits, SendError> {
@@ -130,31 +268,107 @@ impl CreditUser {
             let rx_channel = {
                 let channel = match self.channel.upgrade() {
                     Some(channel) => channel,
-                    None => return Err(SendError::ChMux),
+                    None => return Err(self.terminated()),
                 };
                 let mut channel = channel.lock().unwrap();
-                if let Some(gracefully) = channel.closed {
-                    if !self.override_graceful_close || !gracefully {
-                        return Err(SendError::Closed { gracefully });
+                self.closed(&channel)?;
+
+                let allowance = channel.allowance();
+                if allowance >= min_req {
+                    let channel_taken = allowance.min(req);
+                    match channel.connection.clone() {
+                        Some(connection) => {
+                            let channel_taken = channel_taken.min(connection.grant);
+                            channel.credits -= channel_taken;
+                            Ok((connection, channel_taken))
+                        }
+                        None => {
+                            channel.credits -= channel_taken;
+                            return Ok(AssignedCredits::new(channel_taken, self.channel.clone()));
+                        }
                     }
-                }
-
-                if channel.credits >= min_req {
-                    let channel_taken = channel.credits.min(req);
-                    channel.credits -= channel_taken;
-
-                    return Ok(AssignedCredits::new(channel_taken, self.channel.clone()));
                 } else {
                     let (tx_channel, rx_channel) = oneshot::channel();
                     channel.notify.push(tx_channel);
-                    rx_channel
+                    Err(rx_channel)
                 }
             };
 
+            let rx_channel = match rx_channel {
+                Ok((connection, channel_taken)) => {
+                    return self.request_connection(connection, channel_taken, min_req).await
+                }
+                Err(rx_channel) => rx_channel,
+            };
+
+            #[cfg(feature = "tracing")]
+            tracing::trace!(req, min_req, "waiting for flow control credits");
+            let start = Instant::now();
             let _ = rx_channel.await;
+            let blocked = start.elapsed();
+            #[cfg(feature = "tracing")]
+            tracing::trace!(?blocked, "flow control credits available");
+            if let Some(channel) = self.channel.upgrade() {
+                channel.lock().unwrap().blocked += blocked;
+            }
         }
     }
 
+    /// Takes credits from the connection budget for credits taken from the channel.
+    /// Blocks until at least `min_req` credits become available or the channel is closed.
+    ///
+    /// Channel credits not covered by the connection budget are returned to the channel.
+    async fn request_connection(
+        &self, connection: Arc<ConnectionCredits>, channel_taken: u32, min_req: u32,
+    ) -> Result<AssignedCredits, SendError> {
+        let (taken, blocked) = match connection.try_acquire(channel_taken, min_req) {
+            Some(taken) => (taken, Duration::ZERO),
+            None => {
+                #[cfg(feature = "tracing")]
+                tracing::trace!(min_req, "waiting for connection flow control credits");
+                let start = Instant::now();
+                let acquire = connection.acquire(min_req);
+                pin_mut!(acquire);
+                loop {
+                    let rx_channel = {
+                        let channel = match self.channel.upgrade() {
+                            Some(channel) => channel,
+                            None => return Err(self.terminated()),
+                        };
+                        let mut channel = channel.lock().unwrap();
+                        self.closed(&channel)?;
+                        let (tx_channel, rx_channel) = oneshot::channel();
+                        channel.notify.push(tx_channel);
+                        rx_channel
+                    };
+
+                    tokio::select! {
+                        () = &mut acquire => break,
+                        _ = rx_channel => (),
+                    }
+                }
+                let blocked = start.elapsed();
+                #[cfg(feature = "tracing")]
+                tracing::trace!(?blocked, "connection flow control credits available");
+                (min_req, blocked)
+            }
+        };
+
+        let channel = match self.channel.upgrade() {
+            Some(channel) => channel,
+            None => {
+                connection.release(taken);
+                return Err(self.terminated());
+            }
+        };
+        let mut channel = channel.lock().unwrap();
+        channel.credits += channel_taken - taken;
+        channel.connection_used += taken;
+        channel.blocked += blocked;
+
+        Ok(AssignedCredits::new(taken, self.channel.clone()))
+    }
+
     /// Requests the specified number of credits for sending without blocking.
     /// Returns requested credits if fully available, otherwise None.
     pub fn try_request(&self, req: u32) -> Result<Option<AssignedCredits>, SendError> {
@@ -162,31 +376,75 @@ impl CreditUser {
 
         let channel = match self.channel.upgrade() {
             Some(channel) => channel,
-            None => return Err(SendError::ChMux),
+            None => return Err(self.terminated()),
         };
         let mut channel = channel.lock().unwrap();
-        if let Some(gracefully) = channel.closed {
-            if !self.override_graceful_close || !gracefully {
-                return Err(SendError::Closed { gracefully });
+        self.closed(&channel)?;
+
+        if channel.allowance() < req {
+            return Ok(None);
+        }
+        let connection_taken = match &channel.connection {
+            Some(connection) => match connection.try_acquire(req, req) {
+                Some(taken) => taken,
+                None => return Ok(None),
+            },
+            None => 0,
+        };
+
+        channel.credits -= req;
+        channel.connection_used += connection_taken;
+        Ok(Some(AssignedCredits::new(req, self.channel.clone())))
+    }
+
+    /// Number of credits that are currently not available for sending,
+    /// i.e. data sent that has not yet been consumed by the remote endpoint.
+    pub fn used(&self) -> u32 {
+        match self.channel.upgrade() {
+            Some(channel) => {
+                let channel = channel.lock().unwrap();
+                channel.limit.saturating_sub(channel.credits)
             }
+            None => 0,
         }
+    }
 
-        if channel.credits >= req {
-            channel.credits -= req;
-            Ok(Some(AssignedCredits::new(req, self.channel.clone())))
-        } else {
-            Ok(None)
+    /// True, if no credits are currently available for sending.
+    pub fn is_exhausted(&self) -> bool {
+        match self.channel.upgrade() {
+            Some(channel) => channel.lock().unwrap().credits == 0,
+            None => false,
+        }
+    }
+
+    /// Total time spent waiting for credits to become available.
+    pub fn blocked(&self) -> Duration {
+        match self.channel.upgrade() {
+            Some(channel) => channel.lock().unwrap().blocked,
+            None => Duration::ZERO,
         }
     }
 }
 
 /// Creates a pair of credit provider and credit user, initially filled
 /// with the specified number of credits.
-pub(crate) fn credit_send_pair(initial_credits: u32) -> (CreditProvider, CreditUser) {
-    let inner =
-        Arc::new(Mutex::new(ChannelCreditsInner { credits: initial_credits, closed: None, notify: Vec::new() }));
-
-    let user = CreditUser { channel: Arc::downgrade(&inner), override_graceful_close: false };
+///
+/// If a connection budget is specified, sending is additionally limited by it.
+pub(crate) fn credit_send_pair(
+    initial_credits: u32, connection: Option<Arc<ConnectionCredits>>, shutdown: Arc<AtomicBool>,
+) -> (CreditProvider, CreditUser) {
+    let inner = Arc::new(Mutex::new(ChannelCreditsInner {
+        credits: initial_credits,
+        limit: initial_credits,
+        closed: None,
+        idle_timeout: false,
+        notify: Vec::new(),
+        blocked: Duration::ZERO,
+        connection,
+        connection_used: 0,
+    }));
+
+    let user = CreditUser { channel: Arc::downgrade(&inner), shutdown, override_graceful_close: false };
     let provider = CreditProvider(inner);
     (provider, user)
 }
@@ -228,14 +486,20 @@ impl ChannelCreditMonitor {
 pub(crate) struct ChannelCreditReturner {
     monitor: Weak<Mutex<ChannelCreditMonitorInner>>,
     to_return: u32,
+    /// Accumulated credits are returned once this is reached.
+    threshold: u32,
+    /// Return all accumulated credits once all received data has been consumed.
+    return_when_idle: bool,
     return_fut: Option<BoxFuture<'static, ()>>,
 }
 
 impl ChannelCreditReturner {
     /// Starts returning channel-specific credit.
     ///
+    /// `idle` indicates that no more received data is queued.
+    ///
     /// poll_return_flush must have completed (Poll::Ready) before this function is called.
-    pub fn start_return(&mut self, credit: UsedCredit, remote_port: u32, tx: &mpsc::Sender<PortEvt>) {
+    pub fn start_return(&mut self, credit: UsedCredit, remote_port: u32, tx: &mpsc::Sender<PortEvt>, idle: bool) {
         assert!(self.return_fut.is_none(), "start_return_one called without poll_return_flush");
 
         if let Some(monitor) = self.monitor.upgrade() {
@@ -244,11 +508,11 @@ impl ChannelCreditReturner {
             monitor.used -= credit.0;
             self.to_return += credit.0;
 
-            // Make sure remote endpoint has at least 4 credits (size of u32),
-            // to be able to send a port data message with one port chunk.
-            let threshold = if monitor.limit >= 8 { monitor.limit / 2 } else { 1 };
+            // When the remote endpoint limits its connection buffer, the credits held by
+            // this port may be required by other ports and must not be withheld.
+            let idle_return = idle && self.return_when_idle && self.to_return > 0;
 
-            if self.to_return >= threshold {
+            if self.to_return >= self.threshold || idle_return {
                 let msg = PortEvt::ReturnCredits { remote_port, credits: self.to_return };
                 self.to_return = 0;
 
@@ -275,8 +539,23 @@ impl ChannelCreditReturner {
 }
 
 /// A pair of ChannelCreditMonitor and ChannelCreditReturner.
-pub(crate) fn credit_monitor_pair(limit: u32) -> (ChannelCreditMonitor, ChannelCreditReturner) {
+///
+/// Credits are returned once `threshold` is reached, by default half of the limit.
+/// If `return_when_idle` is true, they are also returned once all received data has been consumed.
+pub(crate) fn credit_monitor_pair(
+    limit: u32, threshold: Option<u32>, return_when_idle: bool,
+) -> (ChannelCreditMonitor, ChannelCreditReturner) {
+    // Make sure remote endpoint has at least 4 credits (size of u32),
+    // to be able to send a port data message with one port chunk.
+    let threshold = if limit >= 8 { threshold.unwrap_or(limit / 2) } else { 1 };
+
     let monitor = ChannelCreditMonitor(Arc::new(Mutex::new(ChannelCreditMonitorInner { used: 0, limit })));
-    let returner = ChannelCreditReturner { monitor: Arc::downgrade(&monitor.0), to_return: 0, return_fut: None };
+    let returner = ChannelCreditReturner {
+        monitor: Arc::downgrade(&monitor.0),
+        to_return: 0,
+        threshold,
+        return_when_idle,
+        return_fut: None,
+    };
     (monitor, returner)
 }
@@ -97,6 +97,10 @@ pub(crate) async fn forward(rx: &mut super::Receiver, tx: &mut super::Sender) ->
                         }
                         Err(RecvChunkError::Cancelled) => break,
                         Err(RecvChunkError::ChMux) => return Err(ForwardError::Recv(RecvError::ChMux)),
+                        Err(RecvChunkError::Shutdown) => return Err(ForwardError::Recv(RecvError::Shutdown)),
+                        Err(RecvChunkError::IdleTimeout) => {
+                            return Err(ForwardError::Recv(RecvError::IdleTimeout))
+                        }
                     }
                 }
             }
@@ -5,14 +5,17 @@ use futures::{
     task::{Context, Poll},
     FutureExt,
 };
-use std::{error::Error, fmt, pin::Pin, sync::Arc};
+use std::{error::Error, fmt, pin::Pin, sync::Arc, time::Duration};
 use tokio::sync::{mpsc, oneshot, Mutex};
 
 use super::{
     mux::PortEvt,
     port_allocator::{PortAllocator, PortNumber},
     receiver::Receiver,
+    router::Router,
     sender::Sender,
+    shutdown::ShutdownHandle,
+    stats::{Stats, StatsCounters},
 };
 
 /// An multiplexer listener error.
@@ -52,6 +55,7 @@ impl From<ListenerError> for std::io::Error {
 pub struct Request {
     remote_port: u32,
     id: u32,
+    metadata: Vec<u8>,
     wait: bool,
     allocator: PortAllocator,
     tx: mpsc::Sender<PortEvt>,
@@ -63,6 +67,7 @@ impl fmt::Debug for Request {
         f.debug_struct("Request")
             .field("remote_port", &self.remote_port)
             .field("id", &self.id)
+            .field("metadata", &self.metadata)
             .field("wait", &self.wait)
             .finish()
     }
@@ -70,17 +75,18 @@ impl fmt::Debug for Request {
 
 impl Request {
     pub(crate) fn new(
-        remote_port: u32, id: u32, wait: bool, allocator: PortAllocator, tx: mpsc::Sender<PortEvt>,
+        remote_port: u32, id: u32, metadata: Vec<u8>, wait: bool, allocator: PortAllocator,
+        tx: mpsc::Sender<PortEvt>,
     ) -> Self {
         let (done_tx, done_rx) = oneshot::channel();
         let drop_tx = tx.clone();
         tokio::spawn(async move {
             if done_rx.await.is_err() {
-                let _ = drop_tx.send(PortEvt::Rejected { remote_port, no_ports: false }).await;
+                let _ = drop_tx.send(PortEvt::Rejected { remote_port, no_ports: false, no_route: false }).await;
             }
         });
 
-        Self { remote_port, id, wait, allocator, tx, done_tx: Some(done_tx) }
+        Self { remote_port, id, metadata, wait, allocator, tx, done_tx: Some(done_tx) }
     }
 
     /// The remote port number.
@@ -95,6 +101,13 @@ impl Request {
         self.id
     }
 
+    /// The remotely provided metadata.
+    ///
+    /// If no metadata was provided, this is empty.
+    pub fn metadata(&self) -> &[u8] {
+        &self.metadata
+    }
+
     /// Indicates whether the handler of the request should wait for a local
     /// port to become available, if all are currently in use.
     pub fn is_wait(&self) -> bool {
@@ -131,8 +144,17 @@ impl Request {
     ///
     /// Setting `no_ports` to true indicates to the remote endpoint that the request
     /// was rejected because no local port could be allocated.
-    pub async fn reject(mut self, no_ports: bool) {
-        let _ = self.tx.send(PortEvt::Rejected { remote_port: self.remote_port, no_ports }).await;
+    pub async fn reject(self, no_ports: bool) {
+        self.reject_int(no_ports, false).await
+    }
+
+    /// Rejects the connect request because no route matches its id.
+    pub(crate) async fn reject_no_route(self) {
+        self.reject_int(false, true).await
+    }
+
+    async fn reject_int(mut self, no_ports: bool, no_route: bool) {
+        let _ = self.tx.send(PortEvt::Rejected { remote_port: self.remote_port, no_ports, no_route }).await;
         let _ = self.done_tx.take().unwrap().send(());
     }
 }
@@ -156,7 +178,8 @@ pub struct Listener {
     wait_rx: mpsc::Receiver<RemoteConnectMsg>,
     no_wait_rx: mpsc::Receiver<RemoteConnectMsg>,
     port_allocator: PortAllocator,
-    terminate_tx: mpsc::UnboundedSender<()>,
+    shutdown: ShutdownHandle,
+    stats: Arc<StatsCounters>,
     closed: bool,
 }
 
@@ -169,9 +192,9 @@ impl fmt::Debug for Listener {
 impl Listener {
     pub(crate) fn new(
         wait_rx: mpsc::Receiver<RemoteConnectMsg>, no_wait_rx: mpsc::Receiver<RemoteConnectMsg>,
-        port_allocator: PortAllocator, terminate_tx: mpsc::UnboundedSender<()>,
+        port_allocator: PortAllocator, shutdown: ShutdownHandle, stats: Arc<StatsCounters>,
     ) -> Self {
-        Self { wait_rx, no_wait_rx, port_allocator, terminate_tx, closed: false }
+        Self { wait_rx, no_wait_rx, port_allocator, shutdown, stats, closed: false }
     }
 
     /// Obtains the port allocator.
@@ -179,6 +202,11 @@ impl Listener {
         self.port_allocator.clone()
     }
 
+    /// Returns a snapshot of the connection statistics.
+    pub fn stats(&self) -> Stats {
+        self.stats.snapshot()
+    }
+
     /// Accept a connection returning the sender and receiver for the opened port.
     ///
     /// Returns [None] when the client of the remote endpoint has been dropped and
@@ -250,9 +278,26 @@ impl Listener {
         ListenerStream::new(self)
     }
 
+    /// Convert this into a router, which dispatches connection requests by their id.
+    pub fn into_router(self) -> Router {
+        Router::new(self)
+    }
+
     /// Terminates the multiplexer, forcibly closing all open ports.
     pub fn terminate(&self) {
-        let _ = self.terminate_tx.send(());
+        self.shutdown.terminate()
+    }
+
+    /// Gracefully shuts down the multiplexer and waits for it to terminate.
+    ///
+    /// See [ShutdownHandle::shutdown] for details.
+    pub async fn shutdown(&self, timeout: Duration) {
+        self.shutdown.shutdown(timeout).await
+    }
+
+    /// Returns a handle for terminating or gracefully shutting down the multiplexer.
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        self.shutdown.clone()
     }
 }
 
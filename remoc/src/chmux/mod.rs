@@ -8,6 +8,18 @@
 //! Instead use methods from [Connect](crate::Connect) to establish a connection over
 //! a physical transport and work with high-level [remote channels](crate::rch).
 //!
+//! # Flow control
+//! Each port has its own flow control window, sized by the [receive buffer](Cfg::receive_buffer)
+//! of the remote endpoint.
+//! A [Sender] only submits data to the shared transport when credits for its port are available,
+//! and received data is queued per port without blocking the multiplexer.
+//! Thus a port that is read slowly only backpressures its own sender, while other ports of the
+//! same connection continue to make progress.
+//! Optionally, the total amount of in-flight data of all ports can be limited
+//! using [Cfg::max_connection_buffer].
+//! Credits for consumed data are returned to the sender in batches,
+//! see [Cfg::credit_return_threshold].
+//!
 //! # Protocol version compatibility
 //! Two endpoints can only communicate if they have the same [protocol version](PROTOCOL_VERSION).
 //! A change in protocol version will be accompanied by an increase of the
@@ -17,32 +29,65 @@ use std::{error::Error, fmt};
 
 mod any_storage;
 mod cfg;
+mod checksum;
 mod client;
 mod credit;
 mod forward;
 mod listener;
 mod msg;
 mod mux;
+mod negotiated;
 mod port_allocator;
 mod receiver;
+mod router;
 mod sender;
+mod shutdown;
+mod stats;
 
 pub use any_storage::{AnyBox, AnyEntry, AnyStorage};
-pub use cfg::{Cfg, PortsExhausted};
+pub use cfg::{Cfg, PortAllocation, PortsExhausted};
 pub use client::{Client, Connect, ConnectError};
 pub use forward::ForwardError;
 pub use listener::{Listener, ListenerError, ListenerStream, Request};
 pub use mux::ChMux;
-pub use port_allocator::{PortAllocator, PortNumber, PortReq};
-pub use receiver::{DataBuf, Received, Receiver, ReceiverStream, RecvAnyError, RecvChunkError, RecvError};
+pub use negotiated::Negotiated;
+pub use port_allocator::{PortAllocator, PortEvent, PortEvents, PortNumber, PortReq};
+pub use receiver::{
+    DataBuf, Received, Receiver, ReceiverChunkStream, ReceiverStream, RecvAnyError, RecvChunkError, RecvError,
+};
+pub use router::{RouteError, RouteStream, Router};
 pub use sender::{ChunkSender, Closed, SendError, Sender, SenderSink, TrySendError};
+pub use shutdown::{CloseReason, ShutdownHandle};
+pub use stats::Stats;
 
 /// Channel multiplexer protocol version.
-pub const PROTOCOL_VERSION: u8 = 3;
+pub const PROTOCOL_VERSION: u8 = 8;
 
 /// Lowest protocol version that supports port ids.
 const PROTOCOL_VERSION_PORT_ID: u8 = 3;
 
+/// Lowest protocol version that supports port request metadata.
+const PROTOCOL_VERSION_PORT_METADATA: u8 = 4;
+
+/// Lowest protocol version that supports transmitting a close reason.
+const PROTOCOL_VERSION_CLOSE_REASON: u8 = 5;
+
+/// Lowest protocol version that supports notifying about idle port timeouts.
+const PROTOCOL_VERSION_PORT_IDLE_TIMEOUT: u8 = 6;
+
+/// Lowest protocol version that supports checksums of transport frames.
+const PROTOCOL_VERSION_CHECKSUM: u8 = 7;
+
+/// Lowest protocol version that returns credits of idle ports to a remote endpoint
+/// with limited connection buffer.
+const PROTOCOL_VERSION_IDLE_CREDIT_RETURN: u8 = 7;
+
+/// Lowest protocol version that supports headers preceding a data message.
+const PROTOCOL_VERSION_DATA_HEADERS: u8 = 8;
+
+/// Lowest protocol version that responds to pings.
+const PROTOCOL_VERSION_PONG: u8 = 8;
+
 /// Channel multiplexer error.
 #[derive(Debug, Clone)]
 pub enum ChMuxError<SinkError, StreamError> {
@@ -59,6 +104,12 @@ pub enum ChMuxError<SinkError, StreamError> {
     Timeout,
     /// A multiplex protocol error occurred.
     Protocol(String),
+    /// The connection was closed by the remote endpoint with the specified reason.
+    Closed(CloseReason),
+    /// Data received from the transport stream failed the integrity check.
+    ///
+    /// This can only occur when [checksums](Cfg::checksum) are enabled.
+    Corrupted,
 }
 
 impl<SinkError, StreamError> fmt::Display for ChMuxError<SinkError, StreamError>
@@ -74,6 +125,8 @@ where
             Self::Reset => write!(f, "connection reset"),
             Self::Timeout => write!(f, "connection timeout"),
             Self::Protocol(err) => write!(f, "protocol error: {err}"),
+            Self::Closed(reason) => write!(f, "connection closed by remote endpoint: {reason}"),
+            Self::Corrupted => write!(f, "received data is corrupted (checksum mismatch)"),
         }
     }
 }
@@ -95,6 +148,8 @@ impl From<ChMuxError<std::io::Error, std::io::Error>> for std::io::Error {
             ChMuxError::Reset => std::io::Error::new(ErrorKind::ConnectionReset, err.to_string()),
             ChMuxError::Timeout => std::io::Error::new(ErrorKind::TimedOut, err.to_string()),
             ChMuxError::Protocol(_) => std::io::Error::new(ErrorKind::InvalidData, err.to_string()),
+            ChMuxError::Closed(_) => std::io::Error::new(ErrorKind::ConnectionAborted, err.to_string()),
+            ChMuxError::Corrupted => std::io::Error::new(ErrorKind::InvalidData, err.to_string()),
         }
     }
 }
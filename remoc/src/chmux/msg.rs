@@ -4,7 +4,7 @@ use std::{
     time::Duration,
 };
 
-use super::{Cfg, ChMuxError};
+use super::{Cfg, ChMuxError, CloseReason};
 
 fn invalid_data(msg: &str) -> io::Error {
     io::Error::new(io::ErrorKind::InvalidData, format!("invalid value for {} received", msg))
@@ -27,7 +27,11 @@ pub enum MultiplexMsg {
         cfg: ExchangedCfg,
     },
     /// Ping to keep connection alive when there is no data to send.
+    ///
+    /// Endpoints supporting it respond with a pong.
     Ping,
+    /// Response to a ping.
+    Pong,
     /// Open connection on specified client port and assign a server port.
     OpenPort {
         /// Requesting client port.
@@ -37,6 +41,8 @@ pub enum MultiplexMsg {
         wait: bool,
         /// Port id
         id: Option<u32>,
+        /// Port metadata
+        metadata: Option<Vec<u8>>,
     },
     /// Connection accepted and server port assigned.
     PortOpened {
@@ -52,6 +58,8 @@ pub enum MultiplexMsg {
         // Flags u8.
         /// Rejected because no server ports was available and `wait` was not specified.
         no_ports: bool,
+        /// Rejected because no route matched the id of the request.
+        no_route: bool,
     },
     /// Data for specified port.
     ///
@@ -67,6 +75,8 @@ pub enum MultiplexMsg {
         first: bool,
         /// Last chunk of data.
         last: bool,
+        /// Data is a header block that precedes the next data message.
+        headers: bool,
     },
     /// Ports sent over a port.
     PortData {
@@ -86,6 +96,8 @@ pub enum MultiplexMsg {
         ports: Vec<u32>,
         /// Port ids
         ids: Option<Vec<u32>>,
+        /// Port metadata
+        metadata: Option<Vec<Vec<u8>>>,
     },
     /// Give flow credits to a port.
     PortCredits {
@@ -110,12 +122,20 @@ pub enum MultiplexMsg {
         /// Port of side that receives this message.
         port: u32,
     },
+    /// Port has been idle for longer than the idle timeout and is closed.
+    PortIdleTimeout {
+        /// Port of side that receives this message.
+        port: u32,
+    },
     /// All clients have been dropped, therefore no more OpenPort requests will occur.
     ClientFinish,
     /// Listener has been dropped, therefore no more OpenPort requests will be handled.
     ListenerFinish,
     /// Terminate connection.
-    Goodbye,
+    Goodbye {
+        /// Reason for closing the connection.
+        reason: Option<CloseReason>,
+    },
 }
 
 pub const MSG_RESET: u8 = 1;
@@ -133,25 +153,48 @@ pub const MSG_RECEIVE_FINISH: u8 = 12;
 pub const MSG_CLIENT_FINISH: u8 = 13;
 pub const MSG_LISTENER_FINISH: u8 = 14;
 pub const MSG_GOODBYE: u8 = 15;
+pub const MSG_PORT_IDLE_TIMEOUT: u8 = 16;
+pub const MSG_PONG: u8 = 17;
 
 pub const MSG_OPEN_PORT_FLAG_WAIT: u8 = 0b0000_0001;
 pub const MSG_OPEN_PORT_FLAG_ID: u8 = 0b0000_0010;
+pub const MSG_OPEN_PORT_FLAG_METADATA: u8 = 0b0000_0100;
 
 pub const MSG_REJECTED_FLAG_NO_PORTS: u8 = 0b0000_0001;
+pub const MSG_REJECTED_FLAG_NO_ROUTE: u8 = 0b0000_0010;
 
 pub const MSG_DATA_FLAG_FIRST: u8 = 0b0000_0001;
 pub const MSG_DATA_FLAG_LAST: u8 = 0b0000_0010;
+pub const MSG_DATA_FLAG_HEADERS: u8 = 0b0000_0100;
 
 pub const MSG_PORT_DATA_FLAG_FIRST: u8 = 0b0000_0001;
 pub const MSG_PORT_DATA_FLAG_LAST: u8 = 0b0000_0010;
 pub const MSG_PORT_DATA_FLAG_WAIT: u8 = 0b0000_0100;
 pub const MSG_PORT_DATA_FLAG_IDS: u8 = 0b0000_1000;
+pub const MSG_PORT_DATA_FLAG_METADATA: u8 = 0b0001_0000;
+
+pub const CFG_FLAG_CHECKSUM: u8 = 0b0000_0001;
+pub const CFG_FLAG_CONNECTION_BUFFER_LIMITED: u8 = 0b0000_0010;
 
 /// Maximum message length.
 ///
-/// Currently this is 16 to reserve space for further use.
+/// Currently this is 512 to accommodate port request metadata and
+/// reserve space for further use.
 /// Port data, limited by the maximum chunk size, may be append to a message.
-pub const MAX_MSG_LENGTH: usize = 16;
+pub const MAX_MSG_LENGTH: usize = 512;
+
+fn write_metadata(mut writer: impl io::Write, metadata: &[u8]) -> Result<(), io::Error> {
+    let len =
+        u8::try_from(metadata.len()).map_err(|_| io::Error::new(ErrorKind::InvalidInput, "metadata too long"))?;
+    writer.write_u8(len)?;
+    writer.write_all(metadata)
+}
+
+fn read_metadata(mut reader: impl io::Read) -> Result<Vec<u8>, io::Error> {
+    let mut metadata = vec![0; reader.read_u8()?.into()];
+    reader.read_exact(&mut metadata)?;
+    Ok(metadata)
+}
 
 impl MultiplexMsg {
     pub(crate) fn write(&self, mut writer: impl io::Write) -> Result<(), io::Error> {
@@ -168,7 +211,10 @@ impl MultiplexMsg {
             MultiplexMsg::Ping => {
                 writer.write_u8(MSG_PING)?;
             }
-            MultiplexMsg::OpenPort { client_port, wait, id } => {
+            MultiplexMsg::Pong => {
+                writer.write_u8(MSG_PONG)?;
+            }
+            MultiplexMsg::OpenPort { client_port, wait, id, metadata } => {
                 writer.write_u8(MSG_OPEN_PORT)?;
                 writer.write_u32::<LE>(*client_port)?;
                 let mut flags = 0;
@@ -178,22 +224,35 @@ impl MultiplexMsg {
                 if id.is_some() {
                     flags |= MSG_OPEN_PORT_FLAG_ID;
                 }
+                if metadata.is_some() {
+                    flags |= MSG_OPEN_PORT_FLAG_METADATA;
+                }
                 writer.write_u8(flags)?;
                 if let Some(id) = id {
                     writer.write_u32::<LE>(*id)?;
                 }
+                if let Some(metadata) = metadata {
+                    write_metadata(&mut writer, metadata)?;
+                }
             }
             MultiplexMsg::PortOpened { client_port, server_port } => {
                 writer.write_u8(MSG_PORT_OPENED)?;
                 writer.write_u32::<LE>(*client_port)?;
                 writer.write_u32::<LE>(*server_port)?;
             }
-            MultiplexMsg::Rejected { client_port, no_ports } => {
+            MultiplexMsg::Rejected { client_port, no_ports, no_route } => {
                 writer.write_u8(MSG_REJECTED)?;
                 writer.write_u32::<LE>(*client_port)?;
-                writer.write_u8(if *no_ports { MSG_REJECTED_FLAG_NO_PORTS } else { 0 })?;
+                let mut flags = 0;
+                if *no_ports {
+                    flags |= MSG_REJECTED_FLAG_NO_PORTS;
+                }
+                if *no_route {
+                    flags |= MSG_REJECTED_FLAG_NO_ROUTE;
+                }
+                writer.write_u8(flags)?;
             }
-            MultiplexMsg::Data { port, first, last } => {
+            MultiplexMsg::Data { port, first, last, headers } => {
                 writer.write_u8(MSG_DATA)?;
                 writer.write_u32::<LE>(*port)?;
                 let mut flags = 0;
@@ -203,9 +262,12 @@ impl MultiplexMsg {
                 if *last {
                     flags |= MSG_DATA_FLAG_LAST;
                 }
+                if *headers {
+                    flags |= MSG_DATA_FLAG_HEADERS;
+                }
                 writer.write_u8(flags)?;
             }
-            MultiplexMsg::PortData { port, first, last, wait, ports, ids } => {
+            MultiplexMsg::PortData { port, first, last, wait, ports, ids, metadata } => {
                 writer.write_u8(MSG_PORT_DATA)?;
                 writer.write_u32::<LE>(*port)?;
                 let mut flags = 0;
@@ -221,19 +283,23 @@ impl MultiplexMsg {
                 if ids.is_some() {
                     flags |= MSG_PORT_DATA_FLAG_IDS;
                 }
+                if metadata.is_some() {
+                    flags |= MSG_PORT_DATA_FLAG_METADATA;
+                }
                 writer.write_u8(flags)?;
-                match ids {
-                    Some(ids) => {
-                        assert_eq!(ports.len(), ids.len(), "ports and port ids must have same length");
-                        for (p, id) in ports.iter().zip(ids) {
-                            writer.write_u32::<LE>(*p)?;
-                            writer.write_u32::<LE>(*id)?;
-                        }
+                if let Some(ids) = ids {
+                    assert_eq!(ports.len(), ids.len(), "ports and port ids must have same length");
+                }
+                if let Some(metadata) = metadata {
+                    assert_eq!(ports.len(), metadata.len(), "ports and port metadata must have same length");
+                }
+                for (i, p) in ports.iter().enumerate() {
+                    writer.write_u32::<LE>(*p)?;
+                    if let Some(ids) = ids {
+                        writer.write_u32::<LE>(ids[i])?;
                     }
-                    None => {
-                        for p in ports {
-                            writer.write_u32::<LE>(*p)?;
-                        }
+                    if let Some(metadata) = metadata {
+                        write_metadata(&mut writer, &metadata[i])?;
                     }
                 }
             }
@@ -254,14 +320,22 @@ impl MultiplexMsg {
                 writer.write_u8(MSG_RECEIVE_FINISH)?;
                 writer.write_u32::<LE>(*port)?;
             }
+            MultiplexMsg::PortIdleTimeout { port } => {
+                writer.write_u8(MSG_PORT_IDLE_TIMEOUT)?;
+                writer.write_u32::<LE>(*port)?;
+            }
             MultiplexMsg::ClientFinish => {
                 writer.write_u8(MSG_CLIENT_FINISH)?;
             }
             MultiplexMsg::ListenerFinish => {
                 writer.write_u8(MSG_LISTENER_FINISH)?;
             }
-            MultiplexMsg::Goodbye => {
+            MultiplexMsg::Goodbye { reason } => {
                 writer.write_u8(MSG_GOODBYE)?;
+                if let Some(reason) = reason {
+                    writer.write_u32::<LE>(reason.code())?;
+                    write_metadata(&mut writer, reason.message().as_bytes())?;
+                }
             }
         }
         Ok(())
@@ -279,6 +353,7 @@ impl MultiplexMsg {
                 Self::Hello { version: reader.read_u8()?, cfg: ExchangedCfg::read(&mut reader)? }
             }
             MSG_PING => Self::Ping,
+            MSG_PONG => Self::Pong,
             MSG_OPEN_PORT => {
                 let client_port = reader.read_u32::<LE>()?;
                 let flags = reader.read_u8()?;
@@ -287,15 +362,25 @@ impl MultiplexMsg {
                 if let Some(id) = &mut id {
                     *id = reader.read_u32::<LE>()?;
                 }
-                Self::OpenPort { client_port, wait, id }
+                let metadata = if flags & MSG_OPEN_PORT_FLAG_METADATA != 0 {
+                    Some(read_metadata(&mut reader)?)
+                } else {
+                    None
+                };
+                Self::OpenPort { client_port, wait, id, metadata }
             }
             MSG_PORT_OPENED => {
                 Self::PortOpened { client_port: reader.read_u32::<LE>()?, server_port: reader.read_u32::<LE>()? }
             }
-            MSG_REJECTED => Self::Rejected {
-                client_port: reader.read_u32::<LE>()?,
-                no_ports: reader.read_u8()? & MSG_REJECTED_FLAG_NO_PORTS != 0,
-            },
+            MSG_REJECTED => {
+                let client_port = reader.read_u32::<LE>()?;
+                let flags = reader.read_u8()?;
+                Self::Rejected {
+                    client_port,
+                    no_ports: flags & MSG_REJECTED_FLAG_NO_PORTS != 0,
+                    no_route: flags & MSG_REJECTED_FLAG_NO_ROUTE != 0,
+                }
+            }
             MSG_DATA => {
                 let port = reader.read_u32::<LE>()?;
                 let flags = reader.read_u8()?;
@@ -303,6 +388,7 @@ impl MultiplexMsg {
                     port,
                     first: flags & MSG_DATA_FLAG_FIRST != 0,
                     last: flags & MSG_DATA_FLAG_LAST != 0,
+                    headers: flags & MSG_DATA_FLAG_HEADERS != 0,
                 }
             }
             MSG_PORT_DATA => {
@@ -312,6 +398,7 @@ impl MultiplexMsg {
                 let last = flags & MSG_PORT_DATA_FLAG_LAST != 0;
                 let wait = flags & MSG_PORT_DATA_FLAG_WAIT != 0;
                 let mut ids = (flags & MSG_PORT_DATA_FLAG_IDS != 0).then_some(Vec::new());
+                let mut metadata = (flags & MSG_PORT_DATA_FLAG_METADATA != 0).then_some(Vec::new());
                 let mut ports = Vec::new();
                 loop {
                     match reader.read_u32::<LE>() {
@@ -322,8 +409,11 @@ impl MultiplexMsg {
                     if let Some(ids) = &mut ids {
                         ids.push(reader.read_u32::<LE>()?);
                     }
+                    if let Some(metadata) = &mut metadata {
+                        metadata.push(read_metadata(&mut reader)?);
+                    }
                 }
-                Self::PortData { port, first, last, wait, ports, ids }
+                Self::PortData { port, first, last, wait, ports, ids, metadata }
             }
             MSG_PORT_CREDITS => {
                 Self::PortCredits { port: reader.read_u32::<LE>()?, credits: reader.read_u32::<LE>()? }
@@ -331,9 +421,21 @@ impl MultiplexMsg {
             MSG_SEND_FINISH => Self::SendFinish { port: reader.read_u32::<LE>()? },
             MSG_RECEIVE_CLOSE => Self::ReceiveClose { port: reader.read_u32::<LE>()? },
             MSG_RECEIVE_FINISH => Self::ReceiveFinish { port: reader.read_u32::<LE>()? },
+            MSG_PORT_IDLE_TIMEOUT => Self::PortIdleTimeout { port: reader.read_u32::<LE>()? },
             MSG_CLIENT_FINISH => Self::ClientFinish,
             MSG_LISTENER_FINISH => Self::ListenerFinish,
-            MSG_GOODBYE => Self::Goodbye,
+            MSG_GOODBYE => {
+                let reason = match reader.read_u32::<LE>() {
+                    Ok(code) => {
+                        let message = String::from_utf8(read_metadata(&mut reader)?)
+                            .map_err(|_| invalid_data("close reason"))?;
+                        Some(CloseReason::new(code, message))
+                    }
+                    Err(err) if err.kind() == ErrorKind::UnexpectedEof => None,
+                    Err(err) => return Err(err),
+                };
+                Self::Goodbye { reason }
+            }
             _ => return Err(invalid_data("invalid message id")),
         };
         Ok(msg)
@@ -363,6 +465,10 @@ pub struct ExchangedCfg {
     pub port_receive_buffer: u32,
     /// Length of connection request queue.
     pub connect_queue: u16,
+    /// Checksums of transport frames requested.
+    pub checksum: bool,
+    /// Amount of in-flight data of all ports is limited.
+    pub connection_buffer_limited: bool,
 }
 
 impl ExchangedCfg {
@@ -373,11 +479,19 @@ impl ExchangedCfg {
         writer.write_u32::<LE>(self.chunk_size)?;
         writer.write_u32::<LE>(self.port_receive_buffer)?;
         writer.write_u16::<LE>(self.connect_queue)?;
+        let mut flags = 0;
+        if self.checksum {
+            flags |= CFG_FLAG_CHECKSUM;
+        }
+        if self.connection_buffer_limited {
+            flags |= CFG_FLAG_CONNECTION_BUFFER_LIMITED;
+        }
+        writer.write_u8(flags)?;
         Ok(())
     }
 
     pub(crate) fn read(mut reader: impl io::Read) -> Result<Self, io::Error> {
-        let this = Self {
+        let mut this = Self {
             connection_timeout: match reader.read_u64::<LE>()? {
                 0 => None,
                 millis => Some(Duration::from_millis(millis)),
@@ -391,7 +505,18 @@ impl ExchangedCfg {
                 _ => return Err(invalid_data("port_receive_buffer")),
             },
             connect_queue: reader.read_u16::<LE>()?,
+            checksum: false,
+            connection_buffer_limited: false,
+        };
+
+        let flags = match reader.read_u8() {
+            Ok(flags) => flags,
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => 0,
+            Err(err) => return Err(err),
         };
+        this.checksum = flags & CFG_FLAG_CHECKSUM != 0;
+        this.connection_buffer_limited = flags & CFG_FLAG_CONNECTION_BUFFER_LIMITED != 0;
+
         Ok(this)
     }
 }
@@ -403,6 +528,8 @@ impl From<&Cfg> for ExchangedCfg {
             chunk_size: cfg.chunk_size,
             port_receive_buffer: cfg.receive_buffer,
             connect_queue: cfg.connect_queue,
+            checksum: cfg.checksum,
+            connection_buffer_limited: cfg.max_connection_buffer.is_some(),
         }
     }
 }
@@ -5,6 +5,7 @@ use futures::{
     stream::{Stream, StreamExt},
     Future, FutureExt,
 };
+use rand::{rngs::StdRng, SeedableRng};
 use std::{
     collections::{HashMap, HashSet},
     convert::TryFrom,
@@ -21,20 +22,26 @@ use std::{
     time::Duration,
 };
 use tokio::{
-    sync::{mpsc, mpsc::Permit, oneshot},
-    time::{sleep, timeout},
+    sync::{mpsc, mpsc::Permit, oneshot, Notify},
+    time::{sleep, sleep_until, timeout, Instant},
     try_join,
 };
 
 use super::{
+    checksum,
     client::{Client, ConnectRequest, ConnectResponse},
-    credit::{credit_monitor_pair, credit_send_pair, ChannelCreditMonitor, CreditProvider},
+    credit::{credit_monitor_pair, credit_send_pair, ChannelCreditMonitor, ConnectionCredits, CreditProvider},
     listener::{Listener, RemoteConnectMsg, Request},
     msg::{ExchangedCfg, MultiplexMsg},
+    negotiated::Negotiated,
     port_allocator::{PortAllocator, PortNumber},
     receiver::{PortReceiveMsg, ReceivedData, ReceivedPortRequests, Receiver},
     sender::Sender,
-    AnyStorage, Cfg, ChMuxError, PortReq, PROTOCOL_VERSION, PROTOCOL_VERSION_PORT_ID,
+    shutdown::{ShutdownHandle, TerminateReq},
+    stats::{Stats, StatsCounters},
+    AnyStorage, Cfg, ChMuxError, CloseReason, PortReq, PROTOCOL_VERSION, PROTOCOL_VERSION_CLOSE_REASON,
+    PROTOCOL_VERSION_DATA_HEADERS, PROTOCOL_VERSION_IDLE_CREDIT_RETURN, PROTOCOL_VERSION_PORT_ID,
+    PROTOCOL_VERSION_PORT_IDLE_TIMEOUT, PROTOCOL_VERSION_PORT_METADATA,
 };
 
 /// Multiplexer protocol error.
@@ -77,6 +84,10 @@ enum PortState {
         /// Remote receiver has been dropped, thus no more sent data will be processed and
         /// no port credits will be returned.
         remote_receiver_dropped: bool,
+        /// Time of last data sent or received over the port.
+        last_activity: Instant,
+        /// Port has been closed due to idle timeout.
+        idle: bool,
     },
 }
 
@@ -98,9 +109,13 @@ pub(crate) enum PortEvt {
         remote_port: u32,
         /// True if rejection due to no ports available.
         no_ports: bool,
+        /// True if rejection due to no matching route.
+        no_route: bool,
     },
     /// Send message with content.
     SendData {
+        /// Local port that sends data.
+        local_port: u32,
         /// Remote port that will receive data.
         remote_port: u32,
         /// Data to send.
@@ -109,9 +124,13 @@ pub(crate) enum PortEvt {
         first: bool,
         /// Last chunk of data.
         last: bool,
+        /// Data is a header block.
+        headers: bool,
     },
     /// Send ports.
     SendPorts {
+        /// Local port that sends ports.
+        local_port: u32,
         /// Remote port that will receive ports.
         remote_port: u32,
         /// First chunk of ports.
@@ -145,6 +164,11 @@ pub(crate) enum PortEvt {
         /// Local port.
         local_port: u32,
     },
+    /// Flush transport sink.
+    Flush {
+        /// Notified once all previously queued messages have been flushed.
+        done_tx: oneshot::Sender<()>,
+    },
 }
 
 // Global event.
@@ -159,7 +183,19 @@ enum GlobalEvt {
     /// Event from an open port.
     Port(PortEvt),
     /// Send Goodbye message.
-    SendGoodbye,
+    SendGoodbye {
+        /// Reason for closing the connection.
+        reason: Option<CloseReason>,
+    },
+    /// Start graceful shutdown.
+    Shutdown {
+        /// Time after which open ports are forcibly closed.
+        timeout: Duration,
+        /// Dropped when the multiplexer has terminated.
+        done_tx: oneshot::Sender<()>,
+    },
+    /// Check ports for idle timeout.
+    IdleCheck,
     /// Flush transport send queue.
     Flush,
 }
@@ -168,8 +204,8 @@ enum GlobalEvt {
 enum SendCmd {
     /// Send attached message.
     Send(TransportMsg),
-    /// Flush sink.
-    Flush,
+    /// Flush sink and notify the attached sender, if any.
+    Flush(Option<oneshot::Sender<()>>),
 }
 
 /// Message with optionally associated data.
@@ -204,6 +240,8 @@ pub struct ChMux<TransportSink, TransportStream> {
     remote_cfg: ExchangedCfg,
     /// Remote protocol version.
     remote_protocol_version: u8,
+    /// Negotiated connection parameters.
+    negotiated: Negotiated,
     /// Channel for connection requests from local client.
     connect_rx: Option<mpsc::UnboundedReceiver<ConnectRequest>>,
     /// Channels for connection requests from remote endpoint with wait set and not set.
@@ -218,8 +256,20 @@ pub struct ChMux<TransportSink, TransportStream> {
     channel_tx: mpsc::Sender<PortEvt>,
     /// Channel receiver of event loop.
     channel_rx: Option<mpsc::Receiver<PortEvt>>,
-    /// Force termination request.
-    terminate_rx: Option<mpsc::UnboundedReceiver<()>>,
+    /// Termination requests.
+    terminate_rx: Option<mpsc::UnboundedReceiver<TerminateReq>>,
+    /// Handle for requesting termination.
+    shutdown_handle: ShutdownHandle,
+    /// Deadline of graceful shutdown, if in progress.
+    shutdown_deadline: Option<Instant>,
+    /// Notifications for requesters of graceful shutdown, dropped on termination.
+    shutdown_done: Vec<oneshot::Sender<()>>,
+    /// Graceful shutdown timed out and open ports have been forcibly closed.
+    shutdown_forced: Arc<AtomicBool>,
+    /// Time of next check of ports for idle timeout, if enabled.
+    idle_check: Option<Instant>,
+    /// Budget for sending data shared by all ports, if limited.
+    connection_credits: Option<Arc<ConnectionCredits>>,
     /// All user clients have been dropped.
     all_clients_dropped: bool,
     /// Remote client has been dropped.
@@ -230,12 +280,16 @@ pub struct ChMux<TransportSink, TransportStream> {
     goodbye_sent: bool,
     /// Goodbye message has been received.
     goodbye_received: bool,
+    /// Reason for closing the connection received from the remote endpoint.
+    close_reason: Option<CloseReason>,
     /// Transport sender.
     transport_sink: Option<TransportSink>,
     /// Transport receiver.
     transport_stream: Option<TransportStream>,
     /// Storage.
     storage: AnyStorage,
+    /// Statistics counters.
+    stats: Arc<StatsCounters>,
 }
 
 impl<TransportSink, TransportStream> fmt::Debug for ChMux<TransportSink, TransportStream> {
@@ -271,11 +325,17 @@ where
         cfg.check();
 
         // Say hello to remote endpoint and exchange configurations.
-        let fut = Self::exchange_hello(&cfg, &mut transport_sink, &mut transport_stream);
+        let stats = Arc::new(StatsCounters::default());
+        let fut = Self::exchange_hello(&cfg, &mut transport_sink, &mut transport_stream, &stats);
         let (remote_protocol_version, remote_cfg) = match cfg.connection_timeout {
             Some(dur) => timeout(dur, fut).await.map_err(|_| ChMuxError::Timeout)??,
             None => fut.await?,
         };
+        tracing::debug!(
+            local_protocol_version = PROTOCOL_VERSION,
+            remote_protocol_version,
+            "multiplexer connection established"
+        );
 
         // Create channels.
         let (channel_tx, channel_rx) = mpsc::channel(cfg.shared_send_queue);
@@ -285,10 +345,29 @@ where
         let (terminate_tx, terminate_rx) = mpsc::unbounded_channel();
 
         // Create user objects.
-        let port_allocator = PortAllocator::new(cfg.max_ports);
+        let port_allocator = PortAllocator::new(
+            cfg.max_ports,
+            cfg.port_range_start..=cfg.port_range_end,
+            cfg.port_allocation,
+            cfg.port_allocation_seed.map(StdRng::seed_from_u64),
+        );
         let remote_listener_dropped = Arc::new(AtomicBool::new(false));
+        let negotiated = Negotiated::new(&cfg, remote_protocol_version, &remote_cfg);
+        let idle_check = cfg.idle_timeout.map(|timeout| Instant::now() + timeout);
+        // Older remote endpoints withhold the credits of a port until half of its
+        // receive buffer has been consumed, thus a port must be able to use that much.
+        let min_port_share = if remote_protocol_version >= PROTOCOL_VERSION_IDLE_CREDIT_RETURN {
+            0
+        } else {
+            remote_cfg.port_receive_buffer / 2
+        };
+        let connection_credits = cfg
+            .max_connection_buffer
+            .map(|limit| ConnectionCredits::new(limit, remote_cfg.chunk_size, min_port_share));
+        let shutdown_handle = ShutdownHandle::new(terminate_tx);
         let multiplexer = ChMux {
             remote_protocol_version,
+            negotiated: negotiated.clone(),
             local_cfg: cfg,
             remote_cfg: remote_cfg.clone(),
             connect_rx: Some(connect_rx),
@@ -299,14 +378,22 @@ where
             channel_tx,
             channel_rx: Some(channel_rx),
             terminate_rx: Some(terminate_rx),
+            shutdown_handle: shutdown_handle.clone(),
+            shutdown_deadline: None,
+            shutdown_done: Vec::new(),
+            shutdown_forced: Arc::new(AtomicBool::new(false)),
+            idle_check,
+            connection_credits,
             remote_client_dropped: false,
             remote_listener_dropped: remote_listener_dropped.clone(),
             all_clients_dropped: false,
             goodbye_sent: false,
             goodbye_received: false,
+            close_reason: None,
             transport_sink: Some(transport_sink),
             transport_stream: Some(transport_stream),
             storage: AnyStorage::new(),
+            stats: stats.clone(),
         };
 
         let client = Client::new(
@@ -314,22 +401,46 @@ where
             remote_cfg.connect_queue,
             port_allocator.clone(),
             remote_listener_dropped,
-            terminate_tx.clone(),
+            shutdown_handle.clone(),
+            stats.clone(),
+            negotiated,
         );
-        let listener = Listener::new(listen_wait_rx, listen_no_wait_rx, port_allocator, terminate_tx);
+        let listener = Listener::new(listen_wait_rx, listen_no_wait_rx, port_allocator, shutdown_handle, stats);
 
         Ok((multiplexer, client, listener))
     }
 
+    /// Returns a snapshot of the connection statistics.
+    pub fn stats(&self) -> Stats {
+        self.stats.snapshot()
+    }
+
+    /// Returns the connection parameters negotiated with the remote endpoint.
+    pub fn negotiated(&self) -> &Negotiated {
+        &self.negotiated
+    }
+
+    /// Returns a handle for terminating or gracefully shutting down the multiplexer.
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        self.shutdown_handle.clone()
+    }
+
     /// Feed transport message to sink and log it.
     #[tracing::instrument(level = "trace", skip_all, fields(msg=?msg.msg, data=?msg.data))]
     async fn feed_msg(
-        msg: TransportMsg, sink: &mut TransportSink,
+        msg: TransportMsg, sink: &mut TransportSink, checksum: bool, stats: &StatsCounters,
     ) -> Result<(), ChMuxError<TransportSinkError, TransportStreamError>> {
-        sink.feed(msg.msg.to_vec().into()).await.map_err(ChMuxError::SinkError)?;
+        let msg_data = msg.msg.to_vec();
+        let msg_data = if checksum { checksum::append(&msg_data) } else { msg_data.into() };
+        let msg_len = msg_data.len();
+        sink.feed(msg_data).await.map_err(ChMuxError::SinkError)?;
+        stats.sent(msg_len);
 
         if let Some(data) = msg.data {
+            let data = if checksum { checksum::append(&data) } else { data };
+            let data_len = data.len();
             sink.feed(data).await.map_err(ChMuxError::SinkError)?;
+            stats.sent(data_len);
         }
 
         Ok(())
@@ -337,26 +448,42 @@ where
 
     /// Flush sink and log it.
     #[tracing::instrument(level = "trace", skip_all)]
-    async fn flush(sink: &mut TransportSink) -> Result<(), ChMuxError<TransportSinkError, TransportStreamError>> {
-        sink.flush().await.map_err(ChMuxError::SinkError)
+    async fn flush(
+        sink: &mut TransportSink, stats: &StatsCounters,
+    ) -> Result<(), ChMuxError<TransportSinkError, TransportStreamError>> {
+        sink.flush().await.map_err(ChMuxError::SinkError)?;
+        stats.flushed();
+        Ok(())
     }
 
     /// Receive message and log it.
     #[tracing::instrument(level = "trace", skip_all, fields(msg, data))]
     async fn recv_msg(
-        stream: &mut TransportStream,
+        stream: &mut TransportStream, checksum: bool, stats: &StatsCounters,
     ) -> Result<TransportMsg, ChMuxError<TransportSinkError, TransportStreamError>> {
+        let verify = |frame: Bytes| {
+            if checksum {
+                checksum::verify(frame).ok_or(ChMuxError::Corrupted)
+            } else {
+                Ok(frame)
+            }
+        };
+
         let msg_data = match stream.next().await {
             Some(Ok(msg_data)) => msg_data,
             Some(Err(err)) => return Err(ChMuxError::StreamError(err)),
             None => return Err(ChMuxError::StreamClosed),
         };
+        stats.received(msg_data.len());
 
-        let msg = MultiplexMsg::from_slice(&msg_data)?;
+        let msg = MultiplexMsg::from_slice(&verify(msg_data)?)?;
 
         let data = if let MultiplexMsg::Data { .. } = &msg {
             match stream.next().await {
-                Some(Ok(data)) => Some(data),
+                Some(Ok(data)) => {
+                    stats.received(data.len());
+                    Some(verify(data)?)
+                }
                 Some(Err(err)) => return Err(ChMuxError::StreamError(err)),
                 None => return Err(ChMuxError::StreamClosed),
             }
@@ -375,25 +502,27 @@ where
     /// Exchange Hello message with remote endpoint.
     #[tracing::instrument(level = "trace", skip_all)]
     async fn exchange_hello(
-        cfg: &Cfg, sink: &mut TransportSink, stream: &mut TransportStream,
+        cfg: &Cfg, sink: &mut TransportSink, stream: &mut TransportStream, stats: &StatsCounters,
     ) -> Result<(u8, ExchangedCfg), ChMuxError<TransportSinkError, TransportStreamError>> {
         // Say hello to remote endpoint and send our configuration.
         let send_task = async {
-            Self::feed_msg(TransportMsg::new(MultiplexMsg::Reset), sink).await?;
-            Self::flush(sink).await?;
+            Self::feed_msg(TransportMsg::new(MultiplexMsg::Reset), sink, false, stats).await?;
+            Self::flush(sink, stats).await?;
             Self::feed_msg(
                 TransportMsg::new(MultiplexMsg::Hello { version: PROTOCOL_VERSION, cfg: cfg.into() }),
                 sink,
+                false,
+                stats,
             )
             .await?;
-            Self::flush(sink).await?;
+            Self::flush(sink, stats).await?;
             Ok(())
         };
 
         // Receive hello and configuration from remote endpoint.
         let recv_task = async {
             loop {
-                match Self::recv_msg(stream).await {
+                match Self::recv_msg(stream, false, stats).await {
                     Ok(TransportMsg { msg: MultiplexMsg::Hello { version, cfg }, .. }) => {
                         break Ok((version, cfg))
                     }
@@ -420,6 +549,13 @@ where
         terminate &= self.listen_tx.is_none() || self.remote_client_dropped;
         // No remote port requests are outstanding.
         terminate &= self.outstanding_remote_port_requests.is_empty();
+        // During graceful shutdown we only wait for all ports to be closed.
+        terminate |= self.shutdown_deadline.is_some()
+            && self.ports.is_empty()
+            && self.outstanding_remote_port_requests.is_empty();
+        // If graceful shutdown timed out, we request connection termination
+        // even with still connected ports.
+        terminate |= self.shutdown_forced.load(Ordering::SeqCst);
         // If goodbye has been sent, we request connection termination,
         // possibly even with still connected ports.
         terminate |= self.goodbye_sent;
@@ -440,12 +576,19 @@ where
         let local_port_num = *local_port;
 
         let sender_tx = self.channel_tx.clone();
-        let (sender_credit_provider, sender_credit_user) = credit_send_pair(self.remote_cfg.port_receive_buffer);
+        let (sender_credit_provider, sender_credit_user) = credit_send_pair(
+            self.remote_cfg.port_receive_buffer,
+            self.connection_credits.clone(),
+            self.shutdown_forced.clone(),
+        );
 
         let receiver_tx = self.channel_tx.clone();
         let (receiver_tx_data, receiver_rx_data) = mpsc::unbounded_channel();
-        let (receiver_credit_monitor, receiver_credit_returner) =
-            credit_monitor_pair(self.local_cfg.receive_buffer);
+        let (receiver_credit_monitor, receiver_credit_returner) = credit_monitor_pair(
+            self.local_cfg.receive_buffer,
+            self.local_cfg.credit_return_threshold,
+            self.remote_cfg.connection_buffer_limited,
+        );
 
         let hangup_notify = Arc::new(std::sync::Mutex::new(Some(Vec::new())));
         let hangup_recved = Arc::new(AtomicBool::new(false));
@@ -463,18 +606,22 @@ where
                 receiver_dropped: false,
                 sender_dropped: false,
                 remote_receiver_dropped: false,
+                last_activity: Instant::now(),
+                idle: false,
             },
         ) {
             panic!(
                 "create_port called for local port {local_port_num} already connected to remote port {remote_port}"
             );
         }
+        self.stats.port_opened();
 
         let sender = Sender::new(
             local_port_num,
             remote_port,
             self.remote_cfg.chunk_size as usize,
             self.local_cfg.max_data_size,
+            self.remote_protocol_version >= PROTOCOL_VERSION_DATA_HEADERS,
             sender_tx,
             sender_credit_user,
             Arc::downgrade(&hangup_recved),
@@ -493,6 +640,7 @@ where
             receiver_credit_returner,
             self.port_allocator.clone(),
             self.storage.clone(),
+            self.shutdown_forced.clone(),
         );
 
         (sender, receiver)
@@ -530,14 +678,102 @@ where
         if free {
             tracing::trace!(local_port, "freed port");
             self.ports.remove(&local_port);
+            self.stats.port_closed();
+        }
+    }
+
+    /// Records that data has been sent over a port for idle timeout tracking.
+    fn port_activity(&mut self, local_port: u32) {
+        if self.local_cfg.idle_timeout.is_some() {
+            if let Some(PortState::Connected { last_activity, .. }) = self.ports.get_mut(&local_port) {
+                *last_activity = Instant::now();
+            }
+        }
+    }
+
+    /// Finds a port that has been idle for longer than the idle timeout
+    /// and schedules the next check.
+    ///
+    /// Returns the local and remote port number of the idle port.
+    fn find_idle_port(&mut self) -> Option<(u32, u32)> {
+        let idle_timeout = self.local_cfg.idle_timeout?;
+        let now = Instant::now();
+
+        let mut next_check = now + idle_timeout;
+        let mut idle_port = None;
+        for (local_port, state) in &self.ports {
+            if let PortState::Connected {
+                remote_port,
+                last_activity,
+                idle: false,
+                sender_dropped,
+                receiver_dropped,
+                ..
+            } = state
+            {
+                // Ports that have been dropped locally are not affected.
+                if *sender_dropped && *receiver_dropped {
+                    continue;
+                }
+
+                let deadline = *last_activity + idle_timeout;
+                if deadline <= now && idle_port.is_none() {
+                    idle_port = Some((**local_port, *remote_port));
+                } else {
+                    next_check = next_check.min(deadline);
+                }
+            }
+        }
+
+        self.idle_check = Some(next_check);
+        idle_port
+    }
+
+    /// Closes a port due to idle timeout.
+    ///
+    /// The local sender and receiver fail with an idle timeout error.
+    /// The port is released once they have been dropped on both endpoints.
+    fn close_idle_port(&mut self, local_port: u32) {
+        if let Some(PortState::Connected {
+            receiver_tx_data,
+            sender_credit_provider,
+            remote_receiver_closed,
+            remote_receiver_closed_notify,
+            idle,
+            ..
+        }) = self.ports.get_mut(&local_port)
+        {
+            *idle = true;
+
+            // Fail local receiver after already received data.
+            if let Some(receiver_tx_data) = receiver_tx_data {
+                let _ = receiver_tx_data.send(PortReceiveMsg::IdleTimeout);
+            }
+
+            // Fail local sender.
+            sender_credit_provider.idle_timeout();
+            if !remote_receiver_closed.load(Ordering::SeqCst) {
+                remote_receiver_closed.store(true, Ordering::SeqCst);
+                let notifies = remote_receiver_closed_notify.lock().unwrap().take().unwrap();
+                for tx in notifies {
+                    let _ = tx.send(());
+                }
+            }
+        } else {
+            panic!("close_idle_port called for port {local_port} not in connected state");
         }
     }
 
     /// Sends data over the transport sink.
     ///
     /// Automatically sends pings if no data is to be transmitted.
+    /// If a ping timeout is specified, pings are sent regardless of other data
+    /// and the connection fails when the remote endpoint does not respond in time.
+    #[allow(clippy::too_many_arguments)]
     async fn send_task(
-        mut sink: &mut TransportSink, ping_interval: Option<Duration>, mut rx: mpsc::Receiver<SendCmd>,
+        mut sink: &mut TransportSink, ping_interval: Option<Duration>, ping_timeout: Option<Duration>,
+        pong_due: &Notify, pong_received: &Notify, mut rx: mpsc::Receiver<SendCmd>, checksum: bool,
+        stats: &StatsCounters,
     ) -> Result<(), ChMuxError<TransportSinkError, TransportStreamError>> {
         async fn get_next_ping(ping_interval: Option<Duration>) {
             match ping_interval {
@@ -546,50 +782,87 @@ where
             }
         }
 
-        let mut next_ping = get_next_ping(ping_interval).fuse().boxed();
+        // Fails when a sent ping is not answered by a pong in time, even if the sink is blocked.
+        let ping_sent = Notify::new();
+        let ping_watchdog = async {
+            let Some(ping_timeout) = ping_timeout else { return future::pending().await };
+            loop {
+                ping_sent.notified().await;
+                if timeout(ping_timeout, pong_received.notified()).await.is_err() {
+                    return ChMuxError::Timeout;
+                }
+            }
+        };
+
+        let send_loop = async {
+            let mut next_ping = get_next_ping(ping_interval).fuse().boxed();
 
-        loop {
-            SinkReady::new(&mut sink).await.map_err(ChMuxError::SinkError)?;
+            loop {
+                SinkReady::new(&mut sink).await.map_err(ChMuxError::SinkError)?;
 
-            tokio::select! {
-                biased;
+                tokio::select! {
+                    biased;
 
-                cmd_opt = rx.recv() => {
-                    match cmd_opt {
-                        Some(SendCmd::Send (msg)) => {
-                            let is_goodbye = matches!(&msg, TransportMsg {msg: MultiplexMsg::Goodbye, ..});
-                            Self::feed_msg(msg, sink).await?;
-                            if is_goodbye {
-                                break;
-                            }
+                    () = pong_due.notified() => {
+                        Self::feed_msg(TransportMsg::new(MultiplexMsg::Pong), sink, checksum, stats).await?;
+                        Self::flush(sink, stats).await?;
+                    }
 
-                            next_ping = get_next_ping(ping_interval).fuse().boxed();
+                    cmd_opt = rx.recv() => {
+                        match cmd_opt {
+                            Some(SendCmd::Send (msg)) => {
+                                let is_goodbye = matches!(&msg, TransportMsg {msg: MultiplexMsg::Goodbye { .. } | MultiplexMsg::Reset, ..});
+                                Self::feed_msg(msg, sink, checksum, stats).await?;
+                                if is_goodbye {
+                                    break;
+                                }
+
+                                if ping_timeout.is_none() {
+                                    next_ping = get_next_ping(ping_interval).fuse().boxed();
+                                }
+                            }
+                            Some(SendCmd::Flush(done_tx)) => {
+                                Self::flush(sink, stats).await?;
+                                if let Some(done_tx) = done_tx {
+                                    let _ = done_tx.send(());
+                                }
+                            }
+                            None => break,
                         }
-                        Some(SendCmd::Flush) => Self::flush(sink).await?,
-                        None => break,
                     }
-                }
 
-                () = &mut next_ping => {
-                    Self::feed_msg(TransportMsg::new(MultiplexMsg::Ping), sink).await?;
-                    Self::flush(sink).await?;
-                    next_ping = get_next_ping(ping_interval).fuse().boxed();
+                    () = &mut next_ping => {
+                        ping_sent.notify_one();
+                        Self::feed_msg(TransportMsg::new(MultiplexMsg::Ping), sink, checksum, stats).await?;
+                        Self::flush(sink, stats).await?;
+                        next_ping = get_next_ping(ping_interval).fuse().boxed();
+                    }
                 }
             }
-        }
 
-        // Flushing may fail after Goodbye message has been sent, because the remote
-        // endpoint may immediately close the connection.
-        let _ = Self::flush(sink).await;
+            // Flushing may fail after Goodbye message has been sent, because the remote
+            // endpoint may immediately close the connection.
+            let _ = Self::flush(sink, stats).await;
 
-        Ok(())
+            Ok(())
+        };
+
+        tokio::select! {
+            res = send_loop => res,
+            err = ping_watchdog => Err(err),
+        }
     }
 
     /// Receives data over the transport sink.
     ///
     /// Watches the connection timeout.
+    /// Notifies the send task about received pings, if they should be responded to,
+    /// and about received pongs.
+    #[allow(clippy::too_many_arguments)]
     async fn recv_task(
-        stream: &mut TransportStream, connection_timeout: Option<Duration>, tx: mpsc::Sender<TransportMsg>,
+        stream: &mut TransportStream, connection_timeout: Option<Duration>, respond_to_pings: bool,
+        pong_due: &Notify, pong_received: &Notify, tx: mpsc::Sender<TransportMsg>, checksum: bool,
+        stats: &StatsCounters,
     ) -> Result<(), ChMuxError<TransportSinkError, TransportStreamError>> {
         async fn get_connection_timeout(connection_timeout: Option<Duration>) {
             match connection_timeout {
@@ -603,9 +876,14 @@ where
             tokio::select! {
                 biased;
 
-                msg = Self::recv_msg(stream) => {
+                msg = Self::recv_msg(stream, checksum, stats) => {
                     let msg = msg?;
-                    let is_goodbye = matches!(&msg, TransportMsg {msg: MultiplexMsg::Goodbye, ..});
+                    match &msg.msg {
+                        MultiplexMsg::Ping if respond_to_pings => pong_due.notify_one(),
+                        MultiplexMsg::Pong => pong_received.notify_one(),
+                        _ => (),
+                    }
+                    let is_goodbye = matches!(&msg, TransportMsg {msg: MultiplexMsg::Goodbye { .. }, ..});
                     tx_permit.send(msg);
                     if is_goodbye {
                         break;
@@ -625,21 +903,64 @@ where
     ///
     /// The dispatcher terminates when the client, server and all channels have been dropped or
     /// the transport is closed.
+    /// If the remote endpoint closed the connection with a [reason](CloseReason),
+    /// [ChMuxError::Closed] is returned.
     #[tracing::instrument(level = "trace", skip_all)]
-    pub async fn run(mut self) -> Result<(), ChMuxError<TransportSinkError, TransportStreamError>> {
+    pub async fn run(self) -> Result<(), ChMuxError<TransportSinkError, TransportStreamError>> {
+        let stats = self.stats.clone();
+        let result = self.dispatch().await;
+
+        let Stats { sent_bytes, received_bytes, .. } = stats.snapshot();
+        match &result {
+            Ok(()) => tracing::debug!(sent_bytes, received_bytes, "multiplexer connection closed"),
+            Err(err) => tracing::debug!(%err, sent_bytes, received_bytes, "multiplexer connection failed"),
+        }
+
+        result
+    }
+
+    /// Dispatches messages until the multiplexer terminates.
+    async fn dispatch(mut self) -> Result<(), ChMuxError<TransportSinkError, TransportStreamError>> {
         let mut transport_sink = self.transport_sink.take().unwrap();
         let mut transport_stream = self.transport_stream.take().unwrap();
 
         // Create send over transport task.
         let (send_tx, send_rx) = mpsc::channel(self.local_cfg.transport_send_queue);
-        let send_task =
-            Self::send_task(&mut transport_sink, self.remote_cfg.connection_timeout.map(|d| d / 2), send_rx)
-                .fuse();
+        let ping_interval =
+            match (self.remote_cfg.connection_timeout.map(|d| d / 2), self.local_cfg.ping_interval) {
+                (Some(remote), Some(local)) => Some(remote.min(local)),
+                (remote, local) => remote.or(local),
+            };
+        let ping_timeout = self.local_cfg.ping_timeout.filter(|_| self.negotiated.ping_timeout);
+        let (pong_due, pong_received) = (Notify::new(), Notify::new());
+        let stats = self.stats.clone();
+        let checksum = self.negotiated.checksum;
+        let send_task = Self::send_task(
+            &mut transport_sink,
+            ping_interval,
+            ping_timeout,
+            &pong_due,
+            &pong_received,
+            send_rx,
+            checksum,
+            &stats,
+        )
+        .fuse();
         pin_mut!(send_task);
 
         // Create receive over transport task.
         let (recv_tx, mut recv_rx) = mpsc::channel(self.local_cfg.transport_receive_queue);
-        let recv_task = Self::recv_task(&mut transport_stream, self.local_cfg.connection_timeout, recv_tx).fuse();
+        let recv_task = Self::recv_task(
+            &mut transport_stream,
+            self.local_cfg.connection_timeout,
+            self.negotiated.ping_timeout,
+            &pong_due,
+            &pong_received,
+            recv_tx,
+            checksum,
+            &stats,
+        )
+        .fuse();
         pin_mut!(recv_task);
 
         // Setup channels.
@@ -649,7 +970,10 @@ where
         let mut flushed = false;
         let mut send_task_ended = false;
 
-        while !(self.goodbye_sent && self.goodbye_received && send_task_ended) {
+        while !(self.goodbye_sent
+            && (self.goodbye_received || self.shutdown_forced.load(Ordering::SeqCst))
+            && send_task_ended)
+        {
             let send_prep_task = async {
                 // Obtain permit to ensure that space is available in transport send queue.
                 let permit = match send_tx.reserve().await {
@@ -686,14 +1010,26 @@ where
                         GlobalEvt::Port(msg)
                     },
 
-                    // Local request to terminate forcibly.
-                    Some(()) = terminate_rx.recv(), if !self.goodbye_sent => {
-                        GlobalEvt::SendGoodbye
+                    // Local request to terminate forcibly or to shut down gracefully.
+                    Some(req) = terminate_rx.recv(), if !self.goodbye_sent => {
+                        match req {
+                            TerminateReq::Forced { reason } => GlobalEvt::SendGoodbye { reason },
+                            TerminateReq::Graceful { timeout, done_tx } => GlobalEvt::Shutdown { timeout, done_tx },
+                        }
                     }
 
                     // Send Goodbye message and terminate.
                     () = future::ready(()), if self.should_terminate() && !self.goodbye_sent => {
-                        GlobalEvt::SendGoodbye
+                        GlobalEvt::SendGoodbye { reason: None }
+                    }
+
+                    // Check ports for idle timeout.
+                    () = async { match self.idle_check {
+                        Some(idle_check) => sleep_until(idle_check).await,
+                        None => future::pending().await,
+                    }} => {
+                        flushed = false;
+                        GlobalEvt::IdleCheck
                     }
 
                     // Flush transport sink if no requests are queued.
@@ -723,10 +1059,22 @@ where
 
                 // Receive task failed.
                 Err(err) = &mut recv_task => return Err(err),
+
+                // Graceful shutdown timed out.
+                () = async { match self.shutdown_deadline {
+                    Some(deadline) => sleep_until(deadline).await,
+                    None => future::pending().await,
+                }}, if !self.shutdown_forced.load(Ordering::SeqCst) => {
+                    tracing::debug!(open_ports = self.ports.len(), "graceful shutdown timed out");
+                    self.shutdown_forced.store(true, Ordering::SeqCst);
+                }
             }
         }
 
-        Ok(())
+        match self.close_reason.take() {
+            Some(reason) => Err(ChMuxError::Closed(reason)),
+            None => Ok(()),
+        }
     }
 
     /// Handle local event that results in sending a message to the remote endpoint.
@@ -741,16 +1089,26 @@ where
 
         match event {
             // Process local connect request.
-            GlobalEvt::ConnectReq(ConnectRequest { local_port, id, sent_tx: _sent_tx, response_tx, wait }) => {
-                if !self.remote_listener_dropped.load(Ordering::SeqCst) {
+            GlobalEvt::ConnectReq(ConnectRequest {
+                local_port,
+                id,
+                metadata,
+                sent_tx: _sent_tx,
+                response_tx,
+                wait,
+            }) => {
+                if !self.remote_listener_dropped.load(Ordering::SeqCst) && self.shutdown_deadline.is_none() {
                     let local_port_num = *local_port;
                     if self.ports.insert(local_port, PortState::Connecting { response_tx }).is_some() {
                         panic!("ConnectRequest for already used local port {local_port_num}");
                     }
                     let id = (self.remote_protocol_version >= PROTOCOL_VERSION_PORT_ID).then_some(id);
-                    send_msg(permit, MultiplexMsg::OpenPort { client_port: local_port_num, wait, id });
+                    let metadata = (self.remote_protocol_version >= PROTOCOL_VERSION_PORT_METADATA
+                        && !metadata.is_empty())
+                    .then_some(metadata);
+                    send_msg(permit, MultiplexMsg::OpenPort { client_port: local_port_num, wait, id, metadata });
                 } else {
-                    let _ = response_tx.send(ConnectResponse::Rejected { no_ports: false });
+                    let _ = response_tx.send(ConnectResponse::Rejected { no_ports: false, no_route: false });
                 }
             }
 
@@ -759,6 +1117,13 @@ where
                 if !self.outstanding_remote_port_requests.remove(&remote_port) {
                     panic!("Accepted non-outstanding remote port {remote_port} request");
                 }
+                if self.shutdown_deadline.is_some() {
+                    send_msg(
+                        permit,
+                        MultiplexMsg::Rejected { client_port: remote_port, no_ports: false, no_route: false },
+                    );
+                    return Ok(());
+                }
                 let local_port_num = *local_port;
                 send_msg(
                     permit,
@@ -769,25 +1134,30 @@ where
             }
 
             // Remote connect request was rejected by local listener.
-            GlobalEvt::Port(PortEvt::Rejected { remote_port, no_ports }) => {
+            GlobalEvt::Port(PortEvt::Rejected { remote_port, no_ports, no_route }) => {
                 if !self.outstanding_remote_port_requests.remove(&remote_port) {
                     panic!("Rejected non-outstanding remote port {remote_port} request");
                 }
-                send_msg(permit, MultiplexMsg::Rejected { client_port: remote_port, no_ports });
+                send_msg(permit, MultiplexMsg::Rejected { client_port: remote_port, no_ports, no_route });
             }
 
             // Send data from port.
-            GlobalEvt::Port(PortEvt::SendData { remote_port, data, first, last }) => {
-                let msg = MultiplexMsg::Data { port: remote_port, first, last };
+            GlobalEvt::Port(PortEvt::SendData { local_port, remote_port, data, first, last, headers }) => {
+                self.port_activity(local_port);
+                let msg = MultiplexMsg::Data { port: remote_port, first, last, headers };
                 tracing::trace!(op="send", msg=?msg, data=?&data);
                 permit.send(SendCmd::Send(TransportMsg::with_data(msg, data)));
             }
 
             // Send ports from port.
-            GlobalEvt::Port(PortEvt::SendPorts { remote_port, ports, first, last, wait }) => {
+            GlobalEvt::Port(PortEvt::SendPorts { local_port, remote_port, ports, first, last, wait }) => {
+                self.port_activity(local_port);
                 let mut port_nums = Vec::new();
                 let mut ids = (self.remote_protocol_version >= PROTOCOL_VERSION_PORT_ID).then_some(Vec::new());
-                for (PortReq { port, id }, response_tx) in ports {
+                let mut metadata = (self.remote_protocol_version >= PROTOCOL_VERSION_PORT_METADATA
+                    && ports.iter().any(|(req, _)| !req.metadata.is_empty()))
+                .then_some(Vec::new());
+                for (PortReq { port, id, metadata: port_metadata }, response_tx) in ports {
                     let port_num = *port;
                     if self.ports.insert(port, PortState::Connecting { response_tx }).is_some() {
                         panic!("SendPorts with already used local port {port_num}");
@@ -796,10 +1166,21 @@ where
                     if let Some(ids) = &mut ids {
                         ids.push(id);
                     }
+                    if let Some(metadata) = &mut metadata {
+                        metadata.push(port_metadata);
+                    }
                 }
                 send_msg(
                     permit,
-                    MultiplexMsg::PortData { port: remote_port, first, last, wait, ports: port_nums, ids },
+                    MultiplexMsg::PortData {
+                        port: remote_port,
+                        first,
+                        last,
+                        wait,
+                        ports: port_nums,
+                        ids,
+                        metadata,
+                    },
                 );
             }
 
@@ -842,6 +1223,11 @@ where
                 }
             }
 
+            // Flush requested by local port.
+            GlobalEvt::Port(PortEvt::Flush { done_tx }) => {
+                permit.send(SendCmd::Flush(Some(done_tx)));
+            }
+
             // Local port receiver has been dropped.
             // No port credits can be returned afterwards.
             GlobalEvt::Port(PortEvt::ReceiverDropped { local_port }) => match self.ports.get_mut(&local_port) {
@@ -870,15 +1256,47 @@ where
                 send_msg(permit, MultiplexMsg::ListenerFinish);
             }
 
+            // Start graceful shutdown.
+            GlobalEvt::Shutdown { timeout, done_tx } => {
+                let deadline = Instant::now() + timeout;
+                self.shutdown_deadline = Some(match self.shutdown_deadline {
+                    Some(prev) => prev.min(deadline),
+                    None => deadline,
+                });
+                self.shutdown_done.push(done_tx);
+
+                // Stop accepting connect requests from remote endpoint.
+                if self.listen_tx.take().is_some() {
+                    send_msg(permit, MultiplexMsg::ListenerFinish);
+                }
+            }
+
             // Send Goodbye message.
-            GlobalEvt::SendGoodbye => {
+            GlobalEvt::SendGoodbye { reason } => {
                 self.goodbye_sent = true;
-                send_msg(permit, MultiplexMsg::Goodbye);
+                if self.shutdown_forced.load(Ordering::SeqCst) && !self.ports.is_empty() {
+                    // Graceful shutdown timed out with ports still open.
+                    send_msg(permit, MultiplexMsg::Reset);
+                } else {
+                    let reason = reason.filter(|_| self.remote_protocol_version >= PROTOCOL_VERSION_CLOSE_REASON);
+                    send_msg(permit, MultiplexMsg::Goodbye { reason });
+                }
+            }
+
+            // Close port that has been idle for too long.
+            GlobalEvt::IdleCheck => {
+                if let Some((local_port, remote_port)) = self.find_idle_port() {
+                    tracing::debug!(local_port, "port idle timeout");
+                    self.close_idle_port(local_port);
+                    if self.remote_protocol_version >= PROTOCOL_VERSION_PORT_IDLE_TIMEOUT {
+                        send_msg(permit, MultiplexMsg::PortIdleTimeout { port: remote_port });
+                    }
+                }
             }
 
             // Flush transport sink.
             GlobalEvt::Flush => {
-                permit.send(SendCmd::Flush);
+                permit.send(SendCmd::Flush(None));
             }
         }
         Ok(())
@@ -904,11 +1322,11 @@ where
                 ));
             }
 
-            //  Nothing to do for ping message.
-            MultiplexMsg::Ping => (),
+            //  Nothing to do for ping and pong messages, they are handled by the transport tasks.
+            MultiplexMsg::Ping | MultiplexMsg::Pong => (),
 
             // Open port request from remote endpoint.
-            MultiplexMsg::OpenPort { client_port, wait, id } => {
+            MultiplexMsg::OpenPort { client_port, wait, id, metadata } => {
                 if !self.outstanding_remote_port_requests.insert(client_port) {
                     return Err(protocol_err(format!(
                         "remote endpoint sent OpenPort request for same remote port {client_port} twice"
@@ -917,6 +1335,7 @@ where
                 let req = RemoteConnectMsg::Request(Request::new(
                     client_port,
                     id.unwrap_or(client_port),
+                    metadata.unwrap_or_default(),
                     wait,
                     self.port_allocator.clone(),
                     self.channel_tx.clone(),
@@ -944,9 +1363,9 @@ where
             }
 
             // Port open rejected response from remote endpoint.
-            MultiplexMsg::Rejected { client_port, no_ports } => {
+            MultiplexMsg::Rejected { client_port, no_ports, no_route } => {
                 if let Some(PortState::Connecting { response_tx }) = self.ports.remove(&client_port) {
-                    let _ = response_tx.send(ConnectResponse::Rejected { no_ports });
+                    let _ = response_tx.send(ConnectResponse::Rejected { no_ports, no_route });
                 } else {
                     return Err(protocol_err(format!(
                         "received Rejected message for port {client_port} not in connecting state"
@@ -955,13 +1374,15 @@ where
             }
 
             // Data from remote endpoint.
-            MultiplexMsg::Data { port, first, last } => {
+            MultiplexMsg::Data { port, first, last, headers } => {
                 if let Some(PortState::Connected {
                     receiver_tx_data: Some(receiver_tx_data),
                     receiver_credit_monitor,
+                    last_activity,
                     ..
                 }) = self.ports.get_mut(&port)
                 {
+                    *last_activity = Instant::now();
                     let data = data.unwrap();
                     let used_credit = match u32::try_from(data.len()) {
                         Ok(size) if size <= self.local_cfg.chunk_size => {
@@ -978,6 +1399,7 @@ where
                         buf: data,
                         first,
                         last,
+                        headers,
                         credit: used_credit,
                     }));
                 } else {
@@ -989,13 +1411,15 @@ where
             }
 
             // Ports from remote endpoint.
-            MultiplexMsg::PortData { port, first, last, wait, ports, ids } => {
+            MultiplexMsg::PortData { port, first, last, wait, ports, ids, metadata } => {
                 if let Some(PortState::Connected {
                     receiver_tx_data: Some(receiver_tx_data),
                     receiver_credit_monitor,
+                    last_activity,
                     ..
                 }) = self.ports.get_mut(&port)
                 {
+                    *last_activity = Instant::now();
                     for port in &ports {
                         if !self.outstanding_remote_port_requests.insert(*port) {
                             return Err(protocol_err(format!(
@@ -1020,11 +1444,20 @@ where
                     let port_allocator = self.port_allocator.clone();
                     let channel_tx = self.channel_tx.clone();
                     let ids = ids.unwrap_or_else(|| ports.clone());
+                    let metadata = metadata.unwrap_or_else(|| vec![Vec::new(); ports.len()]);
                     let requests = ports
                         .into_iter()
                         .zip(ids)
-                        .map(|(remote_port, id)| {
-                            Request::new(remote_port, id, wait, port_allocator.clone(), channel_tx.clone())
+                        .zip(metadata)
+                        .map(|((remote_port, id), metadata)| {
+                            Request::new(
+                                remote_port,
+                                id,
+                                metadata,
+                                wait,
+                                port_allocator.clone(),
+                                channel_tx.clone(),
+                            )
                         })
                         .collect();
                     let _ = receiver_tx_data.send(PortReceiveMsg::PortRequests(ReceivedPortRequests {
@@ -1080,6 +1513,7 @@ where
                     sender_credit_provider,
                     remote_receiver_closed_notify,
                     remote_receiver_closed,
+                    idle,
                     ..
                 }) = self.ports.get_mut(&port)
                 {
@@ -1095,7 +1529,7 @@ where
                         }
 
                         self.maybe_free_port(port);
-                    } else {
+                    } else if !*idle {
                         return Err(protocol_err(format!(
                             "received more than one ReceiveClose message for port {}",
                             &port
@@ -1141,6 +1575,19 @@ where
                 }
             }
 
+            // Remote endpoint closed port due to idle timeout.
+            MultiplexMsg::PortIdleTimeout { port } => match self.ports.get(&port) {
+                Some(PortState::Connected { idle: false, .. }) => self.close_idle_port(port),
+                // Port timed out on both endpoints simultaneously.
+                Some(PortState::Connected { idle: true, .. }) => (),
+                _ => {
+                    return Err(protocol_err(format!(
+                        "received PortIdleTimeout message for port {} not in connected state",
+                        &port
+                    )))
+                }
+            },
+
             // Remote endpoint will send no more connect requests.
             MultiplexMsg::ClientFinish => {
                 if let Some((listen_wait_tx, listen_no_wait_tx)) = &self.listen_tx {
@@ -1173,8 +1620,9 @@ where
             }
 
             // Remote endpoint terminates connection.
-            MultiplexMsg::Goodbye => {
+            MultiplexMsg::Goodbye { reason } => {
                 self.goodbye_received = true;
+                self.close_reason = reason;
             }
         }
 
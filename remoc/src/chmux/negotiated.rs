@@ -0,0 +1,84 @@
+//! Connection parameters negotiated during the handshake.
+
+use std::time::Duration;
+
+use super::{
+    msg::ExchangedCfg, Cfg, PROTOCOL_VERSION, PROTOCOL_VERSION_CHECKSUM, PROTOCOL_VERSION_CLOSE_REASON,
+    PROTOCOL_VERSION_PONG, PROTOCOL_VERSION_PORT_ID, PROTOCOL_VERSION_PORT_IDLE_TIMEOUT,
+    PROTOCOL_VERSION_PORT_METADATA,
+};
+
+/// Connection parameters agreed upon with the remote endpoint during the handshake.
+///
+/// This is obtained by calling `negotiated` on the [ChMux](super::ChMux),
+/// [Client](super::Client) or [Connect](crate::Connect).
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct Negotiated {
+    /// Protocol version of the local endpoint.
+    pub local_protocol_version: u8,
+    /// Protocol version of the remote endpoint.
+    pub remote_protocol_version: u8,
+    /// Size in bytes of chunks sent to the remote endpoint.
+    ///
+    /// This is the chunk size configured by the remote endpoint.
+    pub send_chunk_size: u32,
+    /// Maximum size in bytes of chunks received from the remote endpoint.
+    ///
+    /// This is the locally configured chunk size.
+    pub recv_chunk_size: u32,
+    /// Size in bytes of the receive buffer of each remote port.
+    pub remote_port_receive_buffer: u32,
+    /// Length of the connection request queue of the remote endpoint.
+    pub remote_connect_queue: u16,
+    /// Maximum number of open local ports.
+    ///
+    /// The port limit of the remote endpoint is not exchanged.
+    pub max_ports: u32,
+    /// Time after which the remote endpoint closes the connection when no data is received.
+    pub remote_connection_timeout: Option<Duration>,
+    /// Whether port ids are supported by both endpoints.
+    pub port_ids: bool,
+    /// Whether port request metadata is supported by both endpoints.
+    pub port_metadata: bool,
+    /// Whether transmitting a close reason is supported by both endpoints.
+    pub close_reason: bool,
+    /// Whether notifying about idle port timeouts is supported by both endpoints.
+    pub port_idle_timeout: bool,
+    /// Whether transport frames are protected by checksums.
+    ///
+    /// This is the case if either endpoint [enabled checksums](Cfg::checksum) and both
+    /// endpoints support them.
+    pub checksum: bool,
+    /// Whether the remote endpoint responds to pings, making the
+    /// [ping timeout](Cfg::ping_timeout) effective.
+    pub ping_timeout: bool,
+}
+
+impl Negotiated {
+    pub(crate) fn new(local_cfg: &Cfg, remote_protocol_version: u8, remote_cfg: &ExchangedCfg) -> Self {
+        Self {
+            local_protocol_version: PROTOCOL_VERSION,
+            remote_protocol_version,
+            send_chunk_size: remote_cfg.chunk_size,
+            recv_chunk_size: local_cfg.chunk_size,
+            remote_port_receive_buffer: remote_cfg.port_receive_buffer,
+            remote_connect_queue: remote_cfg.connect_queue,
+            max_ports: local_cfg.max_ports,
+            remote_connection_timeout: remote_cfg.connection_timeout,
+            port_ids: remote_protocol_version >= PROTOCOL_VERSION_PORT_ID,
+            port_metadata: remote_protocol_version >= PROTOCOL_VERSION_PORT_METADATA,
+            close_reason: remote_protocol_version >= PROTOCOL_VERSION_CLOSE_REASON,
+            port_idle_timeout: remote_protocol_version >= PROTOCOL_VERSION_PORT_IDLE_TIMEOUT,
+            checksum: (local_cfg.checksum || remote_cfg.checksum)
+                && remote_protocol_version >= PROTOCOL_VERSION_CHECKSUM,
+            ping_timeout: remote_protocol_version >= PROTOCOL_VERSION_PONG,
+        }
+    }
+
+    /// Protocol version used for communication, i.e. the lower version of both endpoints.
+    pub fn protocol_version(&self) -> u8 {
+        self.local_protocol_version.min(self.remote_protocol_version)
+    }
+}
@@ -1,35 +1,178 @@
+use futures::{
+    stream::{self, BoxStream},
+    Stream, StreamExt,
+};
+use rand::{rngs::StdRng, Rng};
 use std::{
     borrow::Borrow,
-    collections::HashSet,
+    collections::{HashSet, VecDeque},
     fmt,
     hash::Hash,
-    mem,
-    ops::Deref,
-    sync::{Arc, Mutex},
+    mem::size_of,
+    ops::{Deref, RangeInclusive},
+    pin::Pin,
+    sync::{Arc, Mutex, MutexGuard},
+    task::{Context, Poll},
+    time::Duration,
 };
-use tokio::sync::oneshot;
+use tokio::sync::{broadcast, oneshot};
+
+use super::PortAllocation;
 
 struct PortAllocatorInner {
     used: HashSet<u32>,
     limit: u32,
-    notify_tx: Vec<oneshot::Sender<()>>,
+    min: u32,
+    max: u32,
+    allocation: PortAllocation,
+    /// Offset from `min` at which the next sequential allocation starts.
+    next: u64,
+    /// Released port numbers below `next` for compact allocation.
+    free: Vec<u32>,
+    /// Tasks waiting for a port number to become available in FIFO order.
+    notify_tx: VecDeque<oneshot::Sender<PortNumber>>,
+    /// Subscribers to allocation events, created on first subscription.
+    events_tx: Option<broadcast::Sender<PortEvent>>,
+    /// Random number generator for random allocation; thread RNG if unset.
+    rng: Option<StdRng>,
 }
 
 impl PortAllocatorInner {
+    /// Number of port numbers within the allocation range.
+    fn range_len(&self) -> u64 {
+        u64::from(self.max - self.min) + 1
+    }
+
+    /// Port number at the specified offset within the range.
+    fn number(&self, offset: u64) -> u32 {
+        self.min + offset as u32
+    }
+
+    /// Maximum number of port numbers that can be allocated simultaneously.
+    fn capacity(&self) -> usize {
+        usize::try_from(self.range_len()).unwrap_or(usize::MAX).min(self.limit as usize)
+    }
+
     fn is_available(&self) -> bool {
-        self.used.len() <= self.limit as usize
+        self.used.len() < self.capacity()
+    }
+
+    /// Notifies event subscribers, if any.
+    fn notify(&self, event: PortEvent) {
+        if let Some(events_tx) = &self.events_tx {
+            let _ = events_tx.send(event);
+        }
     }
 
     fn try_allocate(&mut self, this: Arc<Mutex<PortAllocatorInner>>) -> Option<PortNumber> {
-        if self.is_available() {
-            let number = loop {
-                let cand = rand::random();
-                if !self.used.contains(&cand) {
-                    break cand;
+        if !self.is_available() {
+            return None;
+        }
+
+        let number = match self.allocation {
+            PortAllocation::Compact => self.allocate_compact()?,
+            PortAllocation::Random => {
+                let range = self.min..=self.max;
+                let number = match &mut self.rng {
+                    Some(rng) => rng.gen_range(range),
+                    None => rand::thread_rng().gen_range(range),
+                };
+                let start = u64::from(number - self.min);
+                self.probe(start)?
+            }
+            PortAllocation::Sequential => self.probe(self.next)?,
+        };
+
+        self.used.insert(number);
+        #[cfg(feature = "tracing")]
+        tracing::trace!(port = number, used = self.used.len(), "allocated port");
+        self.notify(PortEvent::Allocated(number));
+        Some(PortNumber { number, allocator: this })
+    }
+
+    /// Probes linearly from the start position for an unused port number, so that
+    /// allocation terminates even if the range is nearly exhausted.
+    fn probe(&mut self, start: u64) -> Option<u32> {
+        let len = self.range_len();
+        let offset =
+            (0..len).map(|i| (start + i) % len).find(|&offset| !self.used.contains(&self.number(offset)))?;
+        self.next = (offset + 1) % len;
+        Some(self.number(offset))
+    }
+
+    /// Takes a released port number or, if none is available, the next unused one.
+    ///
+    /// Port numbers below `next` are either used or contained in the free list.
+    fn allocate_compact(&mut self) -> Option<u32> {
+        if let Some(number) = self.free.pop() {
+            return Some(number);
+        }
+
+        // Skip port numbers that have been allocated specifically.
+        while self.next < self.range_len() {
+            let number = self.number(self.next);
+            self.next += 1;
+            if !self.used.contains(&number) {
+                return Some(number);
+            }
+        }
+
+        None
+    }
+
+    /// Releases the specified port number.
+    fn release(&mut self, number: u32) {
+        self.used.remove(&number);
+        #[cfg(feature = "tracing")]
+        tracing::trace!(port = number, used = self.used.len(), "released port");
+        self.notify(PortEvent::Released(number));
+        if self.allocation == PortAllocation::Compact && u64::from(number - self.min) < self.next {
+            self.free.push(number);
+        }
+    }
+
+    /// Hands available port numbers over to waiting tasks in FIFO order.
+    ///
+    /// The lock is released before a port number is handed over, since dropping it
+    /// in case the waiting task has gone away re-enters this function.
+    fn serve_waiters<'a>(
+        mut inner: MutexGuard<'a, PortAllocatorInner>, this: &'a Arc<Mutex<PortAllocatorInner>>,
+    ) {
+        while let Some(tx) = inner.notify_tx.pop_front() {
+            if tx.is_closed() {
+                continue;
+            }
+
+            match inner.try_allocate(this.clone()) {
+                Some(number) => {
+                    drop(inner);
+
+                    // If the waiting task has gone away in the meantime, dropping
+                    // the returned port number hands it to the next waiting task.
+                    let _ = tx.send(number);
+                    inner = this.lock().unwrap();
                 }
-            };
+                None => {
+                    inner.notify_tx.push_front(tx);
+                    break;
+                }
+            }
+        }
+    }
 
-            self.used.insert(number);
+    /// Removes waiters that have given up waiting.
+    fn prune_waiters(&mut self) {
+        self.notify_tx.retain(|tx| !tx.is_closed());
+    }
+
+    fn try_allocate_specific(&mut self, number: u32, this: Arc<Mutex<PortAllocatorInner>>) -> Option<PortNumber> {
+        if self.is_available() && (self.min..=self.max).contains(&number) && self.used.insert(number) {
+            if self.allocation == PortAllocation::Compact && u64::from(number - self.min) < self.next {
+                self.free.retain(|&free| free != number);
+            }
+            #[cfg(feature = "tracing")]
+            tracing::trace!(port = number, used = self.used.len(), "allocated specific port");
+            self.notify(PortEvent::Allocated(number));
             Some(PortNumber { number, allocator: this })
         } else {
             None
@@ -40,27 +183,151 @@ impl PortAllocatorInner {
 /// Local port number allocator.
 ///
 /// State is shared between clones of this type.
+///
+/// # Port numbers
+/// Port numbers are 32-bit values and encoded as such in all multiplexer messages.
+/// They are local to an endpoint: each endpoint allocates the numbers of its own ports
+/// and the remote endpoint refers to them by these numbers.
+/// Thus port numbers of both endpoints cannot collide and the size of the port number
+/// space only needs to accommodate the [maximum number of open ports](Self::limit).
+///
+/// By default port numbers are allocated [randomly](super::PortAllocation::Random).
+/// The random and [sequential](super::PortAllocation::Sequential)
+/// [strategies](super::Cfg::port_allocation) start at a random or sequential position
+/// and probe the [range](Self::range) linearly for an unused port number.
+/// Allocation therefore never retries randomly and always terminates, even when
+/// the range is almost exhausted.
+/// [Compact allocation](super::PortAllocation::Compact) reuses released port numbers
+/// from a free list, which takes constant time.
 #[derive(Clone)]
 pub struct PortAllocator(Arc<Mutex<PortAllocatorInner>>);
 
 impl fmt::Debug for PortAllocator {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let inner = self.0.lock().unwrap();
-        f.debug_struct("PortAllocator").field("used", &inner.used.len()).field("limit", &inner.limit).finish()
+        f.debug_struct("PortAllocator")
+            .field("used", &inner.used.len())
+            .field("limit", &inner.limit)
+            .field("range", &(inner.min..=inner.max))
+            .field("allocation", &inner.allocation)
+            .finish()
     }
 }
 
 impl PortAllocator {
-    /// Creates a new port number allocator.
-    pub(crate) fn new(limit: u32) -> PortAllocator {
-        let inner = PortAllocatorInner { used: HashSet::new(), limit, notify_tx: Vec::new() };
+    /// Number of events buffered for each subscriber of [events](Self::events).
+    pub const EVENT_BUFFER: usize = 1024;
+
+    /// Creates a new port number allocator that allocates port numbers
+    /// from the specified range using the specified strategy.
+    ///
+    /// Random allocation uses the specified random number generator or,
+    /// if none is specified, the thread-local random number generator.
+    ///
+    /// # Panics
+    /// Panics if the range is empty.
+    pub(crate) fn new(
+        limit: u32, range: RangeInclusive<u32>, allocation: PortAllocation, rng: Option<StdRng>,
+    ) -> PortAllocator {
+        assert!(!range.is_empty(), "port range must not be empty");
+        let inner = PortAllocatorInner {
+            used: HashSet::new(),
+            limit,
+            min: *range.start(),
+            max: *range.end(),
+            allocation,
+            next: 0,
+            free: Vec::new(),
+            notify_tx: VecDeque::new(),
+            events_tx: None,
+            rng,
+        };
         PortAllocator(Arc::new(Mutex::new(inner)))
     }
 
+    /// Number of currently allocated port numbers.
+    pub fn used(&self) -> usize {
+        let inner = self.0.lock().unwrap();
+        inner.used.len()
+    }
+
+    /// Maximum number of open ports.
+    ///
+    /// This is initially set to [Cfg::max_ports](super::Cfg::max_ports)
+    /// and can be changed using [set_limit](Self::set_limit).
+    pub fn limit(&self) -> u32 {
+        let inner = self.0.lock().unwrap();
+        inner.limit
+    }
+
+    /// Sets the maximum number of open ports and returns the limit in effect.
+    ///
+    /// If the limit is raised, tasks waiting for a port number are served
+    /// in FIFO order.
+    /// The limit cannot be lowered below the number of currently [used](Self::used)
+    /// port numbers; it is clamped to that number instead.
+    ///
+    /// # Panics
+    /// Panics if the limit is zero or exceeds 2^31.
+    pub fn set_limit(&self, limit: u32) -> u32 {
+        assert!(limit > 0, "port limit must not be zero");
+        assert!(limit <= 2u32.pow(31), "port limit must not exceed 2^31");
+
+        let mut inner = self.0.lock().unwrap();
+        let used = u32::try_from(inner.used.len()).unwrap_or(u32::MAX);
+        inner.limit = limit.max(used);
+        let limit = inner.limit;
+        PortAllocatorInner::serve_waiters(inner, &self.0);
+        limit
+    }
+
+    /// Number of port numbers that can currently be allocated without waiting.
+    ///
+    /// This is limited by the [limit](Self::limit) and the size of the [range](Self::range).
+    pub fn available(&self) -> usize {
+        let inner = self.0.lock().unwrap();
+        inner.capacity().saturating_sub(inner.used.len())
+    }
+
+    /// Number of tasks currently waiting for a port number to become available.
+    pub fn waiters(&self) -> usize {
+        let inner = self.0.lock().unwrap();
+        inner.notify_tx.iter().filter(|tx| !tx.is_closed()).count()
+    }
+
+    /// Range of port numbers that are allocated.
+    pub fn range(&self) -> RangeInclusive<u32> {
+        let inner = self.0.lock().unwrap();
+        inner.min..=inner.max
+    }
+
+    /// Subscribes to port number allocation and release events.
+    ///
+    /// Only events occurring after this call are reported.
+    /// Events are buffered for up to [EVENT_BUFFER](Self::EVENT_BUFFER) events per subscriber.
+    /// If a subscriber falls behind, the oldest events are discarded and
+    /// reported as [PortEvent::Lost], thus a slow subscriber never delays allocation.
+    ///
+    /// The stream ends when the allocator and all port numbers allocated by it have been dropped.
+    pub fn events(&self) -> PortEvents {
+        let mut inner = self.0.lock().unwrap();
+        let events_rx = match &inner.events_tx {
+            Some(events_tx) => events_tx.subscribe(),
+            None => {
+                let (events_tx, events_rx) = broadcast::channel(Self::EVENT_BUFFER);
+                inner.events_tx = Some(events_tx);
+                events_rx
+            }
+        };
+        PortEvents::new(events_rx)
+    }
+
     /// Allocates a local port number.
     ///
-    /// Port numbers are allocated randomly.
+    /// Port numbers are allocated from the [range](Self::range) using
+    /// the [configured strategy](super::Cfg::port_allocation).
     /// If all ports are currently in use, this waits for a port number to become available.
+    /// Waiting tasks are served in FIFO order.
     pub async fn allocate(&self) -> PortNumber {
         loop {
             let rx = {
@@ -69,23 +336,134 @@ impl PortAllocator {
                     Some(number) => return number,
                     None => {
                         let (tx, rx) = oneshot::channel();
-                        inner.notify_tx.push(tx);
+                        inner.prune_waiters();
+                        inner.notify_tx.push_back(tx);
                         rx
                     }
                 }
             };
 
-            let _ = rx.await;
+            if let Ok(number) = rx.await {
+                return number;
+            }
         }
     }
 
     /// Tries to allocate a local port number.
     ///
-    /// If all port are currently in use, this returns [None].
+    /// If all port are currently in use or the [range](Self::range) is exhausted,
+    /// this returns [None].
     pub fn try_allocate(&self) -> Option<PortNumber> {
         let mut inner = self.0.lock().unwrap();
         inner.try_allocate(self.0.clone())
     }
+
+    /// Allocates a local port number, waiting at most the specified time.
+    ///
+    /// This behaves like [allocate](Self::allocate), but returns [None] if no
+    /// port number becomes available within the specified timeout.
+    pub async fn allocate_timeout(&self, timeout: Duration) -> Option<PortNumber> {
+        match tokio::time::timeout(timeout, self.allocate()).await {
+            Ok(number) => Some(number),
+            Err(_) => {
+                let mut inner = self.0.lock().unwrap();
+                inner.prune_waiters();
+                None
+            }
+        }
+    }
+
+    /// Allocates the specified number of local port numbers.
+    ///
+    /// As many port numbers as are currently available are allocated at once.
+    /// If fewer than `n` port numbers are available, this waits for the remaining
+    /// port numbers to become available.
+    ///
+    /// Port numbers allocated by this function are held while waiting.
+    /// Thus, concurrent calls requesting more ports than the [limit](Self::limit)
+    /// allows may wait forever.
+    pub async fn allocate_many(&self, n: usize) -> Vec<PortNumber> {
+        let mut numbers = self.try_allocate_many(n);
+        while numbers.len() < n {
+            numbers.push(self.allocate().await);
+        }
+        numbers
+    }
+
+    /// Tries to allocate up to the specified number of local port numbers.
+    ///
+    /// This never waits and returns fewer than `n` port numbers if not
+    /// enough are currently available.
+    pub fn try_allocate_many(&self, n: usize) -> Vec<PortNumber> {
+        let mut inner = self.0.lock().unwrap();
+        let mut numbers = Vec::with_capacity(n.min(inner.capacity()));
+        while numbers.len() < n {
+            match inner.try_allocate(self.0.clone()) {
+                Some(number) => numbers.push(number),
+                None => break,
+            }
+        }
+        numbers
+    }
+
+    /// Tries to allocate the specified local port number.
+    ///
+    /// This returns [None] if the port number is already in use, is outside
+    /// the [range](Self::range) or if all ports are currently in use.
+    ///
+    /// With [compact allocation](super::PortAllocation::Compact) this takes time
+    /// proportional to the number of released port numbers awaiting reuse.
+    pub fn try_allocate_specific(&self, number: u32) -> Option<PortNumber> {
+        let mut inner = self.0.lock().unwrap();
+        inner.try_allocate_specific(number, self.0.clone())
+    }
+}
+
+/// A port number allocation event.
+///
+/// Obtained from [PortAllocator::events].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PortEvent {
+    /// The port number was allocated.
+    Allocated(u32),
+    /// The port number was released.
+    Released(u32),
+    /// The specified number of events was discarded because the subscriber fell behind.
+    Lost(u64),
+}
+
+/// A stream of port number allocation events.
+///
+/// Obtained from [PortAllocator::events].
+pub struct PortEvents(BoxStream<'static, PortEvent>);
+
+impl fmt::Debug for PortEvents {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("PortEvents").finish()
+    }
+}
+
+impl PortEvents {
+    fn new(events_rx: broadcast::Receiver<PortEvent>) -> Self {
+        Self(
+            stream::unfold(events_rx, |mut events_rx| async move {
+                match events_rx.recv().await {
+                    Ok(event) => Some((event, events_rx)),
+                    Err(broadcast::error::RecvError::Lagged(n)) => Some((PortEvent::Lost(n), events_rx)),
+                    Err(broadcast::error::RecvError::Closed) => None,
+                }
+            })
+            .boxed(),
+        )
+    }
+}
+
+impl Stream for PortEvents {
+    type Item = PortEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        self.0.poll_next_unpin(cx)
+    }
 }
 
 /// An allocated local port number.
@@ -150,15 +528,9 @@ impl Borrow<u32> for PortNumber {
 
 impl Drop for PortNumber {
     fn drop(&mut self) {
-        let notify_tx = {
-            let mut inner = self.allocator.lock().unwrap();
-            inner.used.remove(&self.number);
-            mem::take(&mut inner.notify_tx)
-        };
-
-        for tx in notify_tx {
-            let _ = tx.send(());
-        }
+        let mut inner = self.allocator.lock().unwrap();
+        inner.release(self.number);
+        PortAllocatorInner::serve_waiters(inner, &self.allocator);
     }
 }
 
@@ -166,19 +538,27 @@ impl Drop for PortNumber {
 ///
 /// The id can be set freely by the user.
 /// It is initialized to the [port number](Self::port).
+///
+/// Additionally, opaque [metadata](Self::metadata) of up to
+/// [MAX_METADATA_LEN](Self::MAX_METADATA_LEN) bytes can be attached.
+/// It is empty by default.
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct PortReq {
     /// The allocated, local port number.
     pub port: PortNumber,
     /// A user-specified id.
     pub id: u32,
+    /// User-specified metadata.
+    ///
+    /// This is private to enforce the length limit checked by [with_metadata](Self::with_metadata).
+    pub(crate) metadata: Vec<u8>,
 }
 
 impl From<PortNumber> for PortReq {
     /// Create a new port connection request with [`id`](Self::id) set to
     /// the [port number](Self::port).
     fn from(port: PortNumber) -> Self {
-        Self { id: port.number, port }
+        Self { id: port.number, port, metadata: Vec::new() }
     }
 }
 
@@ -189,6 +569,9 @@ impl From<PortReq> for PortNumber {
 }
 
 impl PortReq {
+    /// Maximum length of [metadata](Self::metadata) in bytes.
+    pub const MAX_METADATA_LEN: usize = 255;
+
     /// Create a new port connection request with [`id`](Self::id) set to
     /// the [port number](Self::port).
     pub fn new(port: PortNumber) -> Self {
@@ -200,4 +583,27 @@ impl PortReq {
         self.id = id;
         self
     }
+
+    /// User-specified metadata.
+    ///
+    /// It is discarded if the remote endpoint does not support metadata,
+    /// i.e. it uses a protocol version below 4.
+    pub fn metadata(&self) -> &[u8] {
+        &self.metadata
+    }
+
+    /// Sets the metadata to the specified value.
+    ///
+    /// # Panics
+    /// Panics if the metadata exceeds [MAX_METADATA_LEN](Self::MAX_METADATA_LEN) bytes.
+    pub fn with_metadata(mut self, metadata: Vec<u8>) -> Self {
+        assert!(metadata.len() <= Self::MAX_METADATA_LEN, "port request metadata is too long");
+        self.metadata = metadata;
+        self
+    }
+
+    /// Size of this request when encoded in a port data message.
+    pub(crate) fn encoded_len(&self) -> usize {
+        2 * size_of::<u32>() + 1 + self.metadata.len()
+    }
 }
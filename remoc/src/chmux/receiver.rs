@@ -4,7 +4,16 @@ use futures::{
     stream::Stream,
     task::{Context, Poll},
 };
-use std::{collections::VecDeque, error::Error, fmt, mem, pin::Pin};
+use std::{
+    collections::VecDeque,
+    error::Error,
+    fmt, mem,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
 use tokio::sync::{mpsc, oneshot};
 use tokio_util::sync::ReusableBoxFuture;
 
@@ -21,6 +30,11 @@ use super::{
 pub enum RecvError {
     /// Multiplexer terminated.
     ChMux,
+    /// The connection was shut down while the channel was still open.
+    Shutdown,
+    /// The channel was closed because it was idle for longer than the
+    /// [idle timeout](super::Cfg::idle_timeout).
+    IdleTimeout,
     /// Data exceeds maximum size.
     ExceedsMaxDataSize(usize),
     /// Received ports exceed maximum count.
@@ -30,12 +44,12 @@ pub enum RecvError {
 impl RecvError {
     /// Returns true, if error is due to multiplexer being terminated.
     pub fn is_terminated(&self) -> bool {
-        matches!(self, Self::ChMux)
+        matches!(self, Self::ChMux | Self::Shutdown)
     }
 
     /// Returns whether the error is final, i.e. no further receive operation can succeed.
     pub fn is_final(&self) -> bool {
-        self.is_terminated()
+        self.is_terminated() || matches!(self, Self::IdleTimeout)
     }
 }
 
@@ -43,6 +57,8 @@ impl fmt::Display for RecvError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Self::ChMux => write!(f, "multiplexer terminated"),
+            Self::Shutdown => write!(f, "connection shut down"),
+            Self::IdleTimeout => write!(f, "channel idle timeout"),
             Self::ExceedsMaxDataSize(max_size) => {
                 write!(f, "data exceeds maximum allowed size of {max_size} bytes")
             }
@@ -60,6 +76,8 @@ impl From<RecvError> for std::io::Error {
         use std::io::ErrorKind;
         match err {
             RecvError::ChMux => Self::new(ErrorKind::ConnectionReset, err.to_string()),
+            RecvError::Shutdown => Self::new(ErrorKind::ConnectionAborted, err.to_string()),
+            RecvError::IdleTimeout => Self::new(ErrorKind::TimedOut, err.to_string()),
             RecvError::ExceedsMaxDataSize(_) => Self::new(ErrorKind::InvalidData, err.to_string()),
             RecvError::ExceedsMaxPortCount(_) => Self::new(ErrorKind::InvalidData, err.to_string()),
         }
@@ -99,12 +117,17 @@ pub enum RecvChunkError {
     ChMux,
     /// Remote endpoint cancelled transmission.
     Cancelled,
+    /// The connection was shut down while the channel was still open.
+    Shutdown,
+    /// The channel was closed because it was idle for longer than the
+    /// [idle timeout](super::Cfg::idle_timeout).
+    IdleTimeout,
 }
 
 impl RecvChunkError {
     /// Returns true, if error is due to multiplexer being terminated.
     pub fn is_terminated(&self) -> bool {
-        matches!(self, Self::ChMux)
+        matches!(self, Self::ChMux | Self::Shutdown)
     }
 }
 
@@ -113,6 +136,8 @@ impl fmt::Display for RecvChunkError {
         match self {
             Self::ChMux => write!(f, "multiplexer terminated"),
             Self::Cancelled => write!(f, "transmission cancelled"),
+            Self::Shutdown => write!(f, "connection shut down"),
+            Self::IdleTimeout => write!(f, "channel idle timeout"),
         }
     }
 }
@@ -125,6 +150,8 @@ pub(crate) struct ReceivedData {
     pub first: bool,
     /// Last chunk of data.
     pub last: bool,
+    /// Data is a header block preceding the next data message.
+    pub headers: bool,
     /// Flow-control credit.
     pub credit: UsedCredit,
 }
@@ -149,6 +176,8 @@ pub(crate) enum PortReceiveMsg {
     PortRequests(ReceivedPortRequests),
     /// Sender has closed its end.
     Finished,
+    /// Port has been closed due to idle timeout.
+    IdleTimeout,
 }
 
 /// A buffer containing received data.
@@ -294,11 +323,16 @@ pub struct Receiver {
     tx: mpsc::Sender<PortEvt>,
     rx: mpsc::UnboundedReceiver<PortReceiveMsg>,
     receiving: Receiving,
+    receiving_headers: Option<DataBuf>,
+    next_headers: Option<DataBuf>,
+    headers: Option<DataBuf>,
     credits: ChannelCreditReturner,
     closed: bool,
     finished: bool,
+    idle_timeout: bool,
     port_allocator: PortAllocator,
     storage: AnyStorage,
+    shutdown: Arc<AtomicBool>,
     _drop_tx: oneshot::Sender<()>,
 }
 
@@ -320,7 +354,7 @@ impl Receiver {
     pub(crate) fn new(
         local_port: u32, remote_port: u32, max_data_size: usize, max_port_count: usize,
         tx: mpsc::Sender<PortEvt>, rx: mpsc::UnboundedReceiver<PortReceiveMsg>, credits: ChannelCreditReturner,
-        port_allocator: PortAllocator, storage: AnyStorage,
+        port_allocator: PortAllocator, storage: AnyStorage, shutdown: Arc<AtomicBool>,
     ) -> Self {
         let (_drop_tx, drop_rx) = oneshot::channel();
         let tx_drop = tx.clone();
@@ -337,11 +371,16 @@ impl Receiver {
             tx,
             rx,
             receiving: Receiving::Nothing,
+            receiving_headers: None,
+            next_headers: None,
+            headers: None,
             credits,
             closed: false,
             finished: false,
+            idle_timeout: false,
             port_allocator,
             storage,
+            shutdown,
             _drop_tx,
         }
     }
@@ -409,10 +448,14 @@ impl Receiver {
     ///
     /// This is unlimited in size.
     #[inline]
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all, fields(local_port = self.local_port, remote_port = self.remote_port)))]
     pub async fn recv_chunk(&mut self) -> Result<Option<Bytes>, RecvChunkError> {
         if self.finished {
             return Ok(None);
         }
+        if self.idle_timeout {
+            return Err(RecvChunkError::IdleTimeout);
+        }
 
         loop {
             self.credits.return_flush().await;
@@ -432,7 +475,15 @@ impl Receiver {
                 // Try to receive next chunk.
                 _ => match self.rx.recv().await {
                     Some(PortReceiveMsg::Data(data)) => {
-                        self.credits.start_return(data.credit, self.remote_port, &self.tx);
+                        self.credits.start_return(data.credit, self.remote_port, &self.tx, self.rx.is_empty());
+
+                        if data.headers {
+                            self.recv_headers(data.buf, data.first, data.last);
+                            continue;
+                        }
+                        if data.first {
+                            self.headers = self.next_headers.take();
+                        }
 
                         match (&self.receiving, data.first) {
                             // First segment without last segment indicates that last transmission
@@ -444,6 +495,8 @@ impl Receiver {
                             }
                             // Either continuation or start of transmission.
                             (Receiving::Chunks { .. }, false) | (_, true) => {
+                                #[cfg(feature = "tracing")]
+                                tracing::trace!(len = data.buf.len(), last = data.last, "received chunk");
                                 self.receiving =
                                     Receiving::Chunks { chunks: VecDeque::new(), completed: data.last };
                                 return Ok(Some(data.buf));
@@ -455,7 +508,7 @@ impl Receiver {
 
                     // Either aborted transmission or port data to ignore.
                     Some(PortReceiveMsg::PortRequests(req)) => {
-                        self.credits.start_return(req.credit, self.remote_port, &self.tx);
+                        self.credits.start_return(req.credit, self.remote_port, &self.tx, self.rx.is_empty());
                         if let Receiving::Chunks { .. } = &self.receiving {
                             self.receiving = Receiving::Nothing;
                             return Err(RecvChunkError::Cancelled);
@@ -473,6 +526,14 @@ impl Receiver {
                         }
                     }
 
+                    // Port idle timeout.
+                    Some(PortReceiveMsg::IdleTimeout) => {
+                        self.idle_timeout = true;
+                        self.receiving = Receiving::Nothing;
+                        return Err(RecvChunkError::IdleTimeout);
+                    }
+
+                    None if self.shutdown.load(Ordering::SeqCst) => return Err(RecvChunkError::Shutdown),
                     None => return Err(RecvChunkError::ChMux),
                 },
             }
@@ -481,10 +542,14 @@ impl Receiver {
 
     /// Receives data or ports over the channel.
     #[inline]
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all, fields(local_port = self.local_port, remote_port = self.remote_port)))]
     pub async fn recv_any(&mut self) -> Result<Option<Received>, RecvError> {
         if self.finished {
             return Ok(None);
         }
+        if self.idle_timeout {
+            return Err(RecvError::IdleTimeout);
+        }
 
         loop {
             self.credits.return_flush().await;
@@ -492,10 +557,15 @@ impl Receiver {
             match self.rx.recv().await {
                 // Data message.
                 Some(PortReceiveMsg::Data(data)) => {
-                    self.credits.start_return(data.credit, self.remote_port, &self.tx);
+                    self.credits.start_return(data.credit, self.remote_port, &self.tx, self.rx.is_empty());
 
+                    if data.headers {
+                        self.recv_headers(data.buf, data.first, data.last);
+                        continue;
+                    }
                     if data.first {
                         self.receiving = Receiving::Data(DataBuf::new());
+                        self.headers = self.next_headers.take();
                     }
 
                     if let Receiving::Data(mut data_buf) = mem::take(&mut self.receiving) {
@@ -504,6 +574,8 @@ impl Receiver {
                             // Data fits into buffer.
                             Ok(()) => {
                                 if data.last {
+                                    #[cfg(feature = "tracing")]
+                                    tracing::trace!(len = data_buf.remaining(), "received data");
                                     return Ok(Some(Received::Data(data_buf)));
                                 } else {
                                     self.receiving = Receiving::Data(data_buf);
@@ -513,6 +585,8 @@ impl Receiver {
                             // Maximum message size has been reached.
                             Err(buf) => {
                                 data_buf.bufs.push_back(buf);
+                                #[cfg(feature = "tracing")]
+                                tracing::trace!("received data exceeds maximum size, switching to chunks");
                                 self.receiving =
                                     Receiving::Chunks { chunks: data_buf.bufs, completed: data.last };
                                 return Ok(Some(Received::Chunks));
@@ -523,7 +597,7 @@ impl Receiver {
 
                 // Port connection requests.
                 Some(PortReceiveMsg::PortRequests(req)) => {
-                    self.credits.start_return(req.credit, self.remote_port, &self.tx);
+                    self.credits.start_return(req.credit, self.remote_port, &self.tx, self.rx.is_empty());
 
                     if req.first {
                         self.receiving = Receiving::Requests(Vec::new());
@@ -538,6 +612,8 @@ impl Receiver {
                         }
 
                         if req.last {
+                            #[cfg(feature = "tracing")]
+                            tracing::trace!(ports = requests.len(), "received ports");
                             return Ok(Some(Received::Requests(requests)));
                         } else {
                             self.receiving = Receiving::Requests(requests);
@@ -551,11 +627,46 @@ impl Receiver {
                     return Ok(None);
                 }
 
+                // Port idle timeout.
+                Some(PortReceiveMsg::IdleTimeout) => {
+                    self.idle_timeout = true;
+                    self.receiving = Receiving::Nothing;
+                    return Err(RecvError::IdleTimeout);
+                }
+
+                None if self.shutdown.load(Ordering::SeqCst) => return Err(RecvError::Shutdown),
                 None => return Err(RecvError::ChMux),
             }
         }
     }
 
+    /// Processes a chunk of a header block preceding the next data message.
+    ///
+    /// A header block exceeding the maximum data size is discarded.
+    fn recv_headers(&mut self, chunk: Bytes, first: bool, last: bool) {
+        let mut buf = match (first, self.receiving_headers.take()) {
+            (true, _) => DataBuf::new(),
+            (false, Some(buf)) => buf,
+            (false, None) => return,
+        };
+
+        if buf.try_push(chunk, self.max_data_size).is_err() {
+            tracing::debug!("received header block exceeds maximum size, discarding");
+            return;
+        }
+
+        if last {
+            self.next_headers = Some(buf);
+        } else {
+            self.receiving_headers = Some(buf);
+        }
+    }
+
+    /// Takes the header block that preceded the most recently started message.
+    pub(crate) fn take_headers(&mut self) -> Option<DataBuf> {
+        self.headers.take()
+    }
+
     /// Closes the sender at the remote endpoint, preventing it from sending new data.
     /// Already sent message will still be received.
     #[inline]
@@ -666,3 +777,61 @@ impl From<Receiver> for ReceiverStream {
         Self::new(recv)
     }
 }
+
+/// A stream receiving the raw chunks of data sent over a channel.
+///
+/// In contrast to [ReceiverStream] the received data is neither reassembled into messages
+/// nor limited in size.
+/// Message boundaries are not preserved, i.e. the chunks of all received messages are
+/// yielded consecutively.
+/// Received ports are silently rejected.
+///
+/// If the remote endpoint cancels the transmission of a message,
+/// [RecvChunkError::Cancelled] is returned and the stream continues with the next message.
+///
+/// This is useful for forwarding data without deserializing it.
+pub struct ReceiverChunkStream {
+    inner: ReusableBoxFuture<'static, (Result<Option<Bytes>, RecvChunkError>, Receiver)>,
+}
+
+impl fmt::Debug for ReceiverChunkStream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ReceiverChunkStream").finish()
+    }
+}
+
+impl ReceiverChunkStream {
+    /// Creates a new `ReceiverChunkStream`.
+    pub fn new(rx: Receiver) -> Self {
+        Self { inner: ReusableBoxFuture::new(Self::make_future(rx)) }
+    }
+
+    async fn make_future(mut rx: Receiver) -> (Result<Option<Bytes>, RecvChunkError>, Receiver) {
+        loop {
+            match rx.recv_chunk().await {
+                // End of message or empty chunk.
+                Ok(None) if !rx.finished => (),
+                Ok(Some(chunk)) if chunk.is_empty() => (),
+                result => break (result, rx),
+            }
+        }
+    }
+}
+
+impl Stream for ReceiverChunkStream {
+    type Item = Result<Bytes, RecvChunkError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let (result, rx) = ready!(self.inner.poll(cx));
+        self.inner.set(Self::make_future(rx));
+        Poll::Ready(result.transpose())
+    }
+}
+
+impl Unpin for ReceiverChunkStream {}
+
+impl From<Receiver> for ReceiverChunkStream {
+    fn from(recv: Receiver) -> Self {
+        Self::new(recv)
+    }
+}
@@ -0,0 +1,200 @@
+use futures::Stream;
+use std::{
+    error::Error,
+    fmt,
+    ops::RangeInclusive,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+use tokio::sync::{mpsc, oneshot};
+
+use super::listener::{Listener, ListenerError, Request};
+
+/// An error occurred during registration of a route.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RouteError {
+    /// A route for some of the ids is already registered.
+    AlreadyRegistered,
+}
+
+impl fmt::Display for RouteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::AlreadyRegistered => write!(f, "route for id is already registered"),
+        }
+    }
+}
+
+impl Error for RouteError {}
+
+/// A registered route.
+struct Route {
+    ids: RangeInclusive<u32>,
+    tx: mpsc::Sender<Result<Request, ListenerError>>,
+}
+
+/// Dispatches incoming connection requests to routes by their [id](Request::id).
+///
+/// Obtained by calling [Listener::into_router].
+/// Each route is registered for an id or a range of ids using [listen](Self::listen)
+/// or [listen_range](Self::listen_range) and provides a stream of the
+/// matching connection requests.
+/// This allows different services to accept their own ports from one connection.
+///
+/// Connection requests whose id matches no registered route are rejected.
+/// The remote endpoint then receives [ConnectError::NoRoute](super::ConnectError::NoRoute).
+///
+/// Each route queues up to [queue_length](Self::queue_length) connection requests.
+/// Requests arriving while the queue of their route is full are rejected and the
+/// remote endpoint receives [ConnectError::Rejected](super::ConnectError::Rejected).
+///
+/// Dropping the router stops dispatching, ends all route streams and drops the listener.
+pub struct Router {
+    routes: Arc<Mutex<Option<Vec<Route>>>>,
+    queue_length: usize,
+    _stop_tx: oneshot::Sender<()>,
+}
+
+impl fmt::Debug for Router {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let routes = self.routes.lock().unwrap();
+        let ids: Vec<_> = routes.iter().flatten().map(|route| route.ids.clone()).collect();
+        f.debug_struct("Router").field("routes", &ids).field("queue_length", &self.queue_length).finish()
+    }
+}
+
+impl Router {
+    /// Default length of the connection request queue of each route.
+    pub const DEFAULT_QUEUE_LENGTH: usize = 16;
+
+    pub(crate) fn new(mut listener: Listener) -> Self {
+        let routes = Arc::new(Mutex::new(Some(Vec::<Route>::new())));
+        let (_stop_tx, mut stop_rx) = oneshot::channel();
+
+        let task_routes = routes.clone();
+        tokio::spawn(async move {
+            loop {
+                let res = tokio::select! {
+                    res = listener.inspect() => res,
+                    _ = &mut stop_rx => break,
+                };
+
+                match res {
+                    Ok(Some(req)) => {
+                        let unrouted = {
+                            let mut routes = task_routes.lock().unwrap();
+                            let routes = routes.as_mut().unwrap();
+                            routes.retain(|route| !route.tx.is_closed());
+                            match routes.iter().find(|route| route.ids.contains(&req.id())) {
+                                Some(route) => {
+                                    if let Err(mpsc::error::TrySendError::Full(_)) = route.tx.try_send(Ok(req)) {
+                                        tracing::debug!("rejecting request because route queue is full");
+                                    }
+                                    None
+                                }
+                                None => Some(req),
+                            }
+                        };
+
+                        if let Some(req) = unrouted {
+                            tracing::debug!(id = req.id(), "rejecting request without route");
+                            req.reject_no_route().await;
+                        }
+                    }
+                    Ok(None) => break,
+                    Err(err) => {
+                        let routes = task_routes.lock().unwrap();
+                        for route in routes.iter().flatten() {
+                            let _ = route.tx.try_send(Err(err.clone()));
+                        }
+                        break;
+                    }
+                }
+            }
+
+            task_routes.lock().unwrap().take();
+        });
+
+        Self { routes, queue_length: Self::DEFAULT_QUEUE_LENGTH, _stop_tx }
+    }
+
+    /// Length of the connection request queue of each route.
+    pub fn queue_length(&self) -> usize {
+        self.queue_length
+    }
+
+    /// Sets the length of the connection request queue of routes registered afterwards.
+    ///
+    /// # Panics
+    /// Panics if the length is zero.
+    pub fn set_queue_length(&mut self, queue_length: usize) {
+        assert!(queue_length > 0, "queue length must not be zero");
+        self.queue_length = queue_length;
+    }
+
+    /// Registers a route for connection requests with the specified id.
+    pub fn listen(&self, id: u32) -> Result<RouteStream, RouteError> {
+        self.listen_range(id..=id)
+    }
+
+    /// Registers a route for connection requests with an id within the specified range.
+    ///
+    /// Fails if the range overlaps with the ids of another registered route.
+    /// A route is unregistered when its stream is dropped.
+    pub fn listen_range(&self, ids: RangeInclusive<u32>) -> Result<RouteStream, RouteError> {
+        let (tx, rx) = mpsc::channel(self.queue_length);
+
+        let mut routes = self.routes.lock().unwrap();
+        if let Some(routes) = routes.as_mut() {
+            routes.retain(|route| !route.tx.is_closed());
+            if routes.iter().any(|route| route.ids.start() <= ids.end() && ids.start() <= route.ids.end()) {
+                return Err(RouteError::AlreadyRegistered);
+            }
+            routes.push(Route { ids: ids.clone(), tx });
+        }
+
+        Ok(RouteStream { ids, rx })
+    }
+}
+
+impl Drop for Router {
+    fn drop(&mut self) {
+        // empty
+    }
+}
+
+/// A stream of connection requests matching a route of a [Router].
+///
+/// Accept or reject the obtained [requests](Request).
+/// Ends when the router is dropped or the client of the remote endpoint has been dropped.
+///
+/// Dropping the stream unregisters the route.
+pub struct RouteStream {
+    ids: RangeInclusive<u32>,
+    rx: mpsc::Receiver<Result<Request, ListenerError>>,
+}
+
+impl fmt::Debug for RouteStream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RouteStream").field("ids", &self.ids).finish()
+    }
+}
+
+impl RouteStream {
+    /// The range of ids this route is registered for.
+    pub fn ids(&self) -> &RangeInclusive<u32> {
+        &self.ids
+    }
+}
+
+impl Stream for RouteStream {
+    type Item = Result<Request, ListenerError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        Pin::into_inner(self).rx.poll_recv(cx)
+    }
+}
+
+impl Unpin for RouteStream {}
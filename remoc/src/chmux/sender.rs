@@ -15,6 +15,7 @@ use std::{
         atomic::{AtomicBool, Ordering},
         Arc, Weak,
     },
+    time::Duration,
 };
 use tokio::sync::{mpsc, oneshot, Mutex};
 
@@ -36,6 +37,11 @@ pub enum SendError {
         /// True, if remote endpoint still processes messages that were already sent.
         gracefully: bool,
     },
+    /// The connection was shut down while the channel was still open.
+    Shutdown,
+    /// The channel was closed because it was idle for longer than the
+    /// [idle timeout](super::Cfg::idle_timeout).
+    IdleTimeout,
 }
 
 impl SendError {
@@ -61,6 +67,8 @@ impl fmt::Display for SendError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Self::ChMux => write!(f, "multiplexer terminated"),
+            Self::Shutdown => write!(f, "connection shut down"),
+            Self::IdleTimeout => write!(f, "channel idle timeout"),
             Self::Closed { gracefully } => write!(
                 f,
                 "remote endpoint closed channel{}",
@@ -83,6 +91,8 @@ impl From<SendError> for std::io::Error {
         use std::io::ErrorKind;
         match err {
             SendError::ChMux => Self::new(ErrorKind::ConnectionReset, err.to_string()),
+            SendError::Shutdown => Self::new(ErrorKind::ConnectionAborted, err.to_string()),
+            SendError::IdleTimeout => Self::new(ErrorKind::TimedOut, err.to_string()),
             SendError::Closed { gracefully: false } => Self::new(ErrorKind::ConnectionReset, err.to_string()),
             SendError::Closed { gracefully: true } => Self::new(ErrorKind::ConnectionAborted, err.to_string()),
         }
@@ -133,8 +143,8 @@ impl From<SendError> for TrySendError {
     }
 }
 
-impl From<mpsc::error::TrySendError<PortEvt>> for TrySendError {
-    fn from(err: mpsc::error::TrySendError<PortEvt>) -> Self {
+impl<T> From<mpsc::error::TrySendError<T>> for TrySendError {
+    fn from(err: mpsc::error::TrySendError<T>) -> Self {
         match err {
             mpsc::error::TrySendError::Full(_) => Self::Full,
             mpsc::error::TrySendError::Closed(_) => Self::Send(SendError::ChMux),
@@ -192,13 +202,14 @@ pub struct Sender {
     remote_port: u32,
     chunk_size: usize,
     max_data_size: usize,
+    data_headers: bool,
     tx: mpsc::Sender<PortEvt>,
     credits: CreditUser,
     hangup_recved: Weak<AtomicBool>,
     hangup_notify: Weak<std::sync::Mutex<Option<Vec<oneshot::Sender<()>>>>>,
     port_allocator: PortAllocator,
     storage: AnyStorage,
-    _drop_tx: oneshot::Sender<()>,
+    drop_tx: Option<oneshot::Sender<()>>,
 }
 
 impl fmt::Debug for Sender {
@@ -217,16 +228,17 @@ impl Sender {
     /// Create a new sender.
     #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
-        local_port: u32, remote_port: u32, chunk_size: usize, max_data_size: usize, tx: mpsc::Sender<PortEvt>,
-        credits: CreditUser, hangup_recved: Weak<AtomicBool>,
+        local_port: u32, remote_port: u32, chunk_size: usize, max_data_size: usize, data_headers: bool,
+        tx: mpsc::Sender<PortEvt>, credits: CreditUser, hangup_recved: Weak<AtomicBool>,
         hangup_notify: Weak<std::sync::Mutex<Option<Vec<oneshot::Sender<()>>>>>, port_allocator: PortAllocator,
         storage: AnyStorage,
     ) -> Self {
-        let (_drop_tx, drop_rx) = oneshot::channel();
+        let (drop_tx, drop_rx) = oneshot::channel();
         let tx_drop = tx.clone();
         tokio::spawn(async move {
-            let _ = drop_rx.await;
-            let _ = tx_drop.send(PortEvt::SenderDropped { local_port }).await;
+            if drop_rx.await.is_err() {
+                let _ = tx_drop.send(PortEvt::SenderDropped { local_port }).await;
+            }
         });
 
         Self {
@@ -234,13 +246,14 @@ impl Sender {
             remote_port,
             chunk_size,
             max_data_size,
+            data_headers,
             tx,
             credits,
             hangup_recved,
             hangup_notify,
             port_allocator,
             storage,
-            _drop_tx,
+            drop_tx: Some(drop_tx),
         }
     }
 
@@ -269,6 +282,26 @@ impl Sender {
         self.max_data_size
     }
 
+    /// Number of bytes sent that have not yet been consumed by the remote endpoint.
+    ///
+    /// This is the occupied part of the flow control window, which
+    /// is limited by the receive buffer size of the remote endpoint.
+    pub fn buffered_bytes(&self) -> usize {
+        self.credits.used() as usize
+    }
+
+    /// Returns true, if the flow control window is exhausted and
+    /// thus sending must wait for the remote endpoint to consume data.
+    pub fn is_backpressured(&self) -> bool {
+        self.credits.is_exhausted()
+    }
+
+    /// Total time sends on this port have spent waiting for the
+    /// remote endpoint to consume data.
+    pub fn blocked_duration(&self) -> Duration {
+        self.credits.blocked()
+    }
+
     /// Sends data over the channel.
     ///
     /// Waits until send space becomes available.
@@ -277,12 +310,36 @@ impl Sender {
     /// # Cancel safety
     /// If this function is cancelled before completion, the remote endpoint will receive no data.
     #[inline]
-    pub async fn send(&mut self, mut data: Bytes) -> Result<(), SendError> {
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all, fields(local_port = self.local_port, remote_port = self.remote_port, len = data.len())))]
+    pub async fn send(&mut self, data: Bytes) -> Result<(), SendError> {
+        self.send_int(data, false).await
+    }
+
+    /// Sends a header block that precedes the next message sent over the channel.
+    ///
+    /// Returns `false` without sending anything if the remote endpoint does
+    /// not support headers.
+    pub(crate) async fn send_headers(&mut self, data: Bytes) -> Result<bool, SendError> {
+        if !self.data_headers {
+            return Ok(false);
+        }
+        self.send_int(data, true).await?;
+        Ok(true)
+    }
+
+    async fn send_int(&mut self, mut data: Bytes, headers: bool) -> Result<(), SendError> {
         if data.is_empty() {
             let mut credits = self.credits.request(1, 1).await?;
             credits.take(1);
 
-            let msg = PortEvt::SendData { remote_port: self.remote_port, data, first: true, last: true };
+            let msg = PortEvt::SendData {
+                local_port: self.local_port,
+                remote_port: self.remote_port,
+                data,
+                first: true,
+                last: true,
+                headers,
+            };
             self.tx.send(msg).await?;
         } else {
             let mut first = true;
@@ -299,10 +356,12 @@ impl Sender {
                 credits.take(chunk.len() as u32);
 
                 let msg = PortEvt::SendData {
+                    local_port: self.local_port,
                     remote_port: self.remote_port,
                     data: chunk,
                     first,
                     last: data.is_empty(),
+                    headers,
                 };
                 self.tx.send(msg).await?;
 
@@ -324,16 +383,28 @@ impl Sender {
     /// Does not wait until send space becomes available.
     /// The maximum size of data sendable by this function is limited by
     /// the total receive buffer size.
+    ///
+    /// If [TrySendError::Full] is returned, no data has been sent and
+    /// the channel remains usable.
     #[inline]
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all, fields(local_port = self.local_port, remote_port = self.remote_port, len = data.len())))]
     pub fn try_send(&mut self, data: &Bytes) -> Result<(), TrySendError> {
         let mut data = data.clone();
 
         if data.is_empty() {
             match self.credits.try_request(1)? {
                 Some(mut credits) => {
+                    let permit = self.tx.try_reserve()?;
                     credits.take(1);
-                    let msg = PortEvt::SendData { remote_port: self.remote_port, data, first: true, last: true };
-                    self.tx.try_send(msg)?;
+                    let msg = PortEvt::SendData {
+                        local_port: self.local_port,
+                        remote_port: self.remote_port,
+                        data,
+                        first: true,
+                        last: true,
+                        headers: false,
+                    };
+                    permit.send(msg);
                     Ok(())
                 }
                 None => Err(TrySendError::Full),
@@ -341,20 +412,27 @@ impl Sender {
         } else {
             match self.credits.try_request(data.len().min(u32::MAX as usize) as u32)? {
                 Some(mut credits) => {
+                    // Reserve queue space for all chunks, so that data is either sent
+                    // completely or not at all.
+                    let n_chunks = (data.len() + self.chunk_size - 1) / self.chunk_size;
+                    let permits = (0..n_chunks).map(|_| self.tx.try_reserve()).collect::<Result<Vec<_>, _>>()?;
+
                     let mut first = true;
-                    while !data.is_empty() {
+                    for permit in permits {
                         let at = data.len().min(self.chunk_size);
                         let chunk = data.split_to(at);
 
                         credits.take(chunk.len() as u32);
 
                         let msg = PortEvt::SendData {
+                            local_port: self.local_port,
                             remote_port: self.remote_port,
                             data: chunk,
                             first,
                             last: data.is_empty(),
+                            headers: false,
                         };
-                        self.tx.try_send(msg)?;
+                        permit.send(msg);
 
                         first = false;
                     }
@@ -370,6 +448,7 @@ impl Sender {
     /// The receiver limits the number of ports sendable per call, see
     /// [Receiver::max_ports](super::Receiver::max_ports).
     #[inline]
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all, fields(local_port = self.local_port, remote_port = self.remote_port, ports = ports.len())))]
     pub async fn connect(&mut self, ports: Vec<PortReq>, wait: bool) -> Result<Vec<Connect>, SendError> {
         let mut ports_response = Vec::new();
         let mut sent_txs = Vec::new();
@@ -382,9 +461,11 @@ impl Sender {
             let response = tokio::spawn(async move {
                 match response_rx.await {
                     Ok(ConnectResponse::Accepted(sender, receiver)) => Ok((sender, receiver)),
-                    Ok(ConnectResponse::Rejected { no_ports }) => {
+                    Ok(ConnectResponse::Rejected { no_ports, no_route }) => {
                         if no_ports {
                             Err(ConnectError::RemotePortsExhausted)
+                        } else if no_route {
+                            Err(ConnectError::NoRoute)
                         } else {
                             Err(ConnectError::Rejected)
                         }
@@ -409,13 +490,25 @@ impl Sender {
                     self.credits.request(data_len.min(u32::MAX as usize) as u32, size_of::<u32>() as u32).await?;
             }
 
+            // Limit ports by credits and encoded size, but always send at least one port.
             let max_ports = self.chunk_size.min(credits.available() as usize) / size_of::<u32>();
+            let max_ports = ports_response
+                .iter()
+                .take(max_ports)
+                .scan(0, |len, (req, _)| {
+                    *len += req.encoded_len();
+                    Some(*len)
+                })
+                .take_while(|&len| len <= self.chunk_size)
+                .count()
+                .max(1);
             let next =
                 if ports_response.len() > max_ports { ports_response.split_off(max_ports) } else { Vec::new() };
 
             credits.take((ports_response.len() * size_of::<u32>()) as u32);
 
             let msg = PortEvt::SendPorts {
+                local_port: self.local_port,
                 remote_port: self.remote_port,
                 first,
                 last: next.is_empty(),
@@ -431,6 +524,22 @@ impl Sender {
         Ok(connects)
     }
 
+    /// Flushes the transport of the connection.
+    ///
+    /// Completes once all data sent over this channel before has been written to
+    /// the transport sink and the sink has been flushed.
+    /// This does not wait for the remote endpoint to receive the data.
+    ///
+    /// Since the transport is shared, this also flushes data queued by
+    /// other channels of the connection.
+    /// Normally the transport is flushed automatically once no more data is queued for
+    /// the [flush delay](super::Cfg::flush_delay).
+    pub async fn flush(&self) -> Result<(), SendError> {
+        let (done_tx, done_rx) = oneshot::channel();
+        self.tx.send(PortEvt::Flush { done_tx }).await?;
+        done_rx.await.map_err(|_| SendError::ChMux)
+    }
+
     /// True, once the remote endpoint has closed its receiver.
     #[inline]
     pub fn is_closed(&self) -> bool {
@@ -461,7 +570,23 @@ impl Sender {
         self.credits.override_graceful_close = override_graceful_close;
     }
 
+    /// Finishes sending, indicating the end of data to the remote endpoint.
+    ///
+    /// The remote [Receiver](super::Receiver) will return `Ok(None)` after it has
+    /// received all data sent before.
+    /// The opposite direction of the port is unaffected, i.e. data can still be
+    /// received from the remote endpoint.
+    ///
+    /// Dropping the sender has the same effect, but does not wait until the
+    /// end of data has been queued for transmission.
+    pub async fn finish(mut self) {
+        let _ = self.tx.send(PortEvt::SenderDropped { local_port: self.local_port }).await;
+        let _ = self.drop_tx.take().unwrap().send(());
+    }
+
     /// Convert this into a sink.
+    ///
+    /// See [SenderSink] for details.
     pub fn into_sink(self) -> SenderSink {
         SenderSink::new(self)
     }
@@ -501,8 +626,14 @@ impl<'a> ChunkSender<'a> {
             }
             self.credits.take(1);
 
-            let msg =
-                PortEvt::SendData { remote_port: self.sender.remote_port, data, first: self.first, last: finish };
+            let msg = PortEvt::SendData {
+                local_port: self.sender.local_port,
+                remote_port: self.sender.remote_port,
+                data,
+                first: self.first,
+                last: finish,
+                headers: false,
+            };
             self.sender.tx.send(msg).await?;
 
             self.first = false;
@@ -519,10 +650,12 @@ impl<'a> ChunkSender<'a> {
                 self.credits.take(chunk.len() as u32);
 
                 let msg = PortEvt::SendData {
+                    local_port: self.sender.local_port,
                     remote_port: self.sender.remote_port,
                     data: chunk,
                     first: self.first,
                     last: data.is_empty() && finish,
+                    headers: false,
                 };
                 self.sender.tx.send(msg).await?;
 
@@ -561,6 +694,31 @@ impl<'a> ChunkSender<'a> {
 }
 
 /// A sink sending byte data over a channel.
+///
+/// Each item is sent as a separate message.
+/// Sending waits for flow control credits of the remote endpoint, thus the sink only
+/// becomes ready and completes flushing once the previous item has been accepted
+/// for transmission.
+/// Closing the sink drops the sender, which closes the channel at the remote endpoint
+/// after all data has been transmitted.
+///
+/// # Example
+///
+/// The following function relays all data received over one channel to another channel,
+/// possibly on a different connection, without deserializing it.
+///
+/// ```
+/// use futures::{future, StreamExt};
+/// use remoc::chmux::{Receiver, ReceiverChunkStream, SendError, Sender, SenderSink};
+///
+/// async fn relay(rx: Receiver, tx: Sender) -> Result<(), SendError> {
+///     ReceiverChunkStream::from(rx)
+///         .take_while(|res| future::ready(res.is_ok()))
+///         .map(|res| Ok(res.unwrap()))
+///         .forward(SenderSink::from(tx))
+///         .await
+/// }
+/// ```
 pub struct SenderSink {
     sender: Option<Arc<Mutex<Sender>>>,
     send_fut: Option<BoxFuture<'static, Result<(), SendError>>>,
@@ -627,3 +785,9 @@ impl Sink<Bytes> for SenderSink {
         Poll::Ready(Ok(()))
     }
 }
+
+impl From<Sender> for SenderSink {
+    fn from(sender: Sender) -> Self {
+        Self::new(sender)
+    }
+}
@@ -0,0 +1,129 @@
+use std::{fmt, time::Duration};
+use tokio::sync::{mpsc, oneshot};
+
+/// Reason for closing a connection that is transmitted to the remote endpoint.
+///
+/// A reason is sent by [ShutdownHandle::close_with_reason] and returned by the remote
+/// endpoint as [ChMuxError::Closed](super::ChMuxError::Closed) when its multiplexer terminates.
+/// The meaning of the code is defined by the application.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CloseReason {
+    code: u32,
+    message: String,
+}
+
+impl CloseReason {
+    /// Maximum length of the message in bytes.
+    pub const MAX_MESSAGE_LEN: usize = u8::MAX as usize;
+
+    /// Creates a close reason consisting of a code and a message.
+    ///
+    /// A message exceeding [MAX_MESSAGE_LEN](Self::MAX_MESSAGE_LEN) bytes is truncated
+    /// at the last character boundary within the limit.
+    pub fn new(code: u32, message: impl Into<String>) -> Self {
+        let mut message = message.into();
+        if message.len() > Self::MAX_MESSAGE_LEN {
+            let len = (0..=Self::MAX_MESSAGE_LEN).rev().find(|&len| message.is_char_boundary(len)).unwrap_or(0);
+            message.truncate(len);
+        }
+        Self { code, message }
+    }
+
+    /// Application-defined code.
+    pub fn code(&self) -> u32 {
+        self.code
+    }
+
+    /// Human-readable message, possibly empty.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for CloseReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.message.is_empty() {
+            write!(f, "code {}", self.code)
+        } else {
+            write!(f, "code {}: {}", self.code, &self.message)
+        }
+    }
+}
+
+/// Request to the multiplexer to terminate.
+#[derive(Debug)]
+pub(crate) enum TerminateReq {
+    /// Terminate immediately, forcibly closing all open ports.
+    Forced {
+        /// Reason transmitted to the remote endpoint.
+        reason: Option<CloseReason>,
+    },
+    /// Shut down gracefully.
+    Graceful {
+        /// Time after which open ports are forcibly closed.
+        timeout: Duration,
+        /// Dropped when the multiplexer has terminated.
+        done_tx: oneshot::Sender<()>,
+    },
+}
+
+/// Handle for terminating or gracefully shutting down a multiplexer.
+///
+/// This can be cloned and does not keep the connection alive.
+#[derive(Clone)]
+pub struct ShutdownHandle {
+    tx: mpsc::UnboundedSender<TerminateReq>,
+}
+
+impl fmt::Debug for ShutdownHandle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ShutdownHandle").finish()
+    }
+}
+
+impl ShutdownHandle {
+    pub(crate) fn new(tx: mpsc::UnboundedSender<TerminateReq>) -> Self {
+        Self { tx }
+    }
+
+    /// Terminates the multiplexer, forcibly closing all open ports.
+    pub fn terminate(&self) {
+        let _ = self.tx.send(TerminateReq::Forced { reason: None });
+    }
+
+    /// Terminates the multiplexer like [terminate](Self::terminate) and
+    /// transmits the specified reason to the remote endpoint.
+    ///
+    /// The multiplexer of the remote endpoint terminates with
+    /// [ChMuxError::Closed](super::ChMuxError::Closed) containing the reason.
+    /// If the remote endpoint uses a protocol version that does not support close reasons,
+    /// the connection is terminated without transmitting it.
+    /// A message exceeding [CloseReason::MAX_MESSAGE_LEN] bytes is truncated.
+    pub fn close_with_reason(&self, code: u32, message: impl Into<String>) {
+        let reason = CloseReason::new(code, message);
+        let _ = self.tx.send(TerminateReq::Forced { reason: Some(reason) });
+    }
+
+    /// Gracefully shuts down the multiplexer and waits for it to terminate.
+    ///
+    /// New ports are neither opened nor accepted anymore, locally as well as remotely.
+    /// Data already sent over open ports is transmitted and the multiplexer waits for
+    /// all ports to be closed.
+    /// Then the remote endpoint is asked to acknowledge the termination of the connection.
+    ///
+    /// If this does not complete within the specified timeout, ports that are still open
+    /// are closed and fail with a `Shutdown` error, i.e. [SendError::Shutdown](super::SendError::Shutdown)
+    /// and [RecvError::Shutdown](super::RecvError::Shutdown).
+    /// The multiplexer then resets the connection without waiting for acknowledgement from the
+    /// remote endpoint, whose multiplexer consequently fails with [ChMuxError::Reset](super::ChMuxError::Reset).
+    ///
+    /// This returns immediately if the multiplexer has already terminated.
+    /// The result of the connection is returned by [ChMux::run](super::ChMux::run).
+    pub async fn shutdown(&self, timeout: Duration) {
+        let (done_tx, done_rx) = oneshot::channel();
+        if self.tx.send(TerminateReq::Graceful { timeout, done_tx }).is_ok() {
+            let _ = done_rx.await;
+        }
+    }
+}
@@ -0,0 +1,74 @@
+//! Connection statistics.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Statistics of a channel multiplexer connection.
+///
+/// This is a snapshot obtained by calling `stats` on the [ChMux](super::ChMux),
+/// [Client](super::Client) or [Listener](super::Listener).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct Stats {
+    /// Total number of bytes passed to the transport sink.
+    pub sent_bytes: u64,
+    /// Total number of bytes received from the transport stream.
+    pub received_bytes: u64,
+    /// Total number of ports that have been opened.
+    pub opened_ports: u64,
+    /// Number of currently open ports.
+    pub open_ports: u64,
+    /// Number of bytes passed to the transport sink that have not been flushed yet.
+    pub unflushed_bytes: u64,
+}
+
+/// Statistics counters shared between the multiplexer and its handles.
+#[derive(Debug, Default)]
+pub(crate) struct StatsCounters {
+    sent_bytes: AtomicU64,
+    received_bytes: AtomicU64,
+    opened_ports: AtomicU64,
+    closed_ports: AtomicU64,
+    unflushed_bytes: AtomicU64,
+}
+
+impl StatsCounters {
+    /// Records bytes passed to the transport sink.
+    pub(crate) fn sent(&self, bytes: usize) {
+        self.sent_bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+        self.unflushed_bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Records that the transport sink has been flushed.
+    pub(crate) fn flushed(&self) {
+        self.unflushed_bytes.store(0, Ordering::Relaxed);
+    }
+
+    /// Records bytes received from the transport stream.
+    pub(crate) fn received(&self, bytes: usize) {
+        self.received_bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Records that a port has been opened.
+    pub(crate) fn port_opened(&self) {
+        self.opened_ports.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records that a port has been closed.
+    pub(crate) fn port_closed(&self) {
+        self.closed_ports.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Takes a snapshot of the counters.
+    pub(crate) fn snapshot(&self) -> Stats {
+        let opened_ports = self.opened_ports.load(Ordering::Relaxed);
+        let closed_ports = self.closed_ports.load(Ordering::Relaxed);
+        Stats {
+            sent_bytes: self.sent_bytes.load(Ordering::Relaxed),
+            received_bytes: self.received_bytes.load(Ordering::Relaxed),
+            opened_ports,
+            open_ports: opened_ports.saturating_sub(closed_ports),
+            unflushed_bytes: self.unflushed_bytes.load(Ordering::Relaxed),
+        }
+    }
+}
@@ -11,6 +11,8 @@ use super::{Codec, DeserializationError, SerializationError};
 pub struct Bincode;
 
 impl Codec for Bincode {
+    const ID: Option<&'static str> = Some("bincode");
+
     #[inline]
     fn serialize<Writer, Item>(writer: Writer, item: &Item) -> Result<(), super::SerializationError>
     where
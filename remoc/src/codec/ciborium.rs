@@ -1,3 +1,4 @@
+use ciborium::tag::{Accepted, Required};
 use serde::{Deserialize, Serialize};
 
 use super::{Codec, DeserializationError, SerializationError};
@@ -18,6 +19,8 @@ use super::{Codec, DeserializationError, SerializationError};
 pub struct Ciborium;
 
 impl Codec for Ciborium {
+    const ID: Option<&'static str> = Some("ciborium");
+
     #[inline]
     fn serialize<Writer, Item>(writer: Writer, item: &Item) -> Result<(), super::SerializationError>
     where
@@ -36,3 +39,45 @@ impl Codec for Ciborium {
         ciborium::de::from_reader(reader).map_err(DeserializationError::new)
     }
 }
+
+/// Self-described CBOR codec using [ciborium].
+///
+/// This behaves like [Ciborium], but prefixes each encoded value with the
+/// self-described CBOR tag 55799, as specified in RFC 8949 section 3.4.6.
+/// This allows other implementations to recognize the data as CBOR at the
+/// expense of three additional bytes per value.
+///
+/// The tag is optional during decoding, thus this codec is able to decode
+/// values encoded with [Ciborium].
+#[cfg_attr(docsrs, doc(cfg(feature = "codec-ciborium")))]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CiboriumSelfDescribed;
+
+impl CiboriumSelfDescribed {
+    /// Self-described CBOR tag.
+    pub const TAG: u64 = 55799;
+}
+
+impl Codec for CiboriumSelfDescribed {
+    const ID: Option<&'static str> = Some("ciborium-self-described");
+
+    #[inline]
+    fn serialize<Writer, Item>(writer: Writer, item: &Item) -> Result<(), super::SerializationError>
+    where
+        Writer: std::io::Write,
+        Item: serde::Serialize,
+    {
+        ciborium::ser::into_writer(&Required::<_, { Self::TAG }>(item), writer).map_err(SerializationError::new)
+    }
+
+    #[inline]
+    fn deserialize<Reader, Item>(reader: Reader) -> Result<Item, super::DeserializationError>
+    where
+        Reader: std::io::Read,
+        Item: serde::de::DeserializeOwned,
+    {
+        let Accepted::<_, { Self::TAG }>(item) =
+            ciborium::de::from_reader(reader).map_err(DeserializationError::new)?;
+        Ok(item)
+    }
+}
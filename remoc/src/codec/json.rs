@@ -13,6 +13,8 @@ use super::{Codec, DeserializationError, SerializationError};
 pub struct Json;
 
 impl Codec for Json {
+    const ID: Option<&'static str> = Some("json");
+
     #[inline]
     fn serialize<Writer, Item>(writer: Writer, item: &Item) -> Result<(), super::SerializationError>
     where
@@ -31,3 +33,42 @@ impl Codec for Json {
         serde_json::from_reader(reader).map_err(DeserializationError::new)
     }
 }
+
+/// Newline-delimited JSON codec.
+///
+/// This behaves like [Json], but terminates each encoded value with a newline character.
+/// Values are encoded in compact form and newlines within strings are escaped,
+/// thus each value occupies exactly one line.
+/// This makes the output consumable by tools expecting [newline-delimited JSON],
+/// such as `jq` and log pipelines, when bridged to a byte stream.
+///
+/// The terminating newline is optional during decoding, thus this codec is able to decode
+/// values encoded with [Json].
+///
+/// [newline-delimited JSON]: https://github.com/ndjson/ndjson-spec
+#[cfg_attr(docsrs, doc(cfg(feature = "codec-json")))]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct JsonLines;
+
+impl Codec for JsonLines {
+    const ID: Option<&'static str> = Some("json-lines");
+
+    #[inline]
+    fn serialize<Writer, Item>(mut writer: Writer, item: &Item) -> Result<(), super::SerializationError>
+    where
+        Writer: std::io::Write,
+        Item: serde::Serialize,
+    {
+        serde_json::to_writer(&mut writer, item).map_err(SerializationError::new)?;
+        writer.write_all(b"\n").map_err(SerializationError::new)
+    }
+
+    #[inline]
+    fn deserialize<Reader, Item>(reader: Reader) -> Result<Item, super::DeserializationError>
+    where
+        Reader: std::io::Read,
+        Item: serde::de::DeserializeOwned,
+    {
+        serde_json::from_reader(reader).map_err(DeserializationError::new)
+    }
+}
@@ -11,6 +11,8 @@ use super::{Codec, DeserializationError, SerializationError};
 pub struct MessagePack;
 
 impl Codec for MessagePack {
+    const ID: Option<&'static str> = Some("message-pack");
+
     #[inline]
     fn serialize<Writer, Item>(mut writer: Writer, item: &Item) -> Result<(), super::SerializationError>
     where
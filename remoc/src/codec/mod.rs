@@ -10,6 +10,9 @@
 //! Each codec is gated by the corresponding crate feature `codec-*`, i.e.
 //! the JSON codec is only available if the crate features `codec-json` is enabled.
 //! The crate feature `full-codecs` enables all codecs.
+//! Only the serialization crates of enabled codecs are compiled, thus enabling a single
+//! codec, for example `default-codec-bincode` without default features, does not
+//! pull in the dependencies of other codecs.
 //!
 //! The default codec, named [Default](struct@Default), can be selected by enabling the
 //! appropriate `default-codec-*` crate feature.
@@ -143,6 +146,13 @@ impl<'de> Deserialize<'de> for DeserializationError {
 
 /// Serializes and deserializes items from and to byte data.
 pub trait Codec: Send + Sync + Serialize + for<'de> Deserialize<'de> + Clone + Unpin + 'static {
+    /// Stable identifier of the data format.
+    ///
+    /// It is exchanged when [establishing a connection](crate::Connect) and the connection
+    /// fails if the identifiers of both endpoints differ.
+    /// If [None], the codec is not verified.
+    const ID: Option<&'static str> = None;
+
     /// Serializes the specified item into the data format.
     fn serialize<Writer, Item>(writer: Writer, item: &Item) -> Result<(), SerializationError>
     where
@@ -172,19 +182,19 @@ pub use self::bincode::Bincode as Default;
 
 #[cfg(feature = "codec-ciborium")]
 mod ciborium;
-#[cfg(feature = "codec-ciborium")]
-pub use self::ciborium::Ciborium;
 #[cfg(feature = "default-codec-ciborium")]
 #[doc(no_inline)]
 pub use self::ciborium::Ciborium as Default;
+#[cfg(feature = "codec-ciborium")]
+pub use self::ciborium::{Ciborium, CiboriumSelfDescribed};
 
 #[cfg(feature = "codec-json")]
 mod json;
-#[cfg(feature = "codec-json")]
-pub use json::Json;
 #[cfg(feature = "default-codec-json")]
 #[doc(no_inline)]
 pub use json::Json as Default;
+#[cfg(feature = "codec-json")]
+pub use json::{Json, JsonLines};
 
 #[cfg(feature = "codec-message-pack")]
 mod message_pack;
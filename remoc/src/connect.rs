@@ -1,19 +1,21 @@
 //! Initial connection functions.
 
 use bytes::Bytes;
-use futures::{future::BoxFuture, Future, FutureExt, Sink, Stream, TryStreamExt};
+use futures::{future::BoxFuture, Future, FutureExt, Sink, SinkExt, Stream, StreamExt, TryStreamExt};
 use std::{
     convert::TryInto,
     error::Error,
     fmt, io,
     pin::Pin,
     task::{Context, Poll},
+    time::Duration,
 };
 use tokio::io::{AsyncRead, AsyncWrite, BufReader, BufWriter};
 use tokio_util::codec::LengthDelimitedCodec;
+use tracing::Instrument;
 
 use crate::{
-    chmux::{ChMux, ChMuxError},
+    chmux::{ChMux, ChMuxError, Negotiated, ShutdownHandle},
     codec,
     rch::base,
     RemoteSend,
@@ -177,13 +179,27 @@ impl<TransportSinkError, TransportStreamError> From<base::ConnectError>
 /// ```
 #[cfg_attr(docsrs, doc(cfg(feature = "rch")))]
 #[must_use = "You must poll or spawn the Connect future for the connection to work."]
-pub struct Connect<'transport, TransportSinkError, TransportStreamError>(
-    BoxFuture<'transport, Result<(), ChMuxError<TransportSinkError, TransportStreamError>>>,
-);
+pub struct Connect<'transport, TransportSinkError, TransportStreamError> {
+    fut: BoxFuture<'transport, Result<(), ChMuxError<TransportSinkError, TransportStreamError>>>,
+    negotiated: Negotiated,
+    shutdown: ShutdownHandle,
+}
 
 impl<'transport, TransportSinkError, TransportStreamError>
     Connect<'transport, TransportSinkError, TransportStreamError>
 {
+    /// Returns the connection parameters negotiated with the remote endpoint.
+    pub fn negotiated(&self) -> &Negotiated {
+        &self.negotiated
+    }
+
+    /// Returns a handle for terminating or gracefully shutting down the connection.
+    ///
+    /// Obtain the handle before spawning the connection onto a task.
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        self.shutdown.clone()
+    }
+
     /// Establishes a connection over a framed transport (a [sink](Sink) and a [stream](Stream) of binary data) and
     /// returns a remote [sender](base::Sender) and [receiver](base::Receiver).
     ///
@@ -212,23 +228,46 @@ impl<'transport, TransportSinkError, TransportStreamError>
         Rx: RemoteSend,
         Codec: codec::Codec,
     {
-        let (mux, client, mut listener) = ChMux::new(cfg, transport_sink, transport_stream).await?;
-        let mut connection = Self(mux.run().boxed());
-
-        tokio::select! {
-            biased;
-            Err(err) = &mut connection => Err(err.into()),
-            result = base::connect(&client, &mut listener) => {
-                match result {
-                    Ok((tx, rx)) => Ok((connection, tx, rx)),
-                    Err(err) => Err(err.into()),
+        #[cfg(feature = "tracing")]
+        let span = tracing::debug_span!("connect");
+        #[cfg(not(feature = "tracing"))]
+        let span = tracing::Span::none();
+        async move {
+            let (mux, client, mut listener) = ChMux::new(cfg, transport_sink, transport_stream).await?;
+            let negotiated = mux.negotiated().clone();
+            let shutdown = mux.shutdown_handle();
+            let mut connection = Self { fut: mux.run().boxed(), negotiated, shutdown };
+
+            tokio::select! {
+                biased;
+                Err(err) = &mut connection => Err(err.into()),
+                result = base::connect(&client, &mut listener) => {
+                    match result {
+                        Ok((tx, rx)) => {
+                            tracing::debug!("initial channel connected");
+                            Ok((connection, tx, rx))
+                        }
+                        Err(err) => {
+                            tracing::debug!(%err, "connecting initial channel failed");
+                            Err(err.into())
+                        }
+                    }
                 }
             }
         }
+        .instrument(span)
+        .await
     }
 }
 
 impl<'transport> Connect<'transport, io::Error, io::Error> {
+    /// Returns a builder for configuring and establishing a connection.
+    ///
+    /// See [ConnectBuilder] for details.
+    pub fn builder() -> ConnectBuilder {
+        ConnectBuilder::new()
+    }
+
     /// Establishes a connection over an IO transport (an [AsyncRead] and [AsyncWrite]) and
     /// returns a remote [sender](base::Sender) and [receiver](base::Receiver).
     ///
@@ -300,6 +339,340 @@ impl<'transport> Connect<'transport, io::Error, io::Error> {
         let buf_output = BufWriter::with_capacity(buffer, output);
         Self::io(cfg, buf_input, buf_output).await
     }
+
+    /// Establishes a connection between two endpoints within the same process and
+    /// returns the connection, remote [sender](base::Sender) and [receiver](base::Receiver)
+    /// of each endpoint.
+    ///
+    /// The endpoints are connected by an in-memory transport, which queues up to
+    /// `queue_length` chmux messages in each direction.
+    /// Both endpoints use the same chmux configuration.
+    /// Items sent by the sender of one endpoint are received by the receiver of the other.
+    ///
+    /// This is useful for testing code that uses Remoc without establishing a physical connection.
+    ///
+    /// You must poll both returned [Connect] futures or spawn them for the connection to work.
+    /// Dropping one of them simulates a connection failure.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use remoc::prelude::*;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let ((a_conn, mut a_tx, _a_rx), (b_conn, _b_tx, mut b_rx)) =
+    ///         remoc::Connect::loopback::<String, (), remoc::codec::Default>(remoc::Cfg::default(), 1)
+    ///             .await
+    ///             .unwrap();
+    ///     tokio::spawn(a_conn);
+    ///     tokio::spawn(b_conn);
+    ///
+    ///     a_tx.send("Hello".to_string()).await.unwrap();
+    ///     assert_eq!(b_rx.recv().await.unwrap(), Some("Hello".to_string()));
+    /// }
+    /// ```
+    ///
+    /// # Panics
+    /// Panics if the chmux configuration is invalid.
+    #[allow(clippy::type_complexity)]
+    pub async fn loopback<Tx, Rx, Codec>(
+        cfg: crate::Cfg, queue_length: usize,
+    ) -> Result<
+        (
+            (Connect<'transport, io::Error, io::Error>, base::Sender<Tx, Codec>, base::Receiver<Rx, Codec>),
+            (Connect<'transport, io::Error, io::Error>, base::Sender<Rx, Codec>, base::Receiver<Tx, Codec>),
+        ),
+        ConnectError<io::Error, io::Error>,
+    >
+    where
+        Tx: RemoteSend,
+        Rx: RemoteSend,
+        Codec: codec::Codec,
+    {
+        fn transport(
+            queue_length: usize,
+        ) -> (
+            impl Sink<Bytes, Error = io::Error> + Send + Sync + Unpin,
+            impl Stream<Item = Result<Bytes, io::Error>> + Send + Sync + Unpin,
+        ) {
+            let (tx, rx) = futures::channel::mpsc::channel::<Bytes>(queue_length);
+            let tx = tx.sink_map_err(|err| io::Error::new(io::ErrorKind::BrokenPipe, err));
+            let rx = rx.map(Ok);
+            (tx, rx)
+        }
+
+        let (a_tx, b_rx) = transport(queue_length);
+        let (b_tx, a_rx) = transport(queue_length);
+
+        futures::try_join!(Self::framed(cfg.clone(), a_tx, a_rx), Self::framed(cfg, b_tx, b_rx))
+    }
+}
+
+/// Builder for configuring and establishing a connection over a physical transport.
+///
+/// This is obtained by [Connect::builder] and collects the [chmux configuration](crate::Cfg),
+/// buffer sizes and limits in one place.
+/// Options that are not set keep their default values.
+/// Call [framed](Self::framed), [io](Self::io) or [loopback](Self::loopback) to establish the connection;
+/// the result is the same as from the corresponding methods of [Connect].
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use remoc::prelude::*;
+///
+/// # async fn example(socket_rx: tokio::net::tcp::OwnedReadHalf, socket_tx: tokio::net::tcp::OwnedWriteHalf) {
+/// let (conn, tx, rx): (_, rch::base::Sender<String>, rch::base::Receiver<String>) = remoc::Connect::builder()
+///     .receive_buffer(1_048_576)
+///     .io_buffer(65_536)
+///     .connection_timeout(Some(Duration::from_secs(30)))
+///     .max_item_size(1_000_000)
+///     .io(socket_rx, socket_tx)
+///     .await
+///     .unwrap();
+/// tokio::spawn(conn);
+/// # }
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "rch")))]
+#[derive(Clone, Debug, Default)]
+pub struct ConnectBuilder {
+    cfg: crate::Cfg,
+    io_buffer: Option<usize>,
+    max_item_size: Option<usize>,
+}
+
+impl ConnectBuilder {
+    /// Creates a builder using the default configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the chmux configuration.
+    ///
+    /// This replaces all chmux options set before, thus it should be called first.
+    pub fn cfg(mut self, cfg: crate::Cfg) -> Self {
+        self.cfg = cfg;
+        self
+    }
+
+    /// Sets the time after which the connection is closed when no data is received.
+    ///
+    /// See [Cfg::connection_timeout](crate::Cfg::connection_timeout).
+    pub fn connection_timeout(mut self, connection_timeout: Option<Duration>) -> Self {
+        self.cfg.connection_timeout = connection_timeout;
+        self
+    }
+
+    /// Sets the maximum interval between messages sent to keep the connection alive.
+    ///
+    /// See [Cfg::ping_interval](crate::Cfg::ping_interval).
+    pub fn ping_interval(mut self, ping_interval: Option<Duration>) -> Self {
+        self.cfg.ping_interval = ping_interval;
+        self
+    }
+
+    /// Sets the time after which a port is closed when no data is transmitted over it.
+    ///
+    /// See [Cfg::idle_timeout](crate::Cfg::idle_timeout).
+    pub fn idle_timeout(mut self, idle_timeout: Option<Duration>) -> Self {
+        self.cfg.idle_timeout = idle_timeout;
+        self
+    }
+
+    /// Sets the size of the receive buffer of each port in bytes.
+    ///
+    /// See [Cfg::receive_buffer](crate::Cfg::receive_buffer).
+    pub fn receive_buffer(mut self, receive_buffer: u32) -> Self {
+        self.cfg.receive_buffer = receive_buffer;
+        self
+    }
+
+    /// Sets the maximum amount of in-flight data of all ports in bytes.
+    ///
+    /// See [Cfg::max_connection_buffer](crate::Cfg::max_connection_buffer).
+    pub fn max_connection_buffer(mut self, max_connection_buffer: Option<usize>) -> Self {
+        self.cfg.max_connection_buffer = max_connection_buffer;
+        self
+    }
+
+    /// Sets the amount of consumed data after which flow control credits are returned.
+    ///
+    /// See [Cfg::credit_return_threshold](crate::Cfg::credit_return_threshold).
+    pub fn credit_return_threshold(mut self, credit_return_threshold: Option<u32>) -> Self {
+        self.cfg.credit_return_threshold = credit_return_threshold;
+        self
+    }
+
+    /// Sets whether to protect transmitted data by checksums.
+    ///
+    /// See [Cfg::checksum](crate::Cfg::checksum).
+    pub fn checksum(mut self, checksum: bool) -> Self {
+        self.cfg.checksum = checksum;
+        self
+    }
+
+    /// Sets the maximum size of a chunk of data in bytes.
+    ///
+    /// See [Cfg::chunk_size](crate::Cfg::chunk_size).
+    pub fn chunk_size(mut self, chunk_size: u32) -> Self {
+        self.cfg.chunk_size = chunk_size;
+        self
+    }
+
+    /// Sets the maximum size of received data per message in bytes.
+    ///
+    /// See [Cfg::max_data_size](crate::Cfg::max_data_size).
+    pub fn max_data_size(mut self, max_data_size: usize) -> Self {
+        self.cfg.max_data_size = max_data_size;
+        self
+    }
+
+    /// Sets the lengths of the transport send and receive queues.
+    ///
+    /// See [Cfg::transport_send_queue](crate::Cfg::transport_send_queue) and
+    /// [Cfg::transport_receive_queue](crate::Cfg::transport_receive_queue).
+    pub fn transport_queues(mut self, send_queue: usize, receive_queue: usize) -> Self {
+        self.cfg.transport_send_queue = send_queue;
+        self.cfg.transport_receive_queue = receive_queue;
+        self
+    }
+
+    /// Sets the size in bytes of the read and write buffers used by [io](Self::io).
+    ///
+    /// By default no buffering is performed, see [Connect::io_buffered].
+    /// This has no effect on [framed](Self::framed).
+    pub fn io_buffer(mut self, io_buffer: usize) -> Self {
+        self.io_buffer = Some(io_buffer);
+        self
+    }
+
+    /// Sets the maximum allowed size in bytes of an item sent or received over the
+    /// initial base channel.
+    ///
+    /// See [base::Sender::set_max_item_size] and [base::Receiver::set_max_item_size].
+    /// By default this is [DEFAULT_MAX_ITEM_SIZE](crate::rch::DEFAULT_MAX_ITEM_SIZE).
+    pub fn max_item_size(mut self, max_item_size: usize) -> Self {
+        self.max_item_size = Some(max_item_size);
+        self
+    }
+
+    /// Returns the chmux configuration that will be used.
+    pub fn chmux_cfg(&self) -> &crate::Cfg {
+        &self.cfg
+    }
+
+    /// Applies the limits of the base channel.
+    fn apply<Tx, Rx, Codec>(&self, tx: &mut base::Sender<Tx, Codec>, rx: &mut base::Receiver<Rx, Codec>)
+    where
+        Tx: RemoteSend,
+        Rx: RemoteSend,
+        Codec: codec::Codec,
+    {
+        if let Some(max_item_size) = self.max_item_size {
+            tx.set_max_item_size(max_item_size);
+            rx.set_max_item_size(max_item_size);
+        }
+    }
+
+    /// Establishes a connection over a framed transport (a [sink](Sink) and a [stream](Stream) of binary data)
+    /// using this configuration.
+    ///
+    /// See [Connect::framed] for details.
+    ///
+    /// # Panics
+    /// Panics if the chmux configuration is invalid.
+    pub async fn framed<
+        'transport,
+        TransportSink,
+        TransportSinkError,
+        TransportStream,
+        TransportStreamError,
+        Tx,
+        Rx,
+        Codec,
+    >(
+        self, transport_sink: TransportSink, transport_stream: TransportStream,
+    ) -> Result<
+        (
+            Connect<'transport, TransportSinkError, TransportStreamError>,
+            base::Sender<Tx, Codec>,
+            base::Receiver<Rx, Codec>,
+        ),
+        ConnectError<TransportSinkError, TransportStreamError>,
+    >
+    where
+        TransportSink: Sink<Bytes, Error = TransportSinkError> + Send + Sync + Unpin + 'transport,
+        TransportSinkError: Error + Send + Sync + 'static,
+        TransportStream: Stream<Item = Result<Bytes, TransportStreamError>> + Send + Sync + Unpin + 'transport,
+        TransportStreamError: Error + Send + Sync + 'static,
+        Tx: RemoteSend,
+        Rx: RemoteSend,
+        Codec: codec::Codec,
+    {
+        let (conn, mut tx, mut rx) = Connect::framed(self.cfg.clone(), transport_sink, transport_stream).await?;
+        self.apply(&mut tx, &mut rx);
+        Ok((conn, tx, rx))
+    }
+
+    /// Establishes a connection over an IO transport (an [AsyncRead] and [AsyncWrite])
+    /// using this configuration.
+    ///
+    /// If an [IO buffer size](Self::io_buffer) has been set, reads and writes are buffered
+    /// as by [Connect::io_buffered], otherwise this behaves like [Connect::io].
+    ///
+    /// # Panics
+    /// Panics if the chmux configuration is invalid.
+    pub async fn io<'transport, Read, Write, Tx, Rx, Codec>(
+        self, input: Read, output: Write,
+    ) -> Result<
+        (Connect<'transport, io::Error, io::Error>, base::Sender<Tx, Codec>, base::Receiver<Rx, Codec>),
+        ConnectError<io::Error, io::Error>,
+    >
+    where
+        Read: AsyncRead + Send + Sync + Unpin + 'transport,
+        Write: AsyncWrite + Send + Sync + Unpin + 'transport,
+        Tx: RemoteSend,
+        Rx: RemoteSend,
+        Codec: codec::Codec,
+    {
+        let (conn, mut tx, mut rx) = match self.io_buffer {
+            Some(buffer) => Connect::io_buffered(self.cfg.clone(), input, output, buffer).await?,
+            None => Connect::io(self.cfg.clone(), input, output).await?,
+        };
+        self.apply(&mut tx, &mut rx);
+        Ok((conn, tx, rx))
+    }
+
+    /// Establishes a connection between two endpoints within the same process
+    /// using this configuration.
+    ///
+    /// See [Connect::loopback] for details.
+    ///
+    /// # Panics
+    /// Panics if the chmux configuration is invalid.
+    #[allow(clippy::type_complexity)]
+    pub async fn loopback<'transport, Tx, Rx, Codec>(
+        self, queue_length: usize,
+    ) -> Result<
+        (
+            (Connect<'transport, io::Error, io::Error>, base::Sender<Tx, Codec>, base::Receiver<Rx, Codec>),
+            (Connect<'transport, io::Error, io::Error>, base::Sender<Rx, Codec>, base::Receiver<Tx, Codec>),
+        ),
+        ConnectError<io::Error, io::Error>,
+    >
+    where
+        Tx: RemoteSend,
+        Rx: RemoteSend,
+        Codec: codec::Codec,
+    {
+        let ((a_conn, mut a_tx, mut a_rx), (b_conn, mut b_tx, mut b_rx)) =
+            Connect::loopback(self.cfg.clone(), queue_length).await?;
+        self.apply(&mut a_tx, &mut a_rx);
+        self.apply(&mut b_tx, &mut b_rx);
+        Ok(((a_conn, a_tx, a_rx), (b_conn, b_tx, b_rx)))
+    }
 }
 
 impl<'transport, TransportSinkError, TransportStreamError> Future
@@ -310,6 +683,6 @@ impl<'transport, TransportSinkError, TransportStreamError> Future
 
     /// This future runs the dispatcher for this connection.
     fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
-        Pin::into_inner(self).0.poll_unpin(cx)
+        Pin::into_inner(self).fut.poll_unpin(cx)
     }
 }
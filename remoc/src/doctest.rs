@@ -9,29 +9,11 @@ where
     T1: crate::RemoteSend,
     T2: crate::RemoteSend,
 {
-    use futures::StreamExt;
-
-    let (transport_a_tx, transport_b_rx) = futures::channel::mpsc::channel::<bytes::Bytes>(0);
-    let (transport_b_tx, transport_a_rx) = futures::channel::mpsc::channel::<bytes::Bytes>(0);
-
-    let transport_a_rx = transport_a_rx.map(Ok::<_, std::io::Error>);
-    let transport_b_rx = transport_b_rx.map(Ok::<_, std::io::Error>);
-
-    let a = async move {
-        let (conn, tx, rx) =
-            crate::Connect::framed(Default::default(), transport_a_tx, transport_a_rx).await.unwrap();
-        tokio::spawn(conn);
-        (tx, rx)
-    };
-
-    let b = async move {
-        let (conn, tx, rx) =
-            crate::Connect::framed(Default::default(), transport_b_tx, transport_b_rx).await.unwrap();
-        tokio::spawn(conn);
-        (tx, rx)
-    };
-
-    futures::join!(a, b)
+    let ((a_conn, a_tx, a_rx), (b_conn, b_tx, b_rx)) =
+        crate::Connect::loopback(Default::default(), 0).await.unwrap();
+    tokio::spawn(a_conn);
+    tokio::spawn(b_conn);
+    ((a_tx, a_rx), (b_tx, b_rx))
 }
 
 #[cfg(feature = "rch")]
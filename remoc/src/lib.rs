@@ -94,6 +94,19 @@
 //! Remoc uses the [Tracing crate](tracing) for logging of events.
 //! Setting the log level to `TRACE` logs multiplexer lifetime events and messages as they are being processed.
 //!
+//! The multiplexer lifecycle is logged at the `DEBUG` level.
+//!
+//! Enabling the optional `tracing` crate feature adds further instrumentation.
+//! Connection establishment is then wrapped in a `DEBUG` span and
+//! sending and receiving on a [chmux](crate::chmux) channel is wrapped in a `TRACE` span
+//! carrying the local and remote port numbers; events within it report byte counts,
+//! received chunks and waiting for flow control credits.
+//! Port allocation and release are logged at the `TRACE` level as well.
+//! Without this feature the instrumentation is compiled out and has no runtime cost.
+//!
+//! To statically disable the remaining log levels, use the `max_level_*` and
+//! `release_max_level_*` features of the [Tracing crate](tracing).
+//!
 //! # Example
 //!
 //! This is a short example; for a fully worked remote trait calling (RTC) example
@@ -236,7 +249,7 @@ pub use remote_send::RemoteSend;
 mod connect;
 #[cfg(feature = "rch")]
 #[cfg_attr(docsrs, doc(cfg(feature = "rch")))]
-pub use connect::{Connect, ConnectError};
+pub use connect::{Connect, ConnectBuilder, ConnectError};
 
 #[cfg(feature = "rch")]
 mod connect_ext;
@@ -244,6 +257,10 @@ mod connect_ext;
 #[cfg_attr(docsrs, doc(cfg(feature = "rch")))]
 pub use connect_ext::{ConnectExt, ConsumeError, ProvideError};
 
+#[cfg(feature = "rch")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rch")))]
+pub mod reconnect;
+
 #[cfg(feature = "rfn")]
 #[cfg_attr(docsrs, doc(cfg(feature = "rfn")))]
 pub mod rfn;
@@ -0,0 +1,310 @@
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{fmt, io, marker::PhantomData, time::Duration};
+use tokio::{
+    sync::{mpsc, oneshot, watch},
+    time::{sleep_until, Instant},
+};
+
+use super::{RecvError, SendError, SendErrorKind, Sender};
+use crate::{
+    chmux,
+    codec::{self, DeserializationError, ErrorMsg, SerializationError},
+};
+
+/// Size of length prefix of an item within a batch.
+const LEN_SIZE: usize = 4;
+
+/// Queue length between buffered sender and its flush task.
+const QUEUE: usize = 32;
+
+/// Message to the flush task.
+enum BufferedMsg {
+    /// Serialized item.
+    Item(Bytes),
+    /// Flush request.
+    Flush(oneshot::Sender<()>),
+}
+
+/// Sends items in batches to a remote endpoint.
+///
+/// Serialized items are accumulated until their total size reaches the capacity
+/// or the flush interval elapses, and are then transmitted as a single message.
+/// This avoids the per-message overhead when sending many small items.
+///
+/// Obtained by calling [Sender::buffered].
+/// The remote endpoint must receive using a [BufferedReceiver].
+///
+/// Dropping the buffered sender flushes all pending items.
+///
+/// # Error reporting
+/// Transmission is performed asynchronously.
+/// Thus, a transmission error is reported by the next call to
+/// [send](Self::send) or [flush](Self::flush).
+pub struct BufferedSender<T, Codec = codec::Default> {
+    tx: mpsc::Sender<BufferedMsg>,
+    error_rx: watch::Receiver<Option<chmux::SendError>>,
+    allocator: chmux::PortAllocator,
+    storage: chmux::AnyStorage,
+    capacity: usize,
+    max_data_size: usize,
+    max_item_size: usize,
+    _data: PhantomData<T>,
+    _codec: PhantomData<Codec>,
+}
+
+impl<T, Codec> fmt::Debug for BufferedSender<T, Codec> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BufferedSender").field("capacity", &self.capacity).finish()
+    }
+}
+
+impl<T, Codec> BufferedSender<T, Codec>
+where
+    T: Serialize + Send + 'static,
+    Codec: codec::Codec,
+{
+    pub(super) fn new(
+        sender: chmux::Sender, max_item_size: usize, capacity: usize, flush_interval: Duration,
+    ) -> Self {
+        let max_data_size = sender.max_data_size();
+        let capacity = capacity.clamp(1, max_data_size);
+        let allocator = sender.port_allocator();
+        let storage = sender.storage();
+
+        let (tx, rx) = mpsc::channel(QUEUE);
+        let (error_tx, error_rx) = watch::channel(None);
+        tokio::spawn(Self::flush_task(sender, rx, error_tx, capacity, flush_interval));
+
+        Self {
+            tx,
+            error_rx,
+            allocator,
+            storage,
+            capacity,
+            max_data_size,
+            max_item_size,
+            _data: PhantomData,
+            _codec: PhantomData,
+        }
+    }
+
+    /// Accumulates items and transmits them in batches.
+    async fn flush_task(
+        mut sender: chmux::Sender, mut rx: mpsc::Receiver<BufferedMsg>,
+        error_tx: watch::Sender<Option<chmux::SendError>>, capacity: usize, flush_interval: Duration,
+    ) {
+        let mut batch = BytesMut::new();
+        let mut deadline = None;
+
+        loop {
+            let mut done = None;
+            let mut closed = false;
+
+            tokio::select! {
+                msg = rx.recv() => match msg {
+                    Some(BufferedMsg::Item(data)) => {
+                        // Transmit batch first, if item does not fit anymore.
+                        if !batch.is_empty() && batch.len() + LEN_SIZE + data.len() > capacity {
+                            if let Err(err) = sender.send(batch.split().freeze()).await {
+                                let _ = error_tx.send(Some(err));
+                                return;
+                            }
+                            deadline = None;
+                        }
+
+                        batch.put_u32_le(data.len() as u32);
+                        batch.put(data);
+                        if batch.len() < capacity {
+                            deadline.get_or_insert_with(|| Instant::now() + flush_interval);
+                            continue;
+                        }
+                    }
+                    Some(BufferedMsg::Flush(done_tx)) => done = Some(done_tx),
+                    None => closed = true,
+                },
+                () = sleep_until(deadline.unwrap_or_else(Instant::now)), if deadline.is_some() => (),
+            }
+
+            // Transmit batch.
+            deadline = None;
+            if !batch.is_empty() {
+                if let Err(err) = sender.send(batch.split().freeze()).await {
+                    let _ = error_tx.send(Some(err));
+                    return;
+                }
+            }
+
+            if let Some(done) = done {
+                let _ = done.send(());
+            }
+            if closed {
+                return;
+            }
+        }
+    }
+
+    /// Returns the transmission error that occurred, if any.
+    fn error(&self) -> Option<chmux::SendError> {
+        self.error_rx.borrow().clone()
+    }
+
+    /// Queues an item for sending over the channel.
+    ///
+    /// The item is serialized immediately and transmitted once the batch is full,
+    /// the flush interval has elapsed or [flush](Self::flush) is called.
+    ///
+    /// The serialized item must fit into a single message of the channel multiplexer
+    /// and must not exceed the [maximum item size](Self::max_item_size), otherwise
+    /// [SendErrorKind::MaxItemSizeExceeded] is returned.
+    /// Items containing ports, i.e. remote channels or objects, cannot be sent
+    /// in batches and fail with a serialization error.
+    pub async fn send(&mut self, item: T) -> Result<(), SendError<T>> {
+        if let Some(err) = self.error() {
+            return Err(SendError::new(SendErrorKind::Send(err), item));
+        }
+
+        let limit = self.max_data_size.saturating_sub(LEN_SIZE).min(self.max_item_size);
+        let (data, ps) = match Sender::<T, Codec>::serialize_buffered(
+            self.allocator.clone(),
+            self.storage.clone(),
+            &item,
+            limit,
+        ) {
+            Ok(Some(v)) => v,
+            Ok(None) => return Err(SendError::new(SendErrorKind::MaxItemSizeExceeded, item)),
+            Err(kind) => return Err(SendError::new(kind, item)),
+        };
+
+        if !ps.requests.is_empty() {
+            let err = SerializationError::new(ErrorMsg(
+                "buffered sender cannot send items containing ports".to_string(),
+            ));
+            return Err(SendError::new(SendErrorKind::Serialize(err), item));
+        }
+
+        if self.tx.send(BufferedMsg::Item(data.freeze())).await.is_err() {
+            let err = self.error().unwrap_or(chmux::SendError::ChMux);
+            return Err(SendError::new(SendErrorKind::Send(err), item));
+        }
+
+        // Ensure that item is dropped before spawning registered tasks.
+        drop(item);
+
+        for task in ps.tasks {
+            tokio::spawn(task);
+        }
+
+        Ok(())
+    }
+
+    /// Transmits all queued items and waits until they have been sent.
+    pub async fn flush(&mut self) -> Result<(), SendError<()>> {
+        let (done_tx, done_rx) = oneshot::channel();
+        if self.tx.send(BufferedMsg::Flush(done_tx)).await.is_err() || done_rx.await.is_err() {
+            let err = self.error().unwrap_or(chmux::SendError::ChMux);
+            return Err(SendError::new(SendErrorKind::Send(err), ()));
+        }
+
+        Ok(())
+    }
+
+    /// The size in bytes of a batch that triggers its transmission.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The maximum allowed size in bytes of an item to be sent.
+    pub fn max_item_size(&self) -> usize {
+        self.max_item_size
+    }
+
+    /// Sets the maximum allowed size in bytes of an item to be sent.
+    ///
+    /// An item must also fit into a single message of the channel multiplexer,
+    /// regardless of this setting.
+    pub fn set_max_item_size(&mut self, max_item_size: usize) {
+        self.max_item_size = max_item_size;
+    }
+}
+
+/// Receives items sent in batches by a [BufferedSender].
+///
+/// Obtained by calling [Receiver::buffered](super::Receiver::buffered).
+pub struct BufferedReceiver<T, Codec = codec::Default> {
+    receiver: chmux::Receiver,
+    batch: Bytes,
+    max_item_size: usize,
+    _data: PhantomData<T>,
+    _codec: PhantomData<Codec>,
+}
+
+impl<T, Codec> fmt::Debug for BufferedReceiver<T, Codec> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BufferedReceiver").finish()
+    }
+}
+
+impl<T, Codec> BufferedReceiver<T, Codec>
+where
+    T: DeserializeOwned + Send + 'static,
+    Codec: codec::Codec,
+{
+    pub(super) fn new(receiver: chmux::Receiver, max_item_size: usize) -> Self {
+        Self { receiver, batch: Bytes::new(), max_item_size, _data: PhantomData, _codec: PhantomData }
+    }
+
+    /// Receive an item from the remote endpoint.
+    ///
+    /// Items of a batch are returned one after another.
+    pub async fn recv(&mut self) -> Result<Option<T>, RecvError> {
+        while self.batch.is_empty() {
+            match self.receiver.recv().await? {
+                Some(mut data) => self.batch = data.copy_to_bytes(data.remaining()),
+                None => return Ok(None),
+            }
+        }
+
+        if self.batch.len() < LEN_SIZE {
+            self.batch.clear();
+            return Err(Self::malformed());
+        }
+        let len = self.batch.get_u32_le() as usize;
+        if len > self.batch.len() {
+            self.batch.clear();
+            return Err(Self::malformed());
+        }
+        let data = self.batch.split_to(len);
+
+        if len > self.max_item_size {
+            return Err(RecvError::MaxItemSizeExceeded);
+        }
+
+        Ok(Some(<Codec as codec::Codec>::deserialize(data.reader())?))
+    }
+
+    fn malformed() -> RecvError {
+        RecvError::Deserialize(DeserializationError::new(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "malformed batch",
+        )))
+    }
+
+    /// Close the channel.
+    ///
+    /// This stops the remote endpoint from sending more data, but allows already sent data
+    /// to be received.
+    pub async fn close(&mut self) {
+        self.receiver.close().await
+    }
+
+    /// The maximum allowed size in bytes of an item to be received.
+    pub fn max_item_size(&self) -> usize {
+        self.max_item_size
+    }
+
+    /// Sets the maximum allowed size in bytes of an item to be received.
+    pub fn set_max_item_size(&mut self, max_item_size: usize) {
+        self.max_item_size = max_item_size;
+    }
+}
@@ -0,0 +1,143 @@
+use bytes::{Buf, BufMut};
+use std::{error::Error, fmt};
+
+/// Key/value headers transmitted alongside an item over a base channel.
+///
+/// Headers are sent using [Sender::send_with_headers](super::Sender::send_with_headers)
+/// and obtained using [Receiver::recv_with_headers](super::Receiver::recv_with_headers).
+/// They are intended for small amounts of metadata, such as request ids or tracing context,
+/// that should not be part of the item type.
+///
+/// The headers are transmitted as a single block preceding the item.
+/// The length of each key and value is limited to [MAX_LEN](Self::MAX_LEN) bytes
+/// and the encoded block must not exceed the
+/// [maximum data size](crate::chmux::Cfg::max_data_size) of the remote endpoint,
+/// otherwise the headers are discarded by the receiver.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Headers(Vec<(String, String)>);
+
+impl Headers {
+    /// Maximum length of the key and of the value of a header in bytes.
+    pub const MAX_LEN: usize = u16::MAX as usize;
+
+    /// Creates empty headers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the header with the specified key, replacing an existing value.
+    ///
+    /// Fails if the key or value exceeds [MAX_LEN](Self::MAX_LEN) bytes.
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<String>) -> Result<(), HeaderTooLongError> {
+        let key = key.into();
+        let value = value.into();
+        if key.len() > Self::MAX_LEN || value.len() > Self::MAX_LEN {
+            return Err(HeaderTooLongError);
+        }
+
+        match self.0.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, v)) => *v = value,
+            None => self.0.push((key, value)),
+        }
+        Ok(())
+    }
+
+    /// Returns the value of the header with the specified key.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.0.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    /// Removes the header with the specified key and returns its value.
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        let pos = self.0.iter().position(|(k, _)| k == key)?;
+        Some(self.0.remove(pos).1)
+    }
+
+    /// Iterates over all headers in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// Number of headers.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether no headers are present.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Encodes the headers into a header block.
+    ///
+    /// Each header is encoded as the length of its key, the key, the length of
+    /// its value and the value, with lengths being little-endian `u16`.
+    pub(super) fn encode(&self) -> Vec<u8> {
+        let mut block = Vec::new();
+        for (key, value) in &self.0 {
+            block.put_u16_le(key.len() as u16);
+            block.put_slice(key.as_bytes());
+            block.put_u16_le(value.len() as u16);
+            block.put_slice(value.as_bytes());
+        }
+        block
+    }
+
+    /// Decodes headers from a header block.
+    ///
+    /// Decoding stops at the first malformed header.
+    pub(super) fn decode(mut block: impl Buf) -> Self {
+        fn field(block: &mut impl Buf) -> Option<String> {
+            if block.remaining() < 2 {
+                return None;
+            }
+            let len = block.get_u16_le() as usize;
+            if block.remaining() < len {
+                return None;
+            }
+            String::from_utf8(block.copy_to_bytes(len).to_vec()).ok()
+        }
+
+        let mut this = Self::new();
+        while block.has_remaining() {
+            match (field(&mut block), field(&mut block)) {
+                (Some(key), Some(value)) => {
+                    let _ = this.insert(key, value);
+                }
+                _ => break,
+            }
+        }
+        this
+    }
+}
+
+impl<K, V> TryFrom<Vec<(K, V)>> for Headers
+where
+    K: Into<String>,
+    V: Into<String>,
+{
+    type Error = HeaderTooLongError;
+
+    /// Collects headers.
+    ///
+    /// Fails if the key or value of a header exceeds [MAX_LEN](Self::MAX_LEN) bytes.
+    fn try_from(entries: Vec<(K, V)>) -> Result<Self, Self::Error> {
+        let mut headers = Self::new();
+        for (key, value) in entries {
+            headers.insert(key, value)?;
+        }
+        Ok(headers)
+    }
+}
+
+/// The key or value of a [header](Headers) is too long.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HeaderTooLongError;
+
+impl fmt::Display for HeaderTooLongError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "header key or value exceeds {} bytes", Headers::MAX_LEN)
+    }
+}
+
+impl Error for HeaderTooLongError {}
@@ -44,12 +44,19 @@
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::{error::Error, fmt};
 
+mod buffered;
+mod headers;
 mod io;
 mod receiver;
 mod sender;
 
-pub use receiver::{PortDeserializer, Receiver, RecvError};
-pub use sender::{Closed, PortSerializer, SendError, SendErrorKind, Sender};
+pub use buffered::{BufferedReceiver, BufferedSender};
+pub use headers::{HeaderTooLongError, Headers};
+pub use receiver::{PortDeserializer, RawReceiver, Receiver, RecvError};
+pub use sender::{
+    Closed, PortSerializer, RawSender, SendAckedError, SendError, SendErrorKind, SendTimeoutError, Sender,
+    TrySendError,
+};
 
 use crate::{chmux, codec, RemoteSend};
 
@@ -59,6 +66,9 @@ const BIG_DATA_CHUNK_QUEUE: usize = 32;
 /// Limit for counting big data instances.
 const BIG_DATA_LIMIT: i8 = 16;
 
+/// Port request id used for acknowledging the reception of an item.
+const ACK_PORT_ID: u32 = u32::MAX;
+
 /// Creating the remote channel failed.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ConnectError {
@@ -68,6 +78,13 @@ pub enum ConnectError {
     Listen(chmux::ListenerError),
     /// The remote endpoint did not send a connect request.
     NoConnectRequest,
+    /// The remote endpoint uses a different codec.
+    CodecMismatch {
+        /// Codec identifier of the local endpoint.
+        local: String,
+        /// Codec identifier of the remote endpoint.
+        remote: String,
+    },
 }
 
 impl fmt::Display for ConnectError {
@@ -76,6 +93,9 @@ impl fmt::Display for ConnectError {
             ConnectError::Connect(err) => write!(f, "connect error: {err}"),
             ConnectError::Listen(err) => write!(f, "listen error: {err}"),
             ConnectError::NoConnectRequest => write!(f, "no connect request received"),
+            ConnectError::CodecMismatch { local, remote } => {
+                write!(f, "codec mismatch: local endpoint uses {local} but remote endpoint uses {remote}")
+            }
         }
     }
 }
@@ -100,6 +120,10 @@ impl From<chmux::ListenerError> for ConnectError {
 /// one connection request from the listener.
 ///
 /// Other connections may coexist on the chmux connection.
+///
+/// The [codec identifier](codec::Codec::ID) is sent along with the connect request.
+/// If the remote endpoint uses a codec with a different identifier,
+/// [ConnectError::CodecMismatch] is returned.
 pub async fn connect<Tx, Rx, Codec>(
     client: &chmux::Client, listener: &mut chmux::Listener,
 ) -> Result<(Sender<Tx, Codec>, Receiver<Rx, Codec>), ConnectError>
@@ -108,9 +132,31 @@ where
     Rx: RemoteSend,
     Codec: codec::Codec,
 {
-    let (client_sr, listener_sr) = tokio::join!(client.connect(), listener.accept());
+    let client_task = async {
+        let port = client.port_allocator().allocate().await;
+        let metadata = Codec::ID.map(|id| id.as_bytes().to_vec()).unwrap_or_default();
+        client.connect_ext(Some(chmux::PortReq::new(port).with_metadata(metadata)), true).await?.await
+    };
+    let listener_task = async {
+        let req = listener.inspect().await?.ok_or(ConnectError::NoConnectRequest)?;
+
+        // Remote endpoints with an older protocol version or a codec
+        // without identifier send no metadata.
+        if let Some(id) = Codec::ID {
+            if !req.metadata().is_empty() && req.metadata() != id.as_bytes() {
+                return Err(ConnectError::CodecMismatch {
+                    local: id.to_string(),
+                    remote: String::from_utf8_lossy(req.metadata()).into_owned(),
+                });
+            }
+        }
+
+        Ok(req.accept().await?)
+    };
+
+    let (client_sr, listener_sr) = tokio::join!(client_task, listener_task);
+    let (_, raw_receiver) = listener_sr?;
     let (raw_sender, _) = client_sr?;
-    let (_, raw_receiver) = listener_sr?.ok_or(ConnectError::NoConnectRequest)?;
     Ok((Sender::new(raw_sender), Receiver::new(raw_receiver)))
 }
 
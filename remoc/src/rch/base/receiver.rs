@@ -1,4 +1,4 @@
-use bytes::{Buf, Bytes};
+use bytes::{Buf, Bytes, BytesMut};
 use futures::{
     future::{BoxFuture, FutureExt},
     Future,
@@ -10,12 +10,15 @@ use std::{
     error::Error,
     fmt,
     marker::PhantomData,
-    panic,
+    mem, panic,
     rc::{Rc, Weak},
 };
 use tokio::task::{self, JoinHandle};
 
-use super::{super::DEFAULT_MAX_ITEM_SIZE, io::ChannelBytesReader, BIG_DATA_CHUNK_QUEUE};
+use super::{
+    super::DEFAULT_MAX_ITEM_SIZE, buffered::BufferedReceiver, io::ChannelBytesReader, Headers,
+    BIG_DATA_CHUNK_QUEUE,
+};
 use crate::{
     chmux::{self, AnyStorage, Received, RecvChunkError},
     codec::{self, DeserializationError},
@@ -180,9 +183,13 @@ pub struct Receiver<T, Codec = codec::Default> {
     recved: Option<Option<Received>>,
     data: DataSource<T>,
     item: Option<T>,
+    peeked: Option<(T, Preamble)>,
+    next_preamble: Preamble,
+    preamble: Preamble,
     port_deser: Option<PortDeserializer>,
     default_max_ports: Option<usize>,
     max_item_size: usize,
+    max_inline_size: usize,
     _codec: PhantomData<Codec>,
 }
 
@@ -192,6 +199,35 @@ impl<T, Codec> fmt::Debug for Receiver<T, Codec> {
     }
 }
 
+/// Headers and acknowledgement request sent before an item.
+#[derive(Default)]
+struct Preamble {
+    headers: Headers,
+    ack: Option<chmux::Request>,
+}
+
+impl Preamble {
+    /// Parses port requests received before an item.
+    ///
+    /// Returns `None` if the requests are not an acknowledgement request.
+    fn parse(requests: Vec<chmux::Request>) -> Option<Self> {
+        match <[_; 1]>::try_from(requests) {
+            Ok([req]) if req.id() == super::ACK_PORT_ID => Some(Self { headers: Headers::new(), ack: Some(req) }),
+            _ => None,
+        }
+    }
+
+    /// Acknowledges the reception of the item, if requested.
+    fn acknowledge(self) -> Headers {
+        if let Some(ack) = self.ack {
+            tokio::spawn(async move {
+                let _ = ack.accept().await;
+            });
+        }
+        self.headers
+    }
+}
+
 enum DataSource<T> {
     None,
     Buffered(Option<chmux::DataBuf>),
@@ -214,16 +250,72 @@ where
             recved: None,
             data: DataSource::None,
             item: None,
+            peeked: None,
+            next_preamble: Preamble::default(),
+            preamble: Preamble::default(),
             port_deser: None,
             default_max_ports: None,
             max_item_size: DEFAULT_MAX_ITEM_SIZE,
+            max_inline_size: usize::MAX,
             _codec: PhantomData,
         }
     }
 
     /// Receive an item from the remote endpoint.
+    ///
+    /// If an item has been obtained by [peek](Self::peek), it is returned.
+    ///
+    /// If the item has been sent using [send_acked](super::Sender::send_acked),
+    /// its reception is acknowledged to the remote endpoint.
+    /// Headers sent with the item are discarded.
     #[inline]
     pub async fn recv(&mut self) -> Result<Option<T>, RecvError> {
+        Ok(self.recv_with_headers().await?.map(|(item, _)| item))
+    }
+
+    /// Receive an item together with its headers from the remote endpoint.
+    ///
+    /// If the item has been sent without headers, they are empty.
+    /// Otherwise this behaves like [recv](Self::recv).
+    #[inline]
+    pub async fn recv_with_headers(&mut self) -> Result<Option<(T, Headers)>, RecvError> {
+        let received = match self.peeked.take() {
+            Some(peeked) => Some(peeked),
+            None => self.recv_item().await?,
+        };
+
+        Ok(received.map(|(item, preamble)| (item, preamble.acknowledge())))
+    }
+
+    /// Receives the next item from the remote endpoint without consuming it.
+    ///
+    /// The item is stored within this receiver and returned by the next call
+    /// to [recv](Self::recv).
+    /// Calling this repeatedly without calling `recv` returns the same item.
+    ///
+    /// The item is subject to the [maximum item size](Self::max_item_size) at the
+    /// time it is peeked; changing the limit afterwards does not affect it.
+    /// [Closing](Self::close) the channel does not discard a peeked item.
+    /// Peeking does not acknowledge the reception of the item.
+    #[inline]
+    pub async fn peek(&mut self) -> Result<Option<&T>, RecvError> {
+        if self.peeked.is_none() {
+            self.peeked = self.recv_item().await?;
+        }
+
+        Ok(self.peeked.as_ref().map(|(item, _)| item))
+    }
+
+    async fn recv_item(&mut self) -> Result<Option<(T, Preamble)>, RecvError> {
+        let res = self.recv_item_int().await;
+        if res.is_err() {
+            // Reject acknowledgement of failed item.
+            self.preamble = Preamble::default();
+        }
+        res
+    }
+
+    async fn recv_item_int(&mut self) -> Result<Option<(T, Preamble)>, RecvError> {
         if self.default_max_ports.is_none() {
             self.default_max_ports = Some(self.receiver.max_ports());
         }
@@ -236,7 +328,15 @@ where
                         self.recved = Some(self.receiver.recv_any().await?);
                     }
 
-                    self.data = match self.recved.take().unwrap() {
+                    let recved = self.recved.take().unwrap();
+                    if let Some(Received::Data(_) | Received::Chunks) = &recved {
+                        self.preamble = mem::take(&mut self.next_preamble);
+                        if let Some(block) = self.receiver.take_headers() {
+                            self.preamble.headers = Headers::decode(block);
+                        }
+                    }
+
+                    self.data = match recved {
                         Some(Received::Data(data)) => DataSource::Buffered(Some(data)),
                         Some(Received::Chunks) => {
                             // Start deserialization thread.
@@ -254,12 +354,19 @@ where
                             });
                             DataSource::Streamed { tx: Some(tx), task, total: 0 }
                         }
-                        Some(Received::Requests(_)) => continue 'restart,
+                        Some(Received::Requests(requests)) => {
+                            // Preamble of the following item.
+                            if let Some(preamble) = Preamble::parse(requests) {
+                                self.next_preamble = preamble;
+                            }
+                            continue 'restart;
+                        }
                         None => return Ok(None),
                     };
                 }
 
                 // Deserialize data.
+                let max_inline_size = self.max_inline_size();
                 match &mut self.data {
                     DataSource::None => unreachable!(),
 
@@ -275,6 +382,22 @@ where
                             return Err(RecvError::MaxItemSizeExceeded);
                         }
 
+                        // Deserialize large data on blocking thread.
+                        if data.remaining() > max_inline_size {
+                            let data = mem::take(data);
+                            let allocator = self.receiver.port_allocator();
+                            let handle_storage = self.receiver.storage();
+                            let task = task::spawn_blocking(move || {
+                                let pds_ref = PortDeserializer::start(allocator, handle_storage);
+                                let item = <Codec as codec::Codec>::deserialize(data.reader())?;
+                                let pds = PortDeserializer::finish(pds_ref);
+
+                                Ok((item, pds))
+                            });
+                            self.data = DataSource::Streamed { tx: None, task, total: 0 };
+                            continue 'restart;
+                        }
+
                         let pdf_ref =
                             PortDeserializer::start(self.receiver.port_allocator(), self.receiver.storage());
                         let item_res = <Codec as codec::Codec>::deserialize(data.reader());
@@ -323,6 +446,14 @@ where
                                     self.data = DataSource::None;
                                     return Err(RecvError::Receive(chmux::RecvError::ChMux));
                                 }
+                                Err(FeedError::RecvChunkError(RecvChunkError::Shutdown)) => {
+                                    self.data = DataSource::None;
+                                    return Err(RecvError::Receive(chmux::RecvError::Shutdown));
+                                }
+                                Err(FeedError::RecvChunkError(RecvChunkError::IdleTimeout)) => {
+                                    self.data = DataSource::None;
+                                    return Err(RecvError::Receive(chmux::RecvError::IdleTimeout));
+                                }
                                 Err(FeedError::MaxItemSizeExceeded) => {
                                     self.data = DataSource::None;
                                     return Err(RecvError::MaxItemSizeExceeded);
@@ -399,7 +530,7 @@ where
                 tokio::spawn(task);
             }
 
-            return Ok(Some(self.item.take().unwrap()));
+            return Ok(Some((self.item.take().unwrap(), mem::take(&mut self.preamble))));
         }
     }
 
@@ -407,11 +538,20 @@ where
     ///
     /// This stops the remote endpoint from sending more data, but allows already sent data
     /// to be received.
+    /// A [peeked](Self::peek) item remains available.
     #[inline]
     pub async fn close(&mut self) {
         self.receiver.close().await
     }
 
+    /// Converts this into a receiver for items sent in batches by a
+    /// [BufferedSender](super::BufferedSender).
+    ///
+    /// A [peeked](Self::peek) item is discarded.
+    pub fn buffered(self) -> BufferedReceiver<T, Codec> {
+        BufferedReceiver::new(self.receiver, self.max_item_size)
+    }
+
     /// The maximum allowed size in bytes of an item to be received.
     ///
     /// The default value is [DEFAULT_MAX_ITEM_SIZE].
@@ -423,4 +563,147 @@ where
     pub fn set_max_item_size(&mut self, max_item_size: usize) {
         self.max_item_size = max_item_size;
     }
+
+    /// The maximum size in bytes of received data that is deserialized on the
+    /// calling async task.
+    ///
+    /// By default this is the [maximum data size](chmux::Receiver::max_data_size) of the
+    /// underlying chmux channel.
+    pub fn max_inline_size(&self) -> usize {
+        self.max_inline_size.min(self.receiver.max_data_size())
+    }
+
+    /// Sets the maximum size in bytes of received data that is deserialized on the
+    /// calling async task.
+    ///
+    /// Larger data is deserialized on a blocking thread using
+    /// [spawn_blocking](tokio::task::spawn_blocking).
+    /// Thus lowering this limit keeps the async executor responsive when receiving
+    /// large items, at the cost of spawn overhead for items exceeding it.
+    /// Data exceeding the maximum data size of the underlying chmux channel is always
+    /// deserialized on a blocking thread while it is being received, thus values
+    /// larger than it have no effect.
+    pub fn set_max_inline_size(&mut self, max_inline_size: usize) {
+        self.max_inline_size = max_inline_size;
+    }
+}
+
+impl<Codec> Receiver<Bytes, Codec> {
+    /// Converts this into a receiver of raw data that is not deserialized.
+    ///
+    /// The remote endpoint must convert its sender using
+    /// [Sender::into_raw](super::Sender::into_raw).
+    /// If an item has been obtained by [peek](Self::peek), it is returned by the
+    /// first call to [RawReceiver::recv].
+    pub fn into_raw(self) -> RawReceiver<Codec> {
+        RawReceiver(self)
+    }
+
+    /// Receives raw data without deserializing it.
+    async fn recv_raw(&mut self) -> Result<Option<Bytes>, RecvError> {
+        if let Some((item, preamble)) = self.peeked.take() {
+            preamble.acknowledge();
+            return Ok(Some(item));
+        }
+
+        'restart: loop {
+            let recved = match self.recved.take() {
+                Some(recved) => recved,
+                None => self.receiver.recv_any().await?,
+            };
+
+            match recved {
+                Some(Received::Data(data)) => {
+                    let preamble = mem::take(&mut self.next_preamble);
+                    if data.remaining() > self.max_item_size {
+                        return Err(RecvError::MaxItemSizeExceeded);
+                    }
+
+                    preamble.acknowledge();
+                    return Ok(Some(data.into()));
+                }
+                Some(Received::Chunks) => {
+                    let preamble = mem::take(&mut self.next_preamble);
+                    let mut data = BytesMut::new();
+                    loop {
+                        match self.receiver.recv_chunk().await {
+                            Ok(Some(chunk)) => {
+                                if data.len() + chunk.len() > self.max_item_size {
+                                    return Err(RecvError::MaxItemSizeExceeded);
+                                }
+                                data.extend_from_slice(&chunk);
+                            }
+                            Ok(None) => break,
+                            Err(RecvChunkError::Cancelled) => continue 'restart,
+                            Err(RecvChunkError::ChMux) => {
+                                return Err(RecvError::Receive(chmux::RecvError::ChMux))
+                            }
+                            Err(RecvChunkError::Shutdown) => {
+                                return Err(RecvError::Receive(chmux::RecvError::Shutdown))
+                            }
+                            Err(RecvChunkError::IdleTimeout) => {
+                                return Err(RecvError::Receive(chmux::RecvError::IdleTimeout))
+                            }
+                        }
+                    }
+
+                    preamble.acknowledge();
+                    return Ok(Some(data.freeze()));
+                }
+                Some(Received::Requests(requests)) => {
+                    // Preamble of the following data.
+                    if let Some(preamble) = Preamble::parse(requests) {
+                        self.next_preamble = preamble;
+                    }
+                }
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+/// Receives raw data sent by a [RawSender](super::RawSender) without deserializing it.
+///
+/// This is obtained by [Receiver::into_raw].
+pub struct RawReceiver<Codec = codec::Default>(Receiver<Bytes, Codec>);
+
+impl<Codec> fmt::Debug for RawReceiver<Codec> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("RawReceiver").field(&self.0).finish()
+    }
+}
+
+impl<Codec> RawReceiver<Codec> {
+    /// Receives raw data.
+    ///
+    /// Data that has been transmitted as a single chunk, i.e. is not larger than
+    /// the chunk size of the chmux connection, is handed out without copying.
+    /// Larger data is reassembled into a continuous buffer.
+    ///
+    /// Data larger than the [maximum item size](Self::max_item_size) is discarded and
+    /// [RecvError::MaxItemSizeExceeded] is returned; the channel remains usable.
+    ///
+    /// If the data has been sent using [send_acked](super::Sender::send_acked),
+    /// its reception is acknowledged to the remote endpoint.
+    /// Headers sent with the data are discarded.
+    pub async fn recv(&mut self) -> Result<Option<Bytes>, RecvError> {
+        self.0.recv_raw().await
+    }
+
+    /// The maximum allowed size in bytes of data to be received.
+    ///
+    /// The default value is [DEFAULT_MAX_ITEM_SIZE].
+    pub fn max_item_size(&self) -> usize {
+        self.0.max_item_size
+    }
+
+    /// Sets the maximum allowed size in bytes of data to be received.
+    pub fn set_max_item_size(&mut self, max_item_size: usize) {
+        self.0.max_item_size = max_item_size;
+    }
+
+    /// Converts this back into a receiver of deserialized items.
+    pub fn into_inner(self) -> Receiver<Bytes, Codec> {
+        self.0
+    }
 }
@@ -1,4 +1,4 @@
-use bytes::BytesMut;
+use bytes::{Bytes, BytesMut};
 use futures::{
     future::{BoxFuture, FutureExt},
     Future,
@@ -13,17 +13,19 @@ use std::{
     panic,
     rc::{Rc, Weak},
     sync::{Arc, Mutex},
+    time::Duration,
 };
-use tokio::task;
+use tokio::{task, time::Instant};
 
 use super::{
     super::{SendErrorExt, DEFAULT_MAX_ITEM_SIZE},
+    buffered::BufferedSender,
     io::{ChannelBytesWriter, LimitedBytesWriter},
-    BIG_DATA_CHUNK_QUEUE, BIG_DATA_LIMIT,
+    Headers, BIG_DATA_CHUNK_QUEUE, BIG_DATA_LIMIT,
 };
 use crate::{
     chmux::{self, AnyStorage, PortReq},
-    codec::{self, SerializationError},
+    codec::{self, ErrorMsg, SerializationError},
 };
 
 pub use crate::chmux::Closed;
@@ -43,9 +45,14 @@ pub enum SendErrorKind {
     /// Serialization of the item failed.
     Serialize(SerializationError),
     /// Sending of the serialized item over the chmux channel failed.
+    ///
+    /// This is caused by the remote endpoint closing the channel or the connection failing.
     Send(chmux::SendError),
     /// Maximum item size was exceeded.
     MaxItemSizeExceeded,
+    /// The item contains a channel that has been forwarded more often than
+    /// its maximum number of hops allows.
+    MaxHopsExceeded,
 }
 
 impl SendErrorKind {
@@ -66,7 +73,7 @@ impl SendErrorKind {
 
     /// Returns whether the error is caused by the item to be sent.
     pub fn is_item_specific(&self) -> bool {
-        matches!(self, Self::Serialize(_) | Self::MaxItemSizeExceeded)
+        matches!(self, Self::Serialize(_) | Self::MaxItemSizeExceeded | Self::MaxHopsExceeded)
     }
 }
 
@@ -125,6 +132,7 @@ impl fmt::Display for SendErrorKind {
             Self::Serialize(err) => write!(f, "serialization error: {err}"),
             Self::Send(err) => write!(f, "send error: {err}"),
             Self::MaxItemSizeExceeded => write!(f, "maximum item size exceeded"),
+            Self::MaxHopsExceeded => write!(f, "maximum number of hops exceeded"),
         }
     }
 }
@@ -137,14 +145,182 @@ impl<T> fmt::Display for SendError<T> {
 
 impl<T> Error for SendError<T> where T: fmt::Debug {}
 
+/// An error that occurred during remote sending with a timeout.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum SendTimeoutError<T> {
+    /// The item could not be transmitted within the timeout.
+    ///
+    /// Sending may be retried.
+    Timeout(T),
+    /// Sending failed.
+    Send(SendError<T>),
+}
+
+impl<T> SendTimeoutError<T> {
+    /// True, if the timeout elapsed before the item could be sent.
+    pub fn is_timeout(&self) -> bool {
+        matches!(self, Self::Timeout(_))
+    }
+
+    /// Returns true, if error it due to channel being closed.
+    pub fn is_closed(&self) -> bool {
+        matches!(self, Self::Send(err) if err.is_closed())
+    }
+
+    /// Returns whether the error is final, i.e. no further send operation can succeed.
+    pub fn is_final(&self) -> bool {
+        matches!(self, Self::Send(err) if err.is_final())
+    }
+
+    /// Returns the item that could not be sent.
+    pub fn into_item(self) -> T {
+        match self {
+            Self::Timeout(item) => item,
+            Self::Send(err) => err.item,
+        }
+    }
+}
+
+impl<T> From<SendError<T>> for SendTimeoutError<T> {
+    fn from(err: SendError<T>) -> Self {
+        Self::Send(err)
+    }
+}
+
+impl<T> fmt::Display for SendTimeoutError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Timeout(_) => write!(f, "send timeout"),
+            Self::Send(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl<T> Error for SendTimeoutError<T> where T: fmt::Debug {}
+
+/// An error that occurred during trying to send remotely without waiting.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum TrySendError<T> {
+    /// The flow control window of the channel is exhausted and
+    /// sending would require waiting.
+    ///
+    /// Sending may be retried.
+    Full(T),
+    /// Sending failed.
+    Send(SendError<T>),
+}
+
+impl<T> TrySendError<T> {
+    /// True, if the channel is currently full.
+    pub fn is_full(&self) -> bool {
+        matches!(self, Self::Full(_))
+    }
+
+    /// Returns true, if error it due to channel being closed.
+    pub fn is_closed(&self) -> bool {
+        matches!(self, Self::Send(err) if err.is_closed())
+    }
+
+    /// Returns whether the error is final, i.e. no further send operation can succeed.
+    pub fn is_final(&self) -> bool {
+        matches!(self, Self::Send(err) if err.is_final())
+    }
+
+    /// Returns the item that could not be sent.
+    pub fn into_item(self) -> T {
+        match self {
+            Self::Full(item) => item,
+            Self::Send(err) => err.item,
+        }
+    }
+}
+
+impl<T> From<SendError<T>> for TrySendError<T> {
+    fn from(err: SendError<T>) -> Self {
+        Self::Send(err)
+    }
+}
+
+impl<T> fmt::Display for TrySendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Full(_) => write!(f, "channel is full"),
+            Self::Send(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl<T> Error for TrySendError<T> where T: fmt::Debug {}
+
+/// An error that occurred during remote sending with acknowledgement.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum SendAckedError<T> {
+    /// Sending failed.
+    Send(SendError<T>),
+    /// The item has been sent, but its reception was not acknowledged by the
+    /// remote endpoint.
+    ///
+    /// This occurs if the connection failed or the remote receiver was dropped
+    /// before the item was received, or if the remote endpoint does not support
+    /// acknowledgements.
+    /// The item may or may not have been received.
+    Unacknowledged(chmux::ConnectError),
+}
+
+impl<T> SendAckedError<T> {
+    /// True, if the item has been sent but its reception was not acknowledged.
+    pub fn is_unacknowledged(&self) -> bool {
+        matches!(self, Self::Unacknowledged(_))
+    }
+
+    /// Returns true, if error it due to channel being closed.
+    pub fn is_closed(&self) -> bool {
+        matches!(self, Self::Send(err) if err.is_closed())
+    }
+
+    /// Returns whether the error is final, i.e. no further send operation can succeed.
+    pub fn is_final(&self) -> bool {
+        match self {
+            Self::Send(err) => err.is_final(),
+            Self::Unacknowledged(err) => matches!(err, chmux::ConnectError::ChMux),
+        }
+    }
+
+    /// Returns the item that could not be sent, if it is still available.
+    pub fn into_item(self) -> Option<T> {
+        match self {
+            Self::Send(err) => Some(err.item),
+            Self::Unacknowledged(_) => None,
+        }
+    }
+}
+
+impl<T> From<SendError<T>> for SendAckedError<T> {
+    fn from(err: SendError<T>) -> Self {
+        Self::Send(err)
+    }
+}
+
+impl<T> fmt::Display for SendAckedError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Send(err) => write!(f, "{err}"),
+            Self::Unacknowledged(err) => write!(f, "item not acknowledged: {err}"),
+        }
+    }
+}
+
+impl<T> Error for SendAckedError<T> where T: fmt::Debug {}
+
 /// Gathers ports to send to the remote endpoint during object serialization.
 pub struct PortSerializer {
     allocator: chmux::PortAllocator,
     #[allow(clippy::type_complexity)]
-    requests:
+    pub(super) requests:
         Vec<(chmux::PortNumber, Box<dyn FnOnce(chmux::Connect) -> BoxFuture<'static, ()> + Send + 'static>)>,
     storage: AnyStorage,
-    tasks: Vec<BoxFuture<'static, ()>>,
+    pub(super) tasks: Vec<BoxFuture<'static, ()>>,
+    max_hops_exceeded: bool,
 }
 
 impl PortSerializer {
@@ -154,7 +330,13 @@ impl PortSerializer {
 
     /// Create a new port serializer and register it as active.
     fn start(allocator: chmux::PortAllocator, storage: AnyStorage) -> Rc<RefCell<Self>> {
-        let this = Rc::new(RefCell::new(Self { allocator, requests: Vec::new(), storage, tasks: Vec::new() }));
+        let this = Rc::new(RefCell::new(Self {
+            allocator,
+            requests: Vec::new(),
+            storage,
+            tasks: Vec::new(),
+            max_hops_exceeded: false,
+        }));
         let weak = Rc::downgrade(&this);
         Self::INSTANCE.with(move |i| i.replace(weak));
         this
@@ -171,6 +353,16 @@ impl PortSerializer {
         }
     }
 
+    /// Determines the kind of a serialization error that occurred while this
+    /// port serializer was active.
+    fn error_kind(this: &Rc<RefCell<Self>>, err: SerializationError) -> SendErrorKind {
+        if this.borrow().max_hops_exceeded {
+            SendErrorKind::MaxHopsExceeded
+        } else {
+            SendErrorKind::Serialize(err)
+        }
+    }
+
     /// Deregister the active port serializer and return it.
     fn finish(this: Rc<RefCell<Self>>) -> Self {
         match Rc::try_unwrap(this) {
@@ -212,6 +404,20 @@ impl PortSerializer {
         Ok(this.storage.clone())
     }
 
+    /// Returns a serialization error indicating that a channel has been
+    /// forwarded more often than its maximum number of hops allows.
+    ///
+    /// Sending then fails with [SendErrorKind::MaxHopsExceeded].
+    pub(crate) fn max_hops_exceeded<E>(msg: impl fmt::Display) -> E
+    where
+        E: serde::ser::Error,
+    {
+        if let Ok(this) = Self::instance::<E>() {
+            this.borrow_mut().max_hops_exceeded = true;
+        }
+        ser::Error::custom(msg)
+    }
+
     /// Spawn a task.
     #[inline]
     pub fn spawn<E>(task: impl Future<Output = ()> + Send + 'static) -> Result<(), E>
@@ -234,6 +440,7 @@ pub struct Sender<T, Codec = codec::Default> {
     sender: chmux::Sender,
     big_data: i8,
     max_item_size: usize,
+    max_inline_size: usize,
     _data: PhantomData<T>,
     _codec: PhantomData<Codec>,
 }
@@ -255,21 +462,22 @@ where
             sender,
             big_data: 0,
             max_item_size: DEFAULT_MAX_ITEM_SIZE,
+            max_inline_size: usize::MAX,
             _data: PhantomData,
             _codec: PhantomData,
         }
     }
 
-    fn serialize_buffered(
+    pub(super) fn serialize_buffered(
         allocator: chmux::PortAllocator, storage: AnyStorage, item: &T, limit: usize,
-    ) -> Result<Option<(BytesMut, PortSerializer)>, SerializationError> {
+    ) -> Result<Option<(BytesMut, PortSerializer)>, SendErrorKind> {
         let mut lw = LimitedBytesWriter::new(limit);
         let ps_ref = PortSerializer::start(allocator, storage);
 
         match <Codec as codec::Codec>::serialize(&mut lw, &item) {
             _ if lw.overflow() => return Ok(None),
             Ok(()) => (),
-            Err(err) => return Err(err),
+            Err(err) => return Err(PortSerializer::error_kind(&ps_ref, err)),
         };
 
         let ps = PortSerializer::finish(ps_ref);
@@ -279,7 +487,7 @@ where
     async fn serialize_streaming(
         allocator: chmux::PortAllocator, storage: AnyStorage, item: T, tx: tokio::sync::mpsc::Sender<BytesMut>,
         chunk_size: usize,
-    ) -> Result<(T, PortSerializer, usize), (SerializationError, T)> {
+    ) -> Result<(T, PortSerializer, usize), (SendErrorKind, T)> {
         let cbw = ChannelBytesWriter::new(tx);
         let mut cbw = BufWriter::with_capacity(chunk_size, cbw);
 
@@ -290,10 +498,15 @@ where
             let ps_ref = PortSerializer::start(allocator, storage);
 
             let item = item_arc_task.lock().unwrap();
-            <Codec as codec::Codec>::serialize(&mut cbw, &*item)?;
+            if let Err(err) = <Codec as codec::Codec>::serialize(&mut cbw, &*item) {
+                return Err(PortSerializer::error_kind(&ps_ref, err));
+            }
 
             let cbw = cbw.into_inner().map_err(|_| {
-                SerializationError::new(std::io::Error::new(std::io::ErrorKind::BrokenPipe, "flush failed"))
+                SendErrorKind::Serialize(SerializationError::new(std::io::Error::new(
+                    std::io::ErrorKind::BrokenPipe,
+                    "flush failed",
+                )))
             })?;
 
             let ps = PortSerializer::finish(ps_ref);
@@ -314,7 +527,7 @@ where
             Ok(Err(err)) => Err((err, item)),
             Err(err) => match err.try_into_panic() {
                 Ok(payload) => panic::resume_unwind(payload),
-                Err(err) => Err((SerializationError::new(err), item)),
+                Err(err) => Err((SendErrorKind::Serialize(SerializationError::new(err)), item)),
             },
         }
     }
@@ -324,6 +537,103 @@ where
     /// The item may contain ports that will be serialized and connected as well.
     #[inline]
     pub async fn send(&mut self, item: T) -> Result<(), SendError<T>> {
+        match self.send_int(item, None).await {
+            Ok(()) => Ok(()),
+            Err(SendTimeoutError::Send(err)) => Err(err),
+            Err(SendTimeoutError::Timeout(_)) => unreachable!("send without deadline timed out"),
+        }
+    }
+
+    /// Sends an item over the channel, waiting at most the specified time
+    /// for it to be transmitted.
+    ///
+    /// If the item cannot be transmitted within the timeout, for example because
+    /// the remote endpoint is applying backpressure, it is returned in
+    /// [SendTimeoutError::Timeout].
+    ///
+    /// The timeout applies to the transmission of the serialized item.
+    /// If it elapses after part of the item has been transmitted, the transmission
+    /// is cancelled and the remote endpoint discards the partially received item.
+    /// Thus the channel remains usable and the item is either received completely
+    /// or not at all.
+    /// Once the item has been transmitted, connecting the ports it contains
+    /// is not subject to the timeout.
+    #[inline]
+    pub async fn send_timeout(&mut self, item: T, timeout: Duration) -> Result<(), SendTimeoutError<T>> {
+        self.send_int(item, Some(Instant::now() + timeout)).await
+    }
+
+    /// Sends an item over the channel and waits until the remote endpoint has received it.
+    ///
+    /// In contrast to [send](Self::send), which completes once the item has been
+    /// handed to the channel multiplexer, this completes only after the remote
+    /// [Receiver](super::Receiver) has returned the item from
+    /// [recv](super::Receiver::recv).
+    /// Peeking the item does not acknowledge it.
+    ///
+    /// The acknowledgement requires an additional chmux port to be opened for each item,
+    /// making this considerably more expensive than [send](Self::send).
+    ///
+    /// If the connection fails or the remote receiver is dropped before the
+    /// acknowledgement arrives, [SendAckedError::Unacknowledged] is returned.
+    /// In this case the item may or may not have been received.
+    /// This error is also returned if the remote endpoint uses a version of this
+    /// crate that does not support acknowledgements.
+    pub async fn send_acked(&mut self, item: T) -> Result<(), SendAckedError<T>> {
+        // Request acknowledgement port before sending the item.
+        let ack = match self.send_ack_request().await {
+            Ok(ack) => ack,
+            Err(err) => return Err(SendError::new(SendErrorKind::Send(err), item).into()),
+        };
+
+        self.send(item).await?;
+
+        match ack.await {
+            Ok(_) => Ok(()),
+            Err(err) => Err(SendAckedError::Unacknowledged(err)),
+        }
+    }
+
+    /// Sends an item together with headers over the channel.
+    ///
+    /// The headers are delivered alongside the item and can be obtained using
+    /// [Receiver::recv_with_headers](super::Receiver::recv_with_headers).
+    /// They are transmitted as a block preceding the item and thus do not count
+    /// towards the [maximum item size](Self::max_item_size).
+    /// If the remote endpoint uses a version of this crate that does not support
+    /// headers, they are discarded.
+    pub async fn send_with_headers(&mut self, item: T, headers: &Headers) -> Result<(), SendError<T>> {
+        if !headers.is_empty() {
+            if let Err(err) = self.sender.send_headers(headers.encode().into()).await {
+                return Err(SendError::new(SendErrorKind::Send(err), item));
+            }
+        }
+
+        self.send(item).await
+    }
+
+    /// Sends the port request preceding an item for requesting its acknowledgement.
+    ///
+    /// Returns the connect request for acknowledgement.
+    async fn send_ack_request(&mut self) -> Result<chmux::Connect, chmux::SendError> {
+        let port = self.sender.port_allocator().allocate().await;
+        let mut connects =
+            self.sender.connect(vec![PortReq::new(port).with_id(super::ACK_PORT_ID)], true).await?;
+        Ok(connects.pop().unwrap())
+    }
+
+    /// Runs the future until the optional deadline is reached.
+    async fn until<F>(deadline: Option<Instant>, fut: F) -> Option<F::Output>
+    where
+        F: Future,
+    {
+        match deadline {
+            Some(deadline) => tokio::time::timeout_at(deadline, fut).await.ok(),
+            None => Some(fut.await),
+        }
+    }
+
+    async fn send_int(&mut self, item: T, deadline: Option<Instant>) -> Result<(), SendTimeoutError<T>> {
         // Determine if it is worthy to try buffered serialization.
         let data_ps = if self.big_data <= 0 {
             // Try buffered serialization.
@@ -331,7 +641,7 @@ where
                 self.sender.port_allocator(),
                 self.sender.storage(),
                 &item,
-                self.sender.max_data_size(),
+                self.max_inline_size(),
             ) {
                 Ok(Some(v)) => {
                     self.big_data = (self.big_data - 1).max(-BIG_DATA_LIMIT);
@@ -341,7 +651,7 @@ where
                     self.big_data = (self.big_data + 1).min(BIG_DATA_LIMIT);
                     None
                 }
-                Err(err) => return Err(SendError::new(SendErrorKind::Serialize(err), item)),
+                Err(kind) => return Err(SendError::new(kind, item).into()),
             }
         } else {
             // Buffered serialization unlikely to succeed.
@@ -351,12 +661,14 @@ where
         let (item, ps) = match data_ps {
             Some((data, ps)) => {
                 if data.len() > self.max_item_size {
-                    return Err(SendError::new(SendErrorKind::MaxItemSizeExceeded, item));
+                    return Err(SendError::new(SendErrorKind::MaxItemSizeExceeded, item).into());
                 }
 
                 // Send buffered data.
-                if let Err(err) = self.sender.send(data.freeze()).await {
-                    return Err(SendError::new(SendErrorKind::Send(err), item));
+                match Self::until(deadline, self.sender.send(data.freeze())).await {
+                    Some(Ok(())) => (),
+                    Some(Err(err)) => return Err(SendError::new(SendErrorKind::Send(err), item).into()),
+                    None => return Err(SendTimeoutError::Timeout(item)),
                 }
                 (item, ps)
             }
@@ -375,6 +687,7 @@ where
                 enum SendTaskError {
                     SendError(chmux::SendError),
                     MaxItemSizeExceeded,
+                    Timeout,
                 }
 
                 let mut sc = self.sender.send_chunks();
@@ -391,14 +704,19 @@ where
                     }
                     Ok(sc)
                 };
+                // Dropping the chunk sender on timeout cancels the partial transmission.
+                let send_task =
+                    async move { Self::until(deadline, send_task).await.unwrap_or(Err(SendTaskError::Timeout)) };
 
                 match tokio::join!(ser_task, send_task) {
                     (Ok((item, ps, size)), Ok(sc)) => {
-                        if let Err(err) = sc.finish().await {
-                            return Err(SendError::new(SendErrorKind::Send(err), item));
+                        match Self::until(deadline, sc.finish()).await {
+                            Some(Ok(())) => (),
+                            Some(Err(err)) => return Err(SendError::new(SendErrorKind::Send(err), item).into()),
+                            None => return Err(SendTimeoutError::Timeout(item)),
                         }
 
-                        if size <= self.sender.max_data_size() {
+                        if size <= self.max_inline_size() {
                             self.big_data = (self.big_data - 1).max(-BIG_DATA_LIMIT);
                         }
 
@@ -410,13 +728,14 @@ where
                         let kind = match err {
                             SendTaskError::SendError(err) => SendErrorKind::Send(err),
                             SendTaskError::MaxItemSizeExceeded => SendErrorKind::MaxItemSizeExceeded,
+                            SendTaskError::Timeout => return Err(SendTimeoutError::Timeout(item)),
                         };
-                        return Err(SendError::new(kind, item));
+                        return Err(SendError::new(kind, item).into());
                     }
-                    (Err((err, item)), _) => {
+                    (Err((kind, item)), _) => {
                         // When serialization fails, the send task will finish successfully
                         // since the rx stream will end normally.
-                        return Err(SendError::new(SendErrorKind::Serialize(err), item));
+                        return Err(SendError::new(kind, item).into());
                     }
                 }
             }
@@ -438,7 +757,7 @@ where
         } else {
             match self.sender.connect(ports, true).await {
                 Ok(connects) => connects,
-                Err(err) => return Err(SendError::new(SendErrorKind::Send(err), item)),
+                Err(err) => return Err(SendError::new(SendErrorKind::Send(err), item).into()),
             }
         };
 
@@ -461,13 +780,94 @@ where
         Ok(())
     }
 
+    /// Tries to send an item over the channel without waiting.
+    ///
+    /// If the flow control window of the channel has not enough space available
+    /// to transmit the serialized item, [TrySendError::Full] is returned together
+    /// with the item.
+    /// In this case nothing has been sent and the channel remains usable.
+    ///
+    /// Items whose serialized size exceeds the maximum data size of the chmux
+    /// connection cannot be sent using this function and fail with
+    /// [SendErrorKind::MaxItemSizeExceeded].
+    /// Items containing ports, i.e. remote channels or objects, also cannot be sent
+    /// using this function and fail with a serialization error.
+    /// Use [send](Self::send) for these items.
+    pub fn try_send(&mut self, item: T) -> Result<(), TrySendError<T>> {
+        let limit = self.sender.max_data_size().min(self.max_item_size);
+        let (data, ps) =
+            match Self::serialize_buffered(self.sender.port_allocator(), self.sender.storage(), &item, limit) {
+                Ok(Some(v)) => v,
+                Ok(None) => return Err(SendError::new(SendErrorKind::MaxItemSizeExceeded, item).into()),
+                Err(kind) => return Err(SendError::new(kind, item).into()),
+            };
+
+        if !ps.requests.is_empty() {
+            let err =
+                SerializationError::new(ErrorMsg("try_send cannot send items containing ports".to_string()));
+            return Err(SendError::new(SendErrorKind::Serialize(err), item).into());
+        }
+
+        match self.sender.try_send(&data.freeze()) {
+            Ok(()) => (),
+            Err(chmux::TrySendError::Full) => return Err(TrySendError::Full(item)),
+            Err(chmux::TrySendError::Send(err)) => {
+                return Err(SendError::new(SendErrorKind::Send(err), item).into())
+            }
+        }
+
+        // Ensure that item is dropped before spawning registered tasks.
+        drop(item);
+
+        for task in ps.tasks {
+            tokio::spawn(task);
+        }
+
+        Ok(())
+    }
+
+    /// Converts this into a sender that transmits items in batches.
+    ///
+    /// Items are accumulated until their total serialized size reaches `capacity` bytes
+    /// or `flush_interval` has elapsed since the first item was queued.
+    /// The capacity is limited to the maximum data size of the channel multiplexer.
+    ///
+    /// The remote endpoint must receive using [Receiver::buffered](super::Receiver::buffered).
+    pub fn buffered(self, capacity: usize, flush_interval: Duration) -> BufferedSender<T, Codec> {
+        BufferedSender::new(self.sender, self.max_item_size, capacity, flush_interval)
+    }
+
     /// True, once the remote endpoint has closed its receiver.
     #[inline]
     pub fn is_closed(&self) -> bool {
         self.sender.is_closed()
     }
 
+    /// Flushes the transport of the connection.
+    ///
+    /// Completes once all items sent before have been written to the transport
+    /// and the transport has been flushed.
+    /// This does not wait for the remote endpoint to receive the items;
+    /// use [send_acked](Self::send_acked) for that.
+    ///
+    /// Ports contained in items are connected asynchronously and thus are not
+    /// covered by this.
+    /// See [chmux::Sender::flush] for details.
+    pub async fn flush(&self) -> Result<(), SendError<()>> {
+        match self.sender.flush().await {
+            Ok(()) => Ok(()),
+            Err(err) => Err(SendError::new(SendErrorKind::Send(err), ())),
+        }
+    }
+
     /// Returns a future that will resolve when the remote endpoint closes its receiver.
+    ///
+    /// The future also resolves when the remote receiver is dropped or the connection fails,
+    /// thus it can be used to stop producing items early.
+    ///
+    /// Notification is best-effort: it relies on the remote endpoint informing
+    /// this side and thus may lag behind the actual closure by the network round-trip time.
+    /// Items sent in the meantime are lost.
     #[inline]
     pub fn closed(&self) -> Closed {
         self.sender.closed()
@@ -490,4 +890,88 @@ where
     pub fn set_max_item_size(&mut self, max_item_size: usize) {
         self.max_item_size = max_item_size;
     }
+
+    /// The maximum serialized size in bytes of an item that is serialized on the
+    /// calling async task.
+    ///
+    /// By default this is the [maximum data size](chmux::Sender::max_data_size) of the
+    /// underlying chmux channel.
+    pub fn max_inline_size(&self) -> usize {
+        self.max_inline_size.min(self.sender.max_data_size())
+    }
+
+    /// Sets the maximum serialized size in bytes of an item that is serialized on the
+    /// calling async task.
+    ///
+    /// Serialization of an item exceeding this size is aborted and restarted on
+    /// a blocking thread using [spawn_blocking](tokio::task::spawn_blocking), with
+    /// the serialized data being sent while serialization progresses.
+    /// Thus lowering this limit keeps the async executor responsive when sending
+    /// large items, at the cost of spawn overhead for items exceeding it.
+    /// After repeatedly encountering large items, serialization starts on a blocking
+    /// thread right away.
+    ///
+    /// Values larger than the maximum data size of the underlying chmux channel have
+    /// no effect.
+    /// [try_send](Self::try_send) always serializes on the calling task.
+    pub fn set_max_inline_size(&mut self, max_inline_size: usize) {
+        self.max_inline_size = max_inline_size;
+    }
+}
+
+impl<Codec> Sender<Bytes, Codec> {
+    /// Converts this into a sender of raw data that is not serialized.
+    ///
+    /// The remote endpoint must convert its receiver using
+    /// [Receiver::into_raw](super::Receiver::into_raw).
+    pub fn into_raw(self) -> RawSender<Codec> {
+        RawSender(self)
+    }
+}
+
+/// Sends raw data over a base channel without serializing it.
+///
+/// This is obtained by [Sender::into_raw].
+/// The data is passed to the chmux channel as is, avoiding the encoding by the codec.
+/// It must be received by a [RawReceiver](super::RawReceiver) on the remote endpoint.
+pub struct RawSender<Codec = codec::Default>(Sender<Bytes, Codec>);
+
+impl<Codec> fmt::Debug for RawSender<Codec> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("RawSender").field(&self.0).finish()
+    }
+}
+
+impl<Codec> RawSender<Codec> {
+    /// Sends raw data.
+    ///
+    /// Data larger than the [maximum item size](Self::max_item_size) is not sent and
+    /// fails with [SendErrorKind::MaxItemSizeExceeded].
+    pub async fn send(&mut self, data: Bytes) -> Result<(), SendError<Bytes>> {
+        if data.len() > self.0.max_item_size {
+            return Err(SendError::new(SendErrorKind::MaxItemSizeExceeded, data));
+        }
+
+        match self.0.sender.send(data.clone()).await {
+            Ok(()) => Ok(()),
+            Err(err) => Err(SendError::new(SendErrorKind::Send(err), data)),
+        }
+    }
+
+    /// The maximum allowed size in bytes of data to be sent.
+    ///
+    /// The default value is [DEFAULT_MAX_ITEM_SIZE].
+    pub fn max_item_size(&self) -> usize {
+        self.0.max_item_size
+    }
+
+    /// Sets the maximum allowed size in bytes of data to be sent.
+    pub fn set_max_item_size(&mut self, max_item_size: usize) {
+        self.0.max_item_size = max_item_size;
+    }
+
+    /// Converts this back into a sender of serialized items.
+    pub fn into_inner(self) -> Sender<Bytes, Codec> {
+        self.0
+    }
 }
@@ -0,0 +1,340 @@
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use serde::{ser, Deserialize, Serialize};
+use std::{error::Error, fmt};
+
+use super::{
+    super::{ConnectError, DEFAULT_MAX_ITEM_SIZE},
+    Receiver, Sender,
+};
+use crate::chmux::{self, RecvChunkError};
+
+/// Length of the message length prefix in bytes.
+const PREFIX_LEN: usize = 4;
+
+/// An error occurred during sending or receiving a framed message.
+#[derive(Debug)]
+pub enum FramedError {
+    /// Connecting the binary channel failed.
+    Connect(ConnectError),
+    /// Sending over the chmux channel failed.
+    Send(chmux::SendError),
+    /// Receiving over the chmux channel failed.
+    Recv(chmux::RecvError),
+    /// Maximum item size was exceeded.
+    ///
+    /// The oversized message is discarded and the channel remains usable.
+    MaxItemSizeExceeded,
+    /// The data ended within a message.
+    ///
+    /// The partially received message is discarded.
+    Truncated,
+}
+
+impl From<ConnectError> for FramedError {
+    fn from(err: ConnectError) -> Self {
+        Self::Connect(err)
+    }
+}
+
+impl From<chmux::SendError> for FramedError {
+    fn from(err: chmux::SendError) -> Self {
+        Self::Send(err)
+    }
+}
+
+impl From<chmux::RecvError> for FramedError {
+    fn from(err: chmux::RecvError) -> Self {
+        Self::Recv(err)
+    }
+}
+
+impl fmt::Display for FramedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Connect(err) => write!(f, "connect error: {err}"),
+            Self::Send(err) => write!(f, "send error: {err}"),
+            Self::Recv(err) => write!(f, "receive error: {err}"),
+            Self::MaxItemSizeExceeded => write!(f, "maximum item size exceeded"),
+            Self::Truncated => write!(f, "data ended within a message"),
+        }
+    }
+}
+
+impl Error for FramedError {}
+
+/// Sends length-prefixed messages over a binary channel.
+///
+/// Each message is preceded by its length encoded as a big-endian 32-bit unsigned integer.
+///
+/// Instances are created by the [framed](super::framed) function or from
+/// a [binary channel sender](Sender).
+/// The maximum item size is not transmitted when sending this to a remote endpoint.
+pub struct FramedSender {
+    sender: Sender,
+    max_item_size: usize,
+}
+
+impl fmt::Debug for FramedSender {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FramedSender").field("max_item_size", &self.max_item_size).finish()
+    }
+}
+
+impl FramedSender {
+    /// Creates a framed sender from a binary channel sender.
+    pub fn new(sender: Sender) -> Self {
+        Self { sender, max_item_size: DEFAULT_MAX_ITEM_SIZE }
+    }
+
+    /// Sends a message.
+    ///
+    /// Messages larger than the [maximum item size](Self::max_item_size) are not sent
+    /// and fail with [FramedError::MaxItemSizeExceeded].
+    pub async fn send(&mut self, data: impl Into<Bytes>) -> Result<(), FramedError> {
+        let data = data.into();
+        let len = match u32::try_from(data.len()) {
+            Ok(len) if data.len() <= self.max_item_size => len,
+            _ => return Err(FramedError::MaxItemSizeExceeded),
+        };
+
+        let mut prefix = BytesMut::with_capacity(PREFIX_LEN);
+        prefix.put_u32(len);
+
+        let tx = self.sender.get().await?;
+        tx.send_chunks().send(prefix.freeze()).await?.send_final(data).await?;
+        Ok(())
+    }
+
+    /// The maximum allowed size in bytes of a message to be sent.
+    ///
+    /// The default value is [DEFAULT_MAX_ITEM_SIZE].
+    pub fn max_item_size(&self) -> usize {
+        self.max_item_size
+    }
+
+    /// Sets the maximum allowed size in bytes of a message to be sent.
+    pub fn set_max_item_size(&mut self, max_item_size: usize) {
+        self.max_item_size = max_item_size;
+    }
+
+    /// Returns the underlying binary channel sender.
+    pub fn into_inner(self) -> Sender {
+        self.sender
+    }
+}
+
+impl From<Sender> for FramedSender {
+    fn from(sender: Sender) -> Self {
+        Self::new(sender)
+    }
+}
+
+impl Serialize for FramedSender {
+    /// Serializes this sender for sending over a chmux channel.
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.sender.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for FramedSender {
+    /// Deserializes this sender after it has been received over a chmux channel.
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        Ok(Self::new(Sender::deserialize(deserializer)?))
+    }
+}
+
+/// Receive buffer splitting the received data into messages.
+#[derive(Default)]
+struct FrameBuf {
+    data: BytesMut,
+    /// Remaining bytes of an oversized message to discard.
+    skip: usize,
+    /// An oversized message has been discarded.
+    oversized: bool,
+}
+
+impl FrameBuf {
+    /// Appends received data, discarding oversized messages as they arrive.
+    fn push(&mut self, data: impl Buf, max_item_size: usize) {
+        self.data.put(data);
+
+        loop {
+            let n = self.skip.min(self.data.len());
+            self.data.advance(n);
+            self.skip -= n;
+
+            if self.skip > 0 || self.data.len() < PREFIX_LEN {
+                break;
+            }
+
+            let len = u32::from_be_bytes(self.data[..PREFIX_LEN].try_into().unwrap()) as usize;
+            if len <= max_item_size {
+                break;
+            }
+
+            self.data.advance(PREFIX_LEN);
+            self.skip = len;
+            self.oversized = true;
+        }
+    }
+
+    /// Removes the next complete message from the buffer.
+    fn pop(&mut self) -> Result<Option<Bytes>, FramedError> {
+        if self.oversized {
+            self.oversized = false;
+            return Err(FramedError::MaxItemSizeExceeded);
+        }
+
+        if self.skip > 0 || self.data.len() < PREFIX_LEN {
+            return Ok(None);
+        }
+
+        let len = u32::from_be_bytes(self.data[..PREFIX_LEN].try_into().unwrap()) as usize;
+        if self.data.len() < PREFIX_LEN + len {
+            return Ok(None);
+        }
+
+        self.data.advance(PREFIX_LEN);
+        Ok(Some(self.data.split_to(len).freeze()))
+    }
+
+    /// Whether a partial message is buffered.
+    fn is_partial(&self) -> bool {
+        !self.data.is_empty() || self.skip > 0
+    }
+}
+
+/// Receives length-prefixed messages over a binary channel.
+///
+/// The received data is treated as a continuous stream, i.e. messages may span multiple
+/// chmux messages and one chmux message may contain multiple messages.
+/// Thus this can receive data sent by a [FramedSender] as well as data of
+/// an external length-delimited protocol written directly into a [binary channel](Sender).
+///
+/// Instances are created by the [framed](super::framed) function or from
+/// a [binary channel receiver](Receiver).
+/// The maximum item size is not transmitted when sending this to a remote endpoint.
+pub struct FramedReceiver {
+    receiver: Receiver,
+    buf: FrameBuf,
+    in_message: bool,
+    max_item_size: usize,
+}
+
+impl fmt::Debug for FramedReceiver {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FramedReceiver").field("max_item_size", &self.max_item_size).finish()
+    }
+}
+
+impl FramedReceiver {
+    /// Creates a framed receiver from a binary channel receiver.
+    pub fn new(receiver: Receiver) -> Self {
+        Self { receiver, buf: FrameBuf::default(), in_message: false, max_item_size: DEFAULT_MAX_ITEM_SIZE }
+    }
+
+    /// Receives the next message.
+    ///
+    /// Returns `Ok(None)` when the channel has been closed by the sender.
+    ///
+    /// A message larger than the [maximum item size](Self::max_item_size) is discarded
+    /// without buffering it and [FramedError::MaxItemSizeExceeded] is returned.
+    pub async fn recv(&mut self) -> Result<Option<Bytes>, FramedError> {
+        loop {
+            if let Some(msg) = self.buf.pop()? {
+                return Ok(Some(msg));
+            }
+
+            if !self.fill().await? {
+                if self.buf.is_partial() {
+                    self.buf = FrameBuf::default();
+                    return Err(FramedError::Truncated);
+                }
+                return Ok(None);
+            }
+        }
+    }
+
+    /// Receives the next chunk of data into the buffer.
+    ///
+    /// Chunks are processed as they arrive, so that messages are returned
+    /// without waiting for the chmux message containing them to complete.
+    ///
+    /// Returns `false` if the channel has been closed.
+    async fn fill(&mut self) -> Result<bool, FramedError> {
+        let rx = self.receiver.get().await?;
+        match rx.recv_chunk().await {
+            Ok(Some(chunk)) => {
+                self.in_message = true;
+                self.buf.push(chunk, self.max_item_size);
+            }
+            Ok(None) if self.in_message => self.in_message = false,
+            Ok(None) => return Ok(false),
+            Err(RecvChunkError::Cancelled) => {
+                self.buf = FrameBuf::default();
+                return Err(FramedError::Truncated);
+            }
+            Err(RecvChunkError::ChMux) => return Err(FramedError::Recv(chmux::RecvError::ChMux)),
+            Err(RecvChunkError::Shutdown) => return Err(FramedError::Recv(chmux::RecvError::Shutdown)),
+            Err(RecvChunkError::IdleTimeout) => return Err(FramedError::Recv(chmux::RecvError::IdleTimeout)),
+        }
+        Ok(true)
+    }
+
+    /// The maximum allowed size in bytes of a message to be received.
+    ///
+    /// The default value is [DEFAULT_MAX_ITEM_SIZE].
+    pub fn max_item_size(&self) -> usize {
+        self.max_item_size
+    }
+
+    /// Sets the maximum allowed size in bytes of a message to be received.
+    pub fn set_max_item_size(&mut self, max_item_size: usize) {
+        self.max_item_size = max_item_size;
+    }
+
+    /// Returns the underlying binary channel receiver.
+    ///
+    /// Buffered data that has not yet been returned as a message is discarded.
+    pub fn into_inner(self) -> Receiver {
+        self.receiver
+    }
+}
+
+impl From<Receiver> for FramedReceiver {
+    fn from(receiver: Receiver) -> Self {
+        Self::new(receiver)
+    }
+}
+
+impl Serialize for FramedReceiver {
+    /// Serializes this receiver for sending over a chmux channel.
+    ///
+    /// Fails if data has already been received.
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        if self.buf.is_partial() || self.in_message {
+            return Err(ser::Error::custom("framed receiver with buffered data cannot be sent"));
+        }
+
+        self.receiver.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for FramedReceiver {
+    /// Deserializes this receiver after it has been received over a chmux channel.
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        Ok(Self::new(Receiver::deserialize(deserializer)?))
+    }
+}
@@ -10,12 +10,16 @@
 //!
 //! This is a wrapper around a [chmux](crate::chmux) channel that allows to
 //! establish a connection by sending the sender or receiver to a remote endpoint.
+//!
+//! Use [framed] to exchange discrete, length-prefixed messages instead of raw data.
 
 use std::sync::{Arc, Mutex};
 
+mod framed;
 mod receiver;
 mod sender;
 
+pub use framed::{FramedError, FramedReceiver, FramedSender};
 pub use receiver::Receiver;
 pub use sender::Sender;
 
@@ -44,3 +48,13 @@ pub fn channel() -> (Sender, Receiver) {
     };
     (sender, receiver)
 }
+
+/// Creates a new binary channel exchanging length-prefixed messages.
+///
+/// The channel is established by sending either the sender or receiver
+/// over a remote channel.
+/// See [FramedSender] and [FramedReceiver] for details.
+pub fn framed() -> (FramedSender, FramedReceiver) {
+    let (sender, receiver) = channel();
+    (FramedSender::new(sender), FramedReceiver::new(receiver))
+}
@@ -16,15 +16,19 @@ mod receiver;
 mod sender;
 
 pub use receiver::{Receiver, ReceiverStream, RecvError, StreamError, TryRecvError};
-pub use sender::{SendError, Sender};
+pub use sender::{LagPolicy, SendError, Sender};
 
 /// Broadcast transport message.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub(crate) enum BroadcastMsg<T> {
     /// Value.
     Value(T),
-    /// Lagged notification.
+    /// Lagged notification without number of skipped values.
+    ///
+    /// This is sent by endpoints using an older version of this crate.
     Lagged,
+    /// Lagged notification with number of skipped values.
+    LaggedBy(u64),
 }
 
 /// Create a bounded, multi-producer, multi-consumer channel where each sent value is broadcasted to all active receivers.
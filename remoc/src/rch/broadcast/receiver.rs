@@ -5,6 +5,7 @@ use std::{
     error::Error,
     fmt,
     pin::Pin,
+    sync::Arc,
     task::{Context, Poll},
 };
 use tokio_util::sync::ReusableBoxFuture;
@@ -23,6 +24,8 @@ pub enum RecvError {
     /// The receiver lagged too far behind.
     ///
     /// Attempting to receive again will return the oldest message still retained by the channel.
+    ///
+    /// This is not returned if a [lag handler](Receiver::set_lag_handler) is set.
     Lagged,
     /// Receiving from a remote endpoint failed.
     RemoteReceive(base::RecvError),
@@ -102,6 +105,8 @@ pub enum TryRecvError {
     /// The receiver lagged too far behind.
     ///
     /// Attempting to receive again will return the oldest message still retained by the channel.
+    ///
+    /// This is not returned if a [lag handler](Receiver::set_lag_handler) is set.
     Lagged,
     /// Receiving from a remote endpoint failed.
     RemoteReceive(base::RecvError),
@@ -202,8 +207,15 @@ pub struct Receiver<
     const MAX_ITEM_SIZE: usize = DEFAULT_MAX_ITEM_SIZE,
 > {
     rx: mpsc::Receiver<BroadcastMsg<T>, Codec, BUFFER, MAX_ITEM_SIZE>,
+    #[serde(skip)]
+    lag: u64,
+    #[serde(skip)]
+    lag_handler: Option<LagHandler>,
 }
 
+/// Callback invoked with the number of skipped values when a receiver lagged behind.
+type LagHandler = Arc<dyn Fn(u64) + Send + Sync>;
+
 impl<T, Codec, const BUFFER: usize, const MAX_ITEM_SIZE: usize> fmt::Debug
     for Receiver<T, Codec, BUFFER, MAX_ITEM_SIZE>
 {
@@ -218,30 +230,122 @@ where
     Codec: codec::Codec,
 {
     pub(crate) fn new(rx: mpsc::Receiver<BroadcastMsg<T>, Codec, BUFFER, MAX_ITEM_SIZE>) -> Self {
-        Self { rx }
+        Self { rx, lag: 0, lag_handler: None }
     }
 
     /// Receives the next value for this receiver.
+    ///
+    /// If a [lag handler](Self::set_lag_handler) is set, it is invoked when the receiver
+    /// lagged behind and receiving continues with the next value.
+    /// Otherwise a [lagged error](RecvError::Lagged) is returned.
+    ///
+    /// # Cancel safety
+    /// This method is cancel safe.
+    /// If it is used in a `tokio::select!` statement and another branch completes first,
+    /// no value has been removed from the channel.
     #[inline]
     pub async fn recv(&mut self) -> Result<T, RecvError> {
-        match self.rx.recv().await {
-            Ok(Some(BroadcastMsg::Value(value))) => Ok(value),
-            Ok(Some(BroadcastMsg::Lagged)) => Err(RecvError::Lagged),
-            Ok(None) => Err(RecvError::Closed),
-            Err(err) => Err(err.into()),
+        loop {
+            match self.rx.recv().await {
+                Ok(Some(BroadcastMsg::Value(value))) => return Ok(value),
+                Ok(Some(BroadcastMsg::Lagged)) => {
+                    if !self.handle_lag(1) {
+                        return Err(RecvError::Lagged);
+                    }
+                }
+                Ok(Some(BroadcastMsg::LaggedBy(skipped))) => {
+                    if !self.handle_lag(skipped) {
+                        return Err(RecvError::Lagged);
+                    }
+                }
+                Ok(None) => return Err(RecvError::Closed),
+                Err(err) => return Err(err.into()),
+            }
         }
     }
 
+    /// Blocking receive to call outside of asynchronous contexts.
+    ///
+    /// A new single-threaded Tokio runtime is used for waiting, thus no runtime needs
+    /// to be available on the calling thread.
+    /// The connection the value is received over must be driven by another runtime.
+    /// Lagging is handled as described for [recv](Self::recv).
+    ///
+    /// # Panics
+    /// This function panics if called within an asynchronous execution context.
+    #[inline]
+    pub fn blocking_recv(&mut self) -> Result<T, RecvError> {
+        let rt = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        rt.block_on(self.recv())
+    }
+
     /// Attempts to return a pending value on this receiver without awaiting.
+    ///
+    /// Lagging is handled as described for [recv](Self::recv).
     #[inline]
     pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
-        match self.rx.try_recv() {
-            Ok(BroadcastMsg::Value(value)) => Ok(value),
-            Ok(BroadcastMsg::Lagged) => Err(TryRecvError::Lagged),
-            Err(err) => Err(err.into()),
+        loop {
+            match self.rx.try_recv() {
+                Ok(BroadcastMsg::Value(value)) => return Ok(value),
+                Ok(BroadcastMsg::Lagged) => {
+                    if !self.handle_lag(1) {
+                        return Err(TryRecvError::Lagged);
+                    }
+                }
+                Ok(BroadcastMsg::LaggedBy(skipped)) => {
+                    if !self.handle_lag(skipped) {
+                        return Err(TryRecvError::Lagged);
+                    }
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+
+    /// Accounts for skipped values and invokes the lag handler, if set.
+    ///
+    /// Returns whether the lag has been handled.
+    fn handle_lag(&mut self, skipped: u64) -> bool {
+        self.lag += skipped;
+        match &self.lag_handler {
+            Some(handler) => {
+                handler(skipped);
+                true
+            }
+            None => false,
         }
     }
 
+    /// Sets a handler that is invoked when this receiver lagged behind.
+    ///
+    /// The handler is called with the number of values that have been skipped.
+    /// If the sender is located on an endpoint using an older version of this crate,
+    /// the number is unknown and reported as one.
+    /// While a handler is set, [recv](Self::recv) and [try_recv](Self::try_recv) do not
+    /// return a lagged error, but silently continue with the next available value.
+    ///
+    /// By default no handler is set and lagging is reported as an error.
+    /// The handler is local to this receiver instance and not transmitted when it is
+    /// sent to a remote endpoint.
+    pub fn set_lag_handler(&mut self, handler: impl Fn(u64) + Send + Sync + 'static) {
+        self.lag_handler = Some(Arc::new(handler));
+    }
+
+    /// Removes the lag handler, restoring reporting of lagging as an error.
+    pub fn clear_lag_handler(&mut self) {
+        self.lag_handler = None;
+    }
+
+    /// The total number of values that have been skipped because this receiver lagged behind.
+    ///
+    /// This is updated when the lag notification is received, i.e. when [recv](Self::recv)
+    /// or [try_recv](Self::try_recv) return a lagged error or the lag handler is invoked.
+    /// The count is local to this receiver instance and not transmitted when it is
+    /// sent to a remote endpoint.
+    pub fn lag(&self) -> u64 {
+        self.lag
+    }
+
     /// The maximum item size in bytes.
     pub fn max_item_size(&self) -> usize {
         self.rx.max_item_size()
@@ -251,7 +355,7 @@ where
     pub fn set_max_item_size<const NEW_MAX_ITEM_SIZE: usize>(
         self,
     ) -> Receiver<T, Codec, BUFFER, NEW_MAX_ITEM_SIZE> {
-        Receiver { rx: self.rx.set_max_item_size() }
+        Receiver { rx: self.rx.set_max_item_size(), lag: self.lag, lag_handler: self.lag_handler }
     }
 
     /// The maximum item size of the remote sender.
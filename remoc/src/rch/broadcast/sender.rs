@@ -3,7 +3,7 @@ use std::{
     convert::{TryFrom, TryInto},
     error::Error,
     fmt, mem,
-    sync::{Arc, Mutex},
+    sync::{Arc, Mutex, Weak},
 };
 
 use super::{
@@ -63,7 +63,10 @@ impl<T> SendError<T> {
 
     /// True, if the remote endpoint closed the channel, was dropped or the connection failed.
     pub fn is_disconnected(&self) -> bool {
-        !matches!(self, Self::RemoteSend(base::SendErrorKind::Serialize(_)))
+        !matches!(
+            self,
+            Self::RemoteSend(base::SendErrorKind::Serialize(_) | base::SendErrorKind::MaxHopsExceeded)
+        )
     }
 
     /// Returns whether the error is final, i.e. no further send operation can succeed.
@@ -109,6 +112,24 @@ impl<T> SendErrorExt for SendError<T> {
     }
 }
 
+/// Policy for handling values sent while a receiver is lagging behind.
+///
+/// A receiver lags behind when its buffer is full at the time a value is sent.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LagPolicy {
+    /// Values sent while the receiver is lagging behind are discarded.
+    ///
+    /// The receiver first obtains all values in its buffer, i.e. the oldest available values,
+    /// followed by a lag notification.
+    #[default]
+    KeepOldest,
+    /// Only the newest value sent while the receiver is lagging behind is retained.
+    ///
+    /// The receiver obtains the newest value once its buffer has space again.
+    /// A lag notification is delivered before it, if values were discarded.
+    SkipToNewest,
+}
+
 /// Sending-half of the broadcast channel.
 ///
 /// Cannot be sent over a remote channel.
@@ -121,9 +142,32 @@ pub struct Sender<T, Codec = codec::Default> {
 
 struct SenderInner<T, Codec> {
     subs: Vec<mpsc::Sender<BroadcastMsg<T>, Codec, 1>>,
-    ready_tx: tokio::sync::mpsc::UnboundedSender<mpsc::Sender<BroadcastMsg<T>, Codec, 1>>,
-    ready_rx: tokio::sync::mpsc::UnboundedReceiver<mpsc::Sender<BroadcastMsg<T>, Codec, 1>>,
-    not_ready: usize,
+    lagging: Vec<Arc<Mutex<Lagging<T>>>>,
+    lag_policy: LagPolicy,
+}
+
+/// State of a subscriber that is lagging behind.
+struct Lagging<T> {
+    /// Number of values that have been discarded.
+    skipped: u64,
+    /// Newest value, if retained by lag policy.
+    newest: Option<T>,
+}
+
+impl<T> Lagging<T> {
+    /// Accounts for a value sent while lagging behind.
+    ///
+    /// The value is only obtained if it is retained by the lag policy.
+    fn push(&mut self, value: impl FnOnce() -> T, lag_policy: LagPolicy) {
+        match lag_policy {
+            LagPolicy::KeepOldest => self.skipped += 1,
+            LagPolicy::SkipToNewest => {
+                if self.newest.replace(value()).is_some() {
+                    self.skipped += 1;
+                }
+            }
+        }
+    }
 }
 
 impl<T, Codec> fmt::Debug for Sender<T, Codec> {
@@ -139,8 +183,7 @@ where
 {
     /// Creates a new sender.
     pub(crate) fn new() -> Self {
-        let (ready_tx, ready_rx) = tokio::sync::mpsc::unbounded_channel();
-        let inner = SenderInner { subs: Vec::new(), ready_tx, ready_rx, not_ready: 0 };
+        let inner = SenderInner { subs: Vec::new(), lagging: Vec::new(), lag_policy: LagPolicy::default() };
         Self { inner: Arc::new(Mutex::new(inner)) }
     }
 
@@ -150,32 +193,34 @@ where
     #[inline]
     pub fn send(&self, value: T) -> Result<(), SendError<T>> {
         let mut inner = self.inner.lock().unwrap();
+        let lag_policy = inner.lag_policy;
 
-        // Fetch subscribers that have become ready again.
-        while let Ok(sub) = inner.ready_rx.try_recv() {
-            inner.subs.push(sub);
-            inner.not_ready -= 1;
+        // Account value for subscribers that are lagging behind.
+        for lagging in &inner.lagging {
+            lagging.lock().unwrap().push(|| value.clone(), lag_policy);
         }
 
         let mut keep = Vec::new();
         let mut last_err = None;
 
         // Broadcast value to all subscribers that are ready.
+        // Capacity is checked before cloning the value, since it may be discarded for
+        // a subscriber that is lagging behind.
         let subs = mem::take(&mut inner.subs);
         for sub in subs {
-            match sub.try_send(BroadcastMsg::Value(value.clone())) {
-                Ok(()) => keep.push(sub),
-                Err(mpsc::TrySendError::Full(BroadcastMsg::Value(_))) => {
+            match sub.try_reserve() {
+                Ok(permit) => {
+                    permit.send(BroadcastMsg::Value(value.clone()));
+                    keep.push(sub);
+                }
+                Err(mpsc::TrySendError::Full(())) => {
                     // Spawn task that waits for subscriber to become ready again,
                     // then add it back to subscriber list.
-                    let ready_tx = inner.ready_tx.clone();
-                    tokio::spawn(async move {
-                        let _ = sub.send(BroadcastMsg::Lagged).await;
-                        // Make sure subscriber has space for next message.
-                        let _permit = sub.reserve().await;
-                        let _ = ready_tx.send(sub);
-                    });
-                    inner.not_ready += 1;
+                    let mut lagging = Lagging { skipped: 0, newest: None };
+                    lagging.push(|| value.clone(), lag_policy);
+                    let lagging = Arc::new(Mutex::new(lagging));
+                    inner.lagging.push(lagging.clone());
+                    tokio::spawn(Self::catch_up(Arc::downgrade(&self.inner), sub, lagging));
                 }
                 Err(mpsc::TrySendError::Closed(_)) => (),
                 Err(err) => last_err = Some(err),
@@ -184,7 +229,7 @@ where
         inner.subs = keep;
 
         // Return detailed error if last subscriber was disconnected because of error.
-        if !(inner.subs.is_empty() && inner.not_ready == 0) {
+        if !(inner.subs.is_empty() && inner.lagging.is_empty()) {
             Ok(())
         } else {
             match last_err {
@@ -197,6 +242,58 @@ where
         }
     }
 
+    /// Delivers the lag notification and retained value to a subscriber that is lagging
+    /// behind, then adds it back to the subscriber list once it has space for the next value.
+    async fn catch_up(
+        inner: Weak<Mutex<SenderInner<T, Codec>>>, sub: mpsc::Sender<BroadcastMsg<T>, Codec, 1>,
+        lagging: Arc<Mutex<Lagging<T>>>,
+    ) {
+        loop {
+            let permit = sub.reserve().await;
+
+            // Pending values are still delivered if the sender has been dropped.
+            let inner = inner.upgrade();
+            let mut inner = inner.as_ref().map(|inner| inner.lock().unwrap());
+
+            let permit = match permit {
+                Ok(permit) => permit,
+                Err(_) => {
+                    if let Some(inner) = &mut inner {
+                        inner.lagging.retain(|l| !Arc::ptr_eq(l, &lagging));
+                    }
+                    return;
+                }
+            };
+
+            let mut state = lagging.lock().unwrap();
+            if state.skipped > 0 {
+                permit.send(BroadcastMsg::LaggedBy(mem::take(&mut state.skipped)));
+            } else if let Some(value) = state.newest.take() {
+                permit.send(BroadcastMsg::Value(value));
+            } else {
+                drop(permit);
+                if let Some(inner) = &mut inner {
+                    inner.lagging.retain(|l| !Arc::ptr_eq(l, &lagging));
+                    inner.subs.push(sub);
+                }
+                return;
+            }
+        }
+    }
+
+    /// Sets the policy for handling values sent while a receiver is lagging behind.
+    ///
+    /// This applies to all receivers of this channel.
+    /// By default [LagPolicy::KeepOldest] is used.
+    pub fn set_lag_policy(&self, lag_policy: LagPolicy) {
+        self.inner.lock().unwrap().lag_policy = lag_policy;
+    }
+
+    /// The policy for handling values sent while a receiver is lagging behind.
+    pub fn lag_policy(&self) -> LagPolicy {
+        self.inner.lock().unwrap().lag_policy
+    }
+
     /// Creates a new receiver that will receive values sent after this call to subscribe.
     pub fn subscribe<const RECEIVE_BUFFER: usize>(
         &self, send_buffer: usize,
@@ -249,7 +346,7 @@ where
     pub fn receiver_count(&self) -> usize {
         let inner = self.inner.lock().unwrap();
 
-        inner.subs.len() + inner.not_ready
+        inner.subs.len() + inner.lagging.len()
     }
 }
 
@@ -41,6 +41,9 @@ pub enum SendErrorKind {
     Connect(ConnectError),
     /// Maximum item size was exceeded.
     MaxItemSizeExceeded,
+    /// The item contains a channel that has been forwarded more often than
+    /// its maximum number of hops allows.
+    MaxHopsExceeded,
 }
 
 impl<T> SendError<T> {
@@ -65,7 +68,10 @@ impl<T> SendError<T> {
 
     /// Whether the error is caused by the item to be sent.
     pub fn is_item_specific(&self) -> bool {
-        matches!(&self.kind, SendErrorKind::Serialize(_) | SendErrorKind::MaxItemSizeExceeded)
+        matches!(
+            &self.kind,
+            SendErrorKind::Serialize(_) | SendErrorKind::MaxItemSizeExceeded | SendErrorKind::MaxHopsExceeded
+        )
     }
 
     /// Returns the error without the contained item.
@@ -99,6 +105,7 @@ impl fmt::Display for SendErrorKind {
             Self::Send(err) => write!(f, "send error: {err}"),
             Self::Connect(err) => write!(f, "connect error: {err}"),
             Self::MaxItemSizeExceeded => write!(f, "maximum item size exceeded"),
+            Self::MaxHopsExceeded => write!(f, "maximum number of hops exceeded"),
         }
     }
 }
@@ -109,6 +116,7 @@ impl From<base::SendErrorKind> for SendErrorKind {
             base::SendErrorKind::Serialize(err) => Self::Serialize(err),
             base::SendErrorKind::Send(err) => Self::Send(err),
             base::SendErrorKind::MaxItemSizeExceeded => Self::MaxItemSizeExceeded,
+            base::SendErrorKind::MaxHopsExceeded => Self::MaxHopsExceeded,
         }
     }
 }
@@ -3,6 +3,8 @@
 //! The sender and receiver can both be sent to remote endpoints.
 //! The channel also works if both halves are local.
 //! Forwarding over multiple connections is supported.
+//! To prevent forwarding loops, the number of hops can be limited
+//! using [Sender::set_max_hops] and [Receiver::set_max_hops].
 //!
 //! This has similar functionality as [tokio::sync::mpsc] with the additional
 //! ability to work over remote connections.
@@ -47,6 +49,10 @@
 
 use bytes::Buf;
 use serde::{de::DeserializeOwned, Serialize};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
 
 use super::{base, ClosedReason, RemoteSendError};
 use crate::{
@@ -61,7 +67,7 @@ mod sender;
 
 pub use distributor::{DistributedReceiverHandle, Distributor};
 pub use receiver::{Receiver, RecvError, TryRecvError};
-pub use sender::{Permit, SendError, Sender, TrySendError};
+pub use sender::{Permit, SendError, Sender, SenderSink, TrySendError, WeakSender};
 
 /// Creates a bounded channel for communicating between asynchronous tasks with back pressure.
 ///
@@ -123,14 +129,13 @@ where
 async fn send_impl<T, Codec>(
     mut rx: tokio::sync::mpsc::Receiver<Result<T, RecvError>>, raw_tx: chmux::Sender,
     mut raw_rx: chmux::Receiver, remote_send_err_tx: tokio::sync::watch::Sender<Option<RemoteSendError>>,
-    closed_tx: tokio::sync::watch::Sender<Option<ClosedReason>>, max_item_size: usize,
+    closed_tx: tokio::sync::watch::Sender<Option<ClosedReason>>, max_item_size: Arc<AtomicUsize>,
 ) where
     T: Serialize + Send + 'static,
     Codec: codec::Codec,
 {
     // Encode data using remote sender.
     let mut remote_tx = base::Sender::<Result<T, RecvError>, Codec>::new(raw_tx);
-    remote_tx.set_max_item_size(max_item_size);
 
     // Process events.
     loop {
@@ -177,6 +182,7 @@ async fn send_impl<T, Codec>(
             value_opt = rx.recv() => {
                 match value_opt {
                     Some(value) => {
+                        remote_tx.set_max_item_size(max_item_size.load(Ordering::Relaxed));
                         if let Err(err) = remote_tx.send(value).await {
                             let _ = remote_send_err_tx.send(Some(RemoteSendError::Send(err.kind)));
                             let _ = closed_tx.send(Some(ClosedReason::Failed));
@@ -193,17 +199,18 @@ async fn send_impl<T, Codec>(
 async fn recv_impl<T, Codec>(
     tx: &tokio::sync::mpsc::Sender<Result<T, RecvError>>, mut raw_tx: chmux::Sender, raw_rx: chmux::Receiver,
     mut remote_send_err_rx: tokio::sync::watch::Receiver<Option<RemoteSendError>>,
-    mut closed_rx: tokio::sync::watch::Receiver<Option<ClosedReason>>, max_item_size: usize,
+    mut closed_rx: tokio::sync::watch::Receiver<Option<ClosedReason>>, max_item_size: Arc<AtomicUsize>,
 ) where
     T: DeserializeOwned + Send + 'static,
     Codec: codec::Codec,
 {
     // Decode raw received data using remote receiver.
     let mut remote_rx = base::Receiver::<Result<T, RecvError>, Codec>::new(raw_rx);
-    remote_rx.set_max_item_size(max_item_size);
 
     // Process events.
     loop {
+        remote_rx.set_max_item_size(max_item_size.load(Ordering::Relaxed));
+
         tokio::select! {
             biased;
 
@@ -6,7 +6,10 @@ use std::{
     fmt,
     marker::PhantomData,
     pin::Pin,
-    sync::Mutex,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
     task::{Context, Poll},
 };
 
@@ -132,6 +135,9 @@ pub struct Receiver<
     successor_tx: Mutex<Option<tokio::sync::oneshot::Sender<ReceiverInner<T>>>>,
     final_err: Option<RecvError>,
     remote_max_item_size: Option<usize>,
+    forward_max_item_size: Arc<AtomicUsize>,
+    hops: u32,
+    max_hops: Option<u32>,
     _codec: PhantomData<Codec>,
 }
 
@@ -165,6 +171,12 @@ pub(crate) struct TransportedReceiver<T, Codec> {
     /// Maximum item size.
     #[serde(default)]
     max_item_size: u64,
+    /// Number of times the receiver has been forwarded before.
+    #[serde(default)]
+    hops: u32,
+    /// Maximum number of hops.
+    #[serde(default)]
+    max_hops: Option<u32>,
 }
 
 impl<T, Codec, const BUFFER: usize, const MAX_ITEM_SIZE: usize> Receiver<T, Codec, BUFFER, MAX_ITEM_SIZE> {
@@ -179,6 +191,9 @@ impl<T, Codec, const BUFFER: usize, const MAX_ITEM_SIZE: usize> Receiver<T, Code
             successor_tx: Mutex::new(None),
             final_err: None,
             remote_max_item_size,
+            forward_max_item_size: Arc::new(AtomicUsize::new(MAX_ITEM_SIZE)),
+            hops: 0,
+            max_hops: None,
             _codec: PhantomData,
         }
     }
@@ -190,6 +205,11 @@ impl<T, Codec, const BUFFER: usize, const MAX_ITEM_SIZE: usize> Receiver<T, Code
     /// When a receive error occurs due to a connection failure and other senders are still
     /// present, it is held back and returned after all other senders have been dropped or failed.
     /// Use [error](Self::error) to check if such an error is present.
+    ///
+    /// # Cancel safety
+    /// This method is cancel safe.
+    /// If it is used in a `tokio::select!` statement and another branch completes first,
+    /// no value has been removed from the channel.
     #[inline]
     pub async fn recv(&mut self) -> Result<Option<T>, RecvError> {
         loop {
@@ -275,10 +295,56 @@ impl<T, Codec, const BUFFER: usize, const MAX_ITEM_SIZE: usize> Receiver<T, Code
         }
     }
 
+    /// Receives values for this receiver and appends them to `buf`.
+    ///
+    /// This waits until at least one value is available and then takes all
+    /// values that are already buffered locally, up to `limit` values in total.
+    /// The number of values appended to `buf` is returned.
+    ///
+    /// This function returns `Ok(0)` when all channel senders have been dropped
+    /// or `limit` is zero.
+    ///
+    /// If a receive error occurs after some values have been received,
+    /// these values remain in `buf` and the error is returned.
+    ///
+    /// # Cancel safety
+    /// This method is cancel safe.
+    /// If it is cancelled, no values have been appended to `buf` or removed from the channel.
+    pub async fn recv_many(&mut self, buf: &mut Vec<T>, limit: usize) -> Result<usize, RecvError> {
+        if limit == 0 {
+            return Ok(0);
+        }
+
+        match self.recv().await? {
+            Some(value) => buf.push(value),
+            None => return Ok(0),
+        }
+
+        let mut n = 1;
+        while n < limit {
+            match self.try_recv() {
+                Ok(value) => {
+                    buf.push(value);
+                    n += 1;
+                }
+                Err(err) => match RecvError::try_from(err) {
+                    Ok(err) => return Err(err),
+                    Err(_) => break,
+                },
+            }
+        }
+
+        Ok(n)
+    }
+
     /// Blocking receive to call outside of asynchronous contexts.
     ///
     /// This function returns `Ok(None)` when the channel sender has been dropped.
     ///
+    /// A new single-threaded Tokio runtime is used for waiting, thus no runtime needs
+    /// to be available on the calling thread.
+    /// The connection the value is received over must be driven by another runtime.
+    ///
     /// # Panics
     /// This function panics if called within an asynchronous execution context.
     #[inline]
@@ -287,6 +353,26 @@ impl<T, Codec, const BUFFER: usize, const MAX_ITEM_SIZE: usize> Receiver<T, Code
         rt.block_on(self.recv())
     }
 
+    /// Returns the number of values in the local buffer of the channel,
+    /// i.e. values that have been received from the remote endpoint and
+    /// are ready to be received without waiting.
+    ///
+    /// This only reflects local state: the remote endpoint may have sent
+    /// more values that are still in transit; these are not accounted for.
+    #[inline]
+    pub fn local_len(&self) -> usize {
+        self.inner.as_ref().unwrap().rx.len()
+    }
+
+    /// Returns whether the local buffer of the channel is empty,
+    /// i.e. no values are ready to be received without waiting.
+    ///
+    /// See [local_len](Self::local_len) for details.
+    #[inline]
+    pub fn is_local_empty(&self) -> bool {
+        self.inner.as_ref().unwrap().rx.is_empty()
+    }
+
     /// Closes the receiving half of a channel without dropping it.
     ///
     /// This allows to process outstanding values while stopping the sender from
@@ -320,6 +406,9 @@ impl<T, Codec, const BUFFER: usize, const MAX_ITEM_SIZE: usize> Receiver<T, Code
             successor_tx: Mutex::new(None),
             final_err: self.final_err.clone(),
             remote_max_item_size: self.remote_max_item_size,
+            forward_max_item_size: self.forward_max_item_size.clone(),
+            hops: self.hops,
+            max_hops: self.max_hops,
             _codec: PhantomData,
         }
     }
@@ -332,6 +421,9 @@ impl<T, Codec, const BUFFER: usize, const MAX_ITEM_SIZE: usize> Receiver<T, Code
             successor_tx: Mutex::new(None),
             final_err: self.final_err.clone(),
             remote_max_item_size: self.remote_max_item_size,
+            forward_max_item_size: self.forward_max_item_size.clone(),
+            hops: self.hops,
+            max_hops: self.max_hops,
             _codec: PhantomData,
         }
     }
@@ -342,14 +434,23 @@ impl<T, Codec, const BUFFER: usize, const MAX_ITEM_SIZE: usize> Receiver<T, Code
     }
 
     /// Sets the maximum item size in bytes.
+    ///
+    /// If this receiver has been received from a remote endpoint, the new limit applies
+    /// to the items received from then on.
+    /// Each endpoint an item passes through enforces its own limit, thus
+    /// effectively the smallest limit along the forwarding path applies.
     pub fn set_max_item_size<const NEW_MAX_ITEM_SIZE: usize>(
         mut self,
     ) -> Receiver<T, Codec, BUFFER, NEW_MAX_ITEM_SIZE> {
+        self.forward_max_item_size.store(NEW_MAX_ITEM_SIZE, Ordering::Relaxed);
         Receiver {
             inner: self.inner.take(),
             successor_tx: Mutex::new(None),
             final_err: self.final_err.clone(),
             remote_max_item_size: self.remote_max_item_size,
+            forward_max_item_size: self.forward_max_item_size.clone(),
+            hops: self.hops,
+            max_hops: self.max_hops,
             _codec: PhantomData,
         }
     }
@@ -362,6 +463,36 @@ impl<T, Codec, const BUFFER: usize, const MAX_ITEM_SIZE: usize> Receiver<T, Code
     pub fn remote_max_item_size(&self) -> Option<usize> {
         self.remote_max_item_size
     }
+
+    /// The number of times this receiver has been forwarded to another endpoint.
+    ///
+    /// This is zero for a receiver created by [channel](super::channel) and
+    /// increases by one each time the receiver is sent to a remote endpoint.
+    pub fn hops(&self) -> u32 {
+        self.hops
+    }
+
+    /// The maximum number of times this receiver may be forwarded to another endpoint.
+    ///
+    /// By default the number of hops is unlimited.
+    pub fn max_hops(&self) -> Option<u32> {
+        self.max_hops
+    }
+
+    /// Sets the maximum number of times this receiver may be forwarded to another endpoint.
+    ///
+    /// The limit is transmitted together with the receiver and thus applies on all endpoints
+    /// the receiver is forwarded to.
+    /// Sending a receiver that has already been forwarded `max_hops` times fails with
+    /// [SendErrorKind::MaxHopsExceeded](base::SendErrorKind::MaxHopsExceeded),
+    /// preventing endless forwarding loops.
+    /// Specify `None` to allow an unlimited number of hops.
+    ///
+    /// Only mpsc channels support limiting the number of hops;
+    /// other channels can be forwarded an unlimited number of times.
+    pub fn set_max_hops(&mut self, max_hops: Option<u32>) {
+        self.max_hops = max_hops;
+    }
 }
 
 impl<T, Codec, const BUFFER: usize, const MAX_ITEM_SIZE: usize> Receiver<T, Codec, BUFFER, MAX_ITEM_SIZE>
@@ -407,6 +538,13 @@ where
     where
         S: serde::Serializer,
     {
+        if self.max_hops.is_some_and(|max_hops| self.hops >= max_hops) {
+            return Err(PortSerializer::max_hops_exceeded(format!(
+                "forwarding loop: mpsc receiver has been forwarded {} times, exceeding maximum number of hops",
+                self.hops
+            )));
+        }
+
         // Register successor of this receiver.
         let (successor_tx, successor_rx) = tokio::sync::oneshot::channel();
         *self.successor_tx.lock().unwrap() = Some(successor_tx);
@@ -428,8 +566,15 @@ where
                     }
                 };
 
-                super::send_impl::<T, Codec>(rx, raw_tx, raw_rx, remote_send_err_tx, closed_tx, MAX_ITEM_SIZE)
-                    .await;
+                super::send_impl::<T, Codec>(
+                    rx,
+                    raw_tx,
+                    raw_rx,
+                    remote_send_err_tx,
+                    closed_tx,
+                    Arc::new(AtomicUsize::new(MAX_ITEM_SIZE)),
+                )
+                .await;
             }
             .boxed()
         })?;
@@ -441,6 +586,8 @@ where
             codec: PhantomData,
             closed: self.inner.as_ref().unwrap().closed,
             max_item_size: self.max_item_size().try_into().unwrap_or(u64::MAX),
+            hops: self.hops,
+            max_hops: self.max_hops,
         };
         transported.serialize(serializer)
     }
@@ -461,7 +608,7 @@ where
         assert!(BUFFER > 0, "BUFFER must not be zero");
 
         // Get chmux port number from deserialized transport type.
-        let TransportedReceiver { port, closed, max_item_size, .. } =
+        let TransportedReceiver { port, closed, max_item_size, hops, max_hops, .. } =
             TransportedReceiver::<T, Codec>::deserialize(deserializer)?;
 
         let max_item_size = usize::try_from(max_item_size).unwrap_or(usize::MAX);
@@ -474,6 +621,8 @@ where
 
         // Create channels.
         let (tx, rx) = tokio::sync::mpsc::channel(BUFFER);
+        let forward_max_item_size = Arc::new(AtomicUsize::new(MAX_ITEM_SIZE));
+        let task_max_item_size = forward_max_item_size.clone();
         let (closed_tx, closed_rx) = tokio::sync::watch::channel(None);
         let (remote_send_err_tx, remote_send_err_rx) = tokio::sync::watch::channel(None);
 
@@ -488,13 +637,24 @@ where
                     }
                 };
 
-                super::recv_impl::<T, Codec>(&tx, raw_tx, raw_rx, remote_send_err_rx, closed_rx, MAX_ITEM_SIZE)
-                    .await;
+                super::recv_impl::<T, Codec>(
+                    &tx,
+                    raw_tx,
+                    raw_rx,
+                    remote_send_err_rx,
+                    closed_rx,
+                    task_max_item_size,
+                )
+                .await;
             }
             .boxed()
         })?;
 
-        Ok(Self::new(rx, closed_tx, closed, remote_send_err_tx, Some(max_item_size)))
+        let mut this = Self::new(rx, closed_tx, closed, remote_send_err_tx, Some(max_item_size));
+        this.forward_max_item_size = forward_max_item_size;
+        this.hops = hops.saturating_add(1);
+        this.max_hops = max_hops;
+        Ok(this)
     }
 }
 
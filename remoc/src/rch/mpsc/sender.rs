@@ -1,11 +1,16 @@
-use futures::FutureExt;
+use futures::{future::BoxFuture, ready, FutureExt, Sink};
 use serde::{Deserialize, Serialize};
 use std::{
     convert::TryFrom,
     error::Error,
     fmt,
     marker::PhantomData,
-    sync::{Arc, Weak},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Weak,
+    },
+    task::{Context, Poll},
 };
 
 use super::{
@@ -18,17 +23,29 @@ use super::{
 use crate::{chmux, codec, RemoteSend};
 
 /// An error occurred during sending over an mpsc channel.
+///
+/// Use [is_item_specific](Self::is_item_specific) to check whether the error was caused
+/// by the item, for example because it could not be serialized, and
+/// [is_disconnected](Self::is_disconnected) to check whether the channel or the
+/// underlying connection failed.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum SendError<T> {
     /// The remote end closed the channel.
     Closed(T),
     /// Sending to a remote endpoint failed.
+    ///
+    /// The error kind distinguishes failures specific to an item, i.e.
+    /// [serialization](base::SendErrorKind::Serialize) and
+    /// [item size](base::SendErrorKind::MaxItemSizeExceeded) errors,
+    /// from [failures of the chmux channel](base::SendErrorKind::Send).
     RemoteSend(base::SendErrorKind),
     /// Connecting a sent channel failed.
     RemoteConnect(chmux::ConnectError),
     /// Listening for a received channel failed.
     RemoteListen(chmux::ListenerError),
     /// Forwarding at a remote endpoint to another remote endpoint failed.
+    ///
+    /// The cause of the error is not transmitted by the forwarding endpoint.
     RemoteForward,
 }
 
@@ -44,7 +61,7 @@ impl<T> SendError<T> {
     /// Currently this can only happen if a serialization error occurred.
     pub fn closed_reason(&self) -> Option<ClosedReason> {
         match self {
-            Self::RemoteSend(base::SendErrorKind::Serialize(_)) => None,
+            Self::RemoteSend(base::SendErrorKind::Serialize(_) | base::SendErrorKind::MaxHopsExceeded) => None,
             Self::RemoteSend(base::SendErrorKind::Send(chmux::SendError::Closed { .. })) => {
                 Some(ClosedReason::Dropped)
             }
@@ -55,7 +72,10 @@ impl<T> SendError<T> {
 
     /// True, if the remote endpoint closed the channel, was dropped or the connection failed.
     pub fn is_disconnected(&self) -> bool {
-        !matches!(self, Self::RemoteSend(base::SendErrorKind::Serialize(_)))
+        !matches!(
+            self,
+            Self::RemoteSend(base::SendErrorKind::Serialize(_) | base::SendErrorKind::MaxHopsExceeded)
+        )
     }
 
     /// Returns whether the error is final, i.e. no further send operation can succeed.
@@ -135,12 +155,16 @@ pub enum TrySendError<T> {
     /// is currently full and sending would require blocking.
     Full(T),
     /// Sending to a remote endpoint failed.
+    ///
+    /// See [SendError::RemoteSend] for details.
     RemoteSend(base::SendErrorKind),
     /// Connecting a sent channel failed.
     RemoteConnect(chmux::ConnectError),
     /// Listening for a received channel failed.
     RemoteListen(chmux::ListenerError),
     /// Forwarding at a remote endpoint to another remote endpoint failed.
+    ///
+    /// The cause of the error is not transmitted by the forwarding endpoint.
     RemoteForward,
 }
 
@@ -152,7 +176,11 @@ impl<T> TrySendError<T> {
 
     /// True, if the remote endpoint closed the channel, was dropped or the connection failed.
     pub fn is_disconnected(&self) -> bool {
-        !matches!(self, Self::RemoteSend(base::SendErrorKind::Serialize(_)) | Self::Full(_))
+        !matches!(
+            self,
+            Self::RemoteSend(base::SendErrorKind::Serialize(_) | base::SendErrorKind::MaxHopsExceeded)
+                | Self::Full(_)
+        )
     }
 
     /// Returns whether the error is final, i.e. no further send operation can succeed.
@@ -246,6 +274,9 @@ pub struct Sender<T, Codec = codec::Default, const BUFFER: usize = DEFAULT_BUFFE
     remote_send_err_rx: tokio::sync::watch::Receiver<Option<RemoteSendError>>,
     dropped_tx: tokio::sync::mpsc::Sender<()>,
     max_item_size: usize,
+    forward_max_item_size: Arc<AtomicUsize>,
+    hops: u32,
+    max_hops: Option<u32>,
     _codec: PhantomData<Codec>,
 }
 
@@ -263,11 +294,95 @@ impl<T, Codec, const BUFFER: usize> Clone for Sender<T, Codec, BUFFER> {
             remote_send_err_rx: self.remote_send_err_rx.clone(),
             dropped_tx: self.dropped_tx.clone(),
             max_item_size: self.max_item_size,
+            forward_max_item_size: self.forward_max_item_size.clone(),
+            hops: self.hops,
+            max_hops: self.max_hops,
+            _codec: PhantomData,
+        }
+    }
+}
+
+/// A sender that does not prevent the channel from being closed.
+///
+/// Obtained by [Sender::downgrade].
+/// The channel is closed once all [Senders](Sender) have been dropped, regardless of
+/// whether weak senders exist; the [Receiver](super::Receiver) then observes the
+/// closure as usual.
+///
+/// A weak sender cannot be sent to a remote endpoint.
+/// [Upgrade](Self::upgrade) it to obtain a sender that can.
+pub struct WeakSender<T, Codec = codec::Default, const BUFFER: usize = DEFAULT_BUFFER> {
+    tx: Weak<tokio::sync::mpsc::Sender<Result<T, RecvError>>>,
+    closed_rx: tokio::sync::watch::Receiver<Option<ClosedReason>>,
+    remote_send_err_rx: tokio::sync::watch::Receiver<Option<RemoteSendError>>,
+    dropped_tx: tokio::sync::mpsc::WeakSender<()>,
+    max_item_size: usize,
+    forward_max_item_size: Arc<AtomicUsize>,
+    hops: u32,
+    max_hops: Option<u32>,
+    _codec: PhantomData<Codec>,
+}
+
+impl<T, Codec, const BUFFER: usize> fmt::Debug for WeakSender<T, Codec, BUFFER> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("WeakSender").finish()
+    }
+}
+
+impl<T, Codec, const BUFFER: usize> Clone for WeakSender<T, Codec, BUFFER> {
+    fn clone(&self) -> Self {
+        Self {
+            tx: self.tx.clone(),
+            closed_rx: self.closed_rx.clone(),
+            remote_send_err_rx: self.remote_send_err_rx.clone(),
+            dropped_tx: self.dropped_tx.clone(),
+            max_item_size: self.max_item_size,
+            forward_max_item_size: self.forward_max_item_size.clone(),
+            hops: self.hops,
+            max_hops: self.max_hops,
             _codec: PhantomData,
         }
     }
 }
 
+impl<T, Codec, const BUFFER: usize> WeakSender<T, Codec, BUFFER>
+where
+    T: Send + 'static,
+{
+    /// Tries to convert this into a [Sender].
+    ///
+    /// Returns [None] if the channel has been closed because all senders were dropped.
+    /// Senders that have been sent to a remote endpoint keep the channel open.
+    pub fn upgrade(&self) -> Option<Sender<T, Codec, BUFFER>> {
+        let tx = self.tx.upgrade()?;
+        let mut sender = match self.dropped_tx.upgrade() {
+            Some(dropped_tx) => Sender {
+                tx: self.tx.clone(),
+                closed_rx: self.closed_rx.clone(),
+                remote_send_err_rx: self.remote_send_err_rx.clone(),
+                dropped_tx,
+                max_item_size: self.max_item_size,
+                forward_max_item_size: self.forward_max_item_size.clone(),
+                hops: self.hops,
+                max_hops: self.max_hops,
+                _codec: PhantomData,
+            },
+            None => Sender::new((*tx).clone(), self.closed_rx.clone(), self.remote_send_err_rx.clone()),
+        };
+        sender.max_item_size = self.max_item_size;
+        sender.forward_max_item_size = self.forward_max_item_size.clone();
+        sender.hops = self.hops;
+        sender.max_hops = self.max_hops;
+        Some(sender)
+    }
+}
+
+impl<T, Codec, const BUFFER: usize> Drop for WeakSender<T, Codec, BUFFER> {
+    fn drop(&mut self) {
+        // empty
+    }
+}
+
 /// Mpsc sender in transport.
 #[derive(Serialize, Deserialize)]
 pub(crate) struct TransportedSender<T, Codec> {
@@ -280,6 +395,12 @@ pub(crate) struct TransportedSender<T, Codec> {
     /// Maximum item size in bytes.
     #[serde(default = "default_max_item_size")]
     max_item_size: u64,
+    /// Number of times the sender has been forwarded before.
+    #[serde(default)]
+    hops: u32,
+    /// Maximum number of hops.
+    #[serde(default)]
+    max_hops: Option<u32>,
 }
 
 const fn default_max_item_size() -> u64 {
@@ -305,6 +426,9 @@ where
             remote_send_err_rx,
             dropped_tx,
             max_item_size: DEFAULT_MAX_ITEM_SIZE,
+            forward_max_item_size: Arc::new(AtomicUsize::new(DEFAULT_MAX_ITEM_SIZE)),
+            hops: 0,
+            max_hops: None,
             _codec: PhantomData,
         };
 
@@ -337,6 +461,9 @@ where
             remote_send_err_rx: tokio::sync::watch::channel(None).1,
             dropped_tx: tokio::sync::mpsc::channel(1).0,
             max_item_size: DEFAULT_MAX_ITEM_SIZE,
+            forward_max_item_size: Arc::new(AtomicUsize::new(DEFAULT_MAX_ITEM_SIZE)),
+            hops: 0,
+            max_hops: None,
             _codec: PhantomData,
         }
     }
@@ -392,6 +519,10 @@ where
 
     /// Blocking send to call outside of asynchronous contexts.
     ///
+    /// A new single-threaded Tokio runtime is used for waiting, thus no runtime needs
+    /// to be available on the calling thread.
+    /// The connection the value is sent over must be driven by another runtime.
+    ///
     /// # Error reporting
     /// Sending and error reporting are done asynchronously.
     /// Thus, the reporting of an error may be delayed and this function may
@@ -408,6 +539,19 @@ where
     /// Wait for channel capacity, returning an owned permit.
     /// Once capacity to send one message is available, it is reserved for the caller.
     ///
+    /// Sending using the [Permit] completes without waiting.
+    /// This allows checking for capacity before producing an expensive value.
+    ///
+    /// Capacity refers to the local buffer of the channel, see [local_capacity](Self::local_capacity).
+    /// Values are forwarded from the local buffer to the remote endpoint while its flow
+    /// control window permits, thus a remote endpoint that applies backpressure
+    /// eventually causes this to wait.
+    ///
+    /// If the permit is dropped without sending, the reserved capacity is released.
+    /// If the connection fails while a permit is held, sending using the permit
+    /// still completes, but the value is dropped and the error is reported by subsequent
+    /// operations on the sender, as for a value sent using [send](Self::send).
+    ///
     /// # Error reporting
     /// Sending and error reporting are done asynchronously.
     /// Thus, the reporting of an error may be delayed and this function may
@@ -429,17 +573,67 @@ where
         }
     }
 
+    /// Attempts to immediately reserve channel capacity, returning an owned permit.
+    ///
+    /// This allows checking for capacity before producing a value.
+    #[inline]
+    pub(crate) fn try_reserve(&self) -> Result<Permit<T>, TrySendError<()>> {
+        if let Some(err) = self.remote_send_err_rx.borrow().as_ref() {
+            return Err(TrySendError::from_remote_send_error(err.clone(), ()));
+        }
+
+        match self.tx.upgrade() {
+            Some(tx) => match (*tx).clone().try_reserve_owned() {
+                Ok(permit) => Ok(Permit(permit)),
+                Err(tokio::sync::mpsc::error::TrySendError::Full(_)) => Err(TrySendError::Full(())),
+                Err(tokio::sync::mpsc::error::TrySendError::Closed(_)) => Err(TrySendError::Closed(())),
+            },
+            None => Err(TrySendError::Closed(())),
+        }
+    }
+
     /// Returns the current capacity of the channel.
+    #[deprecated = "use local_capacity instead"]
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.local_capacity()
+    }
+
+    /// Returns the current capacity of the local buffer of the channel.
+    ///
+    /// This is the number of values that can be sent without waiting.
+    /// Sent values are buffered locally and forwarded to the remote endpoint
+    /// while the flow control window advertised by the remote endpoint permits it.
+    /// Thus, if the remote endpoint applies backpressure, the local buffer fills
+    /// up and the capacity decreases.
+    ///
+    /// This only reflects local state: values forwarded to the remote endpoint
+    /// but not yet received by the remote receiver are not accounted for.
     ///
     /// Zero is returned when the channel has been closed or an error has occurred.
     #[inline]
-    pub fn capacity(&self) -> usize {
+    pub fn local_capacity(&self) -> usize {
         match self.tx.upgrade() {
             Some(tx) => tx.capacity(),
             None => 0,
         }
     }
 
+    /// Returns the size of the local buffer of the channel.
+    ///
+    /// This is the [local capacity](Self::local_capacity) when no values
+    /// are waiting to be forwarded to the remote endpoint.
+    /// It does not include the buffer of the remote endpoint.
+    ///
+    /// Zero is returned when the channel has been closed or an error has occurred.
+    #[inline]
+    pub fn local_max_capacity(&self) -> usize {
+        match self.tx.upgrade() {
+            Some(tx) => tx.max_capacity(),
+            None => 0,
+        }
+    }
+
     /// Completes when the receiver has been closed, dropped or the connection failed.
     ///
     /// Use [closed_reason](Self::closed_reason) to obtain the cause for closure.
@@ -473,6 +667,23 @@ where
         self.closed_reason().is_some()
     }
 
+    /// Creates a [WeakSender] for this channel.
+    ///
+    /// A weak sender does not keep the channel open.
+    pub fn downgrade(&self) -> WeakSender<T, Codec, BUFFER> {
+        WeakSender {
+            tx: self.tx.clone(),
+            closed_rx: self.closed_rx.clone(),
+            remote_send_err_rx: self.remote_send_err_rx.clone(),
+            dropped_tx: self.dropped_tx.downgrade(),
+            max_item_size: self.max_item_size,
+            forward_max_item_size: self.forward_max_item_size.clone(),
+            hops: self.hops,
+            max_hops: self.max_hops,
+            _codec: PhantomData,
+        }
+    }
+
     /// Sets the codec that will be used when sending this sender to a remote endpoint.
     pub fn set_codec<NewCodec>(self) -> Sender<T, NewCodec, BUFFER> {
         Sender {
@@ -481,6 +692,9 @@ where
             remote_send_err_rx: self.remote_send_err_rx.clone(),
             dropped_tx: self.dropped_tx.clone(),
             max_item_size: self.max_item_size,
+            forward_max_item_size: self.forward_max_item_size.clone(),
+            hops: self.hops,
+            max_hops: self.max_hops,
             _codec: PhantomData,
         }
     }
@@ -494,35 +708,236 @@ where
             remote_send_err_rx: self.remote_send_err_rx.clone(),
             dropped_tx: self.dropped_tx.clone(),
             max_item_size: self.max_item_size,
+            forward_max_item_size: self.forward_max_item_size.clone(),
+            hops: self.hops,
+            max_hops: self.max_hops,
             _codec: PhantomData,
         }
     }
 
     /// The maximum allowed item size in bytes.
+    ///
+    /// For a sender received from a remote endpoint this is initially the limit
+    /// of the sender that was sent.
     pub fn max_item_size(&self) -> usize {
         self.max_item_size
     }
 
     /// Sets the maximum allowed item size in bytes.
+    ///
+    /// This limit is transmitted when sending this sender to a remote endpoint.
+    /// If this sender has been received from a remote endpoint, the limit also applies
+    /// to items forwarded from this endpoint, taking effect for the next item sent.
+    /// In this case the change affects all clones of this sender on this endpoint.
+    ///
+    /// Each endpoint an item passes through enforces its own limit, thus
+    /// effectively the smallest limit along the forwarding path applies.
     pub fn set_max_item_size(&mut self, max_item_size: usize) {
         self.max_item_size = max_item_size;
+        self.forward_max_item_size.store(max_item_size, Ordering::Relaxed);
+    }
+
+    /// The number of times this sender has been forwarded to another endpoint.
+    ///
+    /// This is zero for a sender created by [channel](super::channel) and
+    /// increases by one each time the sender is sent to a remote endpoint.
+    pub fn hops(&self) -> u32 {
+        self.hops
+    }
+
+    /// The maximum number of times this sender may be forwarded to another endpoint.
+    ///
+    /// By default the number of hops is unlimited.
+    pub fn max_hops(&self) -> Option<u32> {
+        self.max_hops
+    }
+
+    /// Sets the maximum number of times this sender may be forwarded to another endpoint.
+    ///
+    /// The limit is transmitted together with the sender and thus applies on all endpoints
+    /// the sender is forwarded to.
+    /// Sending a sender that has already been forwarded `max_hops` times fails with
+    /// [SendErrorKind::MaxHopsExceeded](base::SendErrorKind::MaxHopsExceeded),
+    /// preventing endless forwarding loops.
+    /// Specify `None` to allow an unlimited number of hops.
+    ///
+    /// Only mpsc channels support limiting the number of hops;
+    /// other channels can be forwarded an unlimited number of times.
+    pub fn set_max_hops(&mut self, max_hops: Option<u32>) {
+        self.max_hops = max_hops;
+    }
+
+    /// Convert this into a sink.
+    ///
+    /// # Example
+    ///
+    /// In the following example the server forwards a stream of numbers
+    /// into the MPSC channel sender it received from the client.
+    ///
+    /// ```
+    /// use futures::{stream, StreamExt};
+    /// use remoc::prelude::*;
+    ///
+    /// // This would be run on the client.
+    /// async fn client(mut tx: rch::base::Sender<rch::mpsc::Sender<u32>>) {
+    ///     let (seq_tx, mut seq_rx) = rch::mpsc::channel(1);
+    ///     tx.send(seq_tx).await.unwrap();
+    ///
+    ///     for i in 0..4 {
+    ///         assert_eq!(seq_rx.recv().await.unwrap(), Some(i));
+    ///     }
+    ///     assert_eq!(seq_rx.recv().await.unwrap(), None);
+    /// }
+    ///
+    /// // This would be run on the server.
+    /// async fn server(mut rx: rch::base::Receiver<rch::mpsc::Sender<u32>>) {
+    ///     while let Some(seq_tx) = rx.recv().await.unwrap() {
+    ///         stream::iter(0..4).map(Ok).forward(seq_tx.into_sink()).await.unwrap();
+    ///     }
+    /// }
+    /// # tokio_test::block_on(remoc::doctest::client_server(client, server));
+    /// ```
+    pub fn into_sink(self) -> SenderSink<T, Codec, BUFFER> {
+        SenderSink::new(self)
     }
 }
 
 /// Owned permit to send one value into the channel.
+///
+/// Obtained by calling [Sender::reserve].
+/// Dropping the permit without sending releases the reserved capacity.
 pub struct Permit<T>(tokio::sync::mpsc::OwnedPermit<Result<T, RecvError>>);
 
+impl<T> fmt::Debug for Permit<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Permit").finish()
+    }
+}
+
 impl<T> Permit<T>
 where
     T: Send,
 {
     /// Sends a value using the reserved capacity.
+    ///
+    /// This never waits.
+    /// If the channel has been closed or the connection has failed in the meantime,
+    /// the value is dropped.
     #[inline]
     pub fn send(self, value: T) {
         self.0.send(Ok(value));
     }
 }
 
+/// A sink sending values over an mpsc channel.
+///
+/// Obtained by calling [Sender::into_sink].
+/// Capacity is reserved when the sink is polled for readiness and the value
+/// is queued for sending when it is started to be sent.
+///
+/// # Error reporting
+/// Sending and error reporting are done asynchronously.
+/// Thus, an error caused by a value may be reported when the sink is
+/// polled for readiness or flushed afterwards.
+pub struct SenderSink<T, Codec = codec::Default, const BUFFER: usize = DEFAULT_BUFFER> {
+    tx: Option<Sender<T, Codec, BUFFER>>,
+    reserve_fut: Option<BoxFuture<'static, Result<Permit<T>, SendError<()>>>>,
+    permit: Option<Permit<T>>,
+}
+
+impl<T, Codec, const BUFFER: usize> fmt::Debug for SenderSink<T, Codec, BUFFER> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SenderSink").finish()
+    }
+}
+
+impl<T, Codec, const BUFFER: usize> SenderSink<T, Codec, BUFFER> {
+    fn new(tx: Sender<T, Codec, BUFFER>) -> Self {
+        Self { tx: Some(tx), reserve_fut: None, permit: None }
+    }
+}
+
+impl<T, Codec, const BUFFER: usize> SenderSink<T, Codec, BUFFER>
+where
+    T: RemoteSend,
+    Codec: codec::Codec,
+{
+    fn poll_ready(&mut self, cx: &mut Context) -> Poll<Result<(), SendError<()>>> {
+        if self.permit.is_some() {
+            return Poll::Ready(Ok(()));
+        }
+
+        let tx = match &self.tx {
+            Some(tx) => tx,
+            None => return Poll::Ready(Err(SendError::Closed(()))),
+        };
+        let reserve_fut = self.reserve_fut.get_or_insert_with(|| {
+            let tx = tx.clone();
+            async move { tx.reserve().await }.boxed()
+        });
+
+        let res = ready!(reserve_fut.as_mut().poll(cx));
+        self.reserve_fut = None;
+        self.permit = Some(res?);
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(&mut self, value: T) -> Result<(), SendError<()>> {
+        match self.permit.take() {
+            Some(permit) => {
+                permit.send(value);
+                Ok(())
+            }
+            None => panic!("sink is not ready for sending"),
+        }
+    }
+
+    fn poll_flush(&mut self) -> Poll<Result<(), SendError<()>>> {
+        match &self.tx {
+            Some(tx) => match tx.remote_send_err_rx.borrow().as_ref() {
+                Some(err) => Poll::Ready(Err(SendError::from_remote_send_error(err.clone(), ()))),
+                None => Poll::Ready(Ok(())),
+            },
+            None => Poll::Ready(Ok(())),
+        }
+    }
+
+    fn close(&mut self) {
+        self.permit = None;
+        self.reserve_fut = None;
+        self.tx = None;
+    }
+}
+
+impl<T, Codec, const BUFFER: usize> Sink<T> for SenderSink<T, Codec, BUFFER>
+where
+    T: RemoteSend,
+    Codec: codec::Codec,
+{
+    type Error = SendError<()>;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        Pin::into_inner(self).poll_ready(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), Self::Error> {
+        Pin::into_inner(self).start_send(item)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        Pin::into_inner(self).poll_flush()
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        let this = Pin::into_inner(self);
+        let res = ready!(this.poll_flush());
+        this.close();
+        Poll::Ready(res)
+    }
+}
+
+impl<T, Codec, const BUFFER: usize> Unpin for SenderSink<T, Codec, BUFFER> {}
+
 impl<T, Codec, const BUFFER: usize> Drop for Sender<T, Codec, BUFFER> {
     fn drop(&mut self) {
         // empty
@@ -540,13 +955,20 @@ where
     where
         S: serde::Serializer,
     {
+        if self.max_hops.is_some_and(|max_hops| self.hops >= max_hops) {
+            return Err(PortSerializer::max_hops_exceeded(format!(
+                "forwarding loop: mpsc sender has been forwarded {} times, exceeding maximum number of hops",
+                self.hops
+            )));
+        }
+
         let port = match self.tx.upgrade() {
             // Channel is open.
             Some(tx) => {
                 // Prepare channel for takeover.
                 let closed_rx = self.closed_rx.clone();
                 let remote_send_err_rx = self.remote_send_err_rx.clone();
-                let max_item_size = self.max_item_size;
+                let max_item_size = Arc::new(AtomicUsize::new(self.max_item_size));
 
                 Some(PortSerializer::connect(move |connect| {
                     async move {
@@ -584,6 +1006,8 @@ where
             data: PhantomData,
             codec: PhantomData,
             max_item_size: self.max_item_size.try_into().unwrap_or(u64::MAX),
+            hops: self.hops,
+            max_hops: self.max_hops,
         };
         transported.serialize(serializer)
     }
@@ -603,13 +1027,15 @@ where
         assert!(BUFFER > 0, "BUFFER must not be zero");
 
         // Get chmux port number from deserialized transport type.
-        let TransportedSender { port, max_item_size, .. } =
+        let TransportedSender { port, max_item_size, hops, max_hops, .. } =
             TransportedSender::<T, Codec>::deserialize(deserializer)?;
         let max_item_size = usize::try_from(max_item_size).unwrap_or(usize::MAX);
+        let forward_max_item_size = Arc::new(AtomicUsize::new(max_item_size));
 
-        match port {
+        let mut this = match port {
             // Received channel is open.
             Some(port) => {
+                let forward_max_item_size = forward_max_item_size.clone();
                 // Create internal communication channels.
                 let (tx, rx) = tokio::sync::mpsc::channel(BUFFER);
                 let (closed_tx, closed_rx) = tokio::sync::watch::channel(None);
@@ -633,18 +1059,24 @@ where
                             raw_rx,
                             remote_send_err_tx,
                             closed_tx,
-                            max_item_size,
+                            forward_max_item_size,
                         )
                         .await;
                     }
                     .boxed()
                 })?;
 
-                Ok(Self::new(tx, closed_rx, remote_send_err_rx))
+                Self::new(tx, closed_rx, remote_send_err_rx)
             }
 
             // Received closed channel.
-            None => Ok(Self::new_closed()),
-        }
+            None => Self::new_closed(),
+        };
+
+        this.max_item_size = max_item_size;
+        this.forward_max_item_size = forward_max_item_size;
+        this.hops = hops.saturating_add(1);
+        this.max_hops = max_hops;
+        Ok(this)
     }
 }
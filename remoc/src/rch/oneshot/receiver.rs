@@ -150,12 +150,38 @@ where
         self.0.close()
     }
 
-    /// Attempts to receive a value transmitted by the sender.
+    /// Attempts to receive a value transmitted by the sender without waiting.
+    ///
+    /// Returns the value if it has arrived, `Err(TryRecvError::Empty)` if it has not arrived yet
+    /// and `Err(TryRecvError::Closed)` if the sender was dropped without sending a value.
+    /// If the received value could not be deserialized, the
+    /// [deserialization error](base::RecvError::Deserialize) is returned.
+    ///
+    /// This only checks the values already received over the connection and thus
+    /// can be called repeatedly, for example from a polling loop.
     #[inline]
     pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
         Ok(self.0.try_recv()?)
     }
 
+    /// Blocking receive to call outside of asynchronous contexts.
+    ///
+    /// Waits for the value transmitted by the sender.
+    /// A new single-threaded Tokio runtime is used for waiting, thus no runtime needs
+    /// to be available on the calling thread.
+    /// The connection the value is received over must be driven by another runtime.
+    ///
+    /// # Panics
+    /// This function panics if called within an asynchronous execution context.
+    #[inline]
+    pub fn blocking_recv(mut self) -> Result<T, RecvError> {
+        match self.0.blocking_recv() {
+            Ok(Some(v)) => Ok(v),
+            Ok(None) => Err(RecvError::Closed),
+            Err(err) => Err(err.into()),
+        }
+    }
+
     /// The maximum item size in bytes.
     pub fn max_item_size(&self) -> usize {
         self.0.max_item_size()
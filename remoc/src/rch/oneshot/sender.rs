@@ -101,14 +101,30 @@ where
     Codec: codec::Codec,
 {
     /// Sends a value over this channel.
+    ///
+    /// If the receiver is known to have been closed or dropped, the value
+    /// is returned in [SendError::Closed].
     #[inline]
     pub fn send(self, value: T) -> Result<(), SendError<T>> {
+        if let Some(ClosedReason::Closed | ClosedReason::Dropped) = self.0.closed_reason() {
+            return Err(SendError::Closed(value));
+        }
+
         self.0.try_send(value).map_err(|err| err.into())
     }
 
     /// Completes when the receiver has been closed, dropped or the connection failed.
     ///
+    /// This allows the producer of a value to abandon its work when
+    /// the value is no longer needed.
+    ///
     /// Use [closed_reason](Self::closed_reason) to obtain the cause for closure.
+    ///
+    /// # Latency
+    /// If the receiver is located on a remote endpoint, its closure or drop is
+    /// signalled over the connection.
+    /// Thus notification is best-effort and delayed by at least the network latency.
+    /// A value sent in the meantime is silently discarded.
     #[inline]
     pub async fn closed(&self) {
         self.0.closed().await
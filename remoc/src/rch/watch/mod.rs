@@ -13,6 +13,20 @@
 //! with other endpoints, consider using an [read/write lock](crate::robj::rw_lock)
 //! instead.
 //!
+//! # Forwarding
+//!
+//! By default, only the latest value is transmitted to receivers located on remote endpoints.
+//! Use [Sender::set_forward_mode] to transmit every value, as long as the connection keeps pace;
+//! see [ForwardMode] for details.
+//!
+//! # Versions
+//!
+//! Each value is associated with a [version](Ref::version) that increases every time
+//! a new value is stored in the channel.
+//! This allows to detect whether a value has already been processed without comparing it.
+//! Versions are counted separately on each endpoint, thus a receiver located on a remote
+//! endpoint observes different version numbers than the sender.
+//!
 //! # Example
 //!
 //! In the following example the client sends a number and a watch channel sender to the server.
@@ -33,9 +47,7 @@
 //!     tx.send(CountReq { up_to: 4, watch_tx }).await.unwrap();
 //!
 //!     // Intermediate values may be missed.
-//!     while *watch_rx.borrow_and_update().unwrap() != 3 {
-//!         watch_rx.changed().await;
-//!     }
+//!     watch_rx.wait_for(|v| *v == 3).await.unwrap();
 //! }
 //!
 //! // This would be run on the server.
@@ -51,8 +63,15 @@
 //!
 
 use bytes::Buf;
-use serde::{de::DeserializeOwned, Serialize};
-use std::{fmt, ops::Deref};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    fmt, mem,
+    ops::Deref,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
 
 use super::{base, RemoteSendError, DEFAULT_MAX_ITEM_SIZE};
 use crate::{chmux, codec, rch::BACKCHANNEL_MSG_ERROR, RemoteSend};
@@ -60,17 +79,52 @@ use crate::{chmux, codec, rch::BACKCHANNEL_MSG_ERROR, RemoteSend};
 mod receiver;
 mod sender;
 
-pub use receiver::{ChangedError, Receiver, ReceiverStream, RecvError};
+pub use receiver::{ChangedError, Receiver, ReceiverStream, RecvError, WaitError};
 pub use sender::{SendError, Sender};
 
+/// Default maximum number of remote send errors held by a [Sender].
+pub const DEFAULT_ERROR_QUEUE_LENGTH: usize = 16;
+
 /// Returns a reference to the inner value.
-pub struct Ref<'a, T>(tokio::sync::watch::Ref<'a, Result<T, RecvError>>);
+///
+/// A reference is only ever created for a value; if the channel holds an error,
+/// the borrowing method returns that error instead.
+/// Thus dereferencing never panics.
+pub struct Ref<'a, T> {
+    inner: tokio::sync::watch::Ref<'a, Result<T, RecvError>>,
+    version: u64,
+}
+
+impl<'a, T> Ref<'a, T> {
+    /// Creates a reference to the value, or returns the error held by the channel.
+    fn new(
+        inner: tokio::sync::watch::Ref<'a, Result<T, RecvError>>, forward: &Forward<T>,
+    ) -> Result<Self, RecvError> {
+        let version = forward.version();
+        match &*inner {
+            Ok(_) => Ok(Self { inner, version }),
+            Err(err) => Err(err.clone()),
+        }
+    }
+
+    /// The version of the value.
+    ///
+    /// It starts at zero and is incremented each time a value is stored in the channel
+    /// on this endpoint.
+    /// Thus, if two references have the same version, they refer to the same value.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+}
 
 impl<'a, T> Deref for Ref<'a, T> {
     type Target = T;
 
     fn deref(&self) -> &Self::Target {
-        self.0.as_ref().unwrap()
+        match &*self.inner {
+            Ok(value) => value,
+            Err(_) => unreachable!("reference to error"),
+        }
     }
 }
 
@@ -83,6 +137,164 @@ where
     }
 }
 
+/// Mode for forwarding values to receivers located on remote endpoints.
+///
+/// Local receivers are not affected by this and always observe only the latest value.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ForwardMode {
+    /// Only the latest value is forwarded.
+    ///
+    /// Values sent while a previous value is still being transmitted are coalesced.
+    #[default]
+    Coalesce,
+    /// Every value is forwarded, as long as the connection keeps pace.
+    ///
+    /// Up to the specified number of values are buffered for transmission to
+    /// each remote endpoint.
+    /// When the buffer is full due to backpressure, the oldest buffered values are
+    /// dropped; the latest value is always delivered.
+    ///
+    /// Values that arrive at the remote endpoint faster than they are observed by
+    /// the remote receiver are still coalesced there.
+    Queue(usize),
+}
+
+/// Bounded buffer of values for [ForwardMode::Queue].
+struct Queue<T> {
+    tx: tokio::sync::broadcast::Sender<Result<T, RecvError>>,
+    len: usize,
+    clone: fn(&Result<T, RecvError>) -> Result<T, RecvError>,
+}
+
+impl<T> Queue<T> {
+    fn push(&self, value: &Result<T, RecvError>) {
+        let _ = self.tx.send((self.clone)(value));
+    }
+}
+
+/// Forward mode and value version shared by all local handles of a watch channel.
+pub(crate) struct Forward<T> {
+    queue: Arc<Mutex<Option<Queue<T>>>>,
+    version: Arc<AtomicU64>,
+}
+
+impl<T> Clone for Forward<T> {
+    fn clone(&self) -> Self {
+        Self { queue: self.queue.clone(), version: self.version.clone() }
+    }
+}
+
+impl<T> Default for Forward<T> {
+    fn default() -> Self {
+        Self { queue: Arc::new(Mutex::new(None)), version: Arc::new(AtomicU64::new(0)) }
+    }
+}
+
+impl<T> Forward<T> {
+    /// The current forward mode.
+    fn mode(&self) -> ForwardMode {
+        match &*self.queue.lock().unwrap() {
+            Some(queue) => ForwardMode::Queue(queue.len),
+            None => ForwardMode::Coalesce,
+        }
+    }
+
+    /// The version of the value of the local channel.
+    ///
+    /// The version is incremented while the channel is locked for writing,
+    /// thus it is consistent with the value while a reference to it is held.
+    fn version(&self) -> u64 {
+        self.version.load(Ordering::SeqCst)
+    }
+
+    /// Increments the version.
+    fn next_version(&self) {
+        self.version.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Sends a value over the local channel and queues it for forwarding.
+    fn send(
+        &self, tx: &tokio::sync::watch::Sender<Result<T, RecvError>>, value: Result<T, RecvError>,
+    ) -> Result<(), tokio::sync::watch::error::SendError<Result<T, RecvError>>> {
+        if tx.is_closed() {
+            return Err(tokio::sync::watch::error::SendError(value));
+        }
+        let _ = self.send_replace(tx, value);
+        Ok(())
+    }
+
+    /// Modifies the value of the local channel and queues it for forwarding, if modified.
+    fn send_if_modified<F>(&self, tx: &tokio::sync::watch::Sender<Result<T, RecvError>>, func: F) -> bool
+    where
+        F: FnOnce(&mut Result<T, RecvError>) -> bool,
+    {
+        let queue = self.queue.lock().unwrap();
+        let modified = tx.send_if_modified(|value| {
+            let modified = func(value);
+            if modified {
+                self.next_version();
+            }
+            modified
+        });
+        if let (true, Some(queue)) = (modified, &*queue) {
+            queue.push(&tx.borrow());
+        }
+        modified
+    }
+
+    /// Replaces the value of the local channel and queues it for forwarding.
+    fn send_replace(
+        &self, tx: &tokio::sync::watch::Sender<Result<T, RecvError>>, mut value: Result<T, RecvError>,
+    ) -> Result<T, RecvError> {
+        let queue = self.queue.lock().unwrap();
+        if let Some(queue) = &*queue {
+            queue.push(&value);
+        }
+        tx.send_modify(|current| {
+            mem::swap(current, &mut value);
+            self.next_version();
+        });
+        value
+    }
+}
+
+impl<T> Forward<T>
+where
+    T: Clone,
+{
+    /// Creates the forward state for the specified mode.
+    fn new(mode: ForwardMode) -> Self {
+        let this = Self::default();
+        this.set_mode(mode);
+        this
+    }
+
+    /// Sets the forward mode.
+    ///
+    /// Remote endpoints that are already connected switch to coalescing.
+    fn set_mode(&self, mode: ForwardMode) {
+        *self.queue.lock().unwrap() = match mode {
+            ForwardMode::Coalesce => None,
+            ForwardMode::Queue(len) => {
+                assert!(len > 0, "forward queue length must be greater than zero");
+                let (tx, _) = tokio::sync::broadcast::channel(len);
+                Some(Queue { tx, len, clone: Clone::clone })
+            }
+        };
+    }
+
+    /// Returns the current value of the local channel together with a subscription
+    /// to all values forwarded after it.
+    #[allow(clippy::type_complexity)]
+    fn subscribe(
+        &self, rx: &tokio::sync::watch::Receiver<Result<T, RecvError>>,
+    ) -> (Result<T, RecvError>, Option<tokio::sync::broadcast::Receiver<Result<T, RecvError>>>) {
+        let queue = self.queue.lock().unwrap();
+        let value = rx.borrow().clone();
+        (value, queue.as_ref().map(|queue| queue.tx.subscribe()))
+    }
+}
+
 /// Creates a new watch channel, returning the sender and receiver.
 ///
 /// The sender and receiver may be sent to remote endpoints via channels.
@@ -93,11 +305,59 @@ where
     let (tx, rx) = tokio::sync::watch::channel(Ok(init));
     let (remote_send_err_tx, remote_send_err_rx) = tokio::sync::mpsc::unbounded_channel();
 
-    let sender = Sender::new(tx, remote_send_err_tx.clone(), remote_send_err_rx, DEFAULT_MAX_ITEM_SIZE);
-    let receiver = Receiver::new(rx, remote_send_err_tx, None);
+    let forward = Forward::default();
+    let sender =
+        Sender::new(tx, remote_send_err_tx.clone(), remote_send_err_rx, DEFAULT_MAX_ITEM_SIZE, forward.clone());
+    let receiver = Receiver::new(rx, remote_send_err_tx, None, forward);
     (sender, receiver)
 }
 
+/// Creates a watch receiver from an existing local [tokio watch receiver](tokio::sync::watch::Receiver).
+///
+/// The returned receiver may be sent to remote endpoints via channels.
+/// Its initial value is the current value of `local_rx` and it is notified whenever
+/// the local channel changes.
+/// To adopt a [tokio watch sender](tokio::sync::watch::Sender), pass the result of its
+/// `subscribe` method.
+///
+/// This spawns a task that forwards changes of the local channel.
+/// The returned channel is closed when the local channel is closed.
+/// Since no remote [Sender] exists, errors sending the value to remote endpoints are discarded.
+pub fn channel_from_receiver<T, Codec>(mut local_rx: tokio::sync::watch::Receiver<T>) -> Receiver<T, Codec>
+where
+    T: RemoteSend + Clone + Sync,
+{
+    let (tx, rx) = tokio::sync::watch::channel(Ok(local_rx.borrow_and_update().clone()));
+    let (remote_send_err_tx, mut remote_send_err_rx) = tokio::sync::mpsc::unbounded_channel();
+    let forward = Forward::default();
+    let task_forward = forward.clone();
+
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                biased;
+
+                () = tx.closed() => break,
+
+                Some(_) = remote_send_err_rx.recv() => (),
+
+                res = local_rx.changed() => {
+                    if res.is_err() {
+                        break;
+                    }
+
+                    let value = local_rx.borrow_and_update().clone();
+                    if task_forward.send(&tx, Ok(value)).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+
+    Receiver::new(rx, remote_send_err_tx, None, forward)
+}
+
 /// Extensions for watch channels.
 pub trait WatchExt<T, Codec, const MAX_ITEM_SIZE: usize> {
     /// Sets the maximum item size for the channel.
@@ -125,7 +385,7 @@ where
 async fn send_impl<T, Codec>(
     mut rx: tokio::sync::watch::Receiver<Result<T, RecvError>>, raw_tx: chmux::Sender,
     mut raw_rx: chmux::Receiver, remote_send_err_tx: tokio::sync::mpsc::UnboundedSender<RemoteSendError>,
-    max_item_size: usize,
+    max_item_size: usize, mut queue_rx: Option<tokio::sync::broadcast::Receiver<Result<T, RecvError>>>,
 ) where
     T: Serialize + Send + Clone + 'static,
     Codec: codec::Codec,
@@ -151,9 +411,31 @@ async fn send_impl<T, Codec>(
                 }
             }
 
+            // Queued data to send to remote endpoint.
+            queued = async { queue_rx.as_mut().unwrap().recv().await }, if queue_rx.is_some() => {
+                let value = match queued {
+                    Ok(value) => value,
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => {
+                        // Forward mode has been changed, continue with coalescing.
+                        queue_rx = None;
+                        continue;
+                    }
+                };
+                if let Err(err) = remote_tx.send(value).await {
+                    let _ = remote_send_err_tx.send(RemoteSendError::Send(err.kind.clone()));
+                    if err.is_item_specific() {
+                        break
+                    }
+                }
+            }
+
             // Data to send to remote endpoint.
             changed = rx.changed() => {
                 match changed {
+                    Ok(()) if queue_rx.is_some() => {
+                        rx.borrow_and_update();
+                    }
                     Ok(()) => {
                         let value = rx.borrow_and_update().clone();
                         if let Err(err) = remote_tx.send(value).await {
@@ -174,7 +456,7 @@ async fn send_impl<T, Codec>(
 async fn recv_impl<T, Codec>(
     tx: tokio::sync::watch::Sender<Result<T, RecvError>>, mut raw_tx: chmux::Sender, raw_rx: chmux::Receiver,
     mut remote_send_err_rx: tokio::sync::mpsc::UnboundedReceiver<RemoteSendError>,
-    mut current_err: Option<RemoteSendError>, max_item_size: usize,
+    mut current_err: Option<RemoteSendError>, max_item_size: usize, forward: Forward<T>,
 ) where
     T: DeserializeOwned + Send + 'static,
     Codec: codec::Codec,
@@ -211,7 +493,7 @@ async fn recv_impl<T, Codec>(
                         Err(RecvError::RemoteReceive(err))
                     },
                 };
-                if tx.send(value).is_err() {
+                if forward.send(&tx, value).is_err() {
                     break;
                 }
                 if is_final_err {
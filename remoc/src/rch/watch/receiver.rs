@@ -7,6 +7,7 @@ use std::{
     mem,
     pin::Pin,
     task::{Context, Poll},
+    time::Duration,
 };
 use tokio_util::sync::ReusableBoxFuture;
 
@@ -15,7 +16,7 @@ use super::{
         base::{self, PortDeserializer, PortSerializer},
         RemoteSendError, DEFAULT_MAX_ITEM_SIZE,
     },
-    Ref,
+    Forward, Ref,
 };
 use crate::{chmux, codec, RemoteSend};
 
@@ -77,6 +78,49 @@ impl fmt::Display for ChangedError {
 
 impl Error for ChangedError {}
 
+/// An error occurred during waiting for or checking a value of a watch channel.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum WaitError {
+    /// The sender has been dropped or the connection has been lost.
+    Closed,
+    /// The channel holds an error instead of a value.
+    RemoteRecv(RecvError),
+}
+
+impl WaitError {
+    /// True, if remote endpoint has closed channel.
+    pub fn is_closed(&self) -> bool {
+        matches!(self, Self::Closed)
+    }
+
+    /// Returns whether the error is final, i.e. no further receive operation can succeed.
+    pub fn is_final(&self) -> bool {
+        match self {
+            Self::Closed => true,
+            Self::RemoteRecv(err) => err.is_final(),
+        }
+    }
+}
+
+impl From<ChangedError> for WaitError {
+    fn from(err: ChangedError) -> Self {
+        match err {
+            ChangedError::Closed => Self::Closed,
+        }
+    }
+}
+
+impl fmt::Display for WaitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Closed => write!(f, "closed"),
+            Self::RemoteRecv(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl Error for WaitError {}
+
 /// Receive values from the associated [Sender](super::Sender),
 /// which may be located on a remote endpoint.
 ///
@@ -89,6 +133,7 @@ pub struct Receiver<T, Codec = codec::Default, const MAX_ITEM_SIZE: usize = DEFA
     rx: tokio::sync::watch::Receiver<Result<T, RecvError>>,
     remote_send_err_tx: tokio::sync::mpsc::UnboundedSender<RemoteSendError>,
     remote_max_item_size: Option<usize>,
+    forward: Forward<T>,
     _codec: PhantomData<Codec>,
 }
 
@@ -116,37 +161,135 @@ impl<T, Codec, const MAX_ITEM_SIZE: usize> Receiver<T, Codec, MAX_ITEM_SIZE> {
     pub(crate) fn new(
         rx: tokio::sync::watch::Receiver<Result<T, RecvError>>,
         remote_send_err_tx: tokio::sync::mpsc::UnboundedSender<RemoteSendError>,
-        remote_max_item_size: Option<usize>,
+        remote_max_item_size: Option<usize>, forward: Forward<T>,
     ) -> Self {
-        Self { rx, remote_send_err_tx, remote_max_item_size, _codec: PhantomData }
+        Self { rx, remote_send_err_tx, remote_max_item_size, forward, _codec: PhantomData }
     }
 
     /// Returns a reference to the most recently received value.
+    ///
+    /// If the channel holds an error instead of a value, for example because the
+    /// connection to the remote sender failed, that error is returned.
+    /// This never panics.
     #[inline]
     pub fn borrow(&self) -> Result<Ref<'_, T>, RecvError> {
-        let ref_res = self.rx.borrow();
-        match &*ref_res {
-            Ok(_) => Ok(Ref(ref_res)),
-            Err(err) => Err(err.clone()),
-        }
+        Ref::new(self.rx.borrow(), &self.forward)
     }
 
     /// Returns a reference to the most recently received value and mark that value as seen.
+    ///
+    /// If the channel holds an error instead of a value, that error is returned
+    /// and it is marked as seen.
+    /// This never panics.
     #[inline]
     pub fn borrow_and_update(&mut self) -> Result<Ref<'_, T>, RecvError> {
-        let ref_res = self.rx.borrow_and_update();
-        match &*ref_res {
-            Ok(_) => Ok(Ref(ref_res)),
-            Err(err) => Err(err.clone()),
+        Ref::new(self.rx.borrow_and_update(), &self.forward)
+    }
+
+    /// The version of the most recently received value.
+    ///
+    /// The version is incremented each time a value or error is stored in the channel,
+    /// see [Ref::version] for details.
+    /// Comparing versions is a cheap way to detect whether the value has changed
+    /// since it was last processed.
+    #[inline]
+    pub fn version(&self) -> u64 {
+        let _ref_res = self.rx.borrow();
+        self.forward.version()
+    }
+
+    /// Checks whether a value that has not been seen yet is available.
+    ///
+    /// This neither waits nor marks the newest value as seen.
+    ///
+    /// If the unseen value is an error, [WaitError::RemoteRecv] is returned.
+    /// If the sender has been dropped or the connection has been lost,
+    /// [WaitError::Closed] is returned.
+    #[inline]
+    pub fn has_changed(&self) -> Result<bool, WaitError> {
+        if !self.rx.has_changed().map_err(|_| WaitError::Closed)? {
+            return Ok(false);
+        }
+
+        match &*self.rx.borrow() {
+            Ok(_) => Ok(true),
+            Err(err) => Err(WaitError::RemoteRecv(err.clone())),
         }
     }
 
     /// Wait for a change notification, then mark the newest value as seen.
+    ///
+    /// # Cancel safety
+    /// This method is cancel safe.
+    /// If it is cancelled, the newest value is not marked as seen and a change
+    /// is reported by the next call.
     #[inline]
     pub async fn changed(&mut self) -> Result<(), ChangedError> {
         self.rx.changed().await.map_err(|_| ChangedError::Closed)
     }
 
+    /// Blocking wait for a change notification to call outside of asynchronous contexts,
+    /// then mark the newest value as seen.
+    ///
+    /// A new single-threaded Tokio runtime is used for waiting, thus no runtime needs
+    /// to be available on the calling thread.
+    /// The connection the value is received over must be driven by another runtime.
+    ///
+    /// # Panics
+    /// This function panics if called within an asynchronous execution context.
+    #[inline]
+    pub fn blocking_changed(&mut self) -> Result<(), ChangedError> {
+        let rt = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        rt.block_on(self.changed())
+    }
+
+    /// Wait for a change notification with a timeout, then mark the newest value as seen.
+    ///
+    /// Returns `Ok(true)` if a change was observed and `Ok(false)` if the timeout
+    /// elapsed before a change occurred.
+    /// If the timeout elapses, the newest value is not marked as seen, so that
+    /// a subsequent call to [changed](Self::changed) will still observe a
+    /// change that arrives later.
+    ///
+    /// # Cancel safety
+    /// This method is cancel safe.
+    pub async fn changed_timeout(&mut self, timeout: Duration) -> Result<bool, ChangedError> {
+        match tokio::time::timeout(timeout, self.rx.changed()).await {
+            Ok(Ok(())) => Ok(true),
+            Ok(Err(_)) => Err(ChangedError::Closed),
+            Err(_) => Ok(false),
+        }
+    }
+
+    /// Waits for a value that satisfies the provided condition and marks it as seen.
+    ///
+    /// The current value is checked first; if it satisfies the condition, this
+    /// returns immediately.
+    /// Otherwise, this waits for changes until a value satisfying the condition
+    /// is received.
+    ///
+    /// If the channel holds an error instead of a value, the condition is not
+    /// evaluated and [WaitError::RemoteRecv] is returned.
+    ///
+    /// # Cancel safety
+    /// This method is cancel safe.
+    /// If it is cancelled, the newest value may have been marked as seen, but
+    /// it is checked again by the next call.
+    pub async fn wait_for<F>(&mut self, mut f: F) -> Result<Ref<'_, T>, WaitError>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let ref_res = self
+            .rx
+            .wait_for(|value| match value {
+                Ok(value) => f(value),
+                Err(_) => true,
+            })
+            .await
+            .map_err(|_| WaitError::Closed)?;
+        Ref::new(ref_res, &self.forward).map_err(WaitError::RemoteRecv)
+    }
+
     /// Maximum allowed item size in bytes when receiving items.
     pub fn max_item_size(&self) -> usize {
         MAX_ITEM_SIZE
@@ -161,6 +304,7 @@ impl<T, Codec, const MAX_ITEM_SIZE: usize> Receiver<T, Codec, MAX_ITEM_SIZE> {
             ),
             remote_send_err_tx: self.remote_send_err_tx.clone(),
             remote_max_item_size: self.remote_max_item_size,
+            forward: self.forward.clone(),
             _codec: PhantomData,
         }
     }
@@ -175,6 +319,73 @@ impl<T, Codec, const MAX_ITEM_SIZE: usize> Receiver<T, Codec, MAX_ITEM_SIZE> {
     }
 }
 
+impl<T, Codec, const MAX_ITEM_SIZE: usize> Receiver<T, Codec, MAX_ITEM_SIZE>
+where
+    T: Send + Sync + 'static,
+    Codec: Send + 'static,
+{
+    /// Creates a derived watch receiver whose value is the projection of the value of this receiver.
+    ///
+    /// The projection `f` is recomputed whenever the value of this receiver changes.
+    /// The returned receiver is only notified of a change if the projected value differs
+    /// from its previous value.
+    /// Errors are passed through unmodified.
+    ///
+    /// This spawns a task that performs the projection.
+    /// The derived channel is closed when this channel is closed.
+    pub fn map<U, F>(mut self, f: F) -> Receiver<U, Codec, MAX_ITEM_SIZE>
+    where
+        U: PartialEq + Send + Sync + 'static,
+        F: Fn(&T) -> U + Send + 'static,
+    {
+        let project = move |value: &Result<T, RecvError>| value.as_ref().map(&f).map_err(Clone::clone);
+
+        let (tx, rx) = tokio::sync::watch::channel(project(&self.rx.borrow_and_update()));
+        let remote_send_err_tx = self.remote_send_err_tx.clone();
+        let remote_max_item_size = self.remote_max_item_size;
+        let forward = Forward::default();
+        let task_forward = forward.clone();
+
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    biased;
+
+                    () = tx.closed() => break,
+
+                    res = self.rx.changed() => {
+                        if res.is_err() {
+                            break;
+                        }
+
+                        let value = project(&self.rx.borrow_and_update());
+                        task_forward.send_if_modified(&tx, move |current| {
+                            let modified = !matches!((&*current, &value), (Ok(a), Ok(b)) if a == b);
+                            if modified {
+                                *current = value;
+                            }
+                            modified
+                        });
+                    }
+                }
+            }
+        });
+
+        Receiver::new(rx, remote_send_err_tx, remote_max_item_size, forward)
+    }
+}
+
+impl<T, Codec, const MAX_ITEM_SIZE: usize> Receiver<T, Codec, MAX_ITEM_SIZE>
+where
+    T: RemoteSend + Sync,
+    Codec: Send + 'static,
+{
+    /// Converts this into a [ReceiverStream] yielding the received values and errors.
+    pub fn into_stream(self) -> ReceiverStream<T, Codec, MAX_ITEM_SIZE> {
+        ReceiverStream::new(self)
+    }
+}
+
 impl<T, Codec, const MAX_ITEM_SIZE: usize> Drop for Receiver<T, Codec, MAX_ITEM_SIZE> {
     fn drop(&mut self) {
         // empty
@@ -195,6 +406,7 @@ where
         // Prepare channel for takeover.
         let rx = self.rx.clone();
         let remote_send_err_tx = self.remote_send_err_tx.clone();
+        let (data, queue_rx) = self.forward.subscribe(&self.rx);
 
         let port = PortSerializer::connect(|connect| {
             async move {
@@ -207,13 +419,13 @@ where
                     }
                 };
 
-                super::send_impl::<T, Codec>(rx, raw_tx, raw_rx, remote_send_err_tx, MAX_ITEM_SIZE).await;
+                super::send_impl::<T, Codec>(rx, raw_tx, raw_rx, remote_send_err_tx, MAX_ITEM_SIZE, queue_rx)
+                    .await;
             }
             .boxed()
         })?;
 
         // Encode chmux port number in transport type and serialize it.
-        let data = self.rx.borrow().clone();
         let transported = TransportedReceiver::<T, Codec> {
             port,
             data,
@@ -250,6 +462,8 @@ where
         // Create channels.
         let (tx, rx) = tokio::sync::watch::channel(data);
         let (remote_send_err_tx, remote_send_err_rx) = tokio::sync::mpsc::unbounded_channel();
+        let forward = Forward::default();
+        let task_forward = forward.clone();
 
         PortDeserializer::accept(port, |local_port, request| {
             async move {
@@ -257,17 +471,26 @@ where
                 let (raw_tx, raw_rx) = match request.accept_from(local_port).await {
                     Ok(tx_rx) => tx_rx,
                     Err(err) => {
-                        let _ = tx.send(Err(RecvError::RemoteListen(err)));
+                        let _ = task_forward.send(&tx, Err(RecvError::RemoteListen(err)));
                         return;
                     }
                 };
 
-                super::recv_impl::<T, Codec>(tx, raw_tx, raw_rx, remote_send_err_rx, None, MAX_ITEM_SIZE).await;
+                super::recv_impl::<T, Codec>(
+                    tx,
+                    raw_tx,
+                    raw_rx,
+                    remote_send_err_rx,
+                    None,
+                    MAX_ITEM_SIZE,
+                    task_forward,
+                )
+                .await;
             }
             .boxed()
         })?;
 
-        Ok(Self::new(rx, remote_send_err_tx, Some(max_item_size)))
+        Ok(Self::new(rx, remote_send_err_tx, Some(max_item_size), forward))
     }
 }
 
@@ -277,6 +500,12 @@ where
 /// regardless of whether it was the initial value or sent afterwards.
 ///
 /// Note that intermediate values may be missed due to the nature of watch channels.
+///
+/// Each item is either a value or the [RecvError] the channel is holding,
+/// for example because a value received from the remote endpoint could not be
+/// deserialized.
+/// If the channel fails with a final error, such as a connection failure, that
+/// error is yielded before the stream ends.
 pub struct ReceiverStream<T, Codec = codec::Default, const MAX_ITEM_SIZE: usize = DEFAULT_MAX_ITEM_SIZE> {
     inner: ReusableBoxFuture<'static, (Result<(), ChangedError>, Receiver<T, Codec, MAX_ITEM_SIZE>)>,
 }
@@ -1,6 +1,6 @@
 use futures::FutureExt;
 use serde::{Deserialize, Serialize};
-use std::{error::Error, fmt, marker::PhantomData, sync::Mutex};
+use std::{collections::VecDeque, error::Error, fmt, marker::PhantomData, sync::Mutex};
 
 use super::{
     super::{
@@ -8,34 +8,52 @@ use super::{
         RemoteSendError, SendErrorExt,
     },
     receiver::RecvError,
-    Receiver, Ref,
+    Forward, ForwardMode, Receiver, Ref, DEFAULT_ERROR_QUEUE_LENGTH,
 };
 use crate::{chmux, codec, RemoteSend};
 
-/// An error occurred during sending over an mpsc channel.
+/// An error occurred during sending over a watch channel.
+///
+/// Use [is_item_specific](Self::is_item_specific) to check whether the error was caused
+/// by the value, for example because it could not be serialized, and
+/// [is_disconnected](Self::is_disconnected) to check whether the channel or the
+/// underlying connection failed.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum SendError {
     /// The receiver was dropped or the connection failed.
     Closed,
     /// Sending to a remote endpoint failed.
+    ///
+    /// The error kind distinguishes failures specific to a value, i.e.
+    /// [serialization](base::SendErrorKind::Serialize) and
+    /// [item size](base::SendErrorKind::MaxItemSizeExceeded) errors,
+    /// from [failures of the chmux channel](base::SendErrorKind::Send).
     RemoteSend(base::SendErrorKind),
     /// Connecting a sent channel failed.
     RemoteConnect(chmux::ConnectError),
     /// Listening to a received channel failed.
     RemoteListen(chmux::ListenerError),
     /// Forwarding at a remote endpoint to another remote endpoint failed.
+    ///
+    /// The cause of the error is not transmitted by the forwarding endpoint.
     RemoteForward,
 }
 
 impl SendError {
     /// True, if the remote endpoint was dropped or the connection failed.
     pub fn is_closed(&self) -> bool {
-        !matches!(self, Self::RemoteSend(base::SendErrorKind::Serialize(_)))
+        !matches!(
+            self,
+            Self::RemoteSend(base::SendErrorKind::Serialize(_) | base::SendErrorKind::MaxHopsExceeded)
+        )
     }
 
     /// True, if the remote endpoint was dropped or the connection failed.
     pub fn is_disconnected(&self) -> bool {
-        !matches!(self, Self::RemoteSend(base::SendErrorKind::Serialize(_)))
+        !matches!(
+            self,
+            Self::RemoteSend(base::SendErrorKind::Serialize(_) | base::SendErrorKind::MaxHopsExceeded)
+        )
     }
 
     /// Returns whether the error is final, i.e. no further send operation can succeed.
@@ -114,8 +132,12 @@ pub(crate) struct SenderInner<T, Codec> {
     tx: tokio::sync::watch::Sender<Result<T, RecvError>>,
     remote_send_err_tx: tokio::sync::mpsc::UnboundedSender<RemoteSendError>,
     remote_send_err_rx: Mutex<tokio::sync::mpsc::UnboundedReceiver<RemoteSendError>>,
+    queued_err: Mutex<VecDeque<RemoteSendError>>,
     current_err: Mutex<Option<RemoteSendError>>,
+    last_err: Mutex<Option<RemoteSendError>>,
+    error_queue_length: usize,
     max_item_size: usize,
+    forward: Forward<T>,
     _codec: PhantomData<Codec>,
 }
 
@@ -131,6 +153,9 @@ pub(crate) struct TransportedSender<T, Codec> {
     /// Maximum item size in bytes.
     #[serde(default = "default_max_item_size")]
     max_item_size: u64,
+    /// Forward mode.
+    #[serde(default)]
+    forward_mode: ForwardMode,
 }
 
 const fn default_max_item_size() -> u64 {
@@ -146,13 +171,18 @@ where
         tx: tokio::sync::watch::Sender<Result<T, RecvError>>,
         remote_send_err_tx: tokio::sync::mpsc::UnboundedSender<RemoteSendError>,
         remote_send_err_rx: tokio::sync::mpsc::UnboundedReceiver<RemoteSendError>, max_item_size: usize,
+        forward: Forward<T>,
     ) -> Self {
         let inner = SenderInner {
             tx,
             remote_send_err_tx,
             remote_send_err_rx: Mutex::new(remote_send_err_rx),
+            queued_err: Mutex::new(VecDeque::new()),
             current_err: Mutex::new(None),
+            last_err: Mutex::new(None),
+            error_queue_length: DEFAULT_ERROR_QUEUE_LENGTH,
             max_item_size,
+            forward,
             _codec: PhantomData,
         };
         Self { inner: Some(inner), successor_tx: Mutex::new(None) }
@@ -161,6 +191,16 @@ where
     /// Sends a value over this channel, notifying all receivers.
     ///
     /// This method fails if all receivers have been dropped or become disconnected.
+    /// In this case [SendError::Closed] is returned, unless an error occurred during
+    /// sending to a remote endpoint, and the value is not stored in the channel.
+    /// This is consistent with [tokio::sync::watch::Sender::send] and allows
+    /// a producer to stop when no one is interested in its values anymore.
+    ///
+    /// Receivers located on remote endpoints are considered alive until all of them
+    /// have been dropped at that endpoint or the connection has failed.
+    /// Since this is reported asynchronously, sending may succeed for a short time
+    /// after the last remote receiver has been dropped.
+    /// Use [closed](Self::closed) to wait until all receivers are gone.
     ///
     /// # Error reporting
     /// Sending and error reporting are done asynchronously.
@@ -168,7 +208,8 @@ where
     /// return errors caused by previous invocations.
     #[inline]
     pub fn send(&self, value: T) -> Result<(), SendError> {
-        match self.inner.as_ref().unwrap().tx.send(Ok(value)) {
+        let inner = self.inner.as_ref().unwrap();
+        match inner.forward.send(&inner.tx, Ok(value)) {
             Ok(()) => Ok(()),
             Err(_) => match self.error() {
                 Some(err) => Err(err),
@@ -182,6 +223,10 @@ where
     /// This method never fails, even if all receivers have been dropped or become
     /// disconnected.
     ///
+    /// If the channel currently holds an error instead of a value, `func` is
+    /// not called and receivers are not notified.
+    /// Use [`send_if_modified`](Self::send_if_modified) to detect this case.
+    ///
     /// # Panics
     /// This method panics if calling `func` results in a panic.
     #[inline]
@@ -189,7 +234,37 @@ where
     where
         F: FnOnce(&mut T),
     {
-        self.inner.as_ref().unwrap().tx.send_modify(move |v| func(v.as_mut().unwrap()))
+        self.send_if_modified(move |v| {
+            func(v);
+            true
+        });
+    }
+
+    /// Modifies the watched value conditionally in-place, notifying all receivers
+    /// only if modified.
+    ///
+    /// `func` must return `true` if it modified the value and `false` otherwise.
+    /// Receivers, including those located on remote endpoints, are only notified
+    /// if `func` returns `true`.
+    ///
+    /// If the channel currently holds an error instead of a value, `func` is
+    /// not called and `false` is returned.
+    ///
+    /// This method never fails, even if all receivers have been dropped or become
+    /// disconnected.
+    ///
+    /// # Panics
+    /// This method panics if calling `func` results in a panic.
+    #[inline]
+    pub fn send_if_modified<F>(&self, func: F) -> bool
+    where
+        F: FnOnce(&mut T) -> bool,
+    {
+        let inner = self.inner.as_ref().unwrap();
+        inner.forward.send_if_modified(&inner.tx, move |v| match v {
+            Ok(v) => func(v),
+            Err(_) => false,
+        })
     }
 
     /// Sends a new value via the channel, notifying all receivers and returning the
@@ -199,13 +274,18 @@ where
     /// disconnected.
     #[inline]
     pub fn send_replace(&self, value: T) -> T {
-        self.inner.as_ref().unwrap().tx.send_replace(Ok(value)).unwrap()
+        let inner = self.inner.as_ref().unwrap();
+        inner.forward.send_replace(&inner.tx, Ok(value)).unwrap()
     }
 
     /// Returns a reference to the most recently sent value.
+    ///
+    /// Errors are only stored in the channel after the sender has been sent to
+    /// a remote endpoint, thus a sender always holds a value.
     #[inline]
     pub fn borrow(&self) -> Ref<'_, T> {
-        Ref(self.inner.as_ref().unwrap().tx.borrow())
+        let inner = self.inner.as_ref().unwrap();
+        Ref::new(inner.tx.borrow(), &inner.forward).expect("watch sender holds an error")
     }
 
     /// Completes when all receivers have been dropped or the connection failed.
@@ -220,27 +300,62 @@ where
         self.inner.as_ref().unwrap().tx.is_closed()
     }
 
+    /// Returns the number of receivers that currently exist.
+    ///
+    /// Each local receiver is counted individually.
+    /// All receivers located at a remote endpoint are counted as one per
+    /// connection they have been forwarded over; this count drops once every
+    /// receiver at that remote endpoint has been dropped or the connection
+    /// has failed.
+    ///
+    /// Since receivers are dropped asynchronously at remote endpoints, the
+    /// returned value may not reflect the current state immediately.
+    #[inline]
+    pub fn receiver_count(&self) -> usize {
+        self.inner.as_ref().unwrap().tx.receiver_count()
+    }
+
     /// Creates a new receiver subscribed to this sender.
+    ///
+    /// The receiver starts at the current value, which is marked as seen.
+    /// It can be sent to a remote endpoint.
     pub fn subscribe(&self) -> Receiver<T, Codec> {
         let inner = self.inner.as_ref().unwrap();
-        Receiver::new(inner.tx.subscribe(), inner.remote_send_err_tx.clone(), None)
+        Receiver::new(inner.tx.subscribe(), inner.remote_send_err_tx.clone(), None, inner.forward.clone())
+    }
+
+    /// The mode for forwarding values to receivers located on remote endpoints.
+    pub fn forward_mode(&self) -> ForwardMode {
+        self.inner.as_ref().unwrap().forward.mode()
     }
 
     fn update_error(&self) {
         let inner = self.inner.as_ref().unwrap();
         let mut current_err = inner.current_err.lock().unwrap();
-        if current_err.is_some() {
-            return;
-        }
+        let mut queued_err = inner.queued_err.lock().unwrap();
 
+        // Errors exceeding the queue length are discarded, but still become the last error.
         let mut remote_send_err_rx = inner.remote_send_err_rx.lock().unwrap();
-        if let Ok(err) = remote_send_err_rx.try_recv() {
-            *current_err = Some(err);
+        while let Ok(err) = remote_send_err_rx.try_recv() {
+            *inner.last_err.lock().unwrap() = Some(err.clone());
+            if queued_err.len() + usize::from(current_err.is_some()) < inner.error_queue_length {
+                queued_err.push_back(err);
+            }
+        }
+
+        if current_err.is_none() {
+            *current_err = queued_err.pop_front();
         }
     }
 
     /// Returns the error that occurred during sending to a remote endpoint, if any.
     ///
+    /// Errors reported by all remote endpoints are queued in order of occurrence.
+    /// At most [`error_queue_length`](Self::error_queue_length) errors are held;
+    /// further errors are discarded until queued errors have been cleared,
+    /// but are still reported by [`last_error`](Self::last_error).
+    /// Call [`clear_error`](Self::clear_error) to advance to the next queued error.
+    ///
     /// # Error reporting
     /// Sending and error reporting are done asynchronously.
     /// Thus, the reporting of an error may be delayed.
@@ -252,6 +367,23 @@ where
         current_err.clone().map(|err| err.into())
     }
 
+    /// Returns the most recent error that occurred during sending to a remote endpoint, if any.
+    ///
+    /// In contrast to [`error`](Self::error), which returns the oldest error that
+    /// has not been cleared yet, this always reflects the latest reported error.
+    /// It is not affected by [`clear_error`](Self::clear_error).
+    ///
+    /// # Error reporting
+    /// Sending and error reporting are done asynchronously.
+    /// Thus, the reporting of an error may be delayed.
+    pub fn last_error(&self) -> Option<SendError> {
+        self.update_error();
+
+        let inner = self.inner.as_ref().unwrap();
+        let last_err = inner.last_err.lock().unwrap();
+        last_err.clone().map(|err| err.into())
+    }
+
     /// Clears the error that occurred during sending to a remote endpoint.
     pub fn clear_error(&mut self) {
         self.update_error();
@@ -292,6 +424,47 @@ where
     pub fn set_max_item_size(&mut self, max_item_size: usize) {
         self.inner.as_mut().unwrap().max_item_size = max_item_size;
     }
+
+    /// Maximum number of remote send errors held by this sender.
+    ///
+    /// The default value is [DEFAULT_ERROR_QUEUE_LENGTH].
+    pub fn error_queue_length(&self) -> usize {
+        self.inner.as_ref().unwrap().error_queue_length
+    }
+
+    /// Sets the maximum number of remote send errors held by this sender.
+    ///
+    /// Raise this if many remote endpoints may fail before errors are inspected
+    /// and each of their errors must be reported by [`error`](Self::error).
+    /// Errors that are already held are kept, even if they exceed the new length.
+    /// The queue length is not transmitted when sending this to a remote endpoint.
+    ///
+    /// # Panics
+    /// Panics if the queue length is zero.
+    pub fn set_error_queue_length(&mut self, error_queue_length: usize) {
+        assert!(error_queue_length > 0, "error queue length must not be zero");
+        self.inner.as_mut().unwrap().error_queue_length = error_queue_length;
+    }
+}
+
+impl<T, Codec> Sender<T, Codec>
+where
+    T: Clone + Send + 'static,
+{
+    /// Sets the mode for forwarding values to receivers located on remote endpoints.
+    ///
+    /// The mode applies to receivers that are sent to remote endpoints afterwards and
+    /// is retained when this sender is sent to a remote endpoint.
+    /// Receivers that are already located on remote endpoints switch to coalescing
+    /// when the mode is changed.
+    ///
+    /// By default, [ForwardMode::Coalesce] is used.
+    ///
+    /// # Panics
+    /// Panics if [ForwardMode::Queue] is specified with a length of zero.
+    pub fn set_forward_mode(&mut self, mode: ForwardMode) {
+        self.inner.as_ref().unwrap().forward.set_mode(mode);
+    }
 }
 
 impl<T, Codec> Drop for Sender<T, Codec> {
@@ -314,6 +487,7 @@ where
         S: serde::Serializer,
     {
         let max_item_size = self.max_item_size();
+        let forward_mode = self.forward_mode();
 
         // Prepare channel for takeover.
         let (successor_tx, successor_rx) = tokio::sync::oneshot::channel();
@@ -322,24 +496,34 @@ where
         let port = PortSerializer::connect(move |connect| {
             async move {
                 // Sender has been dropped after sending, so we receive its channels.
-                let SenderInner { tx, remote_send_err_rx, current_err, .. } = match successor_rx.await {
-                    Ok(inner) => inner,
-                    Err(_) => return,
-                };
+                let SenderInner { tx, remote_send_err_rx, queued_err, current_err, forward, .. } =
+                    match successor_rx.await {
+                        Ok(inner) => inner,
+                        Err(_) => return,
+                    };
                 let remote_send_err_rx = remote_send_err_rx.into_inner().unwrap();
-                let current_err = current_err.into_inner().unwrap();
+                let current_err =
+                    current_err.into_inner().unwrap().or_else(|| queued_err.into_inner().unwrap().pop_front());
 
                 // Establish chmux channel.
                 let (raw_tx, raw_rx) = match connect.await {
                     Ok(tx_rx) => tx_rx,
                     Err(err) => {
-                        let _ = tx.send(Err(RecvError::RemoteConnect(err)));
+                        let _ = forward.send(&tx, Err(RecvError::RemoteConnect(err)));
                         return;
                     }
                 };
 
-                super::recv_impl::<T, Codec>(tx, raw_tx, raw_rx, remote_send_err_rx, current_err, max_item_size)
-                    .await;
+                super::recv_impl::<T, Codec>(
+                    tx,
+                    raw_tx,
+                    raw_rx,
+                    remote_send_err_rx,
+                    current_err,
+                    max_item_size,
+                    forward,
+                )
+                .await;
             }
             .boxed()
         })?;
@@ -350,6 +534,7 @@ where
             port,
             data,
             max_item_size: max_item_size.try_into().unwrap_or(u64::MAX),
+            forward_mode,
             codec: PhantomData,
         };
         transported.serialize(serializer)
@@ -368,7 +553,7 @@ where
         D: serde::Deserializer<'de>,
     {
         // Get chmux port number from deserialized transport type.
-        let TransportedSender { port, data, max_item_size, .. } =
+        let TransportedSender { port, data, max_item_size, forward_mode, .. } =
             TransportedSender::<T, Codec>::deserialize(deserializer)?;
         let max_item_size = usize::try_from(max_item_size).unwrap_or(usize::MAX);
         if data.is_err() {
@@ -379,6 +564,8 @@ where
         let (tx, rx) = tokio::sync::watch::channel(data);
         let (remote_send_err_tx, remote_send_err_rx) = tokio::sync::mpsc::unbounded_channel();
         let remote_send_err_tx2 = remote_send_err_tx.clone();
+        let forward = Forward::new(forward_mode);
+        let (_, queue_rx) = forward.subscribe(&rx);
 
         // Accept chmux port request.
         PortDeserializer::accept(port, move |local_port, request| {
@@ -392,11 +579,12 @@ where
                     }
                 };
 
-                super::send_impl::<T, Codec>(rx, raw_tx, raw_rx, remote_send_err_tx, max_item_size).await;
+                super::send_impl::<T, Codec>(rx, raw_tx, raw_rx, remote_send_err_tx, max_item_size, queue_rx)
+                    .await;
             }
             .boxed()
         })?;
 
-        Ok(Self::new(tx, remote_send_err_tx2, remote_send_err_rx, max_item_size))
+        Ok(Self::new(tx, remote_send_err_tx2, remote_send_err_rx, max_item_size, forward))
     }
 }
@@ -0,0 +1,330 @@
+//! Sessions that transparently re-establish lost connections.
+//!
+//! A [session] wraps the [base channels](crate::rch::base) of a connection
+//! established by a user-supplied connect function.
+//! When the connection is lost, the connect function is called again and the
+//! base channels of the new connection are used from then on.
+//! The [Sender] and [Receiver] of the session stay valid across reconnections,
+//! thus code using them does not need to be aware of connection failures.
+//! Changes of the connection state are reported as [events](Event).
+//!
+//! # Connect function
+//!
+//! The connect function establishes the physical transport and then a Remoc connection
+//! over it, for example by using [Connect::io](crate::Connect::io).
+//! It is responsible for retrying with an appropriate delay if the remote endpoint is
+//! temporarily unreachable.
+//! If it returns an error, the session terminates.
+//!
+//! # Buffering and lost items
+//!
+//! Sent items are queued in a buffer of configurable size and transmitted in order.
+//! While the connection is being re-established, items accumulate in the buffer and
+//! [Sender::send] waits once it is full.
+//!
+//! Delivery is not guaranteed across a connection loss.
+//! An item whose transmission fails is dropped and reported by [Event::ItemLost].
+//! Once the connection has failed, no further items are taken from the buffer
+//! until a new connection has been established.
+//! Items that were handed to the connection shortly before it was lost may not have
+//! reached the remote endpoint; this cannot be detected.
+//! Use acknowledgements on the application level if every item must be delivered.
+//!
+//! Remote channels and objects that were sent over a lost connection are bound to it and
+//! fail with a connection error; they must be exchanged again after reconnecting.
+//! Only the session's own sender and receiver are carried over to the new connection.
+//!
+//! # Example
+//!
+//! In the following example the client establishes a session over TCP and sends
+//! a message to the server.
+//!
+//! ```
+//! use std::net::Ipv4Addr;
+//! use tokio::net::{TcpListener, TcpStream};
+//! use remoc::prelude::*;
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 9876)).await.unwrap();
+//!
+//!     // Establish session, connecting to the server each time the connection is lost.
+//!     let (tx, _rx, _events) = remoc::reconnect::session(16, || async {
+//!         let socket = TcpStream::connect((Ipv4Addr::LOCALHOST, 9876)).await?;
+//!         let (socket_rx, socket_tx) = socket.into_split();
+//!         let (conn, tx, rx): (_, rch::base::Sender<String>, rch::base::Receiver<()>) =
+//!             remoc::Connect::io(remoc::Cfg::default(), socket_rx, socket_tx)
+//!                 .await
+//!                 .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
+//!         Ok::<_, std::io::Error>((conn, tx, rx))
+//!     });
+//!     tx.send("Hello".to_string()).await.unwrap();
+//!
+//!     // Accept connection on the server.
+//!     let (socket, _) = listener.accept().await.unwrap();
+//!     let (socket_rx, socket_tx) = socket.into_split();
+//!     let (conn, _tx, mut rx): (_, rch::base::Sender<()>, rch::base::Receiver<String>) =
+//!         remoc::Connect::io(remoc::Cfg::default(), socket_rx, socket_tx).await.unwrap();
+//!     tokio::spawn(conn);
+//!     assert_eq!(rx.recv().await.unwrap(), Some("Hello".to_string()));
+//! }
+//! ```
+
+use futures::{future, Future};
+use std::{error::Error, fmt};
+use tokio::sync::mpsc;
+
+use crate::{
+    chmux::ChMuxError,
+    codec,
+    connect::Connect,
+    rch::base::{self, RecvError},
+    RemoteSend,
+};
+
+/// An event of a [session].
+#[derive(Debug, Clone)]
+pub enum Event<E> {
+    /// A connection has been established.
+    Connected,
+    /// The connection has been lost.
+    ///
+    /// Contains the error of the connection, if it failed.
+    /// A new connection is established next.
+    Disconnected(Option<E>),
+    /// An item could not be transmitted and has been dropped.
+    ItemLost,
+    /// The connect function failed and the session has terminated.
+    Failed(E),
+}
+
+/// Sending to a session failed because it has terminated.
+///
+/// Contains the item that could not be sent.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SendError<T>(pub T);
+
+impl<T> fmt::Debug for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SendError").finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "session terminated")
+    }
+}
+
+impl<T> Error for SendError<T> {}
+
+/// Sends items over a [session].
+///
+/// This can be cloned.
+/// The sending direction of the session is closed when all clones have been dropped.
+pub struct Sender<T> {
+    tx: mpsc::Sender<T>,
+}
+
+impl<T> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Sender").finish()
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        Self { tx: self.tx.clone() }
+    }
+}
+
+impl<T> Sender<T> {
+    /// Queues an item for sending, waiting for buffer space if necessary.
+    ///
+    /// Returning successfully does not mean that the item has been delivered,
+    /// see the [module-level documentation](self) for details.
+    pub async fn send(&self, item: T) -> Result<(), SendError<T>> {
+        self.tx.send(item).await.map_err(|mpsc::error::SendError(item)| SendError(item))
+    }
+
+    /// Returns whether the session has terminated.
+    pub fn is_closed(&self) -> bool {
+        self.tx.is_closed()
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        // empty
+    }
+}
+
+/// Receives items from a [session].
+pub struct Receiver<T> {
+    rx: mpsc::Receiver<T>,
+}
+
+impl<T> fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Receiver").finish()
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Receives an item, waiting across reconnections.
+    ///
+    /// Returns [None] when the session has terminated.
+    pub async fn recv(&mut self) -> Option<T> {
+        self.rx.recv().await
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        // empty
+    }
+}
+
+/// Receives [events](Event) of a [session].
+///
+/// Events are queued until received; dropping this does not affect the session.
+pub struct Events<E> {
+    rx: mpsc::UnboundedReceiver<Event<E>>,
+}
+
+impl<E> fmt::Debug for Events<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Events").finish()
+    }
+}
+
+impl<E> Events<E> {
+    /// Receives the next event.
+    ///
+    /// Returns [None] when the session has terminated.
+    pub async fn recv(&mut self) -> Option<Event<E>> {
+        self.rx.recv().await
+    }
+}
+
+/// Establishes a session using the specified connect function.
+///
+/// The connect function is called immediately from a new task and again each time
+/// the connection is lost.
+/// It must return the [Connect] future and base channels of a newly established connection;
+/// the connect future is spawned by the session.
+///
+/// At most `buffer` items are queued for sending and receiving.
+///
+/// The session terminates when the connect function fails or when both the [Sender] and
+/// [Receiver] have been dropped.
+/// See the [module-level documentation](self) for details.
+///
+/// # Panics
+/// Panics if `buffer` is zero.
+pub fn session<Tx, Rx, Codec, TransportSinkError, TransportStreamError, E, F, Fut>(
+    buffer: usize, mut connect: F,
+) -> (Sender<Tx>, Receiver<Rx>, Events<E>)
+where
+    Tx: RemoteSend,
+    Rx: RemoteSend,
+    Codec: codec::Codec,
+    TransportSinkError: Send + 'static,
+    TransportStreamError: Send + 'static,
+    E: From<ChMuxError<TransportSinkError, TransportStreamError>> + Send + 'static,
+    F: FnMut() -> Fut + Send + 'static,
+    Fut: Future<
+            Output = Result<
+                (
+                    Connect<'static, TransportSinkError, TransportStreamError>,
+                    base::Sender<Tx, Codec>,
+                    base::Receiver<Rx, Codec>,
+                ),
+                E,
+            >,
+        > + Send,
+{
+    assert!(buffer > 0, "buffer must not be zero");
+
+    let (tx, mut local_rx) = mpsc::channel(buffer);
+    let (out_tx, rx) = mpsc::channel(buffer);
+    let (event_tx, event_rx) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        loop {
+            let (conn, mut base_tx, mut base_rx) = match connect().await {
+                Ok(connection) => connection,
+                Err(err) => {
+                    let _ = event_tx.send(Event::Failed(err));
+                    return;
+                }
+            };
+            let _ = event_tx.send(Event::Connected);
+            let mut conn = tokio::spawn(conn);
+
+            let mut in_flight = false;
+            let disconnected = {
+                let send = async {
+                    while let Some(item) = local_rx.recv().await {
+                        in_flight = true;
+                        let res = base_tx.send(item).await;
+                        in_flight = false;
+                        if let Err(err) = res {
+                            let _ = event_tx.send(Event::ItemLost);
+                            if err.is_final() {
+                                // Keep remaining items buffered until the connection
+                                // has been re-established.
+                                future::pending::<()>().await;
+                            }
+                        }
+                    }
+                    drop(base_tx);
+                };
+
+                let recv = async {
+                    let mut recv_done = false;
+                    loop {
+                        let permit = match out_tx.reserve().await {
+                            Ok(permit) => permit,
+                            Err(_) => break,
+                        };
+
+                        tokio::select! {
+                            res = base_rx.recv(), if !recv_done => match res {
+                                Ok(Some(item)) => permit.send(item),
+                                Ok(None) => recv_done = true,
+                                Err(err) if err.is_final() => recv_done = true,
+                                Err(RecvError::Deserialize(err)) => {
+                                    tracing::warn!(%err, "dropping undeserializable item")
+                                }
+                                Err(_) => (),
+                            },
+                            () = out_tx.closed() => break,
+                        }
+                    }
+                };
+
+                tokio::select! {
+                    res = &mut conn => Some(res),
+                    _ = future::join(send, recv) => None,
+                }
+            };
+
+            if in_flight {
+                let _ = event_tx.send(Event::ItemLost);
+            }
+
+            match disconnected {
+                Some(Ok(Ok(()))) | Some(Err(_)) => {
+                    let _ = event_tx.send(Event::Disconnected(None));
+                }
+                Some(Ok(Err(err))) => {
+                    let _ = event_tx.send(Event::Disconnected(Some(err.into())));
+                }
+                None => return,
+            }
+        }
+    });
+
+    (Sender { tx }, Receiver { rx }, Events { rx: event_rx })
+}
@@ -38,6 +38,31 @@
 //! If the caller drops the future while it is executing or the connection is interrupted
 //! the remote function is automatically cancelled at the next `await` point.
 //!
+//! The `call_timeout` and `try_call_timeout` methods of each wrapper fail with
+//! [CallError::Timeout] if the call does not complete within the specified duration.
+//! In this case the remote function is cancelled as well.
+//! Since cancellation is signalled over the connection, the remote function
+//! may continue executing for a short time.
+//!
+//! # State
+//!
+//! A function that operates on long-lived mutable state can be wrapped using [RFnState].
+//! The state is owned by the endpoint providing the function and shared by all callers.
+//! Calls are executed one after another in the order they are received,
+//! making it a lightweight alternative to [remote trait calling](crate::rtc).
+//!
+//! # Streams
+//!
+//! A function returning a [Stream](futures::Stream) can be wrapped using [RFnStream].
+//! Calling it returns a [remote receiver](crate::rch::mpsc::Receiver) for the
+//! items of the stream, which is driven by the endpoint providing the function.
+//!
+//! # Registries
+//!
+//! Many small functions can be exposed over a single remote channel using [RFnRegistry].
+//! The functions are registered by name using [RFnRegistryBuilder] and the caller
+//! selects the function to call by its name.
+//!
 //! # Providers
 //!
 //! Optionally you can use the `provided` method of each wrapper to obtain a
@@ -59,6 +84,7 @@ use std::{error::Error, fmt};
 
 use crate::{
     chmux,
+    codec::{DeserializationError, SerializationError},
     rch::{base, oneshot},
 };
 
@@ -73,6 +99,17 @@ pub enum CallError {
     RemoteConnect(chmux::ConnectError),
     /// Listening for a connection from a received channel failed.
     RemoteListen(chmux::ListenerError),
+    /// The call did not complete within the specified timeout.
+    Timeout,
+    /// No function with the specified name is registered in the [RFnRegistry].
+    UnknownFunction(String),
+    /// Serializing the argument or return value of a [RFnRegistry] function failed.
+    Serialize(SerializationError),
+    /// Deserializing the argument or return value of a [RFnRegistry] function failed.
+    ///
+    /// This happens if the types specified by the caller do not match the function
+    /// and the codec detects the mismatch.
+    Deserialize(DeserializationError),
 }
 
 impl fmt::Display for CallError {
@@ -82,6 +119,10 @@ impl fmt::Display for CallError {
             Self::RemoteReceive(err) => write!(f, "receive error: {err}"),
             Self::RemoteConnect(err) => write!(f, "connect error: {err}"),
             Self::RemoteListen(err) => write!(f, "listen error: {err}"),
+            Self::Timeout => write!(f, "call timed out"),
+            Self::UnknownFunction(name) => write!(f, "unknown function: {name}"),
+            Self::Serialize(err) => write!(f, "serialization error: {err}"),
+            Self::Deserialize(err) => write!(f, "deserialization error: {err}"),
         }
     }
 }
@@ -101,6 +142,91 @@ impl Error for CallError {}
 
 /// Generate argument call stubs.
 macro_rules! arg_stub {
+    (@call $name:ident, ( $( $self_prefix:tt )* ), $( $arg:ident : $arg_type:ident ),*) => {
+        impl < $( $arg_type , )* R, Codec> $name < ($($arg_type ,)*), R, Codec>
+        where
+            $( $arg_type : RemoteSend ,)*
+            R: RemoteSend,
+            Codec: codec::Codec,
+        {
+            /// Try to call the remote function.
+            #[allow(clippy::too_many_arguments)]
+            #[inline]
+            pub async fn try_call( $( $self_prefix )* self, $( $arg : $arg_type ),* ) -> Result<R, CallError> {
+                self.try_call_int(( $($arg ,)* )).await
+            }
+
+            /// Try to call the remote function, failing with [CallError::Timeout] if
+            /// it does not complete within the specified duration.
+            #[allow(clippy::too_many_arguments)]
+            #[inline]
+            pub async fn try_call_timeout( $( $self_prefix )* self, $( $arg : $arg_type , )* timeout: Duration ) -> Result<R, CallError> {
+                match tokio::time::timeout(timeout, self.try_call_int(( $($arg ,)* ))).await {
+                    Ok(result) => result,
+                    Err(_) => Err(CallError::Timeout),
+                }
+            }
+        }
+
+        impl < $($arg_type ,)* RT, RE, Codec> $name < ($($arg_type ,)* ), Result<RT, RE>, Codec>
+        where
+            $( $arg_type : RemoteSend ,)*
+            RT: RemoteSend,
+            RE: RemoteSend + From<CallError>,
+            Codec: codec::Codec,
+        {
+            /// Call the remote function.
+            ///
+            /// The [CallError] type must be convertible to the functions error type.
+            #[allow(clippy::too_many_arguments)]
+            #[inline]
+            pub async fn call($( $self_prefix )* self, $( $arg : $arg_type ),*) -> Result<RT, RE> {
+                self.call_int(( $($arg ,)* )).await
+            }
+
+            /// Call the remote function, failing with [CallError::Timeout] if
+            /// it does not complete within the specified duration.
+            ///
+            /// The [CallError] type must be convertible to the functions error type.
+            #[allow(clippy::too_many_arguments)]
+            #[inline]
+            pub async fn call_timeout($( $self_prefix )* self, $( $arg : $arg_type , )* timeout: Duration) -> Result<RT, RE> {
+                self.try_call_timeout($( $arg , )* timeout).await?
+            }
+        }
+    };
+
+    (state $name:ident, $provider_type:ident, $new:ident, $provided:ident, $( $arg:ident : $arg_type:ident ),*) => {
+        impl < $( $arg_type , )* R, Codec> $name < ($($arg_type ,)*), R, Codec>
+        where
+            $( $arg_type : RemoteSend ,)*
+            R: RemoteSend,
+            Codec: codec::Codec,
+        {
+            /// Create a new remote function operating on the specified state.
+            pub fn $new <S, F>(state: S, mut fun: F) -> Self
+            where
+                S: Send + 'static,
+                F: for<'a> FnMut (&'a mut S, $($arg_type),*) -> BoxFuture<'a, R> + Send + 'static,
+            {
+                Self::new_int(state, move |state, ( $($arg ,)* )| fun(state, $($arg),*))
+            }
+
+            /// Create a new remote function operating on the specified state and return it with its provider.
+            ///
+            /// See the [module-level documentation](super) for details.
+            pub fn $provided <S, F>(state: S, mut fun: F) -> (Self, $provider_type)
+            where
+                S: Send + 'static,
+                F: for<'a> FnMut (&'a mut S, $($arg_type),*) -> BoxFuture<'a, R> + Send + 'static,
+            {
+                Self::provided_int(state, move |state, ( $($arg ,)* )| fun(state, $($arg),*))
+            }
+        }
+
+        arg_stub!(@call $name, (&), $( $arg : $arg_type ),*);
+    };
+
     ($name:ident, $fn_type:ident, $provider_type:ident, $new:ident, $provided:ident, ( $( $self_prefix:tt )* ), $( $arg:ident : $arg_type:ident ),*) => {
         impl < $( $arg_type , )* R, Codec> $name < ($($arg_type ,)*), R, Codec>
         where
@@ -129,28 +255,47 @@ macro_rules! arg_stub {
             {
                 Self::provided_int(move |( $($arg ,)* )| fun($($arg),*))
             }
-
-            /// Try to call the remote function.
-            #[allow(clippy::too_many_arguments)]
-            #[inline]
-            pub async fn try_call( $( $self_prefix )* self, $( $arg : $arg_type ),* ) -> Result<R, CallError> {
-                self.try_call_int(( $($arg ,)* )).await
-            }
         }
 
-        impl < $($arg_type ,)* RT, RE, Codec> $name < ($($arg_type ,)* ), Result<RT, RE>, Codec>
+        arg_stub!(@call $name, ( $( $self_prefix )* ), $( $arg : $arg_type ),*);
+    };
+}
+
+/// Generate argument call stubs for functions returning a stream.
+macro_rules! stream_arg_stub {
+    ($name:ident, $new:ident, $provided:ident, $( $arg:ident : $arg_type:ident ),*) => {
+        impl < $( $arg_type , )* T, Codec> $name < ($($arg_type ,)*), T, Codec>
         where
             $( $arg_type : RemoteSend ,)*
-            RT: RemoteSend,
-            RE: RemoteSend + From<CallError>,
+            T: RemoteSend,
             Codec: codec::Codec,
         {
+            /// Create a new remote function.
+            pub fn $new <F, S>(fun: F) -> Self
+            where
+                F: Fn ($($arg_type),*) -> S + Send + Sync + 'static,
+                S: Stream<Item = T> + Send,
+            {
+                Self::new_int(move |( $($arg ,)* )| fun($($arg),*))
+            }
+
+            /// Create a new remote function and return it with its provider.
+            ///
+            /// See the [module-level documentation](super) for details.
+            pub fn $provided <F, S>(fun: F) -> (Self, RFnStreamProvider)
+            where
+                F: Fn ($($arg_type),*) -> S + Send + Sync + 'static,
+                S: Stream<Item = T> + Send,
+            {
+                Self::provided_int(move |( $($arg ,)* )| fun($($arg),*))
+            }
+
             /// Call the remote function.
             ///
-            /// The [CallError] type must be convertible to the functions error type.
+            /// Returns a receiver for the items of the stream returned by the function.
             #[allow(clippy::too_many_arguments)]
             #[inline]
-            pub async fn call($( $self_prefix )* self, $( $arg : $arg_type ),*) -> Result<RT, RE> {
+            pub async fn call(&self, $( $arg : $arg_type ),*) -> Result<mpsc::Receiver<T, Codec>, CallError> {
                 self.call_int(( $($arg ,)* )).await
             }
         }
@@ -161,7 +306,13 @@ mod msg;
 mod rfn_const;
 mod rfn_mut;
 mod rfn_once;
+mod rfn_registry;
+mod rfn_state;
+mod rfn_stream;
 
 pub use rfn_const::{RFn, RFnProvider};
 pub use rfn_mut::{RFnMut, RFnMutProvider};
 pub use rfn_once::{RFnOnce, RFnOnceProvider};
+pub use rfn_registry::{RFnRegistry, RFnRegistryBuilder, RFnRegistryProvider};
+pub use rfn_state::{RFnState, RFnStateProvider};
+pub use rfn_stream::{RFnStream, RFnStreamProvider};
@@ -2,7 +2,12 @@
 
 use serde::{Deserialize, Serialize};
 
-use crate::{codec, rch::oneshot, RemoteSend};
+use super::CallError;
+use crate::{
+    codec,
+    rch::{mpsc, oneshot},
+    RemoteSend,
+};
 
 /// Remote function call request.
 #[derive(Serialize, Deserialize)]
@@ -14,3 +19,27 @@ pub struct RFnRequest<A, R, Codec> {
     /// Channel for result transmission.
     pub result_tx: oneshot::Sender<R, Codec>,
 }
+
+/// Remote function call request for a function returning a stream.
+#[derive(Serialize, Deserialize)]
+#[serde(bound(serialize = "A: RemoteSend, T: RemoteSend, Codec: codec::Codec"))]
+#[serde(bound(deserialize = "A: RemoteSend, T: RemoteSend, Codec: codec::Codec"))]
+pub struct RFnStreamRequest<A, T, Codec> {
+    /// Function argument.
+    pub argument: A,
+    /// Channel for stream item transmission.
+    pub item_tx: mpsc::Sender<T, Codec>,
+}
+
+/// Call request for a function of a registry.
+#[derive(Serialize, Deserialize)]
+#[serde(bound(serialize = "Codec: codec::Codec"))]
+#[serde(bound(deserialize = "Codec: codec::Codec"))]
+pub struct RFnRegistryRequest<Codec> {
+    /// Function name.
+    pub name: String,
+    /// Serialized function argument.
+    pub argument: Vec<u8>,
+    /// Channel for transmission of the serialized result.
+    pub result_tx: oneshot::Sender<Result<Vec<u8>, CallError>, Codec>,
+}
@@ -1,6 +1,6 @@
 use futures::{future, pin_mut, Future};
 use serde::{Deserialize, Serialize};
-use std::{fmt, sync::Arc};
+use std::{fmt, sync::Arc, time::Duration};
 
 use super::{msg::RFnRequest, CallError};
 use crate::{
@@ -144,8 +144,13 @@ where
                             Ok(Some(RFnRequest {argument, result_tx})) => {
                                 let fun_task = fun.clone();
                                 tokio::spawn(async move {
-                                    let result = fun_task(argument).await;
-                                    let _ = result_tx.send(result);
+                                    tokio::select! {
+                                        biased;
+                                        () = result_tx.closed() => (),
+                                        result = fun_task(argument) => {
+                                            let _ = result_tx.send(result);
+                                        }
+                                    }
                                 });
                             }
                             Ok(None) => break,
@@ -1,6 +1,6 @@
 use futures::Future;
 use serde::{Deserialize, Serialize};
-use std::fmt;
+use std::{fmt, time::Duration};
 
 use super::{msg::RFnRequest, CallError};
 use crate::{codec, rch::oneshot, RemoteSend};
@@ -116,8 +116,13 @@ where
                 Err(_) = keep_rx => (),
 
                 Ok(RFnRequest {argument, result_tx}) = request_rx => {
-                    let result = fun(argument).await;
-                    let _ = result_tx.send(result);
+                    tokio::select! {
+                        biased;
+                        () = result_tx.closed() => (),
+                        result = fun(argument) => {
+                            let _ = result_tx.send(result);
+                        }
+                    }
                 }
             }
         });
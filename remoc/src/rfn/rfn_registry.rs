@@ -0,0 +1,324 @@
+use futures::{
+    future::{self, BoxFuture},
+    pin_mut, Future, FutureExt,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{collections::HashMap, fmt, marker::PhantomData, sync::Arc, time::Duration};
+
+use super::{msg::RFnRegistryRequest, CallError};
+use crate::{
+    codec,
+    rch::{mpsc, oneshot},
+};
+
+/// Type-erased handler of a registered function.
+type Handler = Box<dyn Fn(Vec<u8>) -> BoxFuture<'static, Result<Vec<u8>, CallError>> + Send + Sync>;
+
+/// Provides the functions of a remotely callable [RFnRegistry].
+///
+/// Dropping the provider will stop making the functions available for remote calls.
+pub struct RFnRegistryProvider {
+    keep_tx: Option<tokio::sync::oneshot::Sender<()>>,
+}
+
+impl fmt::Debug for RFnRegistryProvider {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RFnRegistryProvider").finish()
+    }
+}
+
+impl RFnRegistryProvider {
+    /// Keeps the provider alive until it is not required anymore.
+    pub fn keep(mut self) {
+        let _ = self.keep_tx.take().unwrap().send(());
+    }
+
+    /// Waits until the provider can be safely dropped.
+    ///
+    /// This is the case when all clones of the [RFnRegistry] are dropped.
+    pub async fn done(&mut self) {
+        self.keep_tx.as_mut().unwrap().closed().await
+    }
+}
+
+impl Drop for RFnRegistryProvider {
+    fn drop(&mut self) {
+        // empty
+    }
+}
+
+/// Builds a [RFnRegistry] by registering named functions.
+pub struct RFnRegistryBuilder<Codec = codec::Default> {
+    handlers: HashMap<String, Handler>,
+    _codec: PhantomData<Codec>,
+}
+
+impl<Codec> fmt::Debug for RFnRegistryBuilder<Codec> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RFnRegistryBuilder").field("names", &self.handlers.keys()).finish()
+    }
+}
+
+impl<Codec> Default for RFnRegistryBuilder<Codec> {
+    fn default() -> Self {
+        Self { handlers: HashMap::new(), _codec: PhantomData }
+    }
+}
+
+impl<Codec> RFnRegistryBuilder<Codec>
+where
+    Codec: codec::Codec,
+{
+    /// Creates a builder without registered functions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers an async function under the specified name.
+    ///
+    /// The function takes a single argument; use a tuple to pass multiple values.
+    /// Invocations are executed simultaneously, each on a new async task.
+    ///
+    /// # Panics
+    /// Panics if a function with the same name is already registered.
+    pub fn register<A, R, F, Fut>(mut self, name: impl Into<String>, fun: F) -> Self
+    where
+        A: DeserializeOwned + Send + 'static,
+        R: Serialize + Send + 'static,
+        F: Fn(A) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = R> + Send + 'static,
+    {
+        let name = name.into();
+        assert!(!self.handlers.contains_key(&name), "function {name} is already registered");
+
+        let handler: Handler =
+            Box::new(move |argument| match <Codec as codec::Codec>::deserialize::<_, A>(argument.as_slice()) {
+                Ok(argument) => {
+                    let fut = fun(argument);
+                    async move {
+                        let result = fut.await;
+                        let mut data = Vec::new();
+                        <Codec as codec::Codec>::serialize(&mut data, &result).map_err(CallError::Serialize)?;
+                        Ok(data)
+                    }
+                    .boxed()
+                }
+                Err(err) => future::ready(Err(CallError::Deserialize(err))).boxed(),
+            });
+        self.handlers.insert(name, handler);
+
+        self
+    }
+
+    /// Creates the registry making the registered functions remotely callable.
+    pub fn build(self) -> RFnRegistry<Codec> {
+        let (registry, provider) = self.build_provided();
+        provider.keep();
+        registry
+    }
+
+    /// Creates the registry and returns it with its provider.
+    ///
+    /// See the [module-level documentation](super) for details.
+    pub fn build_provided(self) -> (RFnRegistry<Codec>, RFnRegistryProvider) {
+        let (request_tx, request_rx) = mpsc::channel(1);
+        let request_tx = request_tx.set_buffer();
+        let mut request_rx = request_rx.set_buffer::<1>();
+        let (keep_tx, keep_rx) = tokio::sync::oneshot::channel();
+
+        let mut names: Vec<_> = self.handlers.keys().cloned().collect();
+        names.sort();
+        let handlers = Arc::new(self.handlers);
+
+        tokio::spawn(async move {
+            let term = async move {
+                if let Ok(()) = keep_rx.await {
+                    future::pending().await
+                }
+            };
+            pin_mut!(term);
+
+            loop {
+                tokio::select! {
+                    biased;
+
+                    () = &mut term => break,
+
+                    req_res = request_rx.recv() => {
+                        match req_res {
+                            Ok(Some(RFnRegistryRequest {name, argument, result_tx})) => {
+                                let call = match handlers.get(&name) {
+                                    Some(handler) => handler(argument),
+                                    None => future::ready(Err(CallError::UnknownFunction(name))).boxed(),
+                                };
+                                tokio::spawn(async move {
+                                    tokio::select! {
+                                        biased;
+                                        () = result_tx.closed() => (),
+                                        result = call => {
+                                            let _ = result_tx.send(result);
+                                        }
+                                    }
+                                });
+                            }
+                            Ok(None) => break,
+                            Err(err) if err.is_final() => break,
+                            Err(_) => (),
+                        }
+                    }
+                }
+            }
+        });
+
+        (RFnRegistry { request_tx, names }, RFnRegistryProvider { keep_tx: Some(keep_tx) })
+    }
+}
+
+/// Calls named async functions possibly located on a remote endpoint over a single channel.
+///
+/// A registry is built by registering functions using [RFnRegistryBuilder].
+/// Compared to sending an individual [RFn](super::RFn) for each function, all functions
+/// of a registry share one remote channel.
+/// This reduces the number of chmux ports used by services exposing many small functions.
+///
+/// The caller selects the function by name and specifies the argument and return types.
+/// Arguments and return values are serialized using the codec of the registry separately
+/// from the request, thus they must not contain remote channels or objects.
+/// Whether a mismatch between the specified types and the types of the registered
+/// function is detected depends on the codec.
+/// A self-describing codec, such as JSON, fails the call with [CallError::Deserialize].
+/// Other codecs, such as bincode, may instead decode the data as a value of the wrong type.
+/// Calling a name that is not registered fails with [CallError::UnknownFunction].
+///
+/// The registry can be cloned and called from multiple callers.
+/// For each invocation a new async task is spawned.
+///
+/// # Example
+///
+/// In the following example the server sends a registry with two functions to the client.
+/// The client calls both functions by name.
+///
+/// ```
+/// use remoc::prelude::*;
+///
+/// // This would be run on the client.
+/// async fn client(mut rx: rch::base::Receiver<rfn::RFnRegistry>) {
+///     let registry = rx.recv().await.unwrap().unwrap();
+///     assert_eq!(registry.call::<_, u32, rfn::CallError>("add", (1u32, 2u32)).await.unwrap(), 3);
+///     assert_eq!(registry.call::<_, String, rfn::CallError>("greet", "Alice").await.unwrap(), "Hello Alice");
+///     assert!(matches!(
+///         registry.try_call::<_, u32>("sub", (3u32, 1u32)).await,
+///         Err(rfn::CallError::UnknownFunction(_))
+///     ));
+/// }
+///
+/// // This would be run on the server.
+/// async fn server(mut tx: rch::base::Sender<rfn::RFnRegistry>) {
+///     let registry = rfn::RFnRegistryBuilder::new()
+///         .register("add", |(a, b): (u32, u32)| async move { Ok::<_, rfn::CallError>(a + b) })
+///         .register("greet", |name: String| async move { Ok::<_, rfn::CallError>(format!("Hello {name}")) })
+///         .build();
+///     tx.send(registry).await.unwrap();
+/// }
+/// # tokio_test::block_on(remoc::doctest::client_server(server, client));
+/// ```
+#[derive(Serialize, Deserialize)]
+#[serde(bound(serialize = "Codec: codec::Codec"))]
+#[serde(bound(deserialize = "Codec: codec::Codec"))]
+pub struct RFnRegistry<Codec = codec::Default> {
+    request_tx: mpsc::Sender<RFnRegistryRequest<Codec>, Codec, 1>,
+    names: Vec<String>,
+}
+
+impl<Codec> Clone for RFnRegistry<Codec> {
+    fn clone(&self) -> Self {
+        Self { request_tx: self.request_tx.clone(), names: self.names.clone() }
+    }
+}
+
+impl<Codec> fmt::Debug for RFnRegistry<Codec> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RFnRegistry").field("names", &self.names).finish()
+    }
+}
+
+impl<Codec> RFnRegistry<Codec>
+where
+    Codec: codec::Codec,
+{
+    /// Names of the registered functions in sorted order.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Returns whether a function with the specified name is registered.
+    pub fn contains(&self, name: &str) -> bool {
+        self.names.binary_search_by(|n| n.as_str().cmp(name)).is_ok()
+    }
+
+    /// Try to call the function with the specified name.
+    pub async fn try_call<A, R>(&self, name: &str, argument: A) -> Result<R, CallError>
+    where
+        A: Serialize,
+        R: DeserializeOwned,
+    {
+        if !self.contains(name) {
+            return Err(CallError::UnknownFunction(name.to_string()));
+        }
+
+        let mut data = Vec::new();
+        <Codec as codec::Codec>::serialize(&mut data, &argument).map_err(CallError::Serialize)?;
+
+        let (result_tx, result_rx) = oneshot::channel();
+        let _ =
+            self.request_tx.send(RFnRegistryRequest { name: name.to_string(), argument: data, result_tx }).await;
+
+        let result = result_rx.await??;
+        <Codec as codec::Codec>::deserialize(result.as_slice()).map_err(CallError::Deserialize)
+    }
+
+    /// Try to call the function with the specified name, failing with [CallError::Timeout] if
+    /// it does not complete within the specified duration.
+    pub async fn try_call_timeout<A, R>(&self, name: &str, argument: A, timeout: Duration) -> Result<R, CallError>
+    where
+        A: Serialize,
+        R: DeserializeOwned,
+    {
+        match tokio::time::timeout(timeout, self.try_call(name, argument)).await {
+            Ok(result) => result,
+            Err(_) => Err(CallError::Timeout),
+        }
+    }
+
+    /// Call the function with the specified name.
+    ///
+    /// The function must return a [Result] and the [CallError] type must be
+    /// convertible to its error type.
+    pub async fn call<A, RT, RE>(&self, name: &str, argument: A) -> Result<RT, RE>
+    where
+        A: Serialize,
+        RT: DeserializeOwned,
+        RE: DeserializeOwned + From<CallError>,
+    {
+        self.try_call(name, argument).await?
+    }
+
+    /// Call the function with the specified name, failing with [CallError::Timeout] if
+    /// it does not complete within the specified duration.
+    ///
+    /// The [CallError] type must be convertible to the functions error type.
+    pub async fn call_timeout<A, RT, RE>(&self, name: &str, argument: A, timeout: Duration) -> Result<RT, RE>
+    where
+        A: Serialize,
+        RT: DeserializeOwned,
+        RE: DeserializeOwned + From<CallError>,
+    {
+        self.try_call_timeout(name, argument, timeout).await?
+    }
+}
+
+impl<Codec> Drop for RFnRegistry<Codec> {
+    fn drop(&mut self) {
+        // empty
+    }
+}
@@ -0,0 +1,226 @@
+use futures::{future, future::BoxFuture, pin_mut};
+use serde::{Deserialize, Serialize};
+use std::{fmt, time::Duration};
+
+use super::{msg::RFnRequest, CallError};
+use crate::{
+    codec,
+    rch::{mpsc, oneshot},
+    RemoteSend,
+};
+
+/// Provides a remotely callable async function operating on mutable state.
+///
+/// Dropping the provider will stop making the function available for remote calls.
+pub struct RFnStateProvider {
+    keep_tx: Option<tokio::sync::oneshot::Sender<()>>,
+}
+
+impl fmt::Debug for RFnStateProvider {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RFnStateProvider").finish()
+    }
+}
+
+impl RFnStateProvider {
+    /// Keeps the provider alive until it is not required anymore.
+    pub fn keep(mut self) {
+        let _ = self.keep_tx.take().unwrap().send(());
+    }
+
+    /// Waits until the provider can be safely dropped.
+    ///
+    /// This is the case when all clones of the [RFnState] are dropped.
+    pub async fn done(&mut self) {
+        self.keep_tx.as_mut().unwrap().closed().await
+    }
+}
+
+impl Drop for RFnStateProvider {
+    fn drop(&mut self) {
+        // empty
+    }
+}
+
+/// Calls an async function operating on mutable state possibly located on a remote endpoint.
+///
+/// The state is owned by the endpoint providing the function and persists across calls.
+/// For each invocation the function receives a mutable reference to the state
+/// and returns a boxed future that may borrow it.
+/// For the lifetime of the state reference to be inferred, the closure must be
+/// passed directly to the `new_n` method.
+///
+/// The remote function can be cloned and called from multiple callers.
+/// Calls are executed one after another in the order they are received by the
+/// providing endpoint, i.e. an invocation only starts when the previous one has completed.
+///
+/// An error returned by the function is passed to the caller and does not affect
+/// subsequent calls; the state retains all modifications made so far.
+/// If a call is cancelled, its future is dropped at the next `await` point and
+/// the state may have been partially modified.
+/// If the function panics, the state is lost and this and all subsequent calls fail
+/// with [CallError::Dropped].
+///
+/// The function can take between zero and ten arguments.
+///
+/// # Example
+///
+/// In the following example the server sends a remote function that sums
+/// numbers into a state shared by all callers.
+/// The client receives the remote function, clones it and calls both instances.
+///
+/// ```
+/// use remoc::prelude::*;
+///
+/// type SumRFnState = rfn::RFnState<(u32,), Result<u32, rfn::CallError>>;
+///
+/// // This would be run on the client.
+/// async fn client(mut rx: rch::base::Receiver<SumRFnState>) {
+///     let rfn_state = rx.recv().await.unwrap().unwrap();
+///     let other = rfn_state.clone();
+///     assert_eq!(rfn_state.call(3).await.unwrap(), 3);
+///     assert_eq!(other.call(2).await.unwrap(), 5);
+///     assert_eq!(rfn_state.call(11).await.unwrap(), 16);
+/// }
+///
+/// // This would be run on the server.
+/// async fn server(mut tx: rch::base::Sender<SumRFnState>) {
+///     let rfn_state = rfn::RFnState::new_1(0, |sum: &mut u32, x| -> futures::future::BoxFuture<_> {
+///         Box::pin(async move {
+///             *sum += x;
+///             Ok(*sum)
+///         })
+///     });
+///     tx.send(rfn_state).await.unwrap();
+/// }
+/// # tokio_test::block_on(remoc::doctest::client_server(server, client));
+/// ```
+#[derive(Serialize, Deserialize)]
+#[serde(bound(serialize = "A: RemoteSend, R: RemoteSend, Codec: codec::Codec"))]
+#[serde(bound(deserialize = "A: RemoteSend, R: RemoteSend, Codec: codec::Codec"))]
+pub struct RFnState<A, R, Codec = codec::Default> {
+    request_tx: mpsc::Sender<RFnRequest<A, R, Codec>, Codec, 1>,
+}
+
+impl<A, R, Codec> Clone for RFnState<A, R, Codec> {
+    fn clone(&self) -> Self {
+        Self { request_tx: self.request_tx.clone() }
+    }
+}
+
+impl<A, R, Codec> fmt::Debug for RFnState<A, R, Codec> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RFnState").finish()
+    }
+}
+
+impl<A, R, Codec> RFnState<A, R, Codec>
+where
+    A: RemoteSend,
+    R: RemoteSend,
+    Codec: codec::Codec,
+{
+    /// Create a new remote function.
+    fn new_int<S, F>(state: S, fun: F) -> Self
+    where
+        S: Send + 'static,
+        F: for<'a> FnMut(&'a mut S, A) -> BoxFuture<'a, R> + Send + 'static,
+    {
+        let (rfn, provider) = Self::provided_int(state, fun);
+        provider.keep();
+        rfn
+    }
+
+    /// Create a new remote function and return it with its provider.
+    ///
+    /// See the [module-level documentation](super) for details.
+    fn provided_int<S, F>(mut state: S, mut fun: F) -> (Self, RFnStateProvider)
+    where
+        S: Send + 'static,
+        F: for<'a> FnMut(&'a mut S, A) -> BoxFuture<'a, R> + Send + 'static,
+    {
+        let (request_tx, request_rx) = mpsc::channel(1);
+        let request_tx = request_tx.set_buffer();
+        let mut request_rx = request_rx.set_buffer::<1>();
+        let (keep_tx, keep_rx) = tokio::sync::oneshot::channel();
+
+        tokio::spawn(async move {
+            let term = async move {
+                if let Ok(()) = keep_rx.await {
+                    future::pending().await
+                }
+            };
+            pin_mut!(term);
+
+            loop {
+                tokio::select! {
+                    biased;
+
+                    () = &mut term => break,
+
+                    req_res = request_rx.recv() => {
+                        match req_res {
+                            Ok(Some(RFnRequest {argument, result_tx})) => {
+                                tokio::select! {
+                                    biased;
+                                    () = result_tx.closed() => (),
+                                    result = fun(&mut state, argument) => {
+                                        let _ = result_tx.send(result);
+                                    }
+                                }
+                            }
+                            Ok(None) => break,
+                            Err(err) if err.is_final() => break,
+                            Err(_) => (),
+                        }
+                    }
+                }
+            }
+        });
+
+        (Self { request_tx }, RFnStateProvider { keep_tx: Some(keep_tx) })
+    }
+
+    /// Try to call the remote function.
+    async fn try_call_int(&self, argument: A) -> Result<R, CallError> {
+        let (result_tx, result_rx) = oneshot::channel();
+        let _ = self.request_tx.send(RFnRequest { argument, result_tx }).await;
+
+        let result = result_rx.await?;
+        Ok(result)
+    }
+}
+
+impl<A, RT, RE, Codec> RFnState<A, Result<RT, RE>, Codec>
+where
+    A: RemoteSend,
+    RT: RemoteSend,
+    RE: RemoteSend + From<CallError>,
+    Codec: codec::Codec,
+{
+    /// Call the remote function.
+    ///
+    /// The [CallError] type must be convertible to the functions error type.
+    async fn call_int(&self, argument: A) -> Result<RT, RE> {
+        self.try_call_int(argument).await?
+    }
+}
+
+// Calls for variable number of arguments.
+#[rustfmt::skip] arg_stub!(state RFnState, RFnStateProvider, new_0, provided_0, );
+#[rustfmt::skip] arg_stub!(state RFnState, RFnStateProvider, new_1, provided_1, arg1: A1);
+#[rustfmt::skip] arg_stub!(state RFnState, RFnStateProvider, new_2, provided_2, arg1: A1, arg2: A2);
+#[rustfmt::skip] arg_stub!(state RFnState, RFnStateProvider, new_3, provided_3, arg1: A1, arg2: A2, arg3: A3);
+#[rustfmt::skip] arg_stub!(state RFnState, RFnStateProvider, new_4, provided_4, arg1: A1, arg2: A2, arg3: A3, arg4: A4);
+#[rustfmt::skip] arg_stub!(state RFnState, RFnStateProvider, new_5, provided_5, arg1: A1, arg2: A2, arg3: A3, arg4: A4, arg5: A5);
+#[rustfmt::skip] arg_stub!(state RFnState, RFnStateProvider, new_6, provided_6, arg1: A1, arg2: A2, arg3: A3, arg4: A4, arg5: A5, arg6: A6);
+#[rustfmt::skip] arg_stub!(state RFnState, RFnStateProvider, new_7, provided_7, arg1: A1, arg2: A2, arg3: A3, arg4: A4, arg5: A5, arg6: A6, arg7: A7);
+#[rustfmt::skip] arg_stub!(state RFnState, RFnStateProvider, new_8, provided_8, arg1: A1, arg2: A2, arg3: A3, arg4: A4, arg5: A5, arg6: A6, arg7: A7, arg8: A8);
+#[rustfmt::skip] arg_stub!(state RFnState, RFnStateProvider, new_9, provided_9, arg1: A1, arg2: A2, arg3: A3, arg4: A4, arg5: A5, arg6: A6, arg7: A7, arg8: A8, arg9: A9);
+#[rustfmt::skip] arg_stub!(state RFnState, RFnStateProvider, new_10, provided_10, arg1: A1, arg2: A2, arg3: A3, arg4: A4, arg5: A5, arg6: A6, arg7: A7, arg8: A8, arg9: A9, arg10: A10);
+
+impl<A, R, Codec> Drop for RFnState<A, R, Codec> {
+    fn drop(&mut self) {
+        // empty
+    }
+}
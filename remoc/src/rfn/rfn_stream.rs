@@ -0,0 +1,220 @@
+use futures::{future, pin_mut, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::{fmt, sync::Arc};
+
+use super::{msg::RFnStreamRequest, CallError};
+use crate::{codec, rch::mpsc, RemoteSend};
+
+/// Provides a remotely callable [Fn] function returning a stream.
+///
+/// Dropping the provider will stop making the function available for remote calls.
+pub struct RFnStreamProvider {
+    keep_tx: Option<tokio::sync::oneshot::Sender<()>>,
+}
+
+impl fmt::Debug for RFnStreamProvider {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RFnStreamProvider").finish()
+    }
+}
+
+impl RFnStreamProvider {
+    /// Keeps the provider alive until it is not required anymore.
+    pub fn keep(mut self) {
+        let _ = self.keep_tx.take().unwrap().send(());
+    }
+
+    /// Waits until the provider can be safely dropped.
+    ///
+    /// This is the case when the [RFnStream] is dropped.
+    pub async fn done(&mut self) {
+        self.keep_tx.as_mut().unwrap().closed().await
+    }
+}
+
+impl Drop for RFnStreamProvider {
+    fn drop(&mut self) {
+        // empty
+    }
+}
+
+/// Calls a [Fn] function returning a [Stream] possibly located on a remote endpoint.
+///
+/// Calling the remote function returns a [remote receiver](mpsc::Receiver) that
+/// receives the items of the stream produced by the function.
+/// The stream is driven by the endpoint providing the function.
+///
+/// The remote function can be cloned and executed simultaneously from multiple callers.
+/// For each invocation a new async task is spawned.
+///
+/// The function can take between zero and ten arguments.
+///
+/// # Cancellation
+///
+/// When the caller closes or drops the returned receiver or the connection is interrupted,
+/// the stream is dropped by the providing endpoint.
+/// Since this is signalled over the connection, some items may be produced in the meantime.
+///
+/// # Example
+///
+/// In the following example the server sends a remote function that counts
+/// up to a specified number to the client.
+/// The client receives the remote function and consumes the returned stream.
+///
+/// ```
+/// use remoc::prelude::*;
+///
+/// type CountRFnStream = rfn::RFnStream<(u32,), u32>;
+///
+/// // This would be run on the client.
+/// async fn client(mut rx: rch::base::Receiver<CountRFnStream>) {
+///     let rfn = rx.recv().await.unwrap().unwrap();
+///     let mut counter = rfn.call(3).await.unwrap();
+///     assert_eq!(counter.recv().await.unwrap(), Some(0));
+///     assert_eq!(counter.recv().await.unwrap(), Some(1));
+///     assert_eq!(counter.recv().await.unwrap(), Some(2));
+///     assert_eq!(counter.recv().await.unwrap(), None);
+/// }
+///
+/// // This would be run on the server.
+/// async fn server(mut tx: rch::base::Sender<CountRFnStream>) {
+///     let func = |n| futures::stream::iter(0..n);
+///     let rfn = rfn::RFnStream::new_1(func);
+///     tx.send(rfn).await.unwrap();
+/// }
+/// # tokio_test::block_on(remoc::doctest::client_server(server, client));
+/// ```
+#[derive(Serialize, Deserialize)]
+#[serde(bound(serialize = "A: RemoteSend, T: RemoteSend, Codec: codec::Codec"))]
+#[serde(bound(deserialize = "A: RemoteSend, T: RemoteSend, Codec: codec::Codec"))]
+pub struct RFnStream<A, T, Codec = codec::Default> {
+    request_tx: mpsc::Sender<RFnStreamRequest<A, T, Codec>, Codec, 1>,
+}
+
+impl<A, T, Codec> Clone for RFnStream<A, T, Codec> {
+    fn clone(&self) -> Self {
+        Self { request_tx: self.request_tx.clone() }
+    }
+}
+
+impl<A, T, Codec> fmt::Debug for RFnStream<A, T, Codec> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RFnStream").finish()
+    }
+}
+
+impl<A, T, Codec> RFnStream<A, T, Codec>
+where
+    A: RemoteSend,
+    T: RemoteSend,
+    Codec: codec::Codec,
+{
+    /// Create a new remote function.
+    fn new_int<F, S>(fun: F) -> Self
+    where
+        F: Fn(A) -> S + Send + Sync + 'static,
+        S: Stream<Item = T> + Send,
+    {
+        let (rfn, provider) = Self::provided_int(fun);
+        provider.keep();
+        rfn
+    }
+
+    /// Create a new remote function and return it with its provider.
+    ///
+    /// See the [module-level documentation](super) for details.
+    fn provided_int<F, S>(fun: F) -> (Self, RFnStreamProvider)
+    where
+        F: Fn(A) -> S + Send + Sync + 'static,
+        S: Stream<Item = T> + Send,
+    {
+        let (request_tx, request_rx) = mpsc::channel(1);
+        let request_tx = request_tx.set_buffer();
+        let mut request_rx = request_rx.set_buffer::<1>();
+        let (keep_tx, keep_rx) = tokio::sync::oneshot::channel();
+        let fun = Arc::new(fun);
+
+        tokio::spawn(async move {
+            let term = async move {
+                if let Ok(()) = keep_rx.await {
+                    future::pending().await
+                }
+            };
+            pin_mut!(term);
+
+            loop {
+                tokio::select! {
+                    biased;
+
+                    () = &mut term => break,
+
+                    req_res = request_rx.recv() => {
+                        match req_res {
+                            Ok(Some(RFnStreamRequest {argument, item_tx})) => {
+                                let fun_task = fun.clone();
+                                tokio::spawn(async move {
+                                    let stream = fun_task(argument);
+                                    pin_mut!(stream);
+
+                                    loop {
+                                        tokio::select! {
+                                            biased;
+
+                                            () = item_tx.closed() => break,
+
+                                            item_opt = stream.next() => {
+                                                match item_opt {
+                                                    Some(item) => {
+                                                        if item_tx.send(item).await.is_err() {
+                                                            break;
+                                                        }
+                                                    }
+                                                    None => break,
+                                                }
+                                            }
+                                        }
+                                    }
+                                });
+                            }
+                            Ok(None) => break,
+                            Err(err) if err.is_final() => break,
+                            Err(_) => (),
+                        }
+                    }
+                }
+            }
+        });
+
+        (Self { request_tx }, RFnStreamProvider { keep_tx: Some(keep_tx) })
+    }
+
+    /// Call the remote function.
+    async fn call_int(&self, argument: A) -> Result<mpsc::Receiver<T, Codec>, CallError> {
+        let (item_tx, item_rx) = mpsc::channel(1);
+        match self.request_tx.send(RFnStreamRequest { argument, item_tx }).await {
+            Ok(()) => Ok(item_rx),
+            Err(mpsc::SendError::RemoteConnect(err)) => Err(CallError::RemoteConnect(err)),
+            Err(mpsc::SendError::RemoteListen(err)) => Err(CallError::RemoteListen(err)),
+            Err(_) => Err(CallError::Dropped),
+        }
+    }
+}
+
+// Calls for variable number of arguments.
+#[rustfmt::skip] stream_arg_stub!(RFnStream, new_0, provided_0, );
+#[rustfmt::skip] stream_arg_stub!(RFnStream, new_1, provided_1, arg1: A1);
+#[rustfmt::skip] stream_arg_stub!(RFnStream, new_2, provided_2, arg1: A1, arg2: A2);
+#[rustfmt::skip] stream_arg_stub!(RFnStream, new_3, provided_3, arg1: A1, arg2: A2, arg3: A3);
+#[rustfmt::skip] stream_arg_stub!(RFnStream, new_4, provided_4, arg1: A1, arg2: A2, arg3: A3, arg4: A4);
+#[rustfmt::skip] stream_arg_stub!(RFnStream, new_5, provided_5, arg1: A1, arg2: A2, arg3: A3, arg4: A4, arg5: A5);
+#[rustfmt::skip] stream_arg_stub!(RFnStream, new_6, provided_6, arg1: A1, arg2: A2, arg3: A3, arg4: A4, arg5: A5, arg6: A6);
+#[rustfmt::skip] stream_arg_stub!(RFnStream, new_7, provided_7, arg1: A1, arg2: A2, arg3: A3, arg4: A4, arg5: A5, arg6: A6, arg7: A7);
+#[rustfmt::skip] stream_arg_stub!(RFnStream, new_8, provided_8, arg1: A1, arg2: A2, arg3: A3, arg4: A4, arg5: A5, arg6: A6, arg7: A7, arg8: A8);
+#[rustfmt::skip] stream_arg_stub!(RFnStream, new_9, provided_9, arg1: A1, arg2: A2, arg3: A3, arg4: A4, arg5: A5, arg6: A6, arg7: A7, arg8: A8, arg9: A9);
+#[rustfmt::skip] stream_arg_stub!(RFnStream, new_10, provided_10, arg1: A1, arg2: A2, arg3: A3, arg4: A4, arg5: A5, arg6: A6, arg7: A7, arg8: A8, arg9: A9, arg10: A10);
+
+impl<A, T, Codec> Drop for RFnStream<A, T, Codec> {
+    fn drop(&mut self) {
+        // empty
+    }
+}
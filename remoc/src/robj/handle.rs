@@ -69,6 +69,7 @@
 //! # tokio_test::block_on(remoc::doctest::client_server_bidir(client, server));
 //! ```
 
+use futures::future;
 use serde::{Deserialize, Serialize};
 use std::{
     fmt,
@@ -321,6 +322,41 @@ where
         }
     }
 
+    /// Returns whether the value referenced by the handle still exists.
+    ///
+    /// For a handle to a value stored on a remote endpoint, this reflects the
+    /// last known state reported by the remote endpoint and thus does not perform
+    /// a round trip.
+    /// Dropping of the value is signalled asynchronously over the connection,
+    /// thus this may return true for some time after the value has been dropped.
+    ///
+    /// The result is inherently racy: the value may be dropped immediately after
+    /// this returns true.
+    pub fn is_alive(&self) -> bool {
+        match &self.state {
+            State::LocalCreated { entry, .. } | State::LocalReceived { entry, .. } => {
+                entry.try_read().map(|entry| entry.is_some()).unwrap_or(true)
+            }
+            State::Remote { dropped_tx, .. } => !dropped_tx.is_closed(),
+            State::Empty => false,
+        }
+    }
+
+    /// Completes when the value referenced by the handle, which is stored on a remote
+    /// endpoint, has been dropped or the connection to it has been lost.
+    ///
+    /// This can be used to proactively remove stale handles.
+    ///
+    /// If the value is stored locally, it is kept alive by this handle and
+    /// thus this never completes.
+    pub async fn closed(&self) {
+        match &self.state {
+            State::Remote { dropped_tx, .. } => dropped_tx.closed().await,
+            State::Empty => (),
+            _ => future::pending().await,
+        }
+    }
+
     /// Change the data type of the handle.
     ///
     /// Before the handle can be dereferenced the type must be changed back to the original
@@ -8,6 +8,8 @@
 //! calling [Lazy::get] if interested.
 //! While this can save transmission bandwidth the drawback is an additional
 //! delay of the connection round-trip time when the lazy value is requested.
+//! This delay can be hidden by calling [Lazy::prefetch] some time before
+//! the value is needed.
 //!
 //! This can be forwarded over multiple remote endpoints.
 //!
@@ -216,26 +218,50 @@ where
         (lazy, provider)
     }
 
+    /// Requests the value from the provider.
+    fn request(&self) -> impl Future<Output = Result<T, FetchError>> + Send + 'static {
+        let req_tx = self.request_tx.clone();
+        async move {
+            let (value_tx, value_rx) = oneshot::channel();
+            let _ = req_tx.send(value_tx).await;
+            Ok(value_rx.await?)
+        }
+    }
+
     /// Fetches and caches the value from the provider.
     async fn fetch(&self) {
         let mut fetch_task = self.fetch_task.lock().await;
 
         if fetch_task.is_none() {
-            let req_tx = self.request_tx.clone();
-            *fetch_task = Some(Box::pin(future::maybe_done(
-                async move {
-                    let (value_tx, value_rx) = oneshot::channel();
-                    let _ = req_tx.send(value_tx).await;
-                    let value = value_rx.await?;
-                    Ok(Arc::new(value))
-                }
-                .boxed(),
-            )));
+            *fetch_task = Some(Box::pin(future::maybe_done(self.request().map(|res| res.map(Arc::new)).boxed())));
         }
 
         fetch_task.as_mut().unwrap().await;
     }
 
+    /// Starts fetching the value in the background.
+    ///
+    /// This returns immediately and a later call to [get](Self::get) will not have
+    /// to wait for the value, if it has been received in the meantime.
+    /// A call to [get](Self::get) while the value is being prefetched waits for the
+    /// transfer to complete, i.e. the value is only transferred once.
+    /// Errors that occur during prefetching are returned by [get](Self::get).
+    ///
+    /// This has no effect if the value is already being fetched or has been received.
+    pub fn prefetch(&self) {
+        let mut fetch_task = match self.fetch_task.try_lock() {
+            Ok(fetch_task) => fetch_task,
+            Err(_) => return,
+        };
+
+        if fetch_task.is_none() {
+            let task = tokio::spawn(self.request());
+            *fetch_task = Some(Box::pin(future::maybe_done(
+                async move { task.await.unwrap_or(Err(FetchError::Dropped)).map(Arc::new) }.boxed(),
+            )));
+        }
+    }
+
     /// Requests the value and returns a reference to it.
     ///
     /// The value is stored locally once received and subsequent
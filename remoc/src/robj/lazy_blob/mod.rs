@@ -12,7 +12,8 @@
 //! when transferring a large amount of binary data.
 //!
 //! A [LazyBlob] can be forwarded over multiple remote endpoints.
-//! The size of the binary data is limited by [usize::MAX].
+//! The size of the binary data is limited by [usize::MAX], unless it
+//! is received chunk by chunk using [LazyBlob::stream].
 //!
 //! # Security
 //!
@@ -60,15 +61,18 @@ use bytes::Bytes;
 use futures::{
     future,
     future::{BoxFuture, MaybeDone},
-    FutureExt,
+    ready,
+    task::{Context, Poll},
+    FutureExt, Stream,
 };
 use serde::{Deserialize, Serialize};
 use std::{convert::TryFrom, fmt, pin::Pin, sync::Arc};
 use tokio::sync::Mutex;
+use tokio_util::sync::ReusableBoxFuture;
 
 use crate::{
     chmux,
-    chmux::DataBuf,
+    chmux::{DataBuf, RecvChunkError},
     codec,
     rch::{mpsc, ConnectError},
 };
@@ -241,6 +245,14 @@ where
         usize::try_from(self.len).map_err(|_| UsizeExceeded(self.len))
     }
 
+    /// Requests transmission of the binary data from the provider.
+    async fn connect(req_tx: mpsc::Sender<fw_bin::Sender, Codec, 1>) -> Result<chmux::Receiver, FetchError> {
+        let (fw_tx, fw_rx) = fw_bin::channel();
+        let _ = req_tx.send(fw_tx).await;
+        let bin_rx = fw_rx.into_inner().await.ok_or(FetchError::Dropped)?;
+        bin_rx.into_inner().await.map_err(FetchError::RemoteConnect)
+    }
+
     /// Fetches and caches the binary data from the provider.
     async fn fetch(&self) -> Result<(), FetchError> {
         let mut fetch_task = self.fetch_task.lock().await;
@@ -250,10 +262,7 @@ where
             let len = self.len()?;
             *fetch_task = Some(Box::pin(future::maybe_done(
                 async move {
-                    let (fw_tx, fw_rx) = fw_bin::channel();
-                    let _ = req_tx.send(fw_tx).await;
-                    let bin_rx = fw_rx.into_inner().await.ok_or(FetchError::Dropped)?;
-                    let mut rx = bin_rx.into_inner().await.map_err(FetchError::RemoteConnect)?;
+                    let mut rx = Self::connect(req_tx).await?;
                     rx.set_max_data_size(len);
                     rx.recv().await.map_err(FetchError::RemoteReceive)?.ok_or(FetchError::Dropped)
                 }
@@ -277,6 +286,20 @@ where
         res.as_mut().unwrap().as_mut().output_mut().unwrap().clone()
     }
 
+    /// Returns a stream of chunks of the binary data.
+    ///
+    /// This starts a new transmission of the binary data, which is not cached locally.
+    /// Thus it allows processing of binary data that is too large to be held in memory.
+    /// The provider only sends as much data as permitted by the flow control
+    /// of the [channel multiplexer](chmux), thus it cannot outrun the consumer.
+    ///
+    /// The transmission is not resumable: if it fails, a new stream must be requested
+    /// and the data is transmitted again from the beginning.
+    pub async fn stream(&self) -> Result<BlobStream, FetchError> {
+        let rx = Self::connect(self.req_tx.clone()).await?;
+        Ok(BlobStream::new(rx, self.len))
+    }
+
     /// Returns the binary data.
     ///
     /// The binary data is fetched when not already cached by a previous
@@ -296,3 +319,75 @@ where
         }
     }
 }
+
+/// A stream of chunks of the binary data of a [LazyBlob].
+///
+/// Obtained by calling [LazyBlob::stream].
+///
+/// This can be used together with [tokio_util::io::StreamReader] to obtain an
+/// [AsyncRead](tokio::io::AsyncRead), after mapping the error to [std::io::Error].
+///
+/// [tokio_util::io::StreamReader]: https://docs.rs/tokio-util/latest/tokio_util/io/struct.StreamReader.html
+pub struct BlobStream {
+    inner: ReusableBoxFuture<'static, (Result<Option<Bytes>, FetchError>, chmux::Receiver)>,
+    remaining: u64,
+    done: bool,
+}
+
+impl fmt::Debug for BlobStream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BlobStream").field("remaining", &self.remaining).finish()
+    }
+}
+
+impl BlobStream {
+    fn new(rx: chmux::Receiver, len: u64) -> Self {
+        Self { inner: ReusableBoxFuture::new(Self::make_future(rx)), remaining: len, done: len == 0 }
+    }
+
+    async fn make_future(mut rx: chmux::Receiver) -> (Result<Option<Bytes>, FetchError>, chmux::Receiver) {
+        let result = match rx.recv_chunk().await {
+            Ok(chunk) => Ok(chunk),
+            Err(RecvChunkError::ChMux) => Err(FetchError::RemoteReceive(chmux::RecvError::ChMux)),
+            Err(RecvChunkError::Shutdown) => Err(FetchError::RemoteReceive(chmux::RecvError::Shutdown)),
+            Err(RecvChunkError::IdleTimeout) => Err(FetchError::RemoteReceive(chmux::RecvError::IdleTimeout)),
+            Err(RecvChunkError::Cancelled) => Err(FetchError::Dropped),
+        };
+        (result, rx)
+    }
+
+    /// The number of bytes that have not yet been received.
+    pub fn remaining(&self) -> u64 {
+        self.remaining
+    }
+}
+
+impl Stream for BlobStream {
+    type Item = Result<Bytes, FetchError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        if self.done {
+            return Poll::Ready(None);
+        }
+
+        let (result, rx) = ready!(self.inner.poll(cx));
+        self.inner.set(Self::make_future(rx));
+
+        let result = match result {
+            Ok(Some(chunk)) => match self.remaining.checked_sub(chunk.len() as u64) {
+                Some(remaining) => {
+                    self.remaining = remaining;
+                    Ok(chunk)
+                }
+                None => Err(FetchError::Dropped),
+            },
+            Ok(None) => Err(FetchError::Dropped),
+            Err(err) => Err(err),
+        };
+
+        self.done = result.is_err() || self.remaining == 0;
+        Poll::Ready(Some(result))
+    }
+}
+
+impl Unpin for BlobStream {}
@@ -21,9 +21,30 @@
 //! [remote channel](crate::rch).
 //! The remote endpoints can then use [RwLock::read] and [RwLock::write] to obtain
 //! read or write access respectively.
+//! [RwLock::upgradable_read] obtains read access that can later be upgraded to
+//! write access without allowing other endpoints to change the value in between.
+//! [RwLock::try_read] and [RwLock::try_write] do not wait for a write by another
+//! endpoint to complete, but return None instead.
 //! When the [owner](Owner) is dropped, all locks become invalid and the value
 //! is dropped.
 //!
+//! # Forwarding read access
+//!
+//! [RwLock::read_owned] returns an [OwnedReadGuard] that can be sent to other
+//! endpoints.
+//! This allows an endpoint to lock the value and let another endpoint work with it,
+//! while the value is guaranteed not to change.
+//! The read access is released when all forwarded instances of the guard have been
+//! dropped or their connections have failed.
+//!
+//! # Fairness
+//!
+//! While write access is held or being acquired, other lock requests must wait.
+//! The order in which they are served afterwards is determined by the [Fairness] policy
+//! specified when [creating the owner](Owner::with_fairness).
+//! By default writers are preferred to prevent their starvation by readers.
+//! [Owner::queue] provides the currently waiting requests for diagnosis.
+//!
 //! # Alternatives
 //!
 //! If you require to broadcast value to multiple endpoints that just require read
@@ -83,5 +104,7 @@ mod owner;
 #[allow(clippy::module_inception)]
 mod rw_lock;
 
-pub use owner::Owner;
-pub use rw_lock::{CommitError, LockError, ReadGuard, ReadLock, RwLock, WriteGuard};
+pub use owner::{Access, Fairness, Owner};
+pub use rw_lock::{
+    CommitError, LockError, OwnedReadGuard, ReadGuard, ReadLock, RwLock, UpgradableReadGuard, WriteGuard,
+};
@@ -1,6 +1,7 @@
 //! Messages exchanged between read/write locks and the owner.
 
 use serde::{Deserialize, Serialize};
+use tokio::task::AbortHandle;
 
 use crate::{
     codec,
@@ -8,6 +9,21 @@ use crate::{
     RemoteSend,
 };
 
+/// A lock request from a lock to the owner.
+///
+/// Read and write requests share one channel, so that the owner receives them
+/// in the order they have been sent.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(bound(serialize = "T: RemoteSend, Codec: codec::Codec"))]
+#[serde(bound(deserialize = "T: RemoteSend, Codec: codec::Codec"))]
+#[allow(clippy::large_enum_variant)]
+pub enum Request<T, Codec> {
+    /// Read request.
+    Read(ReadRequest<T, Codec>),
+    /// Write or upgradable read request.
+    Write(WriteRequest<T, Codec>),
+}
+
 /// A read request from a lock to the owner.
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(bound(serialize = "T: RemoteSend, Codec: codec::Codec"))]
@@ -15,6 +31,11 @@ use crate::{
 pub struct ReadRequest<T, Codec> {
     /// Channel for sending the value.
     pub(crate) value_tx: oneshot::Sender<Value<T, Codec>, Codec>,
+    /// Present, if the request must not wait for a write to complete.
+    ///
+    /// Notified instead of sending the value, if the lock is not available.
+    #[serde(default)]
+    pub(crate) would_block_tx: Option<oneshot::Sender<(), Codec>>,
 }
 
 /// A write request from a lock to the owner.
@@ -28,6 +49,27 @@ pub struct WriteRequest<T, Codec> {
     pub(crate) new_value_rx: oneshot::Receiver<T, Codec>,
     /// Channel for confirming that modified value has been stored.
     pub(crate) confirm_tx: oneshot::Sender<(), Codec>,
+    /// Present, if shared read access should be granted until an upgrade is requested.
+    #[serde(default)]
+    pub(crate) upgradable: Option<UpgradableRequest<T, Codec>>,
+    /// Present, if the request must not wait for another write to complete.
+    ///
+    /// Notified instead of sending the value, if the lock is not available.
+    #[serde(default)]
+    pub(crate) would_block_tx: Option<oneshot::Sender<(), Codec>>,
+}
+
+/// Upgradable read part of a write request from a lock to the owner.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(bound(serialize = "T: RemoteSend, Codec: codec::Codec"))]
+#[serde(bound(deserialize = "T: RemoteSend, Codec: codec::Codec"))]
+pub struct UpgradableRequest<T, Codec> {
+    /// Channel for sending the value for reading.
+    pub(crate) value_tx: oneshot::Sender<Value<T, Codec>, Codec>,
+    /// Channel for receiving the upgrade request.
+    ///
+    /// Closed without message, if the upgradable read lock is released.
+    pub(crate) upgrade_rx: oneshot::Receiver<(), Codec>,
 }
 
 /// A value together with invalidation channels.
@@ -41,6 +83,9 @@ pub struct Value<T, Codec> {
     pub(crate) dropped_tx: mpsc::Sender<(), Codec, 1>,
     /// Notification channel that value has been invalidated by the owner.
     pub(crate) invalid_rx: watch::Receiver<bool, Codec>,
+    /// Notification channel that value is still held after it has been invalidated.
+    #[serde(default)]
+    pub(crate) held_tx: Option<mpsc::Sender<(), Codec, 1>>,
 }
 
 impl<T, Codec> Value<T, Codec>
@@ -62,3 +107,51 @@ where
         true
     }
 }
+
+impl<T, Codec> Value<T, Codec>
+where
+    Codec: codec::Codec,
+{
+    /// Waits until the value is invalidated by the owner, dropped or disconnected.
+    pub(crate) async fn invalidated(&self) {
+        let mut invalid_rx = self.invalid_rx.clone();
+        while !invalid_rx.borrow_and_update().map(|v| *v).unwrap_or_default() {
+            if invalid_rx.changed().await.is_err() {
+                break;
+            }
+        }
+    }
+
+    /// True, if the value has been invalidated by the owner.
+    pub(crate) fn is_invalidated(&self) -> bool {
+        self.invalid_rx.borrow().map(|v| *v).unwrap_or(true)
+    }
+
+    /// Spawns a task that notifies the owner when the value is invalidated
+    /// while it is still held.
+    ///
+    /// The task must be aborted when the value is released.
+    pub(crate) fn spawn_held_monitor(&self) -> AbortHandle {
+        let mut invalid_rx = self.invalid_rx.clone();
+        let held_tx = self.held_tx.clone();
+        tokio::spawn(async move {
+            while !invalid_rx.borrow_and_update().map(|v| *v).unwrap_or_default() {
+                if invalid_rx.changed().await.is_err() {
+                    break;
+                }
+            }
+            notify_held(&held_tx);
+        })
+        .abort_handle()
+    }
+}
+
+/// Notifies the owner that a value is still held after it has been invalidated.
+pub(crate) fn notify_held<Codec>(held_tx: &Option<mpsc::Sender<(), Codec, 1>>)
+where
+    Codec: codec::Codec,
+{
+    if let Some(held_tx) = held_tx {
+        let _ = held_tx.try_send(());
+    }
+}
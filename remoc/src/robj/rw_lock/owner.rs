@@ -1,8 +1,13 @@
-use std::fmt;
+use futures::{future, pin_mut, Future};
+use std::{
+    collections::VecDeque,
+    fmt, mem,
+    sync::{Arc, Mutex},
+};
 use tokio::task::JoinHandle;
 
 use super::{
-    msg::{ReadRequest, Value, WriteRequest},
+    msg::{ReadRequest, Request, UpgradableRequest, Value, WriteRequest},
     ReadLock, RwLock,
 };
 use crate::{
@@ -20,118 +25,175 @@ pub struct Owner<T, Codec = codec::Default> {
     task: Option<JoinHandle<T>>,
     rw_lock: RwLock<T, Codec>,
     term_tx: Option<tokio::sync::oneshot::Sender<()>>,
+    fairness: Fairness,
+    queue: Arc<Mutex<Vec<Access>>>,
 }
 
 impl<T, Codec> fmt::Debug for Owner<T, Codec> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("Owner").finish()
+        f.debug_struct("Owner").field("fairness", &self.fairness).finish()
     }
 }
 
+/// Order in which waiting lock requests are served by the [owner](Owner).
+///
+/// Requests wait while write access is held or being acquired.
+/// Read requests cannot be served during that time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Fairness {
+    /// Write requests are served before read requests.
+    ///
+    /// This prevents writers from being starved by readers.
+    #[default]
+    WriterPreferring,
+    /// Read requests are served before write requests.
+    ReaderPreferring,
+    /// Requests are served in the order they have been received.
+    Fifo,
+}
+
+/// Kind of access requested by a waiting lock request.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Access {
+    /// Read access.
+    Read,
+    /// Upgradable read access.
+    UpgradableRead,
+    /// Write access.
+    Write,
+}
+
 impl<T, Codec> Owner<T, Codec>
 where
     T: RemoteSend + Clone + Sync,
     Codec: codec::Codec,
 {
     /// Creates a new [RwLock] owner with the specified shared value.
-    pub fn new(mut value: T) -> Self {
-        let (read_req_tx, read_req_rx) = mpsc::channel(1);
-        let read_req_tx = read_req_tx.set_buffer();
-        let read_req_rx = read_req_rx.set_buffer();
-        let (write_req_tx, write_req_rx) = mpsc::channel(1);
-        let write_req_tx = write_req_tx.set_buffer();
-        let write_req_rx = write_req_rx.set_buffer();
+    ///
+    /// Waiting requests are served [writer-preferring](Fairness::WriterPreferring).
+    pub fn new(value: T) -> Self {
+        Self::with_fairness(value, Fairness::default())
+    }
+
+    /// Creates a new [RwLock] owner with the specified shared value and
+    /// order of serving waiting requests.
+    pub fn with_fairness(mut value: T, fairness: Fairness) -> Self {
+        let (req_tx, req_rx) = mpsc::channel(1);
+        let req_tx = req_tx.set_buffer();
+        let req_rx = req_rx.set_buffer();
         let (term_tx, term_rx) = tokio::sync::oneshot::channel();
+        let queue = Arc::new(Mutex::new(Vec::new()));
 
+        let reqs = Requests::new(req_rx, fairness, queue.clone());
         let task = tokio::spawn(async move {
             tokio::select! {
-                _ = Self::owner_task(&mut value, read_req_rx, write_req_rx) => (),
+                _ = Self::owner_task(&mut value, reqs) => (),
                 _ = term_rx => (),
             }
             value
         });
 
-        let read_lock = ReadLock::new(read_req_tx);
-        let rw_lock = RwLock::new(read_lock, write_req_tx);
+        let read_lock = ReadLock::new(req_tx);
+        let rw_lock = RwLock::new(read_lock);
 
-        Self { task: Some(task), rw_lock, term_tx: Some(term_tx) }
+        Self { task: Some(task), rw_lock, term_tx: Some(term_tx), fairness, queue }
     }
 
     /// Message handler for lock owner.
-    async fn owner_task(
-        value: &mut T, mut read_req_rx: mpsc::Receiver<ReadRequest<T, Codec>, Codec, 1>,
-        mut write_req_rx: mpsc::Receiver<WriteRequest<T, Codec>, Codec, 1>,
-    ) {
-        let (dropped_tx, dropped_rx) = mpsc::channel(1);
-        let mut dropped_tx = dropped_tx.set_buffer();
-        let mut dropped_rx = dropped_rx.set_buffer::<1>();
-        let (mut invalid_tx, mut invalid_rx) = watch::channel(false);
+    async fn owner_task(value: &mut T, mut reqs: Requests<T, Codec>) {
+        let mut holders = Holders::new();
+        let mut stale: Vec<Holders<Codec>> = Vec::new();
 
         loop {
-            tokio::select! {
-                biased;
-
+            match reqs.next().await {
                 // Write value request.
-                res = write_req_rx.recv() => {
-                    let WriteRequest {value_tx, new_value_rx, confirm_tx} = match res {
-                        Ok(Some(req)) => req,
-                        Ok(None) => break,
-                        Err(err) if err.is_final() => break,
-                        Err(_) => continue,
+                Some(Request::Write(req)) => {
+                    let WriteRequest { value_tx, new_value_rx, confirm_tx, upgradable, would_block_tx } = req;
+
+                    // Grant upgradable read access and continue processing read requests
+                    // until upgrade is requested.
+                    if let Some(UpgradableRequest { value_tx, upgrade_rx }) = upgradable {
+                        let read_value = holders.value(value);
+                        let _ = value_tx.send(read_value.clone());
+
+                        let upgrade = reqs.busy(upgrade_rx, Some(&read_value)).await.is_ok();
+                        drop(read_value);
+
+                        if !upgrade {
+                            continue;
+                        }
+                    }
+
+                    // Fail non-blocking request, if read guards of a value invalidated
+                    // by a previous non-blocking request are still held.
+                    stale.retain_mut(|holders| !holders.all_dropped_now());
+                    let would_block_tx = match would_block_tx {
+                        Some(would_block_tx) if !stale.is_empty() => {
+                            let _ = would_block_tx.send(());
+                            continue;
+                        }
+                        other => other,
                     };
 
                     // Invalidate current value.
-                    let _ = invalid_tx.send(true);
+                    holders.invalidate();
 
                     // Wait for drop confirmation from all lock holders.
-                    drop(dropped_tx);
-                    loop {
-                        if let Ok(None) = dropped_rx.recv().await {
-                            break;
+                    let Holders { dropped_rx, held_rx, .. } = &mut holders;
+                    let all_dropped = async {
+                        for stale in &mut stale {
+                            all_dropped(&mut stale.dropped_rx).await;
                         }
-                    }
+                        all_dropped(dropped_rx).await;
+                    };
+                    match would_block_tx {
+                        Some(would_block_tx) => {
+                            // Fail non-blocking request, if a lock holder reports a held read guard.
+                            let acquired = reqs
+                                .busy(
+                                    async {
+                                        tokio::select! {
+                                            biased;
+                                            () = all_dropped => true,
+                                            () = held(held_rx) => false,
+                                        }
+                                    },
+                                    None,
+                                )
+                                .await;
 
-                    // Create new dropped notification channel.
-                    let (new_dropped_tx, new_dropped_rx) = mpsc::channel(1);
-                    let new_dropped_tx = new_dropped_tx.set_buffer();
-                    let new_dropped_rx = new_dropped_rx.set_buffer();
-                    dropped_tx = new_dropped_tx;
-                    dropped_rx = new_dropped_rx;
+                            if !acquired {
+                                let _ = would_block_tx.send(());
+                                stale.push(mem::replace(&mut holders, Holders::new()));
+                                continue;
+                            }
+                        }
+                        None => reqs.busy(all_dropped, None).await,
+                    }
 
-                    // Create new invalidation channel.
-                    let (new_invalid_tx, new_invalid_rx) = watch::channel(false);
-                    invalid_tx = new_invalid_tx;
-                    invalid_rx = new_invalid_rx;
+                    // Create new notification channels.
+                    stale.clear();
+                    holders = Holders::new();
 
                     // Send current value for writing.
                     let _ = value_tx.send(value.clone());
 
                     // Wait for modified value and store it.
-                    if let Ok(nv) = new_value_rx.await {
+                    if let Ok(nv) = reqs.busy(new_value_rx, None).await {
                         *value = nv;
 
                         // Send confirmation.
                         let _ = confirm_tx.send(());
                     }
-                },
+                }
 
                 // Read value request.
-                res = read_req_rx.recv() => {
-                    let ReadRequest {value_tx} = match res {
-                        Ok(Some(req)) => req,
-                        Ok(None) => break,
-                        Err(err) if err.is_final() => break,
-                        Err(_) => continue,
-                    };
-
+                Some(Request::Read(ReadRequest { value_tx, would_block_tx: _ })) => {
                     // Send current value together with invalidation channels.
-                    let v = Value {
-                        value: value.clone(),
-                        dropped_tx: dropped_tx.clone(),
-                        invalid_rx: invalid_rx.clone(),
-                    };
-                    let _ = value_tx.send(v);
-                },
+                    let _ = value_tx.send(holders.value(value));
+                }
+
+                None => break,
             }
         }
     }
@@ -151,6 +213,23 @@ where
     pub fn read_lock(&self) -> ReadLock<T, Codec> {
         self.rw_lock.read_lock()
     }
+
+    /// The order in which waiting requests are served.
+    pub fn fairness(&self) -> Fairness {
+        self.fairness
+    }
+
+    /// The number of lock requests that are waiting for write access to be released.
+    pub fn waiting(&self) -> usize {
+        self.queue.lock().unwrap().len()
+    }
+
+    /// The kinds of access of the waiting lock requests in the order they will be served.
+    ///
+    /// Requests that have not yet been received by the owner are not included.
+    pub fn queue(&self) -> Vec<Access> {
+        self.queue.lock().unwrap().clone()
+    }
 }
 
 impl<T, Codec> Drop for Owner<T, Codec> {
@@ -158,3 +237,221 @@ impl<T, Codec> Drop for Owner<T, Codec> {
         // empty
     }
 }
+
+/// Channels connecting the owner with the holders of a value.
+struct Holders<Codec> {
+    dropped_tx: Option<mpsc::Sender<(), Codec, 1>>,
+    dropped_rx: mpsc::Receiver<(), Codec, 1>,
+    invalid_tx: watch::Sender<bool, Codec>,
+    invalid_rx: watch::Receiver<bool, Codec>,
+    held_tx: mpsc::Sender<(), Codec, 1>,
+    held_rx: mpsc::Receiver<(), Codec, 1>,
+}
+
+impl<Codec> Holders<Codec>
+where
+    Codec: codec::Codec,
+{
+    fn new() -> Self {
+        let (dropped_tx, dropped_rx) = mpsc::channel(1);
+        let (invalid_tx, invalid_rx) = watch::channel(false);
+        let (held_tx, held_rx) = mpsc::channel(1);
+        Self {
+            dropped_tx: Some(dropped_tx.set_buffer()),
+            dropped_rx: dropped_rx.set_buffer(),
+            invalid_tx,
+            invalid_rx,
+            held_tx: held_tx.set_buffer(),
+            held_rx: held_rx.set_buffer(),
+        }
+    }
+
+    /// Value together with invalidation channels for read access.
+    fn value<T>(&self, value: &T) -> Value<T, Codec>
+    where
+        T: Clone,
+    {
+        Value {
+            value: value.clone(),
+            dropped_tx: self.dropped_tx.clone().expect("value has been invalidated"),
+            invalid_rx: self.invalid_rx.clone(),
+            held_tx: Some(self.held_tx.clone()),
+        }
+    }
+
+    /// Notifies all holders that the value has been invalidated.
+    fn invalidate(&mut self) {
+        let _ = self.invalid_tx.send(true);
+        self.dropped_tx = None;
+    }
+
+    /// Whether all holders have dropped the value.
+    fn all_dropped_now(&mut self) -> bool {
+        matches!(self.dropped_rx.try_recv(), Err(mpsc::TryRecvError::Closed))
+    }
+}
+
+/// Waits until all holders have dropped the value.
+async fn all_dropped<Codec>(dropped_rx: &mut mpsc::Receiver<(), Codec, 1>)
+where
+    Codec: codec::Codec,
+{
+    loop {
+        if let Ok(None) = dropped_rx.recv().await {
+            break;
+        }
+    }
+}
+
+/// Waits until a holder reports that the value is still held.
+async fn held<Codec>(held_rx: &mut mpsc::Receiver<(), Codec, 1>)
+where
+    Codec: codec::Codec,
+{
+    loop {
+        match held_rx.recv().await {
+            Ok(Some(())) => break,
+            Ok(None) => future::pending().await,
+            Err(_) => (),
+        }
+    }
+}
+
+impl<T, Codec> Request<T, Codec> {
+    /// Kind of requested access.
+    fn access(&self) -> Access {
+        match self {
+            Self::Read(_) => Access::Read,
+            Self::Write(WriteRequest { upgradable: Some(_), .. }) => Access::UpgradableRead,
+            Self::Write(_) => Access::Write,
+        }
+    }
+}
+
+/// Receives requests for the owner and defers them while a write is in progress.
+struct Requests<T, Codec> {
+    req_rx: mpsc::Receiver<Request<T, Codec>, Codec, 1>,
+    fairness: Fairness,
+    deferred: VecDeque<Request<T, Codec>>,
+    queue: Arc<Mutex<Vec<Access>>>,
+    closed: bool,
+}
+
+impl<T, Codec> Requests<T, Codec>
+where
+    T: RemoteSend + Clone,
+    Codec: codec::Codec,
+{
+    fn new(
+        req_rx: mpsc::Receiver<Request<T, Codec>, Codec, 1>, fairness: Fairness, queue: Arc<Mutex<Vec<Access>>>,
+    ) -> Self {
+        Self { req_rx, fairness, deferred: VecDeque::new(), queue, closed: false }
+    }
+
+    /// Publishes the access kinds of the deferred requests.
+    fn update_queue(&self) {
+        *self.queue.lock().unwrap() = self.deferred.iter().map(Request::access).collect();
+    }
+
+    /// Defers a request, placing it according to the fairness policy.
+    fn defer(&mut self, req: Request<T, Codec>) {
+        let pos = match (self.fairness, &req) {
+            (Fairness::WriterPreferring, Request::Write(_)) => {
+                self.deferred.iter().position(|r| matches!(r, Request::Read(_)))
+            }
+            (Fairness::ReaderPreferring, Request::Read(_)) => {
+                self.deferred.iter().position(|r| matches!(r, Request::Write(_)))
+            }
+            _ => None,
+        };
+
+        match pos {
+            Some(pos) => self.deferred.insert(pos, req),
+            None => self.deferred.push_back(req),
+        }
+        self.update_queue();
+    }
+
+    /// Whether a deferred write request must be served before a read request.
+    fn read_must_wait(&self) -> bool {
+        self.fairness == Fairness::Fifo && self.deferred.iter().any(|r| matches!(r, Request::Write(_)))
+    }
+
+    /// Next request to process.
+    ///
+    /// Requests that are immediately available are ordered according to the fairness policy.
+    /// Requests deferred during a write are processed first.
+    /// Returns None, when the request channel has been closed.
+    async fn next(&mut self) -> Option<Request<T, Codec>> {
+        while !self.closed {
+            match self.req_rx.try_recv() {
+                Ok(req) => self.defer(req),
+                Err(mpsc::TryRecvError::Empty) => break,
+                Err(mpsc::TryRecvError::Closed) => self.closed = true,
+                Err(_) => (),
+            }
+        }
+
+        if let Some(req) = self.deferred.pop_front() {
+            self.update_queue();
+            return Some(req);
+        }
+
+        while !self.closed {
+            match self.req_rx.recv().await {
+                Ok(Some(req)) => return Some(req),
+                Ok(None) => self.closed = true,
+                Err(err) if err.is_final() => self.closed = true,
+                Err(_) => (),
+            }
+        }
+
+        None
+    }
+
+    /// Waits for the future to complete while a write or upgradable read is in progress.
+    ///
+    /// Meanwhile, read requests are served with the specified value, if available
+    /// and permitted by the fairness policy.
+    /// Otherwise they are deferred until the write is complete.
+    /// Write requests are deferred.
+    /// Non-blocking requests that cannot be served are notified immediately.
+    async fn busy<F>(&mut self, fut: F, readable: Option<&Value<T, Codec>>) -> F::Output
+    where
+        F: Future,
+    {
+        pin_mut!(fut);
+
+        loop {
+            tokio::select! {
+                biased;
+
+                out = &mut fut => return out,
+
+                res = self.req_rx.recv(), if !self.closed => match res {
+                    Ok(Some(Request::Write(mut req))) => match req.would_block_tx.take() {
+                        Some(would_block_tx) => {
+                            let _ = would_block_tx.send(());
+                        }
+                        None => self.defer(Request::Write(req)),
+                    },
+                    Ok(Some(Request::Read(mut req))) => {
+                        let readable = readable.filter(|_| !self.read_must_wait());
+                        match (readable, req.would_block_tx.take()) {
+                            (Some(value), _) => {
+                                let _ = req.value_tx.send(value.clone());
+                            }
+                            (None, Some(would_block_tx)) => {
+                                let _ = would_block_tx.send(());
+                            }
+                            (None, None) => self.defer(Request::Read(req)),
+                        }
+                    }
+                    Ok(None) => self.closed = true,
+                    Err(err) if err.is_final() => self.closed = true,
+                    Err(_) => (),
+                },
+            }
+        }
+    }
+}
@@ -5,8 +5,9 @@ use std::{
     ops::{Deref, DerefMut},
     sync::Arc,
 };
+use tokio::task::AbortHandle;
 
-use super::msg::{ReadRequest, Value, WriteRequest};
+use super::msg::{notify_held, ReadRequest, Request, UpgradableRequest, Value, WriteRequest};
 use crate::{
     chmux, codec,
     rch::{base, mpsc, oneshot},
@@ -94,7 +95,7 @@ impl Error for CommitError {}
 #[serde(bound(serialize = "T: RemoteSend, Codec: codec::Codec"))]
 #[serde(bound(deserialize = "T: RemoteSend, Codec: codec::Codec"))]
 pub struct ReadLock<T, Codec = codec::Default> {
-    req_tx: mpsc::Sender<ReadRequest<T, Codec>, Codec, 1>,
+    req_tx: mpsc::Sender<Request<T, Codec>, Codec, 1>,
     #[serde(skip)]
     #[serde(default = "empty_cache")]
     cache: Arc<tokio::sync::RwLock<Option<Value<T, Codec>>>>,
@@ -121,18 +122,23 @@ where
     T: RemoteSend + Sync,
     Codec: codec::Codec,
 {
-    pub(crate) fn new(read_req_tx: mpsc::Sender<ReadRequest<T, Codec>, Codec, 1>) -> Self {
-        Self { req_tx: read_req_tx, cache: empty_cache() }
+    pub(crate) fn new(req_tx: mpsc::Sender<Request<T, Codec>, Codec, 1>) -> Self {
+        Self { req_tx, cache: empty_cache() }
     }
 
     /// Fetches the current shared value, possibly from the local cache.
-    async fn fetch(&self) -> Result<tokio::sync::RwLockReadGuard<'_, Value<T, Codec>>, LockError> {
+    ///
+    /// If `try_only` is true, None is returned when the value is not available without waiting
+    /// for a write to complete.
+    async fn fetch(
+        &self, try_only: bool,
+    ) -> Result<Option<tokio::sync::RwLockReadGuard<'_, Value<T, Codec>>>, LockError> {
         // Return cached value if it is valid.
         {
             let cache_opt = self.cache.read().await;
             match &*cache_opt {
                 Some(cache) if cache.is_valid() => {
-                    return Ok(tokio::sync::RwLockReadGuard::map(cache_opt, |co| co.as_ref().unwrap()))
+                    return Ok(Some(tokio::sync::RwLockReadGuard::map(cache_opt, |co| co.as_ref().unwrap())))
                 }
                 _ => (),
             }
@@ -141,16 +147,37 @@ where
         // Wait for write lock before requesting current value.
         // This is necessary because there may be outstanding read locks
         // for the invalidated value.
-        let mut cache_opt = self.cache.write().await;
+        let mut cache_opt = if try_only {
+            match self.cache.try_write() {
+                Ok(cache_opt) => cache_opt,
+                Err(_) => return Ok(None),
+            }
+        } else {
+            self.cache.write().await
+        };
 
         // Request and receive current value.
         let (value_tx, value_rx) = oneshot::channel();
-        let _ = self.req_tx.send(ReadRequest { value_tx }).await;
-        let value = value_rx.await?;
+        let (would_block_tx, would_block_rx) = if try_only {
+            let (tx, rx) = oneshot::channel();
+            (Some(tx), Some(rx))
+        } else {
+            (None, None)
+        };
+        let _ = self.req_tx.send(Request::Read(ReadRequest { value_tx, would_block_tx })).await;
+        let value = match (value_rx.await, would_block_rx) {
+            (Ok(value), _) => value,
+            (Err(err), Some(would_block_rx)) => match would_block_rx.await {
+                Ok(()) => return Ok(None),
+                Err(_) => return Err(err.into()),
+            },
+            (Err(err), None) => return Err(err.into()),
+        };
 
         // Start task that monitors cache validity and releases cache
         // when it becomes invalid.
         let mut invalid_rx = value.invalid_rx.clone();
+        let held_tx = value.held_tx.clone();
         let cache_lock = self.cache.clone();
         tokio::spawn(async move {
             // Wait for cache invalidation.
@@ -167,9 +194,17 @@ where
 
             // Remove cache, if it is invalid.
             // This will wait until all read locks are released.
+            // The owner is notified if read locks are held, so that it can fail
+            // non-blocking write requests.
             // The validity check is necessary, because a new (valid) cached value may
             // have been written while we were waiting to acquire the write lock.
-            let mut cache_opt = cache_lock.write().await;
+            let mut cache_opt = match cache_lock.try_write() {
+                Ok(cache_opt) => cache_opt,
+                Err(_) => {
+                    notify_held(&held_tx);
+                    cache_lock.write().await
+                }
+            };
             match &*cache_opt {
                 Some(cache) if !cache.is_valid() => *cache_opt = None,
                 _ => (),
@@ -179,9 +214,9 @@ where
         // Store value in cache.
         *cache_opt = Some(value);
 
-        Ok(tokio::sync::RwLockReadGuard::map(tokio::sync::RwLockWriteGuard::downgrade(cache_opt), |co| {
+        Ok(Some(tokio::sync::RwLockReadGuard::map(tokio::sync::RwLockWriteGuard::downgrade(cache_opt), |co| {
             co.as_ref().unwrap()
-        }))
+        })))
     }
 
     /// Locks the current shared value for reading and returns a reference to it.
@@ -189,9 +224,36 @@ where
     /// At first invocation the value is fetched from the [owner](super::Owner) and cached locally.
     /// Thus subsequent invocations are cheap until the value is invalidated.
     pub async fn read(&self) -> Result<ReadGuard<'_, T, Codec>, LockError> {
-        let cache = self.fetch().await?;
+        let cache = self.fetch(false).await?.unwrap();
         Ok(ReadGuard(cache))
     }
+
+    /// Attempts to lock the current shared value for reading without waiting for
+    /// a write to complete.
+    ///
+    /// Returns None, if write access is currently held or being acquired.
+    ///
+    /// If the value is cached locally, this returns immediately.
+    /// Otherwise, the availability of the value is checked by the [owner](super::Owner),
+    /// thus this returns after one round trip.
+    pub async fn try_read(&self) -> Result<Option<ReadGuard<'_, T, Codec>>, LockError> {
+        let cache = self.fetch(true).await?;
+        Ok(cache.map(ReadGuard))
+    }
+}
+
+impl<T, Codec> ReadLock<T, Codec>
+where
+    T: RemoteSend + Sync + Clone,
+    Codec: codec::Codec,
+{
+    /// Locks the current shared value for reading and returns a guard that
+    /// can be sent to remote endpoints.
+    ///
+    /// See [OwnedReadGuard] for details.
+    pub async fn read_owned(&self) -> Result<OwnedReadGuard<T, Codec>, LockError> {
+        Ok(self.read().await?.into_owned())
+    }
 }
 
 /// RAII structure used to release the shared read access of a lock when dropped.
@@ -213,17 +275,26 @@ where
     ///
     /// This also returns when the owner is dropped or a connection error occurs.
     pub async fn invalidated(&self) {
-        let mut invalid_rx = self.0.invalid_rx.clone();
-        while !invalid_rx.borrow_and_update().map(|v| *v).unwrap_or_default() {
-            if invalid_rx.changed().await.is_err() {
-                break;
-            }
-        }
+        self.0.invalidated().await
     }
 
     /// Returns true, if the shared value has been invalidated.
     pub fn is_invalidated(&self) -> bool {
-        self.0.invalid_rx.borrow().map(|v| *v).unwrap_or(true)
+        self.0.is_invalidated()
+    }
+}
+
+impl<'a, T, Codec> ReadGuard<'a, T, Codec>
+where
+    T: Clone,
+    Codec: codec::Codec,
+{
+    /// Converts this into a read guard that can be sent to remote endpoints.
+    ///
+    /// The read access is held until the returned guard and all guards
+    /// it has been forwarded as are dropped.
+    pub fn into_owned(self) -> OwnedReadGuard<T, Codec> {
+        OwnedReadGuard::new((*self.0).clone())
     }
 }
 
@@ -250,6 +321,95 @@ impl<'a, T, Codec> Drop for ReadGuard<'a, T, Codec> {
     }
 }
 
+/// Shared read access of a lock that can be sent to remote endpoints.
+///
+/// This is obtained by [ReadLock::read_owned] or [ReadGuard::into_owned].
+/// Unlike a [ReadGuard] it does not borrow the lock, thus it can be stored,
+/// moved to other tasks and forwarded to other endpoints, for example to let a
+/// worker read a value that has been locked by a coordinator.
+///
+/// As long as this or a forwarded instance of it is held, no write access to the
+/// lock can occur.
+/// If the connection over which a guard has been forwarded fails, the forwarded
+/// guard is considered dropped, thus the lock cannot become permanently held.
+pub struct OwnedReadGuard<T, Codec = codec::Default> {
+    value: Value<T, Codec>,
+    held_monitor: AbortHandle,
+}
+
+impl<T, Codec> OwnedReadGuard<T, Codec>
+where
+    Codec: codec::Codec,
+{
+    fn new(value: Value<T, Codec>) -> Self {
+        let held_monitor = value.spawn_held_monitor();
+        Self { value, held_monitor }
+    }
+
+    /// Waits until the shared value is invalidated because a write request is made.
+    ///
+    /// In this case the holder should drop this guard so that the write can proceed.
+    ///
+    /// This also returns when the owner is dropped or a connection error occurs.
+    pub async fn invalidated(&self) {
+        self.value.invalidated().await
+    }
+
+    /// Returns true, if the shared value has been invalidated.
+    pub fn is_invalidated(&self) -> bool {
+        self.value.is_invalidated()
+    }
+}
+
+impl<T, Codec> Serialize for OwnedReadGuard<T, Codec>
+where
+    T: RemoteSend,
+    Codec: codec::Codec,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.value.serialize(serializer)
+    }
+}
+
+impl<'de, T, Codec> Deserialize<'de> for OwnedReadGuard<T, Codec>
+where
+    T: RemoteSend,
+    Codec: codec::Codec,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        Ok(Self::new(Value::deserialize(deserializer)?))
+    }
+}
+
+impl<T, Codec> Deref for OwnedReadGuard<T, Codec> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value.value
+    }
+}
+
+impl<T, Codec> fmt::Debug for OwnedReadGuard<T, Codec>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", &**self)
+    }
+}
+
+impl<T, Codec> Drop for OwnedReadGuard<T, Codec> {
+    fn drop(&mut self) {
+        self.held_monitor.abort();
+    }
+}
+
 /// A lock that allows reading and writing of a shared value, possibly stored on a remote endpoint.
 ///
 /// This can be cloned and sent to remote endpoints.
@@ -260,12 +420,11 @@ impl<'a, T, Codec> Drop for ReadGuard<'a, T, Codec> {
 #[serde(bound(deserialize = "T: RemoteSend, Codec: codec::Codec"))]
 pub struct RwLock<T, Codec = codec::Default> {
     read: ReadLock<T, Codec>,
-    req_tx: mpsc::Sender<WriteRequest<T, Codec>, Codec, 1>,
 }
 
 impl<T, Codec> Clone for RwLock<T, Codec> {
     fn clone(&self) -> Self {
-        Self { read: self.read.clone(), req_tx: self.req_tx.clone() }
+        Self { read: self.read.clone() }
     }
 }
 
@@ -280,10 +439,13 @@ where
     T: RemoteSend + Sync,
     Codec: codec::Codec,
 {
-    pub(crate) fn new(
-        read_lock: ReadLock<T, Codec>, write_req_tx: mpsc::Sender<WriteRequest<T, Codec>, Codec, 1>,
-    ) -> Self {
-        Self { read: read_lock, req_tx: write_req_tx }
+    pub(crate) fn new(read_lock: ReadLock<T, Codec>) -> Self {
+        Self { read: read_lock }
+    }
+
+    /// Sends a write request to the owner.
+    async fn send_write(&self, req: WriteRequest<T, Codec>) {
+        let _ = self.read.req_tx.send(Request::Write(req)).await;
     }
 
     /// Locks the current shared value for reading and returns a reference to it.
@@ -294,6 +456,14 @@ where
         self.read.read().await
     }
 
+    /// Attempts to lock the current shared value for reading without waiting for
+    /// a write to complete.
+    ///
+    /// See [ReadLock::try_read] for details.
+    pub async fn try_read(&self) -> Result<Option<ReadGuard<'_, T, Codec>>, LockError> {
+        self.read.try_read().await
+    }
+
     /// Locks the current shared value for reading and writing and returns a mutable reference to it.
     ///
     /// To commit the new value [WriteGuard::commit] must be called, otherwise the
@@ -314,18 +484,164 @@ where
         let (new_value_tx, new_value_rx) = oneshot::channel();
         let (confirm_tx, confirm_rx) = oneshot::channel();
 
-        let _ = self.req_tx.send(WriteRequest { value_tx, new_value_rx, confirm_tx }).await;
+        self.send_write(WriteRequest {
+            value_tx,
+            new_value_rx,
+            confirm_tx,
+            upgradable: None,
+            would_block_tx: None,
+        })
+        .await;
         let value = value_rx.await?;
 
         Ok(WriteGuard { value: Some(value), new_value_tx: Some(new_value_tx), confirm_rx: Some(confirm_rx) })
     }
 
+    /// Attempts to lock the current shared value for reading and writing without waiting
+    /// for another write to complete.
+    ///
+    /// Returns None, if write access or upgradable read access is currently held
+    /// or being acquired by another lock, or if a [read guard](ReadGuard) is held.
+    /// The availability is checked by the [owner](super::Owner), thus this returns
+    /// after one round trip in this case.
+    ///
+    /// To check for held read guards, all read guards are invalidated.
+    /// Thus a failed attempt still asks the holders of read guards to release them.
+    pub async fn try_write(&self) -> Result<Option<WriteGuard<T, Codec>>, LockError> {
+        let (value_tx, value_rx) = oneshot::channel();
+        let (new_value_tx, new_value_rx) = oneshot::channel();
+        let (confirm_tx, confirm_rx) = oneshot::channel();
+        let (would_block_tx, would_block_rx) = oneshot::channel();
+
+        self.send_write(WriteRequest {
+            value_tx,
+            new_value_rx,
+            confirm_tx,
+            upgradable: None,
+            would_block_tx: Some(would_block_tx),
+        })
+        .await;
+        let value = match value_rx.await {
+            Ok(value) => value,
+            Err(err) => match would_block_rx.await {
+                Ok(()) => return Ok(None),
+                Err(_) => return Err(err.into()),
+            },
+        };
+
+        Ok(Some(WriteGuard {
+            value: Some(value),
+            new_value_tx: Some(new_value_tx),
+            confirm_rx: Some(confirm_rx),
+        }))
+    }
+
+    /// Locks the current shared value for reading with the possibility of upgrading
+    /// to write access and returns a reference to it.
+    ///
+    /// While the [UpgradableReadGuard] is held, other endpoints can still obtain read access,
+    /// but no write access, thus the shared value cannot change.
+    /// Only one upgradable read guard can exist at a time.
+    /// Upgradable read and write requests are processed by the [owner](super::Owner) in the
+    /// order they are received.
+    ///
+    /// Call [UpgradableReadGuard::upgrade] to obtain write access without releasing the lock.
+    /// The upgrade takes priority over all write requests that are waiting.
+    ///
+    /// The value is always fetched from the owner and not cached locally.
+    pub async fn upgradable_read(&self) -> Result<UpgradableReadGuard<T, Codec>, LockError> {
+        let (value_tx, value_rx) = oneshot::channel();
+        let (new_value_tx, new_value_rx) = oneshot::channel();
+        let (confirm_tx, confirm_rx) = oneshot::channel();
+        let (read_value_tx, read_value_rx) = oneshot::channel();
+        let (upgrade_tx, upgrade_rx) = oneshot::channel();
+
+        let upgradable = UpgradableRequest { value_tx: read_value_tx, upgrade_rx };
+        self.send_write(WriteRequest {
+            value_tx,
+            new_value_rx,
+            confirm_tx,
+            upgradable: Some(upgradable),
+            would_block_tx: None,
+        })
+        .await;
+        let value = read_value_rx.await?;
+
+        Ok(UpgradableReadGuard { value, value_rx, new_value_tx, confirm_rx, upgrade_tx })
+    }
+
     /// Returns a read lock to the shared value.
     pub fn read_lock(&self) -> ReadLock<T, Codec> {
         self.read.clone()
     }
 }
 
+impl<T, Codec> RwLock<T, Codec>
+where
+    T: RemoteSend + Sync + Clone,
+    Codec: codec::Codec,
+{
+    /// Locks the current shared value for reading and returns a guard that
+    /// can be sent to remote endpoints.
+    ///
+    /// See [OwnedReadGuard] for details.
+    pub async fn read_owned(&self) -> Result<OwnedReadGuard<T, Codec>, LockError> {
+        self.read.read_owned().await
+    }
+}
+
+/// RAII structure used to release the upgradable read access of a lock when dropped.
+///
+/// As long as this is held, no write access to the lock can occur, except through
+/// [upgrade](Self::upgrade).
+pub struct UpgradableReadGuard<T, Codec = codec::Default> {
+    value: Value<T, Codec>,
+    value_rx: oneshot::Receiver<T, Codec>,
+    new_value_tx: oneshot::Sender<T, Codec>,
+    confirm_rx: oneshot::Receiver<(), Codec>,
+    upgrade_tx: oneshot::Sender<(), Codec>,
+}
+
+impl<T, Codec> UpgradableReadGuard<T, Codec>
+where
+    T: RemoteSend,
+    Codec: codec::Codec,
+{
+    /// Atomically upgrades to write access.
+    ///
+    /// This waits until all other [read guards](ReadGuard) have been dropped,
+    /// but the shared value cannot be changed by another endpoint in between.
+    /// Thus, the returned [WriteGuard] contains the same value as this guard.
+    ///
+    /// A read guard held by the calling task will cause a deadlock.
+    pub async fn upgrade(self) -> Result<WriteGuard<T, Codec>, LockError> {
+        let Self { value, value_rx, new_value_tx, confirm_rx, upgrade_tx } = self;
+
+        let _ = upgrade_tx.send(());
+        drop(value);
+        let value = value_rx.await?;
+
+        Ok(WriteGuard { value: Some(value), new_value_tx: Some(new_value_tx), confirm_rx: Some(confirm_rx) })
+    }
+}
+
+impl<T, Codec> Deref for UpgradableReadGuard<T, Codec> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value.value
+    }
+}
+
+impl<T, Codec> fmt::Debug for UpgradableReadGuard<T, Codec>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", &**self)
+    }
+}
+
 /// RAII structure used to release the exclusive write access of a lock when dropped.
 ///
 /// To commit changes [commit](Self::commit) must be called.
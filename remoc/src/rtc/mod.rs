@@ -71,18 +71,65 @@
 //! of all trait functions must always be of the [Result] type.
 //! The error type must be able to convert from [CallError] and thus absorb the remote calling error.
 //!
-//! There is no timeout imposed on a remote call, but the underlying [chmux] connection
+//! By default there is no timeout imposed on a remote call, but the underlying [chmux] connection
 //! [pings the remote endpoint](chmux::Cfg::connection_timeout) by default.
 //! If the underlying connection fails, all remote calls will automatically fail.
-//! You can wrap remote calls using [tokio::time::timeout] if you need to use
-//! per-call timeouts.
+//!
+//! # Arguments and return values
+//!
+//! Arguments and return values can be of any [remote sendable](crate::RemoteSend) type.
+//! This includes [remote channels](crate::rch) and other remote objects,
+//! thus a method can, for example, return the receiver of an [mpsc channel](crate::rch::mpsc)
+//! to provide a stream of events to the client.
+//! The server keeps the corresponding sender and uses it independently of further calls.
+//!
+//! # Timeouts
+//!
+//! A default timeout for all calls made through a client can be configured using
+//! [Client::set_timeout].
+//! A call that does not complete within the timeout fails with [CallError::Timeout]
+//! and the invocation on the server is cancelled, unless the method is marked `#[no_cancel]`.
+//! The timeout is sent along with the client to a remote endpoint.
+//!
+//! To override the timeout for a single call, make it through the view returned by
+//! [Client::with_timeout].
+//! The example below shows how to configure a timeout on the generated client
+//! and override it for a single call.
 //!
 //! # Cancellation
 //!
 //! If the client drops the future of a call while it is executing or the connection is interrupted
 //! the trait function on the server is automatically cancelled at the next `await` point.
+//! This also happens when the client is dropped or a [call timeout](Client::set_timeout) elapses.
+//! Cancellation drops the future of the trait function on the server, thus any
+//! resources it holds are released by their [Drop] implementations.
+//! Since cancellation is signalled over the connection, it takes effect with the latency
+//! of the connection.
 //! You can apply the `#[no_cancel]` attribute to a method to always run it to completion.
 //!
+//! # Pipelining
+//!
+//! Calls taking the receiver by reference (`&self`) can be issued without awaiting the
+//! reply of the previous call.
+//! This sends all requests before waiting for the replies and thus saves round trips
+//! over connections with high latency.
+//! Use [pipeline] to perform multiple calls of the same method or
+//! [futures::join!] for calls of different methods.
+//!
+//! Requests are sent to the server in the order in which the calls are listed.
+//! Replies are returned in the same order, regardless of the order in which the server
+//! completes them.
+//! Whether the calls are executed sequentially or in parallel on the server depends
+//! on the `spawn` argument passed to the `serve` method of the server.
+//!
+//! # Testing
+//!
+//! To test code that uses a client without establishing a physical connection,
+//! call `loopback()` on a server type, for example `<TraitServer<Target>>::loopback(target, 1)`.
+//! It returns a client connected to the server over an in-process
+//! [loopback connection](crate::Connect::loopback) and a future that serves the target object.
+//! Spawn the future and use the client as if the server was located on a remote endpoint.
+//!
 //! # Forward and backward compatibility
 //!
 //! All request arguments are packed into an enum case named after the function.
@@ -114,7 +161,7 @@
 //! trait methods on it.
 //!
 //! ```
-//! use std::sync::Arc;
+//! use std::{sync::Arc, time::Duration};
 //! use tokio::sync::RwLock;
 //! use remoc::prelude::*;
 //! use remoc::rtc::CallError;
@@ -190,7 +237,7 @@
 //!
 //!     assert_eq!(remote_counter.value().await.unwrap(), 0);
 //!
-//!     remote_counter.increase(20).await.unwrap();
+//!     remote_counter.with_timeout(Duration::from_secs(1)).increase(20).await.unwrap();
 //!     assert_eq!(remote_counter.value().await.unwrap(), 20);
 //!
 //!     remote_counter.increase(45).await.unwrap();
@@ -203,7 +250,8 @@
 //! async fn server(mut tx: rch::base::Sender<CounterClient>) {
 //!     let mut counter_obj = Arc::new(RwLock::new(CounterObj::new()));
 //!
-//!     let (server, client) = CounterServerSharedMut::new(counter_obj, 1);
+//!     let (server, mut client) = CounterServerSharedMut::new(counter_obj, 1);
+//!     client.set_timeout(Some(Duration::from_secs(10)));
 //!     tx.send(client).await.unwrap();
 //!     server.serve(true).await;
 //! }
@@ -211,17 +259,22 @@
 //! ```
 //!
 
-use futures::{future::BoxFuture, Future, FutureExt};
+use futures::{
+    future::{self, BoxFuture},
+    Future, FutureExt,
+};
 use std::{
     error::Error,
     fmt,
+    ops::{Deref, DerefMut},
     pin::Pin,
     sync::Arc,
     task::{Context, Poll},
+    time::Duration,
 };
 
 use crate::{
-    chmux,
+    chmux, codec,
     rch::{base, mpsc, oneshot},
 };
 
@@ -302,6 +355,8 @@ pub enum CallError {
     RemoteListen(chmux::ListenerError),
     /// Forwarding at a remote endpoint to another remote endpoint failed.
     RemoteForward,
+    /// The call did not complete within the configured timeout.
+    Timeout,
 }
 
 impl fmt::Display for CallError {
@@ -313,6 +368,7 @@ impl fmt::Display for CallError {
             Self::RemoteConnect(err) => write!(f, "connect error: {err}"),
             Self::RemoteListen(err) => write!(f, "listen error: {err}"),
             Self::RemoteForward => write!(f, "forwarding error"),
+            Self::Timeout => write!(f, "call timed out"),
         }
     }
 }
@@ -342,6 +398,24 @@ impl From<oneshot::RecvError> for CallError {
     }
 }
 
+/// Performs multiple remote calls without awaiting each reply before issuing the next call.
+///
+/// The calls are started in the order of the iterator, thus their requests are sent
+/// to the server in that order.
+/// All calls are then awaited simultaneously and their results are returned in the same order.
+///
+/// For example, `rtc::pipeline((0..10).map(|n| client.get(n))).await` performs ten calls
+/// using a single round trip.
+///
+/// See the [module-level documentation](self#pipelining) for details.
+pub async fn pipeline<I>(calls: I) -> Vec<<I::Item as Future>::Output>
+where
+    I: IntoIterator,
+    I::Item: Future,
+{
+    future::join_all(calls).await
+}
+
 /// A request from client to server.
 #[doc(hidden)]
 #[derive(Serialize, Deserialize)]
@@ -356,8 +430,11 @@ pub enum Req<V, R, M> {
 
 /// Client of a remotable trait.
 pub trait Client {
-    /// Returns the current capacity of the channel for sending requests to
-    /// the server.
+    /// Returns the current capacity of the local buffer of the channel for
+    /// sending requests to the server.
+    ///
+    /// Requests forwarded to the server but not yet processed are not accounted for,
+    /// see [mpsc::Sender::local_capacity](crate::rch::mpsc::Sender::local_capacity).
     ///
     /// Zero is returned when the server has been dropped or the connection
     /// has been lost.
@@ -387,6 +464,100 @@ pub trait Client {
 
     /// Sets the maximum allowed size of a reply in bytes.
     fn set_max_reply_size(&mut self, max_reply_size: usize);
+
+    /// The timeout applied to each call made through this client.
+    ///
+    /// None, if calls may take arbitrarily long.
+    ///
+    /// The default implementation returns None.
+    fn timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Sets the timeout applied to each call made through this client.
+    ///
+    /// A call that does not complete in time fails with [CallError::Timeout].
+    /// Set to None to disable the timeout, which is the default.
+    ///
+    /// The default implementation ignores the timeout.
+    /// Clients generated by the [remote] attribute support timeouts.
+    fn set_timeout(&mut self, timeout: Option<Duration>) {
+        let _ = timeout;
+    }
+
+    /// Returns a view of this client that applies the specified timeout to calls
+    /// made through it.
+    ///
+    /// The previous timeout is restored when the view is dropped.
+    /// Thus `client.with_timeout(duration).method().await` overrides the timeout
+    /// for a single call.
+    fn with_timeout(&mut self, timeout: Duration) -> WithTimeout<'_, Self>
+    where
+        Self: Sized,
+    {
+        WithTimeout::new(self, Some(timeout))
+    }
+}
+
+/// A view of a client that applies a different timeout to calls made through it.
+///
+/// This can be obtained via [Client::with_timeout].
+/// The previous timeout of the client is restored when this is dropped.
+pub struct WithTimeout<'a, C>
+where
+    C: Client,
+{
+    client: &'a mut C,
+    prev: Option<Duration>,
+}
+
+impl<'a, C> fmt::Debug for WithTimeout<'a, C>
+where
+    C: Client,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("WithTimeout").field("timeout", &self.client.timeout()).finish()
+    }
+}
+
+impl<'a, C> WithTimeout<'a, C>
+where
+    C: Client,
+{
+    fn new(client: &'a mut C, timeout: Option<Duration>) -> Self {
+        let prev = client.timeout();
+        client.set_timeout(timeout);
+        Self { client, prev }
+    }
+}
+
+impl<'a, C> Deref for WithTimeout<'a, C>
+where
+    C: Client,
+{
+    type Target = C;
+
+    fn deref(&self) -> &Self::Target {
+        self.client
+    }
+}
+
+impl<'a, C> DerefMut for WithTimeout<'a, C>
+where
+    C: Client,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.client
+    }
+}
+
+impl<'a, C> Drop for WithTimeout<'a, C>
+where
+    C: Client,
+{
+    fn drop(&mut self) {
+        self.client.set_timeout(self.prev);
+    }
 }
 
 /// A future that completes when the server or client has been dropped
@@ -438,6 +609,31 @@ where
     /// is called. In the first case, the target object is returned and, in the
     /// second case, None is returned.
     async fn serve(self) -> Option<Target>;
+
+    /// Creates a new server instance for the target object and a client
+    /// connected to it over an in-process [loopback connection](crate::Connect::loopback).
+    ///
+    /// The client is transmitted over the connection, thus requests and replies are
+    /// serialized as if the server was located on a remote endpoint.
+    /// This is useful for testing code that uses the client without establishing
+    /// a physical connection.
+    ///
+    /// The returned future serves the target object and drives the connection.
+    /// It must be spawned or awaited for the client to work.
+    ///
+    /// # Panics
+    /// Panics if the client cannot be transmitted over the loopback connection.
+    /// The future completes with the result of [serve](Self::serve).
+    async fn loopback(target: Target, request_buffer: usize) -> (Self::Client, BoxFuture<'static, Option<Target>>)
+    where
+        Self: Send + 'static,
+        Self::Client: crate::RemoteSend,
+        Codec: codec::Codec,
+    {
+        let (server, client) = Self::new(target, request_buffer);
+        let (client, conn) = loopback_client::<_, Codec>(client).await;
+        (client, serve_loopback(server.serve(), conn).boxed())
+    }
 }
 
 /// A server of a remotable trait taking the target object by reference.
@@ -487,6 +683,34 @@ where
     ///
     /// Serving ends when the client is dropped.
     async fn serve(self, spawn: bool);
+
+    /// Creates a new server instance for a shared reference to the target object and a client
+    /// connected to it over an in-process [loopback connection](crate::Connect::loopback).
+    ///
+    /// The client is transmitted over the connection, thus requests and replies are
+    /// serialized as if the server was located on a remote endpoint.
+    /// This is useful for testing code that uses the client without establishing
+    /// a physical connection.
+    ///
+    /// The returned future serves the target object and drives the connection.
+    /// It must be spawned or awaited for the client to work.
+    ///
+    /// # Panics
+    /// Panics if the client cannot be transmitted over the loopback connection.
+    ///
+    /// See [serve](Self::serve) for the meaning of `spawn`.
+    async fn loopback(
+        target: Arc<Target>, request_buffer: usize, spawn: bool,
+    ) -> (Self::Client, BoxFuture<'static, ()>)
+    where
+        Self: Send + 'static,
+        Self::Client: crate::RemoteSend,
+        Codec: codec::Codec,
+    {
+        let (server, client) = Self::new(target, request_buffer);
+        let (client, conn) = loopback_client::<_, Codec>(client).await;
+        (client, serve_loopback(server.serve(spawn), conn).boxed())
+    }
 }
 
 /// A server of a remotable trait taking the target object by shared mutable reference.
@@ -507,6 +731,80 @@ where
     ///
     /// Serving ends when the client is dropped.
     async fn serve(self, spawn: bool);
+
+    /// Creates a new server instance for a shared mutable reference to the target object and a client
+    /// connected to it over an in-process [loopback connection](crate::Connect::loopback).
+    ///
+    /// The client is transmitted over the connection, thus requests and replies are
+    /// serialized as if the server was located on a remote endpoint.
+    /// This is useful for testing code that uses the client without establishing
+    /// a physical connection.
+    ///
+    /// The returned future serves the target object and drives the connection.
+    /// It must be spawned or awaited for the client to work.
+    ///
+    /// # Panics
+    /// Panics if the client cannot be transmitted over the loopback connection.
+    ///
+    /// See [serve](Self::serve) for the meaning of `spawn`.
+    async fn loopback(
+        target: Arc<LocalRwLock<Target>>, request_buffer: usize, spawn: bool,
+    ) -> (Self::Client, BoxFuture<'static, ()>)
+    where
+        Self: Send + 'static,
+        Self::Client: crate::RemoteSend,
+        Codec: codec::Codec,
+    {
+        let (server, client) = Self::new(target, request_buffer);
+        let (client, conn) = loopback_client::<_, Codec>(client).await;
+        (client, serve_loopback(server.serve(spawn), conn).boxed())
+    }
+}
+
+/// Transmits a client over a new loopback connection.
+///
+/// Returns the received client and a future driving the connection.
+async fn loopback_client<C, Codec>(client: C) -> (C, BoxFuture<'static, ()>)
+where
+    C: crate::RemoteSend,
+    Codec: codec::Codec,
+{
+    let ((mut a_conn, mut a_tx, _a_rx), (mut b_conn, _b_tx, mut b_rx)) =
+        crate::Connect::loopback::<C, (), Codec>(Default::default(), 16)
+            .await
+            .expect("establishing loopback connection failed");
+
+    let transfer = async move {
+        if a_tx.send(client).await.is_err() {
+            panic!("sending client over loopback connection failed");
+        }
+        match b_rx.recv().await {
+            Ok(Some(client)) => client,
+            _ => panic!("receiving client over loopback connection failed"),
+        }
+    };
+
+    let client = tokio::select! {
+        biased;
+        client = transfer => client,
+        _ = &mut a_conn => panic!("loopback connection failed"),
+        _ = &mut b_conn => panic!("loopback connection failed"),
+    };
+
+    let conn = async move {
+        let _ = future::join(a_conn, b_conn).await;
+    };
+    (client, conn.boxed())
+}
+
+/// Serves over a loopback connection until serving ends.
+async fn serve_loopback<T>(serve: impl Future<Output = T>, conn: BoxFuture<'static, ()>) -> T {
+    tokio::pin!(serve);
+    tokio::select! {
+        biased;
+        res = &mut serve => res,
+        () = conn => serve.await,
+    }
 }
 
 // Re-exports for proc macro usage.
@@ -527,6 +825,17 @@ pub fn receiving_request_failed(err: mpsc::RecvError) {
     tracing::warn!(err = ?err, "receiving RTC request failed")
 }
 
+/// Performs a call with an optional timeout for proc macro.
+#[doc(hidden)]
+pub async fn call_with_timeout<T>(
+    timeout: Option<Duration>, call: impl Future<Output = Result<T, CallError>>,
+) -> Result<T, CallError> {
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, call).await.unwrap_or(Err(CallError::Timeout)),
+        None => call.await,
+    }
+}
+
 /// Broadcast sender with no subscribers.
 #[doc(hidden)]
 pub fn empty_client_drop_tx() -> local_broadcast::Sender<()> {
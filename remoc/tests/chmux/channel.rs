@@ -1,6 +1,10 @@
 use chmux::{PortsExhausted, SendError};
-use futures::{channel::oneshot, future::try_join, stream::StreamExt};
-use remoc::chmux::{self, ReceiverStream};
+use futures::{
+    channel::oneshot,
+    future::{self, try_join},
+    stream::StreamExt,
+};
+use remoc::chmux::{self, ReceiverChunkStream, ReceiverStream, SenderSink};
 use std::time::Duration;
 use tokio::time::sleep;
 use tracing::Instrument;
@@ -332,3 +336,808 @@ async fn hangup() {
     a_mux_done_rx.await.unwrap();
     b_mux_done_rx.await.unwrap();
 }
+
+#[tokio::test]
+async fn half_close() {
+    crate::init();
+
+    loop_transport!(0, a_tx, a_rx, b_tx, b_rx);
+    let ((a_mux, a_client, _a_server), (b_mux, _b_client, mut b_server)) =
+        try_join(chmux::ChMux::new(cfg(), a_tx, a_rx), chmux::ChMux::new(cfg2(), b_tx, b_rx)).await.unwrap();
+    tokio::spawn(a_mux.run());
+    tokio::spawn(b_mux.run());
+
+    let server_task = tokio::spawn(async move {
+        let (mut tx, mut rx) = b_server.accept().await.unwrap().unwrap();
+
+        let mut request = Vec::new();
+        while let Some(msg) = rx.recv().await.unwrap() {
+            request.extend_from_slice(&Vec::from(msg));
+        }
+        println!("Server received request: {}", String::from_utf8_lossy(&request));
+
+        request.reverse();
+        tx.send(request.into()).await.unwrap();
+        tx.finish().await;
+    });
+
+    let (mut tx, mut rx) = a_client.connect().await.unwrap();
+    tx.send("request ".into()).await.unwrap();
+    tx.send("body".into()).await.unwrap();
+    println!("Finishing request");
+    tx.finish().await;
+
+    let response: Vec<u8> = rx.recv().await.unwrap().unwrap().into();
+    println!("Client received response: {}", String::from_utf8_lossy(&response));
+    assert_eq!(response, b"ydob tseuqer");
+    assert!(rx.recv().await.unwrap().is_none());
+
+    server_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn ping_interval() {
+    crate::init();
+
+    let a_cfg = chmux::Cfg {
+        connection_timeout: None,
+        ping_interval: Some(Duration::from_millis(50)),
+        ..Default::default()
+    };
+    let b_cfg = chmux::Cfg { connection_timeout: Some(Duration::from_millis(300)), ..Default::default() };
+
+    loop_transport!(0, a_tx, a_rx, b_tx, b_rx);
+    let ((a_mux, a_client, _a_server), (b_mux, _b_client, mut b_server)) =
+        try_join(chmux::ChMux::new(a_cfg, a_tx, a_rx), chmux::ChMux::new(b_cfg, b_tx, b_rx)).await.unwrap();
+    tokio::spawn(a_mux.run());
+    let b_mux_task = tokio::spawn(b_mux.run());
+
+    println!("Idling");
+    sleep(Duration::from_secs(1)).await;
+    assert!(!b_mux_task.is_finished(), "connection timed out");
+
+    let server_task = tokio::spawn(async move {
+        let (_tx, mut rx) = b_server.accept().await.unwrap().unwrap();
+        rx.recv().await.unwrap().unwrap()
+    });
+    let (mut tx, _rx) = a_client.connect().await.unwrap();
+    tx.send("alive".into()).await.unwrap();
+    assert_eq!(Vec::from(server_task.await.unwrap()), b"alive");
+}
+
+#[tokio::test]
+async fn ping_timeout() {
+    crate::init();
+
+    let a_cfg = chmux::Cfg {
+        connection_timeout: None,
+        ping_interval: Some(Duration::from_millis(100)),
+        ping_timeout: Some(Duration::from_millis(200)),
+        ..Default::default()
+    };
+    let b_cfg = chmux::Cfg { connection_timeout: None, ..Default::default() };
+
+    loop_transport!(0, a_tx, a_rx, b_tx, b_rx);
+    let ((a_mux, _a_client, _a_server), (_b_mux, _b_client, _b_server)) =
+        try_join(chmux::ChMux::new(a_cfg, a_tx, a_rx), chmux::ChMux::new(b_cfg, b_tx, b_rx)).await.unwrap();
+
+    println!("Running only A");
+    let res = tokio::time::timeout(Duration::from_secs(5), a_mux.run()).await.expect("no ping timeout");
+    println!("A mux result: {res:?}");
+    assert!(matches!(res, Err(chmux::ChMuxError::Timeout)));
+}
+
+#[tokio::test]
+async fn ping_timeout_alive() {
+    crate::init();
+
+    let a_cfg = chmux::Cfg {
+        connection_timeout: None,
+        ping_interval: Some(Duration::from_millis(50)),
+        ping_timeout: Some(Duration::from_millis(200)),
+        ..Default::default()
+    };
+    let b_cfg = chmux::Cfg { connection_timeout: None, ..Default::default() };
+
+    loop_transport!(0, a_tx, a_rx, b_tx, b_rx);
+    let ((a_mux, a_client, _a_server), (b_mux, _b_client, mut b_server)) =
+        try_join(chmux::ChMux::new(a_cfg, a_tx, a_rx), chmux::ChMux::new(b_cfg, b_tx, b_rx)).await.unwrap();
+    let a_mux_task = tokio::spawn(a_mux.run());
+    tokio::spawn(b_mux.run());
+
+    println!("Idling");
+    sleep(Duration::from_secs(1)).await;
+    assert!(!a_mux_task.is_finished(), "ping timed out");
+
+    let server_task = tokio::spawn(async move {
+        let (_tx, mut rx) = b_server.accept().await.unwrap().unwrap();
+        rx.recv().await.unwrap().unwrap()
+    });
+    let (mut tx, _rx) = a_client.connect().await.unwrap();
+    tx.send("alive".into()).await.unwrap();
+    assert_eq!(Vec::from(server_task.await.unwrap()), b"alive");
+}
+
+#[tokio::test]
+async fn idle_timeout() {
+    crate::init();
+
+    let a_cfg = chmux::Cfg { idle_timeout: Some(Duration::from_millis(300)), ..Default::default() };
+    let b_cfg = chmux::Cfg::default();
+
+    loop_transport!(0, a_tx, a_rx, b_tx, b_rx);
+    let ((a_mux, a_client, a_server), (b_mux, b_client, mut b_server)) =
+        try_join(chmux::ChMux::new(a_cfg, a_tx, a_rx), chmux::ChMux::new(b_cfg, b_tx, b_rx)).await.unwrap();
+    assert!(a_mux.negotiated().port_idle_timeout);
+    let a_mux_task = tokio::spawn(a_mux.run());
+    let b_mux_task = tokio::spawn(b_mux.run());
+
+    let server_task = tokio::spawn(async move { b_server.accept().await.unwrap().unwrap() });
+    let (mut a_tx, mut a_rx) = a_client.connect().await.unwrap();
+    let (mut b_tx, mut b_rx) = server_task.await.unwrap();
+
+    println!("Sending within idle timeout");
+    for i in 0..5u8 {
+        sleep(Duration::from_millis(100)).await;
+        if i % 2 == 0 {
+            a_tx.send(vec![i].into()).await.unwrap();
+            assert_eq!(Vec::from(b_rx.recv().await.unwrap().unwrap()), vec![i]);
+        } else {
+            b_tx.send(vec![i].into()).await.unwrap();
+            assert_eq!(Vec::from(a_rx.recv().await.unwrap().unwrap()), vec![i]);
+        }
+    }
+
+    println!("Idling");
+    sleep(Duration::from_millis(600)).await;
+    assert!(matches!(a_tx.send(vec![1].into()).await, Err(SendError::IdleTimeout)));
+    assert!(matches!(a_rx.recv().await, Err(chmux::RecvError::IdleTimeout)));
+    assert!(matches!(b_tx.send(vec![2].into()).await, Err(SendError::IdleTimeout)));
+    let err = b_rx.recv().await.unwrap_err();
+    println!("Remote receive error: {err}");
+    assert!(matches!(err, chmux::RecvError::IdleTimeout));
+    assert!(err.is_final());
+
+    println!("Dropping ports");
+    drop((a_tx, a_rx, b_tx, b_rx));
+    drop((a_client, a_server, b_client));
+    a_mux_task.await.unwrap().unwrap();
+    b_mux_task.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn port_req_metadata() {
+    crate::init();
+
+    loop_transport!(0, a_tx, a_rx, b_tx, b_rx);
+    let ((a_mux, a_client, _a_server), (b_mux, _b_client, mut b_server)) =
+        try_join(chmux::ChMux::new(cfg(), a_tx, a_rx), chmux::ChMux::new(cfg2(), b_tx, b_rx)).await.unwrap();
+    tokio::spawn(a_mux.run());
+    tokio::spawn(b_mux.run());
+
+    let metadata = vec![1, 2, 3];
+    let max_metadata = vec![0xaa; chmux::PortReq::MAX_METADATA_LEN];
+
+    println!("Connecting with metadata");
+    let port = a_client.port_allocator().allocate().await;
+    let req = chmux::PortReq::new(port).with_id(7).with_metadata(metadata.clone());
+    assert_eq!(req.metadata(), &metadata[..]);
+    let connect = a_client.connect_ext(Some(req), true).await.unwrap();
+
+    let server_task = tokio::spawn(async move {
+        let request = b_server.inspect().await.unwrap().unwrap();
+        println!("Received request: {request:?}");
+        assert_eq!(request.id(), 7);
+        assert_eq!(request.metadata(), &[1, 2, 3]);
+        request.accept().await.unwrap()
+    });
+
+    let (mut a_tx, _a_rx) = connect.await.unwrap();
+    let (_b_tx, mut b_rx) = server_task.await.unwrap();
+
+    println!("Sending ports with metadata");
+    let allocator = a_client.port_allocator();
+    let reqs = vec![
+        chmux::PortReq::new(allocator.allocate().await).with_metadata(metadata.clone()),
+        chmux::PortReq::new(allocator.allocate().await),
+        chmux::PortReq::new(allocator.allocate().await).with_metadata(max_metadata.clone()),
+    ];
+    let connect_task = tokio::spawn(async move { a_tx.connect(reqs, true).await.unwrap() });
+
+    let mut requests = Vec::new();
+    while requests.len() < 3 {
+        match b_rx.recv_any().await.unwrap() {
+            Some(chmux::Received::Requests(reqs)) => requests.extend(reqs),
+            other => panic!("unexpected receive: {other:?}"),
+        }
+    }
+    assert_eq!(requests[0].metadata(), &metadata[..]);
+    assert!(requests[1].metadata().is_empty());
+    assert_eq!(requests[2].metadata(), &max_metadata[..]);
+    let _connects = connect_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn stats() {
+    crate::init();
+
+    let cfg = chmux::Cfg { connection_timeout: None, ..Default::default() };
+    loop_transport!(0, a_tx, a_rx, b_tx, b_rx);
+    let ((a_mux, a_client, _a_server), (b_mux, _b_client, mut b_server)) =
+        try_join(chmux::ChMux::new(cfg.clone(), a_tx, a_rx), chmux::ChMux::new(cfg, b_tx, b_rx)).await.unwrap();
+    let initial = a_mux.stats();
+    println!("Initial stats: {initial:?}");
+    assert!(initial.sent_bytes > 0);
+    assert_eq!(initial.opened_ports, 0);
+    assert_eq!(initial.open_ports, 0);
+    tokio::spawn(a_mux.run());
+    tokio::spawn(b_mux.run());
+
+    const N_PORTS: usize = 3;
+    const LEN: usize = 1000;
+
+    let server_task = tokio::spawn(async move {
+        let mut ports = Vec::new();
+        for _ in 0..N_PORTS {
+            let (tx, mut rx) = b_server.accept().await.unwrap().unwrap();
+            let data = rx.recv().await.unwrap().unwrap();
+            assert_eq!(Vec::from(data), vec![0xab; LEN]);
+            ports.push((tx, rx));
+        }
+        (b_server, ports)
+    });
+
+    let mut ports = Vec::new();
+    for _ in 0..N_PORTS {
+        let (mut tx, rx) = a_client.connect().await.unwrap();
+        tx.send(vec![0xab; LEN].into()).await.unwrap();
+        ports.push((tx, rx));
+    }
+    let (b_server, b_ports) = server_task.await.unwrap();
+    sleep(Duration::from_millis(100)).await;
+
+    let a_stats = a_client.stats();
+    let b_stats = b_server.stats();
+    println!("A stats: {a_stats:?}");
+    println!("B stats: {b_stats:?}");
+    assert!(a_stats.sent_bytes >= (N_PORTS * LEN) as u64);
+    assert_eq!(a_stats.sent_bytes, b_stats.received_bytes);
+    assert_eq!(b_stats.sent_bytes, a_stats.received_bytes);
+    assert_eq!(a_stats.unflushed_bytes, 0);
+    assert_eq!(b_stats.unflushed_bytes, 0);
+    assert_eq!(a_stats.opened_ports, N_PORTS as u64);
+    assert_eq!(b_stats.opened_ports, N_PORTS as u64);
+    assert_eq!(a_stats.open_ports, N_PORTS as u64);
+    assert_eq!(b_stats.open_ports, N_PORTS as u64);
+
+    println!("Closing ports");
+    drop(ports);
+    drop(b_ports);
+    sleep(Duration::from_millis(100)).await;
+
+    let a_stats = a_client.stats();
+    println!("A stats: {a_stats:?}");
+    assert_eq!(a_stats.opened_ports, N_PORTS as u64);
+    assert_eq!(a_stats.open_ports, 0);
+    assert_eq!(b_server.stats().open_ports, 0);
+}
+
+#[tokio::test]
+async fn negotiated() {
+    crate::init();
+
+    let a_cfg = chmux::Cfg { chunk_size: 1000, receive_buffer: 8000, connect_queue: 5, ..Default::default() };
+    let b_cfg = chmux::Cfg { chunk_size: 2000, max_ports: 10, connection_timeout: None, ..Default::default() };
+    loop_transport!(0, a_tx, a_rx, b_tx, b_rx);
+    let ((a_mux, a_client, _a_server), (b_mux, b_client, _b_server)) =
+        try_join(chmux::ChMux::new(a_cfg, a_tx, a_rx), chmux::ChMux::new(b_cfg, b_tx, b_rx)).await.unwrap();
+
+    let a = a_mux.negotiated().clone();
+    let b = b_mux.negotiated().clone();
+    println!("A negotiated: {a:?}");
+    println!("B negotiated: {b:?}");
+
+    assert_eq!(a.local_protocol_version, chmux::PROTOCOL_VERSION);
+    assert_eq!(a.remote_protocol_version, chmux::PROTOCOL_VERSION);
+    assert_eq!(a.protocol_version(), chmux::PROTOCOL_VERSION);
+    assert!(a.port_ids);
+    assert!(a.port_metadata);
+    assert!(a.port_idle_timeout);
+    assert!(!a.checksum);
+    assert!(a.ping_timeout);
+
+    assert_eq!(a.send_chunk_size, 2000);
+    assert_eq!(a.recv_chunk_size, 1000);
+    assert_eq!(b.send_chunk_size, 1000);
+    assert_eq!(b.recv_chunk_size, 2000);
+    assert_eq!(b.remote_port_receive_buffer, 8000);
+    assert_eq!(b.remote_connect_queue, 5);
+    assert_eq!(b.max_ports, 10);
+    assert_eq!(a.remote_connection_timeout, None);
+    assert!(b.remote_connection_timeout.is_some());
+
+    assert_eq!(a_client.negotiated(), &a);
+    assert_eq!(b_client.negotiated(), &b);
+}
+
+#[tokio::test]
+async fn backpressure() {
+    crate::init();
+
+    let cfg = chmux::Cfg { receive_buffer: 16, chunk_size: 4, ..Default::default() };
+    loop_transport!(0, a_tx, a_rx, b_tx, b_rx);
+    let ((a_mux, a_client, _a_server), (b_mux, _b_client, mut b_server)) =
+        try_join(chmux::ChMux::new(cfg.clone(), a_tx, a_rx), chmux::ChMux::new(cfg, b_tx, b_rx)).await.unwrap();
+    tokio::spawn(a_mux.run());
+    tokio::spawn(b_mux.run());
+
+    let server_task = tokio::spawn(async move { b_server.accept().await.unwrap().unwrap() });
+    let (mut a_tx, _a_rx) = a_client.connect().await.unwrap();
+    let (_b_tx, mut b_rx) = server_task.await.unwrap();
+
+    assert_eq!(a_tx.buffered_bytes(), 0);
+    assert!(!a_tx.is_backpressured());
+    assert_eq!(a_tx.blocked_duration(), Duration::ZERO);
+
+    println!("Filling flow control window");
+    a_tx.send(vec![1; 10].into()).await.unwrap();
+    assert_eq!(a_tx.buffered_bytes(), 10);
+    assert!(!a_tx.is_backpressured());
+    a_tx.send(vec![2; 6].into()).await.unwrap();
+    assert_eq!(a_tx.buffered_bytes(), 16);
+    assert!(a_tx.is_backpressured());
+
+    println!("Sending while backpressured");
+    let send_task = tokio::spawn(async move {
+        a_tx.send(vec![3; 4].into()).await.unwrap();
+        a_tx
+    });
+    sleep(Duration::from_millis(200)).await;
+    assert!(!send_task.is_finished());
+
+    for _ in 0..3 {
+        b_rx.recv().await.unwrap().unwrap();
+    }
+    let a_tx = send_task.await.unwrap();
+    sleep(Duration::from_millis(100)).await;
+    println!("Blocked for {:?}", a_tx.blocked_duration());
+    assert!(a_tx.blocked_duration() >= Duration::from_millis(200));
+    // Receiver returns credits once half of its buffer has been consumed.
+    assert_eq!(a_tx.buffered_bytes(), 4);
+    assert!(!a_tx.is_backpressured());
+}
+
+#[tokio::test]
+async fn backpressure_isolation() {
+    crate::init();
+
+    let cfg = chmux::Cfg { receive_buffer: 1024, chunk_size: 256, ..Default::default() };
+    loop_transport!(0, a_tx, a_rx, b_tx, b_rx);
+    let ((a_mux, a_client, _a_server), (b_mux, _b_client, mut b_server)) =
+        try_join(chmux::ChMux::new(cfg.clone(), a_tx, a_rx), chmux::ChMux::new(cfg, b_tx, b_rx)).await.unwrap();
+    tokio::spawn(a_mux.run());
+    tokio::spawn(b_mux.run());
+
+    let server_task = tokio::spawn(async move {
+        let slow = b_server.accept().await.unwrap().unwrap();
+        let fast = b_server.accept().await.unwrap().unwrap();
+        (slow, fast)
+    });
+    let (mut slow_tx, _slow_rx) = a_client.connect().await.unwrap();
+    let (mut fast_tx, _fast_rx) = a_client.connect().await.unwrap();
+    let ((_slow_b_tx, mut slow_b_rx), (_fast_b_tx, mut fast_b_rx)) = server_task.await.unwrap();
+
+    println!("Congesting slow port");
+    let slow_task = tokio::spawn(async move {
+        let mut sent = 0;
+        loop {
+            if slow_tx.send(vec![0; 512].into()).await.is_err() {
+                break;
+            }
+            sent += 1;
+        }
+        sent
+    });
+    sleep(Duration::from_millis(100)).await;
+    assert!(!slow_task.is_finished());
+
+    const N_MSG: usize = 1000;
+    println!("Sending {N_MSG} messages over fast port");
+    let fast_task = tokio::spawn(async move {
+        for i in 0..N_MSG {
+            fast_tx.send(vec![(i % 256) as u8; 512].into()).await.unwrap();
+        }
+    });
+    tokio::time::timeout(Duration::from_secs(10), async {
+        for i in 0..N_MSG {
+            let data = fast_b_rx.recv().await.unwrap().unwrap();
+            assert_eq!(Vec::from(data), vec![(i % 256) as u8; 512]);
+        }
+    })
+    .await
+    .expect("fast port starved by congested port");
+    fast_task.await.unwrap();
+    println!("Fast port completed");
+
+    assert!(!slow_task.is_finished());
+    let data = slow_b_rx.recv().await.unwrap().unwrap();
+    assert_eq!(Vec::from(data).len(), 512);
+    drop(slow_b_rx);
+    let sent = slow_task.await.unwrap();
+    println!("Slow port sent {sent} messages");
+    assert!(sent <= 3);
+}
+
+#[tokio::test]
+async fn connection_backpressure() {
+    crate::init();
+
+    let cfg =
+        chmux::Cfg { receive_buffer: 64, chunk_size: 16, max_connection_buffer: Some(96), ..Default::default() };
+    loop_transport!(0, a_tx, a_rx, b_tx, b_rx);
+    let ((a_mux, a_client, _a_server), (b_mux, _b_client, mut b_server)) =
+        try_join(chmux::ChMux::new(cfg.clone(), a_tx, a_rx), chmux::ChMux::new(cfg, b_tx, b_rx)).await.unwrap();
+    tokio::spawn(a_mux.run());
+    tokio::spawn(b_mux.run());
+
+    let server_task = tokio::spawn(async move {
+        let mut ports = Vec::new();
+        for _ in 0..3 {
+            ports.push(b_server.accept().await.unwrap().unwrap());
+        }
+        ports
+    });
+    let mut a_ports = Vec::new();
+    for _ in 0..3 {
+        a_ports.push(a_client.connect().await.unwrap());
+    }
+    let mut b_ports = server_task.await.unwrap();
+    let (mut third_tx, _) = a_ports.pop().unwrap();
+    let (mut second_tx, _) = a_ports.pop().unwrap();
+    let (mut first_tx, _) = a_ports.pop().unwrap();
+
+    println!("Filling share of first port");
+    for _ in 0..3 {
+        first_tx.send(vec![1; 16].into()).await.unwrap();
+    }
+    assert_eq!(first_tx.buffered_bytes(), 48);
+    let first_task = tokio::spawn(async move {
+        first_tx.send(vec![1; 16].into()).await.unwrap();
+        first_tx
+    });
+    sleep(Duration::from_millis(200)).await;
+    assert!(!first_task.is_finished(), "port exceeded its share of connection buffer");
+
+    println!("Filling connection buffer using second port");
+    for _ in 0..3 {
+        second_tx.send(vec![2; 16].into()).await.unwrap();
+    }
+    let third_task = tokio::spawn(async move {
+        third_tx.send(vec![3; 16].into()).await.unwrap();
+        third_tx
+    });
+    sleep(Duration::from_millis(200)).await;
+    assert!(!third_task.is_finished(), "connection buffer exceeded");
+
+    println!("Consuming data of first port");
+    let (_, first_b_rx) = &mut b_ports[0];
+    for _ in 0..2 {
+        assert_eq!(Vec::from(first_b_rx.recv().await.unwrap().unwrap()), vec![1; 16]);
+    }
+    let third_tx =
+        tokio::time::timeout(Duration::from_secs(5), third_task).await.expect("third port starved").unwrap();
+    assert_eq!(third_tx.buffered_bytes(), 16);
+    let first_tx =
+        tokio::time::timeout(Duration::from_secs(5), first_task).await.expect("first port starved").unwrap();
+    assert_eq!(first_tx.buffered_bytes(), 32);
+}
+
+#[tokio::test]
+async fn connection_backpressure_port_closed() {
+    crate::init();
+
+    let cfg =
+        chmux::Cfg { receive_buffer: 64, chunk_size: 16, max_connection_buffer: Some(64), ..Default::default() };
+    loop_transport!(0, a_tx, a_rx, b_tx, b_rx);
+    let ((a_mux, a_client, _a_server), (b_mux, _b_client, mut b_server)) =
+        try_join(chmux::ChMux::new(cfg.clone(), a_tx, a_rx), chmux::ChMux::new(cfg, b_tx, b_rx)).await.unwrap();
+    tokio::spawn(a_mux.run());
+    tokio::spawn(b_mux.run());
+
+    let server_task = tokio::spawn(async move {
+        let mut ports = Vec::new();
+        for _ in 0..3 {
+            ports.push(b_server.accept().await.unwrap().unwrap());
+        }
+        ports
+    });
+    let mut a_ports = Vec::new();
+    for _ in 0..3 {
+        a_ports.push(a_client.connect().await.unwrap());
+    }
+    let mut b_ports = server_task.await.unwrap();
+    let (mut third_tx, _) = a_ports.pop().unwrap();
+    let (mut second_tx, _) = a_ports.pop().unwrap();
+    let (mut first_tx, _) = a_ports.pop().unwrap();
+
+    println!("Filling connection buffer using first and second port");
+    for _ in 0..2 {
+        first_tx.send(vec![1; 16].into()).await.unwrap();
+        second_tx.send(vec![2; 16].into()).await.unwrap();
+    }
+    let third_task = tokio::spawn(async move { third_tx.send(vec![3; 16].into()).await });
+    sleep(Duration::from_millis(200)).await;
+    assert!(!third_task.is_finished(), "connection buffer exceeded");
+
+    println!("Closing third port on remote endpoint");
+    drop(b_ports.pop().unwrap());
+    let res = tokio::time::timeout(Duration::from_secs(5), third_task)
+        .await
+        .expect("blocked sender did not notice port closure")
+        .unwrap();
+    println!("Send result: {res:?}");
+    assert!(res.is_err());
+}
+
+#[tokio::test]
+async fn connection_buffer_single_port() {
+    crate::init();
+
+    // The share of a port of the connection buffer is smaller than the credit return threshold.
+    let cfg =
+        chmux::Cfg { receive_buffer: 64, chunk_size: 8, max_connection_buffer: Some(24), ..Default::default() };
+    loop_transport!(0, a_tx, a_rx, b_tx, b_rx);
+    let ((a_mux, a_client, _a_server), (b_mux, _b_client, mut b_server)) =
+        try_join(chmux::ChMux::new(cfg.clone(), a_tx, a_rx), chmux::ChMux::new(cfg, b_tx, b_rx)).await.unwrap();
+    tokio::spawn(a_mux.run());
+    tokio::spawn(b_mux.run());
+
+    const N_MSG: usize = 50;
+    let send_task = tokio::spawn(async move {
+        let (mut tx, _rx) = a_client.connect().await.unwrap();
+        for _ in 0..N_MSG {
+            tx.send(vec![1; 8].into()).await.unwrap();
+        }
+    });
+
+    let (_tx, mut rx) = b_server.accept().await.unwrap().unwrap();
+    for i in 0..N_MSG {
+        let msg = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .unwrap_or_else(|_| panic!("stalled at message {i}"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(Vec::from(msg), vec![1; 8]);
+    }
+    send_task.await.unwrap();
+}
+
+/// Transfers data over a port and returns the number of bytes received by the sending endpoint.
+async fn credit_return_traffic(credit_return_threshold: Option<u32>) -> u64 {
+    const N_MSG: usize = 1000;
+    const MSG_LEN: usize = 64;
+
+    let a_cfg = chmux::Cfg::default();
+    let b_cfg = chmux::Cfg { receive_buffer: 4096, credit_return_threshold, ..Default::default() };
+    loop_transport!(0, a_tx, a_rx, b_tx, b_rx);
+    let ((a_mux, a_client, _a_server), (b_mux, _b_client, mut b_server)) =
+        try_join(chmux::ChMux::new(a_cfg, a_tx, a_rx), chmux::ChMux::new(b_cfg, b_tx, b_rx)).await.unwrap();
+    tokio::spawn(a_mux.run());
+    tokio::spawn(b_mux.run());
+
+    let server_task = tokio::spawn(async move { b_server.accept().await.unwrap().unwrap() });
+    let (mut a_tx, _a_rx) = a_client.connect().await.unwrap();
+    let (_b_tx, mut b_rx) = server_task.await.unwrap();
+
+    let start = a_client.stats().received_bytes;
+    let started = tokio::time::Instant::now();
+    let recv_task = tokio::spawn(async move {
+        for _ in 0..N_MSG {
+            b_rx.recv().await.unwrap().unwrap();
+        }
+    });
+    for _ in 0..N_MSG {
+        a_tx.send(vec![1; MSG_LEN].into()).await.unwrap();
+    }
+    recv_task.await.unwrap();
+
+    let reverse = a_client.stats().received_bytes - start;
+    println!(
+        "Threshold {credit_return_threshold:?}: {} bytes in {:?}, {reverse} bytes in reverse direction",
+        N_MSG * MSG_LEN,
+        started.elapsed()
+    );
+    reverse
+}
+
+#[tokio::test]
+async fn credit_return_threshold() {
+    crate::init();
+
+    let per_msg = credit_return_traffic(Some(64)).await;
+    let coalesced = credit_return_traffic(None).await;
+    assert!(coalesced * 4 < per_msg, "credit returns were not coalesced");
+}
+
+#[tokio::test]
+async fn chunk_size() {
+    crate::init();
+
+    const CHUNK_SIZE: usize = 1000;
+    const LEN: usize = 10_500;
+
+    let a_cfg = chmux::Cfg::default();
+    let b_cfg = chmux::Cfg { chunk_size: CHUNK_SIZE as u32, receive_buffer: 100_000, ..Default::default() };
+    loop_transport!(0, a_tx, a_rx, b_tx, b_rx);
+    let ((a_mux, a_client, _a_server), (b_mux, _b_client, mut b_server)) =
+        try_join(chmux::ChMux::new(a_cfg, a_tx, a_rx), chmux::ChMux::new(b_cfg, b_tx, b_rx)).await.unwrap();
+    tokio::spawn(a_mux.run());
+    tokio::spawn(b_mux.run());
+
+    let server_task = tokio::spawn(async move { b_server.accept().await.unwrap().unwrap() });
+    let (mut a_tx, _a_rx) = a_client.connect().await.unwrap();
+    let (_b_tx, mut b_rx) = server_task.await.unwrap();
+    assert_eq!(a_tx.chunk_size(), CHUNK_SIZE);
+
+    println!("Sending {LEN} bytes");
+    let data: Vec<u8> = (0..LEN).map(|i| i as u8).collect();
+    a_tx.send(data.clone().into()).await.unwrap();
+
+    b_rx.set_max_data_size(1);
+    assert!(matches!(b_rx.recv_any().await.unwrap(), Some(chmux::Received::Chunks)));
+    let mut chunks = Vec::new();
+    while let Some(chunk) = b_rx.recv_chunk().await.unwrap() {
+        chunks.push(chunk);
+    }
+
+    let sizes: Vec<_> = chunks.iter().map(|chunk| chunk.len()).collect();
+    println!("Received chunks of sizes {sizes:?}");
+    let mut expected = vec![CHUNK_SIZE; LEN / CHUNK_SIZE];
+    expected.push(LEN % CHUNK_SIZE);
+    assert_eq!(sizes, expected);
+    assert_eq!(chunks.concat(), data);
+}
+
+#[tokio::test]
+async fn checksum_detects_corruption() {
+    crate::init();
+
+    const MARKER: &[u8] = b"corrupt me";
+
+    // Flip a bit of every frame from A to B that contains the marker.
+    loop_transport!(0, a_tx, a_rx, b_tx, b_rx);
+    let b_rx = b_rx.map(|frame| {
+        frame.map(|frame| match frame.windows(MARKER.len()).position(|w| w == MARKER) {
+            Some(pos) => {
+                let mut frame = frame.to_vec();
+                frame[pos] ^= 0x01;
+                frame.into()
+            }
+            None => frame,
+        })
+    });
+
+    let a_cfg = chmux::Cfg { checksum: true, ..Default::default() };
+    let ((a_mux, a_client, _a_server), (b_mux, _b_client, mut b_server)) =
+        try_join(chmux::ChMux::new(a_cfg, a_tx, a_rx), chmux::ChMux::new(Default::default(), b_tx, b_rx))
+            .await
+            .unwrap();
+    assert!(a_mux.negotiated().checksum);
+    assert!(b_mux.negotiated().checksum);
+
+    tokio::spawn(async move {
+        let _ = a_mux.run().await;
+    });
+    let b_mux_task = tokio::spawn(b_mux.run());
+
+    let ((mut tx, _rx), request) = tokio::join!(async { a_client.connect().await.unwrap() }, async {
+        b_server.accept().await.unwrap().unwrap()
+    });
+    let (_b_tx, mut b_rx) = request;
+
+    println!("Sending intact data");
+    tx.send("intact".into()).await.unwrap();
+    let msg = b_rx.recv().await.unwrap().unwrap();
+    assert_eq!(Vec::from(msg), b"intact");
+
+    println!("Sending data that will be corrupted");
+    tx.send(MARKER.into()).await.unwrap();
+
+    let res = b_mux_task.await.unwrap();
+    println!("B mux result: {res:?}");
+    assert!(matches!(res, Err(chmux::ChMuxError::Corrupted)));
+}
+
+#[tokio::test]
+async fn chunk_stream() {
+    crate::init();
+
+    let a_cfg = chmux::Cfg { chunk_size: 16, ..Default::default() };
+    let b_cfg = chmux::Cfg { chunk_size: 16, max_data_size: 64, ..Default::default() };
+    loop_transport!(0, a_tx, a_rx, b_tx, b_rx);
+    let ((a_mux, a_client, _a_server), (b_mux, _b_client, mut b_server)) =
+        try_join(chmux::ChMux::new(a_cfg, a_tx, a_rx), chmux::ChMux::new(b_cfg, b_tx, b_rx)).await.unwrap();
+    tokio::spawn(a_mux.run());
+    tokio::spawn(b_mux.run());
+
+    // Messages exceed the maximum data size of the receiver.
+    let msgs: Vec<Vec<u8>> = (0..10u8).map(|i| vec![i; 50 * usize::from(i)]).collect();
+    let expected = msgs.concat();
+
+    let send_task = tokio::spawn(async move {
+        let (mut tx, _rx) = a_client.connect().await.unwrap();
+        for msg in msgs {
+            println!("Sending message of {} bytes", msg.len());
+            tx.send(msg.into()).await.unwrap();
+        }
+    });
+
+    let (_tx, rx) = b_server.accept().await.unwrap().unwrap();
+    let mut stream = ReceiverChunkStream::from(rx);
+    let mut received = Vec::new();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.unwrap();
+        assert!(chunk.len() <= 16);
+        received.extend_from_slice(&chunk);
+    }
+    println!("Received {} bytes", received.len());
+    assert_eq!(received, expected);
+
+    send_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn relay() {
+    crate::init();
+
+    let cfg = chmux::Cfg { chunk_size: 16, ..Default::default() };
+
+    println!("Connecting source to relay");
+    loop_transport!(0, a_tx, a_rx, b_tx, b_rx);
+    let ((a_mux, a_client, _a_server), (b_mux, _b_client, mut b_server)) =
+        try_join(chmux::ChMux::new(cfg.clone(), a_tx, a_rx), chmux::ChMux::new(cfg.clone(), b_tx, b_rx))
+            .await
+            .unwrap();
+    tokio::spawn(a_mux.run());
+    tokio::spawn(b_mux.run());
+
+    println!("Connecting relay to destination");
+    loop_transport!(0, c_tx, c_rx, d_tx, d_rx);
+    let ((c_mux, c_client, _c_server), (d_mux, _d_client, mut d_server)) =
+        try_join(chmux::ChMux::new(cfg.clone(), c_tx, c_rx), chmux::ChMux::new(cfg, d_tx, d_rx)).await.unwrap();
+    tokio::spawn(c_mux.run());
+    tokio::spawn(d_mux.run());
+
+    let msgs: Vec<Vec<u8>> = (0..20u8).map(|i| vec![i; 10 * usize::from(i)]).collect();
+    let expected = msgs.concat();
+
+    let send_task = tokio::spawn(async move {
+        let (mut tx, _rx) = a_client.connect().await.unwrap();
+        for msg in msgs {
+            tx.send(msg.into()).await.unwrap();
+        }
+    });
+
+    let relay_task = tokio::spawn(async move {
+        let (_tx, rx) = b_server.accept().await.unwrap().unwrap();
+        let (tx, _rx) = c_client.connect().await.unwrap();
+        ReceiverChunkStream::from(rx)
+            .take_while(|res| future::ready(res.is_ok()))
+            .map(|res| Ok(res.unwrap()))
+            .forward(SenderSink::from(tx))
+            .await
+            .unwrap();
+        println!("Relay finished");
+    });
+
+    let (_tx, mut rx) = d_server.accept().await.unwrap().unwrap();
+    let mut received = Vec::new();
+    while let Some(data) = rx.recv().await.unwrap() {
+        received.extend(Vec::<u8>::from(data));
+    }
+    println!("Received {} bytes", received.len());
+    assert_eq!(received, expected);
+
+    send_task.await.unwrap();
+    relay_task.await.unwrap();
+}
@@ -1,4 +1,7 @@
 mod channel;
+mod port_allocator;
+mod router;
+mod shutdown;
 mod tcp;
 
 #[cfg(unix)]
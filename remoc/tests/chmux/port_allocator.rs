@@ -0,0 +1,415 @@
+use futures::{future::try_join, StreamExt};
+use remoc::chmux::{self, PortAllocator};
+use std::{collections::HashSet, time::Duration};
+use tokio::time::sleep;
+
+use crate::loop_transport;
+
+async fn port_allocator(cfg: chmux::Cfg) -> PortAllocator {
+    loop_transport!(0, a_tx, a_rx, b_tx, b_rx);
+    let ((_a_mux, a_client, _a_server), _b) =
+        try_join(chmux::ChMux::new(cfg, a_tx, a_rx), chmux::ChMux::new(Default::default(), b_tx, b_rx))
+            .await
+            .unwrap();
+    a_client.port_allocator()
+}
+
+#[tokio::test]
+async fn range() {
+    crate::init();
+
+    let cfg = chmux::Cfg { port_range_start: 100, port_range_end: 109, ..Default::default() };
+    let allocator = port_allocator(cfg).await;
+    assert_eq!(allocator.range(), 100..=109);
+
+    let mut ports = Vec::new();
+    for _ in 0..10 {
+        let port = allocator.try_allocate().unwrap();
+        println!("Allocated port {port}");
+        assert!(allocator.range().contains(&*port));
+        ports.push(port);
+    }
+
+    let numbers: HashSet<u32> = ports.iter().map(|port| **port).collect();
+    assert_eq!(numbers, (100..=109).collect());
+
+    println!("Range exhausted");
+    assert!(allocator.try_allocate().is_none());
+
+    println!("Releasing port {}", ports[3]);
+    let released = *ports.remove(3);
+    assert_eq!(*allocator.allocate().await, released);
+}
+
+#[tokio::test]
+async fn sequential() {
+    crate::init();
+
+    let cfg = chmux::Cfg {
+        port_range_start: 1000,
+        port_range_end: 1009,
+        port_allocation: chmux::PortAllocation::Sequential,
+        ..Default::default()
+    };
+
+    let mut sequences = Vec::new();
+    for _ in 0..2 {
+        let allocator = port_allocator(cfg.clone()).await;
+
+        let mut sequence = Vec::new();
+        let mut ports = Vec::new();
+        for i in 0..25 {
+            let port = allocator.try_allocate().unwrap();
+            println!("Allocated port {port}");
+            sequence.push(*port);
+            if i % 3 == 0 {
+                ports.push(port);
+            }
+        }
+
+        sequences.push(sequence);
+    }
+
+    assert_eq!(sequences[0], sequences[1]);
+    assert_eq!(sequences[0][..5], [1000, 1001, 1002, 1003, 1004]);
+}
+
+#[tokio::test]
+async fn fifo_wakeup() {
+    crate::init();
+
+    let cfg = chmux::Cfg { port_range_start: 0, port_range_end: 1, ..Default::default() };
+    let allocator = port_allocator(cfg).await;
+
+    let mut ports = vec![allocator.try_allocate().unwrap(), allocator.try_allocate().unwrap()];
+    assert!(allocator.try_allocate().is_none());
+
+    let (order_tx, mut order_rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut tasks = Vec::new();
+    for i in 0..4 {
+        let allocator = allocator.clone();
+        let order_tx = order_tx.clone();
+        tasks.push(tokio::spawn(async move {
+            let port = allocator.allocate().await;
+            println!("Waiter {i} obtained port {port}");
+            order_tx.send(i).unwrap();
+            port
+        }));
+        sleep(Duration::from_millis(10)).await;
+    }
+
+    for i in 0..4 {
+        println!("Releasing port {}", ports[0]);
+        ports.remove(0);
+        assert_eq!(order_rx.recv().await.unwrap(), i);
+        ports.push(tasks.remove(0).await.unwrap());
+        assert!(order_rx.try_recv().is_err());
+    }
+}
+
+#[tokio::test]
+async fn utilization() {
+    crate::init();
+
+    let cfg = chmux::Cfg { max_ports: 3, ..Default::default() };
+    let allocator = port_allocator(cfg).await;
+    println!("{allocator:?}");
+
+    assert_eq!(allocator.limit(), 3);
+    assert_eq!(allocator.used(), 0);
+    assert_eq!(allocator.available(), 3);
+    assert_eq!(allocator.waiters(), 0);
+
+    let mut ports = Vec::new();
+    for i in 1..=3 {
+        ports.push(allocator.try_allocate().unwrap());
+        assert_eq!(allocator.used(), i);
+        assert_eq!(allocator.available(), 3 - i);
+    }
+    assert!(allocator.try_allocate().is_none());
+
+    let waiter = tokio::spawn({
+        let allocator = allocator.clone();
+        async move { allocator.allocate().await }
+    });
+    while allocator.waiters() == 0 {
+        sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(allocator.waiters(), 1);
+
+    ports.pop();
+    let _port = waiter.await.unwrap();
+    assert_eq!(allocator.waiters(), 0);
+    assert_eq!(allocator.used(), 3);
+    assert_eq!(allocator.available(), 0);
+}
+
+#[tokio::test]
+async fn specific() {
+    crate::init();
+
+    let cfg = chmux::Cfg { max_ports: 2, port_range_start: 10, port_range_end: 20, ..Default::default() };
+    let allocator = port_allocator(cfg).await;
+
+    let port = allocator.try_allocate_specific(15).unwrap();
+    assert_eq!(*port, 15);
+    assert!(allocator.try_allocate_specific(15).is_none());
+    assert!(allocator.try_allocate_specific(5).is_none());
+    assert!(allocator.try_allocate_specific(25).is_none());
+
+    let other = allocator.try_allocate_specific(16).unwrap();
+    assert!(allocator.try_allocate_specific(17).is_none());
+
+    drop(other);
+    let other = allocator.try_allocate_specific(17).unwrap();
+    assert_eq!(*other, 17);
+
+    drop(port);
+    assert_eq!(allocator.used(), 1);
+    assert!(allocator.try_allocate_specific(15).is_some());
+}
+
+#[tokio::test]
+async fn many() {
+    crate::init();
+
+    let cfg = chmux::Cfg { max_ports: 10, ..Default::default() };
+    let allocator = port_allocator(cfg).await;
+
+    let mut ports = allocator.try_allocate_many(4);
+    assert_eq!(ports.len(), 4);
+    assert_eq!(allocator.used(), 4);
+
+    let more = allocator.try_allocate_many(10);
+    assert_eq!(more.len(), 6);
+    assert!(allocator.try_allocate_many(1).is_empty());
+    drop(more);
+
+    let more = allocator.allocate_many(6).await;
+    assert_eq!(more.len(), 6);
+
+    let waiter = tokio::spawn({
+        let allocator = allocator.clone();
+        async move { allocator.allocate_many(3).await }
+    });
+
+    for _ in 0..3 {
+        sleep(Duration::from_millis(10)).await;
+        ports.pop();
+    }
+
+    let waited = waiter.await.unwrap();
+    assert_eq!(waited.len(), 3);
+    assert_eq!(allocator.used(), 10);
+
+    let numbers: HashSet<u32> = ports.iter().chain(&more).chain(&waited).map(|port| **port).collect();
+    assert_eq!(numbers.len(), 10);
+}
+
+#[tokio::test]
+async fn timeout() {
+    crate::init();
+
+    let cfg = chmux::Cfg { max_ports: 1, ..Default::default() };
+    let allocator = port_allocator(cfg).await;
+
+    let port = allocator.allocate_timeout(Duration::from_millis(100)).await.unwrap();
+
+    println!("Allocating with timeout while exhausted");
+    for _ in 0..10 {
+        assert!(allocator.allocate_timeout(Duration::from_millis(10)).await.is_none());
+        assert_eq!(allocator.waiters(), 0);
+    }
+
+    let waiter = tokio::spawn({
+        let allocator = allocator.clone();
+        async move { allocator.allocate_timeout(Duration::from_secs(10)).await }
+    });
+    sleep(Duration::from_millis(10)).await;
+    drop(port);
+
+    assert!(waiter.await.unwrap().is_some());
+    assert_eq!(allocator.waiters(), 0);
+    assert_eq!(allocator.used(), 0);
+}
+
+#[tokio::test]
+async fn set_limit() {
+    crate::init();
+
+    let cfg = chmux::Cfg { max_ports: 2, ..Default::default() };
+    let allocator = port_allocator(cfg).await;
+
+    let mut ports = vec![allocator.try_allocate().unwrap(), allocator.try_allocate().unwrap()];
+    assert!(allocator.try_allocate().is_none());
+
+    let waiters: Vec<_> = (0..2)
+        .map(|_| {
+            let allocator = allocator.clone();
+            tokio::spawn(async move { allocator.allocate().await })
+        })
+        .collect();
+    sleep(Duration::from_millis(10)).await;
+    assert_eq!(allocator.waiters(), 2);
+
+    println!("Raising limit");
+    assert_eq!(allocator.set_limit(4), 4);
+    assert_eq!(allocator.limit(), 4);
+    for waiter in waiters {
+        ports.push(waiter.await.unwrap());
+    }
+    assert_eq!(allocator.used(), 4);
+    assert_eq!(allocator.waiters(), 0);
+
+    println!("Lowering limit below usage");
+    assert_eq!(allocator.set_limit(2), 4);
+    assert_eq!(allocator.limit(), 4);
+    assert_eq!(allocator.used(), 4);
+    assert!(allocator.try_allocate().is_none());
+
+    ports.truncate(2);
+    assert_eq!(allocator.used(), 2);
+    assert_eq!(allocator.set_limit(2), 2);
+    assert!(allocator.try_allocate().is_none());
+
+    ports.pop();
+    ports.push(allocator.try_allocate().unwrap());
+    assert_eq!(allocator.used(), 2);
+}
+
+#[tokio::test]
+#[should_panic(expected = "port limit must not be zero")]
+async fn set_limit_zero() {
+    crate::init();
+
+    let allocator = port_allocator(Default::default()).await;
+    allocator.set_limit(0);
+}
+
+#[tokio::test]
+async fn random_exhaustion() {
+    crate::init();
+
+    let cfg = chmux::Cfg {
+        port_range_start: 10_000,
+        port_range_end: 14_095,
+        max_ports: 10_000,
+        port_allocation: chmux::PortAllocation::Random,
+        ..Default::default()
+    };
+    let allocator = port_allocator(cfg).await;
+
+    println!("Allocating all ports of range {:?}", allocator.range());
+    let ports: Vec<_> = (0..4096).map(|_| allocator.try_allocate().unwrap()).collect();
+    let numbers: HashSet<u32> = ports.iter().map(|port| **port).collect();
+    assert_eq!(numbers, (10_000..=14_095).collect());
+    assert!(allocator.try_allocate().is_none());
+}
+
+#[tokio::test]
+async fn seeded_rng() {
+    crate::init();
+
+    let cfg = chmux::Cfg {
+        port_range_start: 0,
+        port_range_end: 1_000_000,
+        port_allocation: chmux::PortAllocation::Random,
+        port_allocation_seed: Some(42),
+        ..Default::default()
+    };
+
+    let mut sequences = Vec::new();
+    for _ in 0..2 {
+        let allocator = port_allocator(cfg.clone()).await;
+
+        let ports: Vec<_> = (0..20).map(|_| allocator.try_allocate().unwrap()).collect();
+        let sequence: Vec<u32> = ports.iter().map(|port| **port).collect();
+        println!("Allocated ports {sequence:?}");
+        sequences.push(sequence);
+    }
+
+    assert_eq!(sequences[0], sequences[1]);
+}
+
+#[tokio::test]
+async fn compact() {
+    crate::init();
+
+    assert_eq!(chmux::Cfg::default().port_allocation, chmux::PortAllocation::Random);
+    let cfg = chmux::Cfg {
+        port_range_start: 100,
+        port_range_end: 109,
+        port_allocation: chmux::PortAllocation::Compact,
+        ..Default::default()
+    };
+    let allocator = port_allocator(cfg).await;
+
+    let specific = allocator.try_allocate_specific(102).unwrap();
+    let mut ports: Vec<_> = (0..4).map(|_| allocator.try_allocate().unwrap()).collect();
+    println!("Allocated ports {ports:?}");
+    assert_eq!(ports.iter().map(|port| **port).collect::<Vec<_>>(), [100, 101, 103, 104]);
+
+    println!("Releasing ports {} and {}", ports[2], ports[0]);
+    ports.remove(2);
+    ports.remove(0);
+    ports.push(allocator.try_allocate().unwrap());
+    assert_eq!(*ports[2], 100);
+
+    println!("Allocating released port specifically");
+    ports.push(allocator.try_allocate_specific(103).unwrap());
+    drop(specific);
+    ports.push(allocator.try_allocate().unwrap());
+    ports.push(allocator.try_allocate().unwrap());
+    println!("Allocated ports {ports:?}");
+    assert_eq!(ports.iter().map(|port| **port).collect::<Vec<_>>(), [101, 104, 100, 103, 102, 105]);
+}
+
+#[tokio::test]
+async fn events() {
+    crate::init();
+
+    let cfg = chmux::Cfg { port_range_start: 10, port_range_end: 20, ..Default::default() };
+    let allocator = port_allocator(cfg).await;
+    let mut events = allocator.events();
+
+    let specific = allocator.try_allocate_specific(15).unwrap();
+    let port = allocator.try_allocate().unwrap();
+    let number = *port;
+    drop(specific);
+    drop(port);
+
+    for expected in [
+        chmux::PortEvent::Allocated(15),
+        chmux::PortEvent::Allocated(number),
+        chmux::PortEvent::Released(15),
+        chmux::PortEvent::Released(number),
+    ] {
+        let event = events.next().await.unwrap();
+        println!("Event: {event:?}");
+        assert_eq!(event, expected);
+    }
+}
+
+#[tokio::test]
+async fn events_lossy() {
+    crate::init();
+
+    let allocator = port_allocator(Default::default()).await;
+    let mut events = allocator.events();
+
+    println!("Generating more events than buffered");
+    let n = PortAllocator::EVENT_BUFFER;
+    for _ in 0..n {
+        allocator.try_allocate().unwrap();
+    }
+
+    let event = events.next().await.unwrap();
+    println!("Event: {event:?}");
+    assert_eq!(event, chmux::PortEvent::Lost(n as u64));
+
+    let mut remaining = 0;
+    while let Ok(Some(_)) = tokio::time::timeout(Duration::from_millis(100), events.next()).await {
+        remaining += 1;
+    }
+    assert_eq!(remaining, n);
+}
@@ -0,0 +1,113 @@
+use futures::{future::try_join, stream::StreamExt};
+use remoc::chmux::{self, ConnectError, RouteError};
+
+use crate::loop_transport;
+
+async fn connect_with_id(
+    client: &chmux::Client, id: u32,
+) -> Result<(chmux::Sender, chmux::Receiver), ConnectError> {
+    let port = client.port_allocator().allocate().await;
+    client.connect_ext(Some(chmux::PortReq::new(port).with_id(id)), true).await?.await
+}
+
+#[tokio::test]
+async fn route_by_id() {
+    crate::init();
+
+    let cfg = chmux::Cfg::default();
+    loop_transport!(0, a_tx, a_rx, b_tx, b_rx);
+    let ((a_mux, a_client, _a_server), (b_mux, _b_client, b_server)) =
+        try_join(chmux::ChMux::new(cfg.clone(), a_tx, a_rx), chmux::ChMux::new(cfg, b_tx, b_rx)).await.unwrap();
+    tokio::spawn(a_mux.run());
+    tokio::spawn(b_mux.run());
+
+    let router = b_server.into_router();
+    let mut single = router.listen(1).unwrap();
+    let mut range = router.listen_range(10..=19).unwrap();
+    println!("Router: {router:?}");
+
+    assert_eq!(router.listen(15).unwrap_err(), RouteError::AlreadyRegistered);
+    assert_eq!(router.listen_range(0..=1).unwrap_err(), RouteError::AlreadyRegistered);
+
+    let single_task = tokio::spawn(async move {
+        let req = single.next().await.unwrap().unwrap();
+        assert_eq!(req.id(), 1);
+        let (mut tx, _rx) = req.accept().await.unwrap();
+        tx.send(vec![1].into()).await.unwrap();
+        single
+    });
+    let range_task = tokio::spawn(async move {
+        for id in [12, 19] {
+            let req = range.next().await.unwrap().unwrap();
+            assert_eq!(req.id(), id);
+            let (mut tx, _rx) = req.accept().await.unwrap();
+            tx.send(vec![id as u8].into()).await.unwrap();
+        }
+        range
+    });
+
+    println!("Connecting to routes");
+    for id in [1, 12, 19] {
+        let (_tx, mut rx) = connect_with_id(&a_client, id).await.unwrap();
+        assert_eq!(Vec::from(rx.recv().await.unwrap().unwrap()), vec![id as u8]);
+    }
+    let single = single_task.await.unwrap();
+    let _range = range_task.await.unwrap();
+
+    println!("Connecting without route");
+    assert!(matches!(connect_with_id(&a_client, 5).await, Err(ConnectError::NoRoute)));
+
+    println!("Unregistering route");
+    drop(single);
+    assert!(matches!(connect_with_id(&a_client, 1).await, Err(ConnectError::NoRoute)));
+    let _single = router.listen(1).unwrap();
+}
+
+#[tokio::test]
+async fn route_end() {
+    crate::init();
+
+    let cfg = chmux::Cfg::default();
+    loop_transport!(0, a_tx, a_rx, b_tx, b_rx);
+    let ((a_mux, a_client, _a_server), (b_mux, _b_client, b_server)) =
+        try_join(chmux::ChMux::new(cfg.clone(), a_tx, a_rx), chmux::ChMux::new(cfg, b_tx, b_rx)).await.unwrap();
+    tokio::spawn(a_mux.run());
+    tokio::spawn(b_mux.run());
+
+    let router = b_server.into_router();
+    let mut route = router.listen(1).unwrap();
+
+    println!("Dropping client");
+    drop(a_client);
+    assert!(route.next().await.is_none());
+}
+
+#[tokio::test]
+async fn route_queue_full() {
+    crate::init();
+
+    let cfg = chmux::Cfg::default();
+    loop_transport!(0, a_tx, a_rx, b_tx, b_rx);
+    let ((a_mux, a_client, _a_server), (b_mux, _b_client, b_server)) =
+        try_join(chmux::ChMux::new(cfg.clone(), a_tx, a_rx), chmux::ChMux::new(cfg, b_tx, b_rx)).await.unwrap();
+    tokio::spawn(a_mux.run());
+    tokio::spawn(b_mux.run());
+
+    let mut router = b_server.into_router();
+    assert_eq!(router.queue_length(), chmux::Router::DEFAULT_QUEUE_LENGTH);
+    router.set_queue_length(1);
+    let mut route = router.listen(1).unwrap();
+
+    println!("Filling route queue");
+    let queued_client = a_client.clone();
+    let queued = tokio::spawn(async move { connect_with_id(&queued_client, 1).await });
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+    println!("Connecting to full route");
+    assert!(matches!(connect_with_id(&a_client, 1).await, Err(ConnectError::Rejected)));
+
+    println!("Accepting queued request");
+    let req = route.next().await.unwrap().unwrap();
+    let (_tx, _rx) = req.accept().await.unwrap();
+    queued.await.unwrap().unwrap();
+}
@@ -0,0 +1,138 @@
+use futures::{future::try_join, stream::StreamExt};
+use remoc::chmux::{self, ChMuxError, CloseReason, ConnectError, RecvError, SendError};
+use std::time::Duration;
+use tokio::time::{sleep, timeout};
+
+use crate::loop_transport;
+
+#[tokio::test]
+async fn drain() {
+    crate::init();
+
+    let cfg = chmux::Cfg { chunk_size: 4, receive_buffer: 8, ..Default::default() };
+    loop_transport!(0, a_tx, a_rx, b_tx, b_rx);
+    let ((a_mux, a_client, _a_server), (b_mux, b_client, mut b_server)) =
+        try_join(chmux::ChMux::new(cfg.clone(), a_tx, a_rx), chmux::ChMux::new(cfg, b_tx, b_rx)).await.unwrap();
+    let a_mux = tokio::spawn(a_mux.run());
+    let b_mux = tokio::spawn(b_mux.run());
+
+    const N_MSG: usize = 100;
+
+    let server_task = tokio::spawn(async move {
+        let (_tx, mut rx) = b_server.accept().await.unwrap().unwrap();
+        let mut received = 0;
+        while let Some(data) = rx.recv().await.unwrap() {
+            assert_eq!(Vec::from(data), vec![received as u8; 10]);
+            received += 1;
+            sleep(Duration::from_millis(1)).await;
+        }
+        println!("Server received {received} messages");
+        received
+    });
+
+    let (mut tx, rx) = a_client.connect().await.unwrap();
+    let send_task = tokio::spawn(async move {
+        for i in 0..N_MSG {
+            tx.send(vec![i as u8; 10].into()).await.unwrap();
+        }
+    });
+
+    println!("Shutting down");
+    let shutdown_task = tokio::spawn(async move {
+        a_client.shutdown(Duration::from_secs(30)).await;
+        a_client
+    });
+
+    sleep(Duration::from_millis(100)).await;
+    println!("Connecting during shutdown");
+    assert!(matches!(b_client.connect().await, Err(ConnectError::Rejected)));
+
+    send_task.await.unwrap();
+    assert_eq!(server_task.await.unwrap(), N_MSG);
+    drop(rx);
+
+    let a_client = timeout(Duration::from_secs(10), shutdown_task).await.unwrap().unwrap();
+    println!("Shutdown complete");
+    let res = a_client.connect().await;
+    println!("Connect after shutdown: {res:?}");
+    assert!(res.is_err());
+
+    a_mux.await.unwrap().unwrap();
+    b_mux.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn timeout_closes_ports() {
+    crate::init();
+
+    let cfg = chmux::Cfg::default();
+    loop_transport!(0, a_tx, a_rx, b_tx, b_rx);
+    let ((a_mux, a_client, _a_server), (b_mux, _b_client, mut b_server)) =
+        try_join(chmux::ChMux::new(cfg.clone(), a_tx, a_rx), chmux::ChMux::new(cfg, b_tx, b_rx)).await.unwrap();
+    let handle = a_mux.shutdown_handle();
+    let a_mux = tokio::spawn(a_mux.run());
+    let b_mux = tokio::spawn(b_mux.run());
+
+    let server_task = tokio::spawn(async move { b_server.accept().await.unwrap().unwrap() });
+    let (mut a_tx, mut a_rx) = a_client.connect().await.unwrap();
+    let (_b_tx, mut b_rx) = server_task.await.unwrap();
+
+    a_tx.send(vec![1; 10].into()).await.unwrap();
+
+    println!("Shutting down");
+    timeout(Duration::from_secs(10), handle.shutdown(Duration::from_millis(200))).await.unwrap();
+    println!("Shutdown complete");
+
+    assert_eq!(Vec::from(b_rx.recv().await.unwrap().unwrap()), vec![1; 10]);
+
+    let res = a_tx.send(vec![2; 10].into()).await;
+    println!("Send after shutdown: {res:?}");
+    assert!(matches!(res, Err(SendError::Shutdown)));
+
+    let res = a_rx.recv().await;
+    println!("Receive after shutdown: {res:?}");
+    assert!(matches!(res, Err(RecvError::Shutdown)));
+
+    a_mux.await.unwrap().unwrap();
+    let res = b_mux.await.unwrap();
+    println!("B mux result: {res:?}");
+    assert!(matches!(res, Err(ChMuxError::Reset)));
+}
+
+#[tokio::test]
+async fn close_with_reason() {
+    crate::init();
+
+    let cfg = chmux::Cfg::default();
+    loop_transport!(0, a_tx, a_rx, b_tx, b_rx);
+    let ((a_mux, _a_client, _a_server), (b_mux, _b_client, _b_server)) =
+        try_join(chmux::ChMux::new(cfg.clone(), a_tx, a_rx), chmux::ChMux::new(cfg, b_tx, b_rx)).await.unwrap();
+    assert!(a_mux.negotiated().close_reason);
+    let handle = a_mux.shutdown_handle();
+    let a_mux = tokio::spawn(a_mux.run());
+    let b_mux = tokio::spawn(b_mux.run());
+
+    println!("Closing with reason");
+    handle.close_with_reason(503, "maintenance");
+
+    let res = timeout(Duration::from_secs(10), a_mux).await.unwrap().unwrap();
+    println!("A mux result: {res:?}");
+    assert!(res.is_ok());
+
+    let res = timeout(Duration::from_secs(10), b_mux).await.unwrap().unwrap();
+    println!("B mux result: {res:?}");
+    match res {
+        Err(ChMuxError::Closed(reason)) => assert_eq!(reason, CloseReason::new(503, "maintenance")),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn close_reason_truncated() {
+    let reason = CloseReason::new(1, "ä".repeat(200));
+    println!("Message length: {}", reason.message().len());
+    assert_eq!(reason.message(), "ä".repeat(127));
+
+    let reason = CloseReason::new(2, "a".repeat(300));
+    assert_eq!(reason.message(), "a".repeat(CloseReason::MAX_MESSAGE_LEN));
+}
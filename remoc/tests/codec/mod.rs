@@ -92,6 +92,28 @@ fn ciborium() {
     roundtrip::<TestStruct, codec::Ciborium>()
 }
 
+#[cfg(feature = "codec-ciborium")]
+#[test]
+fn ciborium_self_described() {
+    roundtrip::<TestStruct, codec::CiboriumSelfDescribed>();
+
+    let data = vec![TestEnum::One(1), TestEnum::Two { field1: "value".to_string(), field2: 2 }];
+
+    let mut buffer = Vec::new();
+    <codec::CiboriumSelfDescribed as codec::Codec>::serialize(&mut buffer, &data).unwrap();
+    assert_eq!(buffer[..3], [0xd9, 0xd9, 0xf7]);
+    let deser: Vec<TestEnum> =
+        <codec::CiboriumSelfDescribed as codec::Codec>::deserialize(buffer.as_slice()).unwrap();
+    assert_eq!(deser, data);
+
+    let mut compact = Vec::new();
+    <codec::Ciborium as codec::Codec>::serialize(&mut compact, &data).unwrap();
+    assert_eq!(compact, buffer[3..]);
+    let deser: Vec<TestEnum> =
+        <codec::CiboriumSelfDescribed as codec::Codec>::deserialize(compact.as_slice()).unwrap();
+    assert_eq!(deser, data);
+}
+
 #[cfg(feature = "codec-json")]
 #[test]
 #[should_panic]
@@ -105,6 +127,37 @@ fn json_with_attr() {
     roundtrip::<TestStructWithAttr, codec::Json>()
 }
 
+#[cfg(feature = "codec-json")]
+#[test]
+fn json_lines() {
+    roundtrip::<TestStructWithAttr, codec::JsonLines>();
+
+    let data = vec![
+        TestEnum::Two { field1: "first line\nsecond line\r\nthird line".to_string(), field2: 1 },
+        TestEnum::Two { field1: "\n".to_string(), field2: 2 },
+        TestEnum::One(3),
+    ];
+
+    let mut buffer = Vec::new();
+    for item in &data {
+        <codec::JsonLines as codec::Codec>::serialize(&mut buffer, item).unwrap();
+    }
+    println!("serialized:\n{}", String::from_utf8_lossy(&buffer));
+
+    let lines: Vec<_> = buffer.split_inclusive(|&b| b == b'\n').collect();
+    assert_eq!(lines.len(), data.len());
+    for (line, item) in lines.into_iter().zip(&data) {
+        assert_eq!(line.last(), Some(&b'\n'));
+        let deser: TestEnum = <codec::JsonLines as codec::Codec>::deserialize(line).unwrap();
+        assert_eq!(&deser, item);
+    }
+
+    let mut compact = Vec::new();
+    <codec::Json as codec::Codec>::serialize(&mut compact, &data[0]).unwrap();
+    let deser: TestEnum = <codec::JsonLines as codec::Codec>::deserialize(compact.as_slice()).unwrap();
+    assert_eq!(deser, data[0]);
+}
+
 #[cfg(feature = "codec-message-pack")]
 #[test]
 fn message_pack() {
@@ -231,3 +231,110 @@ async fn double_forward() {
 
     reply_task.await.unwrap();
 }
+
+#[tokio::test]
+async fn framed() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<bin::FramedSender>().await;
+
+    println!("Sending framed sender");
+    let (tx, mut rx) = bin::framed();
+    a_tx.send(tx).await.unwrap();
+    let mut tx = b_rx.recv().await.unwrap().unwrap();
+
+    let mut rng = rand::thread_rng();
+    let mut msgs = Vec::new();
+    for size in [0, 1, 1024, 16_384, 16_385, 100_000, 1_000_000] {
+        let mut data = vec![0u8; size];
+        rng.fill_bytes(&mut data);
+        msgs.push(Bytes::from(data));
+    }
+
+    let send_msgs = msgs.clone();
+    let send_task = tokio::spawn(async move {
+        for msg in send_msgs {
+            println!("Sending message of length {}", msg.len());
+            tx.send(msg).await.unwrap();
+        }
+
+        println!("Sending oversized message");
+        tx.send(vec![1u8; 200_000]).await.unwrap();
+        tx.send(vec![2u8; 10]).await.unwrap();
+
+        tx.set_max_item_size(10);
+        assert!(matches!(tx.send(vec![3u8; 11]).await, Err(bin::FramedError::MaxItemSizeExceeded)));
+    });
+
+    for msg in msgs {
+        let recved = rx.recv().await.unwrap().unwrap();
+        println!("Received message of length {}", recved.len());
+        assert_eq!(recved, msg, "message mismatch");
+    }
+
+    rx.set_max_item_size(100_000);
+    assert!(matches!(rx.recv().await, Err(bin::FramedError::MaxItemSizeExceeded)));
+    assert_eq!(rx.recv().await.unwrap().unwrap(), Bytes::from(vec![2u8; 10]));
+
+    send_task.await.unwrap();
+    assert!(rx.recv().await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn framed_raw() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<bin::Sender>().await;
+
+    println!("Sending binary channel sender");
+    let (tx, rx) = bin::channel();
+    a_tx.send(tx).await.unwrap();
+    let tx = b_rx.recv().await.unwrap().unwrap();
+    let mut rx = bin::FramedReceiver::from(rx);
+
+    let mut stream = Vec::new();
+    for msg in [&b"hello"[..], b"", b"world"] {
+        stream.extend_from_slice(&(msg.len() as u32).to_be_bytes());
+        stream.extend_from_slice(msg);
+    }
+    stream.extend_from_slice(&[0, 0, 0, 9, 1, 2]);
+
+    println!("Writing length-delimited data with arbitrary boundaries");
+    let mut tx = tx.into_inner().await.unwrap();
+    for part in stream.chunks(3) {
+        tx.send(Bytes::copy_from_slice(part)).await.unwrap();
+    }
+    drop(tx);
+
+    assert_eq!(rx.recv().await.unwrap().unwrap(), &b"hello"[..]);
+    assert_eq!(rx.recv().await.unwrap().unwrap(), &b""[..]);
+    assert_eq!(rx.recv().await.unwrap().unwrap(), &b"world"[..]);
+    assert!(matches!(rx.recv().await, Err(bin::FramedError::Truncated)));
+    assert!(rx.recv().await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn framed_streaming() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<bin::Sender>().await;
+
+    println!("Sending binary channel sender");
+    let (tx, rx) = bin::channel();
+    a_tx.send(tx).await.unwrap();
+    let tx = b_rx.recv().await.unwrap().unwrap();
+    let mut rx = bin::FramedReceiver::from(rx);
+
+    println!("Writing frames within one long-lived message");
+    let mut tx = tx.into_inner().await.unwrap();
+    let mut chunks = tx.send_chunks();
+    for i in 0..5u8 {
+        let mut frame = 3u32.to_be_bytes().to_vec();
+        frame.extend_from_slice(&[i; 3]);
+        chunks = chunks.send(frame.into()).await.unwrap();
+
+        let msg = tokio::time::timeout(std::time::Duration::from_secs(10), rx.recv()).await.unwrap();
+        assert_eq!(msg.unwrap().unwrap(), &[i; 3][..]);
+    }
+    chunks.finish().await.unwrap();
+    drop(tx);
+
+    assert!(rx.recv().await.unwrap().is_none());
+}
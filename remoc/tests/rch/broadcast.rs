@@ -2,12 +2,17 @@ use futures::{try_join, StreamExt};
 use remoc::{
     codec,
     rch::{
-        broadcast::{self, ReceiverStream},
+        broadcast::{self, LagPolicy, ReceiverStream},
         mpsc,
     },
 };
+use serde::{Deserialize, Serialize};
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
 
-use crate::loop_channel_with_cfg;
+use crate::{loop_channel, loop_channel_with_cfg, loop_transport};
 
 #[tokio::test]
 async fn simple() {
@@ -208,3 +213,264 @@ async fn simple_stream() {
     println!("Waiting for tasks to finish");
     try_join!(rx1_task, rx2_task, rx3_task).unwrap();
 }
+
+async fn overrun(lag_policy: LagPolicy) -> (Vec<i16>, u64) {
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<broadcast::Receiver<i16, codec::Default, 1>>().await;
+
+    let (tx, rx) = broadcast::channel::<_, _, 1>(1);
+    tx.set_lag_policy(lag_policy);
+    assert_eq!(tx.lag_policy(), lag_policy);
+
+    println!("Sending remote broadcast channel receiver");
+    a_tx.send(rx).await.unwrap();
+    println!("Receiving remote broadcast channel receiver");
+    let mut rx = b_rx.recv().await.unwrap().unwrap();
+    assert_eq!(rx.lag(), 0);
+
+    println!("Overrunning receive buffer");
+    for i in 0..100 {
+        tx.send(i).unwrap();
+    }
+    drop(tx);
+
+    let mut received = Vec::new();
+    let mut lags = 0;
+    loop {
+        match rx.recv().await {
+            Ok(value) => received.push(value),
+            Err(err) if err.is_closed() => break,
+            Err(err) if err.is_lagged() => {
+                lags += 1;
+                println!("Lagged, total lag is {}", rx.lag());
+            }
+            Err(err) => panic!("receive error: {err}"),
+        }
+    }
+    println!("Received {received:?} with lag {} in {lags} notifications", rx.lag());
+    assert!(lags > 0);
+    assert!(received.windows(2).all(|w| w[0] < w[1]));
+
+    (received, rx.lag())
+}
+
+#[tokio::test]
+async fn lag_keep_oldest() {
+    crate::init();
+
+    let (received, lag) = overrun(LagPolicy::KeepOldest).await;
+    assert!(lag > 0);
+    assert_eq!(received.len() as u64 + lag, 100);
+    assert_eq!(received[0], 0);
+    assert_ne!(received.last(), Some(&99));
+}
+
+#[tokio::test]
+async fn lag_skip_to_newest() {
+    crate::init();
+
+    let (received, lag) = overrun(LagPolicy::SkipToNewest).await;
+    assert!(lag > 0);
+    assert_eq!(received.len() as u64 + lag, 100);
+    assert_eq!(received[0], 0);
+    assert_eq!(received.last(), Some(&99));
+}
+
+static CLONES: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CountedClone(i16);
+
+impl Clone for CountedClone {
+    fn clone(&self) -> Self {
+        CLONES.fetch_add(1, Ordering::SeqCst);
+        Self(self.0)
+    }
+}
+
+#[tokio::test]
+async fn lag_keep_oldest_no_clone() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) =
+        loop_channel::<broadcast::Receiver<CountedClone, codec::Default, 1>>().await;
+
+    let (tx, rx) = broadcast::channel::<_, _, 1>(1);
+    assert_eq!(tx.lag_policy(), LagPolicy::KeepOldest);
+
+    println!("Sending remote broadcast channel receiver");
+    a_tx.send(rx).await.unwrap();
+    println!("Receiving remote broadcast channel receiver");
+    let mut rx = b_rx.recv().await.unwrap().unwrap();
+
+    println!("Overrunning receive buffer");
+    for i in 0..100 {
+        tx.send(CountedClone(i)).unwrap();
+    }
+    drop(tx);
+
+    let mut received = 0;
+    loop {
+        match rx.recv().await {
+            Ok(_) => received += 1,
+            Err(err) if err.is_closed() => break,
+            Err(err) if err.is_lagged() => (),
+            Err(err) => panic!("receive error: {err}"),
+        }
+    }
+
+    let clones = CLONES.load(Ordering::SeqCst);
+    println!("Received {received} values with {clones} clones and lag {}", rx.lag());
+    assert!(rx.lag() > 0);
+    assert_eq!(clones, received);
+}
+
+#[tokio::test]
+async fn lag_handler() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<broadcast::Receiver<i16, codec::Default, 1>>().await;
+
+    let (tx, rx) = broadcast::channel::<_, _, 1>(1);
+
+    println!("Sending remote broadcast channel receiver");
+    a_tx.send(rx).await.unwrap();
+    println!("Receiving remote broadcast channel receiver");
+    let mut rx = b_rx.recv().await.unwrap().unwrap();
+
+    let dropped = Arc::new(AtomicU64::new(0));
+    let handler_dropped = dropped.clone();
+    rx.set_lag_handler(move |skipped| {
+        println!("Lag handler: {skipped} skipped");
+        handler_dropped.fetch_add(skipped, Ordering::SeqCst);
+    });
+
+    println!("Overrunning receive buffer");
+    for i in 0..100 {
+        tx.send(i).unwrap();
+    }
+    drop(tx);
+
+    let mut received = Vec::new();
+    loop {
+        match rx.recv().await {
+            Ok(value) => received.push(value),
+            Err(err) if err.is_closed() => break,
+            Err(err) => panic!("receive error: {err}"),
+        }
+    }
+    let dropped = dropped.load(Ordering::SeqCst);
+    println!("Received {received:?} with {dropped} dropped");
+    assert!(dropped > 0);
+    assert_eq!(dropped, rx.lag());
+    assert_eq!(received.len() as u64 + dropped, 100);
+}
+
+#[tokio::test]
+async fn cancel_safety() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<broadcast::Receiver<i16>>().await;
+
+    let (tx, rx) = broadcast::channel::<_, _, { remoc::rch::DEFAULT_BUFFER }>(16);
+
+    println!("Sending remote broadcast channel receiver");
+    a_tx.send(rx).await.unwrap();
+    println!("Receiving remote broadcast channel receiver");
+    let mut rx = b_rx.recv().await.unwrap().unwrap();
+
+    let send_task = tokio::spawn(async move {
+        for i in 0..100 {
+            tx.send(i).unwrap();
+            tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+        }
+    });
+
+    let mut received = Vec::new();
+    let mut cancelled = 0;
+    loop {
+        tokio::select! {
+            res = rx.recv() => match res {
+                Ok(value) => received.push(value),
+                Err(err) if err.is_closed() => break,
+                Err(err) => panic!("receive error: {err}"),
+            },
+            () = tokio::task::yield_now() => cancelled += 1,
+        }
+    }
+    println!("Received {} values with {cancelled} cancelled receives", received.len());
+    assert!(cancelled > 0);
+    assert_eq!(received, (0..100).collect::<Vec<_>>());
+
+    send_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn blocking_recv() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<broadcast::Receiver<i16>>().await;
+
+    println!("Sending remote broadcast channel receiver");
+    let (tx, rx) = broadcast::channel::<_, _, { remoc::rch::DEFAULT_BUFFER }>(16);
+    a_tx.send(rx).await.unwrap();
+    let mut rx = b_rx.recv().await.unwrap().unwrap();
+
+    let recv_task = tokio::task::spawn_blocking(move || {
+        let mut values = Vec::new();
+        while let Ok(value) = rx.blocking_recv() {
+            values.push(value);
+        }
+        values
+    });
+
+    for i in 0..10 {
+        println!("Sending {i}");
+        tx.send(i).unwrap();
+    }
+    drop(tx);
+
+    assert_eq!(recv_task.await.unwrap(), (0..10).collect::<Vec<_>>());
+}
+
+/// Broadcast transport message as sent by older versions.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+enum OldBroadcastMsg<T> {
+    Value(T),
+    Lagged,
+}
+
+/// Broadcast receiver as transported by older versions.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct OldReceiver {
+    rx: mpsc::Receiver<OldBroadcastMsg<u32>>,
+}
+
+#[tokio::test]
+async fn lag_from_old_sender() {
+    crate::init();
+    loop_transport!(0, a_tx, a_rx, b_tx, b_rx);
+
+    let (a, b) = tokio::join!(
+        remoc::Connect::framed::<_, _, OldReceiver, (), codec::Default>(Default::default(), a_tx, a_rx),
+        remoc::Connect::framed::<_, _, (), broadcast::Receiver<u32>, codec::Default>(
+            Default::default(),
+            b_tx,
+            b_rx
+        ),
+    );
+    let (a_conn, mut a_tx, _a_rx) = a.unwrap();
+    let (b_conn, _b_tx, mut b_rx) = b.unwrap();
+    tokio::spawn(a_conn);
+    tokio::spawn(b_conn);
+
+    println!("Sending receiver in old format");
+    let (tx, rx) = mpsc::channel(4);
+    a_tx.send(OldReceiver { rx }).await.unwrap();
+    let mut rx = b_rx.recv().await.unwrap().unwrap();
+
+    println!("Sending value and unit lag notification");
+    tx.send(OldBroadcastMsg::Value(1)).await.unwrap();
+    tx.send(OldBroadcastMsg::Lagged).await.unwrap();
+    tx.send(OldBroadcastMsg::Value(2)).await.unwrap();
+
+    assert_eq!(rx.recv().await.unwrap(), 1);
+    assert!(rx.recv().await.unwrap_err().is_lagged());
+    assert_eq!(rx.lag(), 1);
+    assert_eq!(rx.recv().await.unwrap(), 2);
+}
@@ -1,10 +1,19 @@
-use futures::{future, StreamExt};
+use futures::{future, stream, StreamExt};
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 use std::time::Duration;
 use tokio::time::sleep;
 
 use crate::{droppable_loop_channel, loop_channel};
-use remoc::rch::{base::SendErrorKind, mpsc, mpsc::SendError, ClosedReason, SendResultExt};
+use remoc::{
+    codec,
+    rch::{
+        base::{RecvError, SendErrorKind},
+        mpsc,
+        mpsc::SendError,
+        ClosedReason, SendResultExt,
+    },
+};
 
 #[tokio::test]
 async fn simple() {
@@ -208,6 +217,48 @@ async fn simple_conn_failure() {
     }
 }
 
+/// Value that fails to serialize when it is negative.
+#[derive(Debug, Deserialize)]
+struct Checked(i16);
+
+impl Serialize for Checked {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.0 < 0 {
+            return Err(serde::ser::Error::custom("negative value"));
+        }
+        serializer.serialize_i16(self.0)
+    }
+}
+
+#[tokio::test]
+async fn serialize_error() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<mpsc::Receiver<Checked>>().await;
+
+    println!("Sending remote mpsc channel receiver");
+    let (tx, rx) = mpsc::channel(16);
+    a_tx.send(rx).await.unwrap();
+    println!("Receiving remote mpsc channel receiver");
+    let mut rx = b_rx.recv().await.unwrap().unwrap();
+
+    tx.send(Checked(1)).await.unwrap();
+    assert_eq!(rx.recv().await.unwrap().unwrap().0, 1);
+
+    println!("Sending unserializable value");
+    tx.send(Checked(-1)).await.unwrap();
+    let err = loop {
+        match tx.send(Checked(2)).await {
+            Ok(()) => sleep(Duration::from_millis(10)).await,
+            Err(err) => break err,
+        }
+    };
+    println!("Send error: {err}");
+    assert!(matches!(err, SendError::RemoteSend(SendErrorKind::Serialize(_))));
+    assert!(err.is_item_specific());
+    assert!(!err.is_disconnected());
+    assert_eq!(err.closed_reason(), None);
+}
+
 #[tokio::test]
 async fn two_sender_conn_failure() {
     crate::init();
@@ -393,6 +444,47 @@ async fn forward() {
     }
 }
 
+#[tokio::test]
+async fn forward_max_hops() {
+    crate::init();
+    let ((mut a0_tx, _), (_, mut b0_rx)) = loop_channel::<mpsc::Sender<i16>>().await;
+    let ((mut a1_tx, _), (_, mut b1_rx)) = loop_channel::<mpsc::Receiver<i16>>().await;
+
+    let (mut tx, mut rx) = mpsc::channel(16);
+    assert_eq!(tx.hops(), 0);
+    assert_eq!(tx.max_hops(), None);
+    tx.set_max_hops(Some(2));
+    rx.set_max_hops(Some(1));
+
+    for hop in 1..=2 {
+        println!("Forwarding sender, hop {hop}");
+        a0_tx.send(tx).await.unwrap();
+        tx = b0_rx.recv().await.unwrap().unwrap();
+        assert_eq!(tx.hops(), hop);
+        assert_eq!(tx.max_hops(), Some(2));
+    }
+
+    println!("Forwarding sender exceeding maximum hops");
+    let err = a0_tx.send(tx).await.unwrap_err();
+    assert!(matches!(err.kind, SendErrorKind::MaxHopsExceeded), "forwarding loop must be detected");
+    println!("Error: {err}");
+    let tx = err.item;
+
+    println!("Forwarding receiver");
+    a1_tx.send(rx).await.unwrap();
+    let rx = b1_rx.recv().await.unwrap().unwrap();
+    assert_eq!(rx.hops(), 1);
+
+    println!("Forwarding receiver exceeding maximum hops");
+    let err = a1_tx.send(rx).await.unwrap_err();
+    assert!(matches!(err.kind, SendErrorKind::MaxHopsExceeded), "forwarding loop must be detected");
+    let mut rx = err.item;
+
+    println!("Verifying that channel is usable");
+    tx.send(123).await.unwrap();
+    assert_eq!(rx.recv().await.unwrap(), Some(123));
+}
+
 #[tokio::test]
 async fn max_item_size_exceeded() {
     crate::init();
@@ -443,3 +535,284 @@ async fn max_item_size_exceeded() {
     assert_eq!(tx.closed_reason(), Some(ClosedReason::Failed));
     println!("Close reason: {:?}", tx.closed_reason());
 }
+
+#[tokio::test]
+async fn max_item_size_after_forward() {
+    crate::init();
+    let ((mut a0_tx, _), (_, mut b0_rx)) = loop_channel::<mpsc::Sender<Vec<u8>>>().await;
+    let ((mut a1_tx, _), (_, mut b1_rx)) = loop_channel::<mpsc::Receiver<Vec<u8>>>().await;
+
+    let (mut tx, rx) = mpsc::channel(16);
+    tx.set_max_item_size(10_000);
+
+    println!("Forwarding sender");
+    a0_tx.send(tx).await.unwrap();
+    let mut tx = b0_rx.recv().await.unwrap().unwrap();
+    assert_eq!(tx.max_item_size(), 10_000, "limit must be preserved when forwarding");
+
+    println!("Forwarding receiver");
+    a1_tx.send(rx).await.unwrap();
+    let rx: mpsc::Receiver<Vec<u8>> = b1_rx.recv().await.unwrap().unwrap();
+    let mut rx = rx.set_max_item_size::<1_000>();
+
+    println!("Sending item within limits");
+    tx.send(vec![1; 100]).await.unwrap();
+    assert_eq!(rx.recv().await.unwrap(), Some(vec![1; 100]));
+
+    println!("Sending item exceeding stricter receiver limit");
+    tx.send(vec![2; 1_000]).await.unwrap();
+    let res = rx.recv().await;
+    println!("Receive result: {res:?}");
+    assert!(matches!(res, Err(mpsc::RecvError::RemoteReceive(RecvError::MaxItemSizeExceeded))));
+
+    println!("Lowering sender limit after forwarding");
+    tx.set_max_item_size(100);
+    tx.send(vec![3; 100]).await.unwrap();
+    sleep(Duration::from_millis(100)).await;
+    let res = tx.send(vec![4; 1]).await;
+    println!("Send result: {res:?}");
+    assert!(matches!(res, Err(SendError::RemoteSend(SendErrorKind::MaxItemSizeExceeded))));
+}
+
+#[tokio::test]
+async fn recv_many() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<mpsc::Receiver<i16, codec::Default, 16>>().await;
+
+    println!("Sending remote mpsc channel receiver");
+    let (tx, rx) = mpsc::channel(16);
+    let rx = rx.set_buffer::<16>();
+    a_tx.send(rx).await.unwrap();
+    println!("Receiving remote mpsc channel receiver");
+    let mut rx = b_rx.recv().await.unwrap().unwrap();
+
+    for i in 0..10 {
+        tx.send(i).await.unwrap();
+    }
+    sleep(Duration::from_millis(100)).await;
+
+    let mut buf = Vec::new();
+    assert_eq!(rx.recv_many(&mut buf, 0).await.unwrap(), 0);
+    assert_eq!(rx.recv_many(&mut buf, 4).await.unwrap(), 4);
+    assert_eq!(buf, [0, 1, 2, 3]);
+    assert_eq!(rx.recv_many(&mut buf, 100).await.unwrap(), 6);
+    assert_eq!(buf, (0..10).collect::<Vec<_>>());
+
+    println!("Waiting for value");
+    let send_task = tokio::spawn(async move {
+        sleep(Duration::from_millis(100)).await;
+        tx.send(10).await.unwrap();
+    });
+    buf.clear();
+    assert_eq!(rx.recv_many(&mut buf, 100).await.unwrap(), 1);
+    assert_eq!(buf, [10]);
+    send_task.await.unwrap();
+
+    println!("Receiving after senders dropped");
+    assert_eq!(rx.recv_many(&mut buf, 100).await.unwrap(), 0);
+    assert_eq!(buf, [10]);
+}
+
+#[tokio::test]
+async fn capacity_and_len() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<mpsc::Sender<i16, codec::Default, 4>>().await;
+
+    println!("Sending remote mpsc channel sender");
+    let (tx, mut rx) = mpsc::channel(16);
+    let tx = tx.set_buffer::<4>();
+    a_tx.send(tx).await.unwrap();
+    println!("Receiving remote mpsc channel sender");
+    let tx = b_rx.recv().await.unwrap().unwrap();
+
+    assert_eq!(tx.local_max_capacity(), 4);
+    assert_eq!(tx.local_capacity(), 4);
+    assert_eq!(rx.local_len(), 0);
+    assert!(rx.is_local_empty());
+
+    println!("Sending values");
+    for i in 0..8 {
+        tx.send(i).await.unwrap();
+    }
+    sleep(Duration::from_millis(100)).await;
+    assert_eq!(rx.local_len(), 8);
+    assert!(!rx.is_local_empty());
+    assert_eq!(tx.local_capacity(), 4);
+
+    for i in 0..8 {
+        assert_eq!(rx.recv().await.unwrap(), Some(i));
+    }
+    assert_eq!(rx.local_len(), 0);
+    assert!(rx.is_local_empty());
+}
+
+#[tokio::test]
+async fn reserve() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<mpsc::Sender<i16, codec::Default, 4>>().await;
+
+    println!("Sending remote mpsc channel sender");
+    let (tx, mut rx) = mpsc::channel(16);
+    let tx = tx.set_buffer::<4>();
+    a_tx.send(tx).await.unwrap();
+    println!("Receiving remote mpsc channel sender");
+    let tx = b_rx.recv().await.unwrap().unwrap();
+
+    println!("Reserving capacity");
+    let permit = tx.reserve().await.unwrap();
+    assert_eq!(tx.local_capacity(), 3);
+    permit.send(1);
+    assert_eq!(rx.recv().await.unwrap(), Some(1));
+
+    println!("Dropping unused permits");
+    let permits = future::join_all((0..4).map(|_| tx.reserve())).await;
+    assert_eq!(tx.local_capacity(), 0);
+    assert!(matches!(tx.try_send(2), Err(mpsc::TrySendError::Full(2))));
+    drop(permits);
+    assert_eq!(tx.local_capacity(), 4);
+
+    tx.send(3).await.unwrap();
+    assert_eq!(rx.recv().await.unwrap(), Some(3));
+}
+
+#[tokio::test]
+async fn reserve_conn_failure() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx), conn) = droppable_loop_channel::<mpsc::Receiver<i16>>().await;
+
+    println!("Sending remote mpsc channel receiver");
+    let (tx, rx) = mpsc::channel(16);
+    a_tx.send(rx).await.unwrap();
+    println!("Receiving remote mpsc channel receiver");
+    let mut rx = b_rx.recv().await.unwrap().unwrap();
+
+    tx.send(1).await.unwrap();
+    assert_eq!(rx.recv().await.unwrap(), Some(1));
+
+    println!("Reserving capacity and dropping connection");
+    let permit = tx.reserve().await.unwrap();
+    drop(conn);
+    tx.closed().await;
+    assert_eq!(tx.closed_reason(), Some(ClosedReason::Failed));
+
+    println!("Sending using permit after connection failure");
+    permit.send(2);
+    assert!(tx.reserve().await.unwrap_err().is_disconnected());
+    assert!(tx.send(3).await.unwrap_err().is_disconnected());
+}
+
+#[tokio::test]
+async fn sink() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<mpsc::Sender<i16>>().await;
+
+    println!("Sending remote mpsc channel sender");
+    let (tx, mut rx) = mpsc::channel(1);
+    a_tx.send(tx).await.unwrap();
+    println!("Receiving remote mpsc channel sender");
+    let tx = b_rx.recv().await.unwrap().unwrap();
+
+    println!("Forwarding stream into sink");
+    let forward_task =
+        tokio::spawn(async move { stream::iter(0..100).map(Ok).forward(tx.into_sink()).await.unwrap() });
+
+    for i in 0..100 {
+        assert_eq!(rx.recv().await.unwrap(), Some(i));
+    }
+    assert_eq!(rx.recv().await.unwrap(), None);
+    forward_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn sink_closed() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<mpsc::Sender<i16>>().await;
+
+    let (tx, rx) = mpsc::channel(1);
+    a_tx.send(tx).await.unwrap();
+    let tx = b_rx.recv().await.unwrap().unwrap();
+
+    println!("Dropping receiver");
+    drop(rx);
+
+    let mut sink = tx.into_sink();
+    let res = stream::iter(0..).map(Ok).forward(&mut sink).await;
+    assert!(res.unwrap_err().is_closed());
+}
+
+#[tokio::test]
+async fn cancel_safety() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<mpsc::Receiver<i16>>().await;
+
+    println!("Sending remote mpsc channel receiver");
+    let (tx, rx) = mpsc::channel(16);
+    a_tx.send(rx).await.unwrap();
+    println!("Receiving remote mpsc channel receiver");
+    let mut rx = b_rx.recv().await.unwrap().unwrap();
+
+    let send_task = tokio::spawn(async move {
+        for i in 0..100 {
+            tx.send(i).await.unwrap();
+            sleep(Duration::from_millis(1)).await;
+        }
+    });
+
+    let mut received = Vec::new();
+    let mut cancelled = 0;
+    loop {
+        tokio::select! {
+            res = rx.recv() => match res.unwrap() {
+                Some(value) => received.push(value),
+                None => break,
+            },
+            () = tokio::task::yield_now() => cancelled += 1,
+        }
+    }
+    println!("Received {} values with {cancelled} cancelled receives", received.len());
+    assert!(cancelled > 0);
+    assert_eq!(received, (0..100).collect::<Vec<_>>());
+
+    send_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn weak_sender() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<mpsc::Receiver<i16>>().await;
+
+    println!("Sending remote mpsc channel receiver");
+    let (tx, rx) = mpsc::channel(16);
+    a_tx.send(rx).await.unwrap();
+    let mut rx = b_rx.recv().await.unwrap().unwrap();
+
+    let weak_tx = tx.downgrade();
+    println!("Sending over upgraded weak sender");
+    weak_tx.upgrade().unwrap().send(1).await.unwrap();
+    assert_eq!(rx.recv().await.unwrap(), Some(1));
+
+    println!("Dropping strong sender");
+    drop(tx);
+    assert_eq!(tokio::time::timeout(Duration::from_secs(10), rx.recv()).await.unwrap().unwrap(), None);
+    assert!(weak_tx.upgrade().is_none());
+}
+
+#[tokio::test]
+async fn weak_sender_remote() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<mpsc::Sender<i16>>().await;
+
+    println!("Sending remote mpsc channel sender");
+    let (tx, mut rx) = mpsc::channel(16);
+    a_tx.send(tx).await.unwrap();
+    let tx = b_rx.recv().await.unwrap().unwrap();
+
+    let weak_tx = tx.downgrade();
+    tx.send(1).await.unwrap();
+    assert_eq!(rx.recv().await.unwrap(), Some(1));
+
+    println!("Dropping remote strong sender");
+    drop(tx);
+    assert_eq!(tokio::time::timeout(Duration::from_secs(10), rx.recv()).await.unwrap().unwrap(), None);
+    assert!(weak_tx.upgrade().is_none());
+}
@@ -1,4 +1,6 @@
-use remoc::rch::oneshot;
+use remoc::rch::{base, oneshot, ClosedReason};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 use crate::loop_channel;
 
@@ -48,3 +50,136 @@ async fn close() {
         Err(err) => panic!("wrong error after close: {err}"),
     }
 }
+
+#[tokio::test]
+async fn drop_notify() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<oneshot::Sender<i16>>().await;
+
+    println!("Sending remote oneshot channel sender");
+    let (tx, rx) = oneshot::channel();
+    a_tx.send(tx).await.unwrap();
+    println!("Receiving remote oneshot channel sender");
+    let tx = b_rx.recv().await.unwrap().unwrap();
+
+    assert!(!tx.is_closed());
+    assert!(tx.closed_reason().is_none());
+
+    println!("Dropping receiver");
+    drop(rx);
+
+    println!("Waiting for close notification");
+    tx.closed().await;
+    assert!(tx.is_closed());
+    assert_eq!(tx.closed_reason(), Some(ClosedReason::Dropped));
+
+    match tx.send(0) {
+        Ok(()) => panic!("send after drop succeeded"),
+        Err(err) if err.is_closed() => (),
+        Err(err) => panic!("wrong error after drop: {err}"),
+    }
+}
+
+#[tokio::test]
+async fn try_recv() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<oneshot::Sender<i16>>().await;
+
+    println!("Sending remote oneshot channel sender");
+    let (tx, mut rx) = oneshot::channel();
+    a_tx.send(tx).await.unwrap();
+    println!("Receiving remote oneshot channel sender");
+    let tx = b_rx.recv().await.unwrap().unwrap();
+
+    assert!(matches!(rx.try_recv(), Err(oneshot::TryRecvError::Empty)));
+
+    let i = 512;
+    println!("Sending {i}");
+    tx.send(i).unwrap();
+
+    let r = loop {
+        match rx.try_recv() {
+            Ok(r) => break r,
+            Err(oneshot::TryRecvError::Empty) => tokio::time::sleep(Duration::from_millis(10)).await,
+            Err(err) => panic!("try_recv failed: {err}"),
+        }
+    };
+    println!("Received {r}");
+    assert_eq!(i, r, "send/receive mismatch");
+}
+
+#[tokio::test]
+async fn try_recv_closed() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<oneshot::Sender<i16>>().await;
+
+    println!("Sending remote oneshot channel sender");
+    let (tx, mut rx) = oneshot::channel();
+    a_tx.send(tx).await.unwrap();
+    println!("Receiving remote oneshot channel sender");
+    let tx = b_rx.recv().await.unwrap().unwrap();
+
+    println!("Dropping sender");
+    drop(tx);
+
+    loop {
+        match rx.try_recv() {
+            Ok(r) => panic!("received {r} from dropped sender"),
+            Err(oneshot::TryRecvError::Empty) => tokio::time::sleep(Duration::from_millis(10)).await,
+            Err(oneshot::TryRecvError::Closed) => break,
+            Err(err) => panic!("wrong error after sender drop: {err}"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Undeserializable;
+
+impl<'de> Deserialize<'de> for Undeserializable {
+    fn deserialize<D: serde::Deserializer<'de>>(_deserializer: D) -> Result<Self, D::Error> {
+        Err(serde::de::Error::custom("cannot deserialize"))
+    }
+}
+
+#[tokio::test]
+async fn try_recv_deserialize_error() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<oneshot::Sender<Undeserializable>>().await;
+
+    println!("Sending remote oneshot channel sender");
+    let (tx, mut rx) = oneshot::channel();
+    a_tx.send(tx).await.unwrap();
+    println!("Receiving remote oneshot channel sender");
+    let tx = b_rx.recv().await.unwrap().unwrap();
+
+    println!("Sending undeserializable value");
+    tx.send(Undeserializable).unwrap();
+
+    loop {
+        match rx.try_recv() {
+            Ok(_) => panic!("received undeserializable value"),
+            Err(oneshot::TryRecvError::Empty) => tokio::time::sleep(Duration::from_millis(10)).await,
+            Err(oneshot::TryRecvError::RemoteReceive(base::RecvError::Deserialize(err))) => {
+                println!("Deserialization error: {err}");
+                break;
+            }
+            Err(err) => panic!("wrong error: {err}"),
+        }
+    }
+}
+
+#[tokio::test]
+async fn blocking_recv() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<oneshot::Receiver<i16>>().await;
+
+    println!("Sending remote oneshot channel receiver");
+    let (tx, rx) = oneshot::channel();
+    a_tx.send(rx).await.unwrap();
+    let rx = b_rx.recv().await.unwrap().unwrap();
+
+    let recv_task = tokio::task::spawn_blocking(move || rx.blocking_recv());
+    println!("Sending value");
+    tx.send(123).unwrap();
+    assert_eq!(recv_task.await.unwrap().unwrap(), 123);
+}
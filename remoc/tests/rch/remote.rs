@@ -1,11 +1,24 @@
+use bytes::Bytes;
+use futures::StreamExt;
 use rand::{Rng, RngCore};
-use std::time::Duration;
-use tokio::time::timeout;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    io::{Read, Write},
+    time::Duration,
+};
+use tokio::time::{timeout, Instant};
 
-use crate::{loop_channel, tcp_loop_channel};
-use remoc::rch::{
-    base::{RecvError, SendError, SendErrorKind},
-    DEFAULT_MAX_ITEM_SIZE,
+use crate::{droppable_loop_channel, loop_channel, loop_channel_with_cfg, loop_transport, tcp_loop_channel};
+use remoc::{
+    codec::{self, Codec, DeserializationError, SerializationError},
+    rch::{
+        base::{
+            self, HeaderTooLongError, Headers, RecvError, SendAckedError, SendError, SendErrorKind,
+            SendTimeoutError, TrySendError,
+        },
+        DEFAULT_MAX_ITEM_SIZE,
+    },
+    ConnectError,
 };
 
 #[tokio::test]
@@ -130,6 +143,65 @@ async fn close_notify() {
     }
 }
 
+#[tokio::test]
+async fn drop_notify() {
+    crate::init();
+    let ((mut a_tx, _), (_, b_rx)) = loop_channel::<u8>().await;
+
+    println!("Checking that channel is not closed");
+    assert!(!a_tx.is_closed());
+
+    println!("Dropping receiver");
+    drop(b_rx);
+
+    println!("Waiting for close notification");
+    a_tx.closed().await;
+    assert!(a_tx.is_closed());
+
+    println!("Testing if send fails");
+    if a_tx.send(1).await.is_ok() {
+        panic!("send succeeded after receiver was dropped");
+    }
+}
+
+#[tokio::test]
+async fn conn_failure_notify() {
+    crate::init();
+    let ((a_tx, _), (_, _b_rx), conn_drop) = droppable_loop_channel::<u8>().await;
+
+    let close_notify = a_tx.closed();
+    if timeout(Duration::from_secs(1), close_notify).await.is_ok() {
+        panic!("close notification before connection failure");
+    }
+
+    println!("Dropping connection");
+    drop(conn_drop);
+
+    println!("Waiting for close notification");
+    a_tx.closed().await;
+}
+
+#[tokio::test]
+async fn send_timeout() {
+    crate::init();
+    let ((mut a_tx, _a_rx), (_b_tx, mut b_rx)) = loop_channel::<Vec<u8>>().await;
+
+    println!("Sending big message without receiver");
+    let data = vec![1u8; 4_000_000];
+    let res = a_tx.send_timeout(data.clone(), Duration::from_millis(100)).await;
+    match res {
+        Err(SendTimeoutError::Timeout(item)) => assert_eq!(item, data),
+        other => panic!("unexpected result: {other:?}"),
+    }
+
+    let recv_task = tokio::spawn(async move { b_rx.recv().await.unwrap().unwrap() });
+
+    println!("Sending small message");
+    let small = vec![2u8; 100];
+    a_tx.send_timeout(small.clone(), Duration::from_secs(10)).await.unwrap();
+    assert_eq!(recv_task.await.unwrap(), small, "partially sent message must be discarded");
+}
+
 #[tokio::test]
 async fn oversized_msg_send_error() {
     crate::init();
@@ -163,3 +235,546 @@ async fn oversized_msg_recv_error() {
     let res = b_rx.recv().await;
     assert!(matches!(res, Err(RecvError::MaxItemSizeExceeded)), "receiving oversized item must fail")
 }
+
+#[tokio::test]
+async fn raw() {
+    crate::init();
+    let ((a_tx, _a_rx), (_b_tx, b_rx)) = loop_channel::<Bytes>().await;
+    let (mut a_tx, mut b_rx) = (a_tx.into_raw(), b_rx.into_raw());
+
+    let small = Bytes::from(vec![1u8; 100]);
+    println!("Sending {} bytes", small.len());
+    a_tx.send(small.clone()).await.unwrap();
+    assert_eq!(b_rx.recv().await.unwrap(), Some(small));
+
+    let mut large = vec![0u8; 1_000_000];
+    rand::thread_rng().fill_bytes(&mut large);
+    let large = Bytes::from(large);
+    println!("Sending {} bytes", large.len());
+    let recv_task = tokio::spawn(async move {
+        let data = b_rx.recv().await.unwrap();
+        (b_rx, data)
+    });
+    a_tx.send(large.clone()).await.unwrap();
+    let (mut b_rx, data) = recv_task.await.unwrap();
+    assert_eq!(data, Some(large));
+
+    println!("Sending oversized data");
+    a_tx.set_max_item_size(10);
+    let res = a_tx.send(Bytes::from(vec![2u8; 100])).await;
+    assert!(matches!(res, Err(SendError { kind: SendErrorKind::MaxItemSizeExceeded, .. })));
+    a_tx.set_max_item_size(DEFAULT_MAX_ITEM_SIZE);
+
+    println!("Receiving oversized data");
+    b_rx.set_max_item_size(10);
+    a_tx.send(Bytes::from(vec![3u8; 100])).await.unwrap();
+    assert!(matches!(b_rx.recv().await, Err(RecvError::MaxItemSizeExceeded)));
+    b_rx.set_max_item_size(DEFAULT_MAX_ITEM_SIZE);
+
+    let data = Bytes::from(vec![4u8; 10]);
+    a_tx.send(data.clone()).await.unwrap();
+    assert_eq!(b_rx.recv().await.unwrap(), Some(data));
+
+    drop(a_tx);
+    assert_eq!(b_rx.recv().await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn max_inline_size() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<Vec<u8>>().await;
+
+    assert_eq!(a_tx.max_inline_size(), remoc::chmux::Cfg::default().max_data_size);
+    assert_eq!(b_rx.max_inline_size(), remoc::chmux::Cfg::default().max_data_size);
+    a_tx.set_max_inline_size(1_000);
+    b_rx.set_max_inline_size(1_000);
+    assert_eq!(a_tx.max_inline_size(), 1_000);
+
+    let recv_task = tokio::spawn(async move {
+        let mut items = Vec::new();
+        while let Some(item) = b_rx.recv().await.unwrap() {
+            items.push(item);
+        }
+        items
+    });
+
+    let mut items = Vec::new();
+    for size in [10, 100, 10_000, 100, 100_000, 1_000_000, 10, 10, 10] {
+        let mut item = vec![0u8; size];
+        rand::thread_rng().fill_bytes(&mut item);
+        println!("Sending item of size {size}");
+        a_tx.send(item.clone()).await.unwrap();
+        items.push(item);
+    }
+    drop(a_tx);
+
+    assert_eq!(recv_task.await.unwrap(), items);
+}
+
+#[tokio::test]
+async fn max_inline_size_offload() {
+    use serde::{Deserializer, Serializer};
+    use std::{
+        sync::Mutex,
+        thread::{self, ThreadId},
+    };
+
+    static TEST_THREAD: Mutex<Option<ThreadId>> = Mutex::new(None);
+    static CALLS: Mutex<Vec<(&str, usize, bool)>> = Mutex::new(Vec::new());
+
+    /// Records whether (de)serialization was offloaded from the test thread.
+    fn record(op: &'static str, len: usize) {
+        let offloaded = *TEST_THREAD.lock().unwrap() != Some(thread::current().id());
+        CALLS.lock().unwrap().push((op, len, offloaded));
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct Item(Vec<u8>);
+
+    impl Serialize for Item {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            record("serialize", self.0.len());
+            self.0.serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Item {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let data = Vec::deserialize(deserializer)?;
+            record("deserialize", data.len());
+            Ok(Self(data))
+        }
+    }
+
+    crate::init();
+    *TEST_THREAD.lock().unwrap() = Some(thread::current().id());
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<Item>().await;
+    a_tx.set_max_inline_size(1_000);
+    b_rx.set_max_inline_size(1_000);
+
+    for size in [10, 10_000] {
+        let item = Item(vec![1; size]);
+        println!("Sending item of size {size}");
+        a_tx.send(item.clone()).await.unwrap();
+        assert_eq!(b_rx.recv().await.unwrap(), Some(item));
+    }
+
+    let calls = CALLS.lock().unwrap().clone();
+    println!("Calls: {calls:?}");
+    let offloaded = |op, len| calls.iter().filter(|c| c.0 == op && c.1 == len).map(|c| c.2).collect::<Vec<_>>();
+    assert_eq!(offloaded("serialize", 10), [false]);
+    assert_eq!(offloaded("deserialize", 10), [false]);
+    assert!(offloaded("serialize", 10_000).ends_with(&[true]));
+    assert_eq!(offloaded("deserialize", 10_000), [true]);
+}
+
+#[tokio::test]
+async fn peek() {
+    crate::init();
+    let ((mut a_tx, _a_rx), (_b_tx, mut b_rx)) = loop_channel::<Vec<u8>>().await;
+
+    for i in 1..=3 {
+        println!("Sending {i}");
+        a_tx.send(vec![i; 100]).await.unwrap();
+    }
+
+    println!("Peeking");
+    assert_eq!(b_rx.peek().await.unwrap(), Some(&vec![1; 100]));
+    assert_eq!(b_rx.peek().await.unwrap(), Some(&vec![1; 100]));
+    assert_eq!(b_rx.recv().await.unwrap(), Some(vec![1; 100]));
+
+    println!("Peeking and closing");
+    assert_eq!(b_rx.peek().await.unwrap(), Some(&vec![2; 100]));
+    b_rx.set_max_item_size(10);
+    b_rx.close().await;
+    assert_eq!(b_rx.recv().await.unwrap(), Some(vec![2; 100]));
+
+    println!("Peeking oversized item");
+    assert!(matches!(b_rx.peek().await, Err(RecvError::MaxItemSizeExceeded)));
+    b_rx.set_max_item_size(DEFAULT_MAX_ITEM_SIZE);
+
+    drop(a_tx);
+    assert_eq!(b_rx.peek().await.unwrap(), None);
+    assert_eq!(b_rx.recv().await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn send_acked() {
+    crate::init();
+    let ((mut a_tx, _a_rx), (_b_tx, mut b_rx)) = loop_channel::<Vec<u8>>().await;
+
+    println!("Sending big item with acknowledgement");
+    a_tx.send(vec![1; 10]).await.unwrap();
+    let send_task = tokio::spawn(async move {
+        a_tx.send_acked(vec![2; 1_000_000]).await.unwrap();
+        a_tx
+    });
+
+    assert_eq!(b_rx.recv().await.unwrap(), Some(vec![1; 10]));
+    assert_eq!(b_rx.peek().await.unwrap().map(|v| v.len()), Some(1_000_000));
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(!send_task.is_finished());
+
+    println!("Receiving");
+    assert_eq!(b_rx.recv().await.unwrap(), Some(vec![2; 1_000_000]));
+    let mut a_tx = timeout(Duration::from_secs(10), send_task).await.unwrap().unwrap();
+    println!("Acknowledged");
+
+    println!("Sending item with acknowledgement to dropped receiver");
+    let send_task = tokio::spawn(async move { a_tx.send_acked(vec![3; 10]).await });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    drop(b_rx);
+    let res = timeout(Duration::from_secs(10), send_task).await.unwrap().unwrap();
+    println!("Result: {res:?}");
+    assert!(matches!(res, Err(SendAckedError::Unacknowledged(_))));
+}
+
+#[tokio::test]
+async fn headers() {
+    crate::init();
+    let ((mut a_tx, _a_rx), (_b_tx, mut b_rx)) = loop_channel::<Vec<u8>>().await;
+
+    let mut headers = Headers::new();
+    headers.insert("request-id", "42").unwrap();
+    headers.insert("trace", "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01").unwrap();
+    assert_eq!(headers.get("request-id"), Some("42"));
+    assert_eq!(headers.insert("big", "x".repeat(Headers::MAX_LEN + 1)), Err(HeaderTooLongError));
+    assert_eq!(headers.len(), 2);
+
+    let many: Headers =
+        (0..1000).map(|i| (format!("key-{i}"), "x".repeat(300))).collect::<Vec<_>>().try_into().unwrap();
+
+    let send_headers = headers.clone();
+    let many_headers = many.clone();
+    let send_task = tokio::spawn(async move {
+        println!("Sending items with headers");
+        a_tx.send_with_headers(vec![1; 10], &send_headers).await.unwrap();
+        a_tx.send(vec![2; 10]).await.unwrap();
+        a_tx.send_with_headers(vec![3; 1_000_000], &send_headers).await.unwrap();
+        a_tx.send_with_headers(vec![4; 10], &send_headers).await.unwrap();
+        a_tx.send_with_headers(vec![5; 10], &many_headers).await.unwrap();
+    });
+
+    println!("Receiving items with headers");
+    assert_eq!(b_rx.recv_with_headers().await.unwrap(), Some((vec![1; 10], headers.clone())));
+    assert_eq!(b_rx.recv_with_headers().await.unwrap(), Some((vec![2; 10], Headers::new())));
+    assert_eq!(b_rx.peek().await.unwrap().map(|v| v.len()), Some(1_000_000));
+    let (item, rxed_headers) = b_rx.recv_with_headers().await.unwrap().unwrap();
+    assert_eq!(item, vec![3; 1_000_000]);
+    assert_eq!(rxed_headers, headers);
+    assert_eq!(b_rx.recv().await.unwrap(), Some(vec![4; 10]));
+    assert_eq!(b_rx.recv_with_headers().await.unwrap(), Some((vec![5; 10], many)));
+    send_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn max_item_size() {
+    crate::init();
+    let ((mut a_tx, _a_rx), (_b_tx, mut b_rx)) = loop_channel::<Vec<u8>>().await;
+
+    assert_eq!(a_tx.max_item_size(), DEFAULT_MAX_ITEM_SIZE);
+    assert_eq!(b_rx.max_item_size(), DEFAULT_MAX_ITEM_SIZE);
+
+    a_tx.set_max_item_size(1234);
+    b_rx.set_max_item_size(5678);
+    assert_eq!(a_tx.max_item_size(), 1234);
+    assert_eq!(b_rx.max_item_size(), 5678);
+}
+
+/// Default codec with a different identifier.
+#[derive(Clone, Serialize, Deserialize)]
+struct OtherCodec;
+
+impl Codec for OtherCodec {
+    const ID: Option<&'static str> = Some("other");
+
+    fn serialize<Writer, Item>(writer: Writer, item: &Item) -> Result<(), SerializationError>
+    where
+        Writer: Write,
+        Item: Serialize,
+    {
+        <codec::Default as Codec>::serialize(writer, item)
+    }
+
+    fn deserialize<Reader, Item>(reader: Reader) -> Result<Item, DeserializationError>
+    where
+        Reader: Read,
+        Item: DeserializeOwned,
+    {
+        <codec::Default as Codec>::deserialize(reader)
+    }
+}
+
+/// Default codec without identifier.
+#[derive(Clone, Serialize, Deserialize)]
+struct UnidentifiedCodec;
+
+impl Codec for UnidentifiedCodec {
+    fn serialize<Writer, Item>(writer: Writer, item: &Item) -> Result<(), SerializationError>
+    where
+        Writer: Write,
+        Item: Serialize,
+    {
+        <codec::Default as Codec>::serialize(writer, item)
+    }
+
+    fn deserialize<Reader, Item>(reader: Reader) -> Result<Item, DeserializationError>
+    where
+        Reader: Read,
+        Item: DeserializeOwned,
+    {
+        <codec::Default as Codec>::deserialize(reader)
+    }
+}
+
+#[tokio::test]
+async fn codec_mismatch() {
+    crate::init();
+    loop_transport!(0, a_tx, a_rx, b_tx, b_rx);
+
+    let (a, b) = tokio::join!(
+        remoc::Connect::framed::<_, _, u8, u8, codec::Default>(Default::default(), a_tx, a_rx),
+        remoc::Connect::framed::<_, _, u8, u8, OtherCodec>(Default::default(), b_tx, b_rx),
+    );
+
+    // The endpoint detecting the mismatch first closes the connection,
+    // thus the other endpoint may fail with a multiplexer error.
+    let mut mismatches = 0;
+    for res in [a.map(|_| ()), b.map(|_| ())] {
+        match res {
+            Err(ConnectError::RemoteConnect(err @ base::ConnectError::CodecMismatch { .. })) => {
+                println!("Connect failed: {err}");
+                mismatches += 1;
+            }
+            Err(ConnectError::ChMux(err)) => println!("Connect failed: {err}"),
+            Err(err) => panic!("unexpected error: {err}"),
+            Ok(()) => panic!("connect with mismatching codecs succeeded"),
+        }
+    }
+    assert!(mismatches > 0);
+}
+
+#[tokio::test]
+async fn codec_unidentified() {
+    crate::init();
+    loop_transport!(0, a_tx, a_rx, b_tx, b_rx);
+
+    let (a, b) = tokio::join!(
+        remoc::Connect::framed::<_, _, u8, u8, codec::Default>(Default::default(), a_tx, a_rx),
+        remoc::Connect::framed::<_, _, u8, u8, UnidentifiedCodec>(Default::default(), b_tx, b_rx),
+    );
+    let (a_conn, mut a_tx, _a_rx) = a.unwrap();
+    let (b_conn, _b_tx, mut b_rx) = b.unwrap();
+    tokio::spawn(a_conn);
+    tokio::spawn(b_conn);
+
+    a_tx.send(123).await.unwrap();
+    assert_eq!(b_rx.recv().await.unwrap(), Some(123));
+}
+
+#[tokio::test]
+async fn builder() {
+    crate::init();
+    loop_transport!(0, a_tx, a_rx, b_tx, b_rx);
+
+    let builder = remoc::Connect::builder().receive_buffer(4096).chunk_size(1024).max_item_size(10_000);
+    assert_eq!(builder.chmux_cfg().receive_buffer, 4096);
+    assert_eq!(builder.chmux_cfg().chunk_size, 1024);
+
+    let (a, b) = tokio::join!(builder.clone().framed(a_tx, a_rx), builder.framed(b_tx, b_rx));
+    let (a_conn, mut a_tx, _a_rx): (_, base::Sender<Vec<u8>>, base::Receiver<Vec<u8>>) = a.unwrap();
+    let (b_conn, _b_tx, mut b_rx): (_, base::Sender<Vec<u8>>, base::Receiver<Vec<u8>>) = b.unwrap();
+    assert_eq!(a_conn.negotiated().send_chunk_size, 1024);
+    assert_eq!(a_conn.negotiated().remote_port_receive_buffer, 4096);
+    tokio::spawn(a_conn);
+    tokio::spawn(b_conn);
+    assert_eq!(a_tx.max_item_size(), 10_000);
+    assert_eq!(b_rx.max_item_size(), 10_000);
+
+    println!("Sending item");
+    let item = vec![1; 1_000];
+    a_tx.send(item.clone()).await.unwrap();
+    assert_eq!(b_rx.recv().await.unwrap(), Some(item));
+
+    println!("Sending oversized item");
+    assert!(a_tx.send(vec![2; 20_000]).await.unwrap_err().is_item_specific());
+}
+
+#[tokio::test]
+async fn loopback() {
+    crate::init();
+
+    let ((a_conn, mut a_tx, mut a_rx), (b_conn, mut b_tx, mut b_rx)) =
+        remoc::Connect::loopback::<u32, String, codec::Default>(Default::default(), 1).await.unwrap();
+    tokio::spawn(a_conn);
+    tokio::spawn(b_conn);
+
+    println!("Sending in both directions");
+    a_tx.send(123).await.unwrap();
+    assert_eq!(b_rx.recv().await.unwrap(), Some(123));
+    b_tx.send("reply".to_string()).await.unwrap();
+    assert_eq!(a_rx.recv().await.unwrap(), Some("reply".to_string()));
+
+    println!("Using builder");
+    let ((a_conn, a_tx, _a_rx), (b_conn, _b_tx, b_rx)) =
+        remoc::Connect::builder().max_item_size(1_000).loopback::<Vec<u8>, (), codec::Default>(0).await.unwrap();
+    tokio::spawn(a_conn);
+    assert_eq!(a_tx.max_item_size(), 1_000);
+    assert_eq!(b_rx.max_item_size(), 1_000);
+
+    println!("Dropping connection");
+    drop(b_conn);
+    let mut b_rx = b_rx;
+    assert!(b_rx.recv().await.is_err());
+}
+
+#[tokio::test]
+async fn try_send() {
+    crate::init();
+    let cfg = remoc::chmux::Cfg { receive_buffer: 64, ..Default::default() };
+    let ((mut a_tx, _a_rx), (_b_tx, mut b_rx)) = loop_channel_with_cfg::<Vec<u8>>(cfg).await;
+
+    println!("Sending until full");
+    let mut sent = Vec::new();
+    for i in 0.. {
+        let item = vec![i; 16];
+        match a_tx.try_send(item.clone()) {
+            Ok(()) => sent.push(item),
+            Err(TrySendError::Full(returned)) => {
+                assert_eq!(returned, item);
+                break;
+            }
+            Err(err) => panic!("try_send failed: {err}"),
+        }
+    }
+    println!("Sent {} items", sent.len());
+    assert!(!sent.is_empty());
+
+    println!("Sending after full");
+    let item = vec![0xff; 16];
+    sent.push(item.clone());
+    let send_task = tokio::spawn(async move {
+        a_tx.send(item).await.unwrap();
+        a_tx
+    });
+
+    for item in sent {
+        assert_eq!(b_rx.recv().await.unwrap(), Some(item));
+    }
+    let mut a_tx = send_task.await.unwrap();
+
+    println!("Sending oversized item");
+    a_tx.set_max_item_size(10);
+    let err = a_tx.try_send(vec![1; 100]).unwrap_err();
+    assert!(matches!(err, TrySendError::Send(SendError { kind: SendErrorKind::MaxItemSizeExceeded, .. })));
+    assert_eq!(err.into_item(), vec![1; 100]);
+}
+
+#[tokio::test]
+async fn buffered() {
+    crate::init();
+    let ((a_tx, _a_rx), (_b_tx, b_rx)) = loop_channel::<Vec<u8>>().await;
+    let mut a_tx = a_tx.buffered(1024, Duration::from_secs(60));
+    let mut b_rx = b_rx.buffered();
+    assert_eq!(a_tx.capacity(), 1024);
+
+    println!("Sending items");
+    for i in 0..100 {
+        a_tx.send(vec![i; i as usize]).await.unwrap();
+    }
+    a_tx.flush().await.unwrap();
+
+    println!("Receiving items");
+    for i in 0..100 {
+        assert_eq!(b_rx.recv().await.unwrap(), Some(vec![i; i as usize]));
+    }
+
+    println!("Sending oversized item");
+    a_tx.set_max_item_size(50);
+    let err = a_tx.send(vec![1; 100]).await.unwrap_err();
+    assert!(matches!(err.kind, SendErrorKind::MaxItemSizeExceeded));
+    assert_eq!(err.item, vec![1; 100]);
+
+    println!("Dropping sender with pending items");
+    a_tx.send(vec![2; 5]).await.unwrap();
+    a_tx.send(vec![3; 5]).await.unwrap();
+    drop(a_tx);
+    assert_eq!(b_rx.recv().await.unwrap(), Some(vec![2; 5]));
+    assert_eq!(b_rx.recv().await.unwrap(), Some(vec![3; 5]));
+    assert_eq!(b_rx.recv().await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn buffered_flush_interval() {
+    crate::init();
+    let ((a_tx, _a_rx), (_b_tx, b_rx)) = loop_channel::<u32>().await;
+    let mut a_tx = a_tx.buffered(1024, Duration::from_millis(100));
+    let mut b_rx = b_rx.buffered();
+
+    println!("Sending item without flushing");
+    a_tx.send(123).await.unwrap();
+    let res = timeout(Duration::from_secs(10), b_rx.recv()).await.unwrap();
+    assert_eq!(res.unwrap(), Some(123));
+}
+
+#[tokio::test(start_paused = true)]
+async fn buffered_flush_interval_reset() {
+    const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+    crate::init();
+    let ((a_tx, _a_rx), (_b_tx, b_rx)) = loop_channel::<Vec<u8>>().await;
+    let mut a_tx = a_tx.buffered(64, FLUSH_INTERVAL);
+    let mut b_rx = b_rx.buffered();
+
+    println!("Sending first item");
+    a_tx.send(vec![1; 20]).await.unwrap();
+    tokio::time::sleep(FLUSH_INTERVAL * 9 / 10).await;
+
+    println!("Sending second item exceeding capacity");
+    let start = Instant::now();
+    a_tx.send(vec![2; 20]).await.unwrap();
+    assert_eq!(b_rx.recv().await.unwrap(), Some(vec![1; 20]));
+
+    println!("Waiting for second item");
+    assert_eq!(b_rx.recv().await.unwrap(), Some(vec![2; 20]));
+    let elapsed = start.elapsed();
+    println!("Second item received after {elapsed:?}");
+    assert!(elapsed >= FLUSH_INTERVAL);
+}
+
+#[tokio::test(start_paused = true)]
+async fn flush() {
+    const FLUSH_DELAY: Duration = Duration::from_secs(1);
+
+    crate::init();
+    let cfg = remoc::chmux::Cfg { flush_delay: FLUSH_DELAY, ..Default::default() };
+    let (a, b) = tokio::io::duplex(100_000);
+    let (a_rx, a_tx) = tokio::io::split(a);
+    let (b_rx, b_tx) = tokio::io::split(b);
+
+    let (a, b) = tokio::join!(
+        remoc::Connect::io_buffered::<_, _, u32, (), codec::Default>(cfg.clone(), a_rx, a_tx, 10_000),
+        remoc::Connect::io_buffered::<_, _, (), u32, codec::Default>(cfg, b_rx, b_tx, 10_000)
+    );
+    let (a_conn, mut a_tx, _a_rx) = a.unwrap_or_else(|err| panic!("connecting A failed: {err}"));
+    let (b_conn, _b_tx, mut b_rx) = b.unwrap_or_else(|err| panic!("connecting B failed: {err}"));
+    tokio::spawn(a_conn);
+    tokio::spawn(b_conn);
+
+    // The clock is paused and advances only while all tasks are idle.
+    // Thus buffered data arrives no earlier than the flush delay, unless it is flushed explicitly.
+    println!("Sending item without flushing");
+    let start = Instant::now();
+    a_tx.send(0).await.unwrap();
+    assert_eq!(b_rx.recv().await.unwrap(), Some(0));
+    assert!(start.elapsed() >= FLUSH_DELAY);
+
+    for i in 1..4 {
+        println!("Sending item {i} and flushing");
+        let start = Instant::now();
+        a_tx.send(i).await.unwrap();
+        a_tx.flush().await.unwrap();
+        assert_eq!(b_rx.recv().await.unwrap(), Some(i));
+        assert!(start.elapsed() < FLUSH_DELAY);
+    }
+}
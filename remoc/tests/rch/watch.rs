@@ -1,10 +1,10 @@
 use futures::StreamExt;
 use remoc::rch::base::SendErrorKind;
 use std::time::Duration;
-use tokio::time::sleep;
+use tokio::time::{sleep, timeout};
 
 use crate::{droppable_loop_channel, loop_channel};
-use remoc::rch::watch::{self, ChangedError, ReceiverStream, SendError};
+use remoc::rch::watch::{self, ChangedError, ReceiverStream, SendError, WaitError, WatchExt};
 
 #[tokio::test]
 async fn simple() {
@@ -145,6 +145,201 @@ async fn modify_stream() {
     recv_task.await.unwrap();
 }
 
+#[tokio::test]
+async fn modify_if_modified() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<watch::Receiver<i16>>().await;
+
+    println!("Sending remote mpsc channel receiver");
+    let (mut tx, rx) = watch::channel(0);
+    a_tx.send(rx).await.unwrap();
+    println!("Receiving remote mpsc channel receiver");
+    let mut rx = b_rx.recv().await.unwrap().unwrap();
+
+    println!("Not modifying");
+    assert!(!tx.send_if_modified(|_| false));
+    assert!(timeout(Duration::from_millis(100), rx.changed()).await.is_err());
+
+    println!("Modifying");
+    assert!(tx.send_if_modified(|v| {
+        *v = 10;
+        true
+    }));
+    rx.changed().await.unwrap();
+    assert_eq!(*rx.borrow_and_update().unwrap(), 10);
+
+    tx.check().unwrap();
+}
+
+#[tokio::test]
+async fn wait_for() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<watch::Receiver<i16>>().await;
+
+    println!("Sending remote mpsc channel receiver");
+    let (tx, rx) = watch::channel(0);
+    a_tx.send(rx).await.unwrap();
+    println!("Receiving remote mpsc channel receiver");
+    let mut rx = b_rx.recv().await.unwrap().unwrap();
+
+    println!("Waiting for current value");
+    assert_eq!(*rx.wait_for(|v| *v == 0).await.unwrap(), 0);
+
+    let send_task = tokio::spawn(async move {
+        for value in 1..=20 {
+            tx.send(value).unwrap();
+            sleep(Duration::from_millis(10)).await;
+        }
+        tx
+    });
+
+    println!("Waiting for value");
+    assert_eq!(*rx.wait_for(|v| *v >= 10).await.unwrap(), 10);
+
+    let tx = send_task.await.unwrap();
+    drop(tx);
+
+    println!("Waiting for value that is never sent");
+    assert!(matches!(rx.wait_for(|v| *v > 20).await, Err(WaitError::Closed)));
+}
+
+#[tokio::test]
+async fn changed_timeout() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<watch::Receiver<i16>>().await;
+
+    println!("Sending remote mpsc channel receiver");
+    let (tx, rx) = watch::channel(0);
+    a_tx.send(rx).await.unwrap();
+    println!("Receiving remote mpsc channel receiver");
+    let mut rx = b_rx.recv().await.unwrap().unwrap();
+
+    println!("Waiting for change that does not occur");
+    assert!(!rx.changed_timeout(Duration::from_millis(100)).await.unwrap());
+
+    println!("Sending value");
+    tx.send(1).unwrap();
+    sleep(Duration::from_millis(100)).await;
+    rx.changed().await.unwrap();
+    assert_eq!(*rx.borrow_and_update().unwrap(), 1);
+
+    println!("Sending value after timeout");
+    assert!(!rx.changed_timeout(Duration::from_millis(10)).await.unwrap());
+    tx.send(2).unwrap();
+    rx.changed().await.unwrap();
+    assert_eq!(*rx.borrow_and_update().unwrap(), 2);
+
+    println!("Waiting for change that occurs");
+    tx.send(3).unwrap();
+    assert!(rx.changed_timeout(Duration::from_secs(10)).await.unwrap());
+    assert_eq!(*rx.borrow_and_update().unwrap(), 3);
+
+    drop(tx);
+    assert!(matches!(rx.changed_timeout(Duration::from_secs(10)).await, Err(ChangedError::Closed)));
+}
+
+#[tokio::test]
+async fn has_changed() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx), conn) = droppable_loop_channel::<watch::Receiver<i16>>().await;
+
+    println!("Sending remote watch channel receiver");
+    let (tx, rx) = watch::channel(0);
+    a_tx.send(rx).await.unwrap();
+    println!("Receiving remote watch channel receiver");
+    let mut rx = b_rx.recv().await.unwrap().unwrap();
+    assert!(!rx.has_changed().unwrap());
+
+    println!("Sending value");
+    tx.send(1).unwrap();
+    while !rx.has_changed().unwrap() {
+        sleep(Duration::from_millis(10)).await;
+    }
+    assert!(rx.has_changed().unwrap());
+    assert_eq!(*rx.borrow_and_update().unwrap(), 1);
+    assert!(!rx.has_changed().unwrap());
+
+    println!("Dropping connection");
+    drop(conn);
+    let err = loop {
+        match rx.has_changed() {
+            Ok(changed) => assert!(!changed),
+            Err(err) => break err,
+        }
+        sleep(Duration::from_millis(10)).await;
+    };
+    println!("Error: {err}");
+    assert!(rx.borrow().is_err());
+    assert!(rx.borrow_and_update().is_err());
+    assert!(matches!(rx.wait_for(|_| true).await, Err(WaitError::RemoteRecv(_))));
+}
+
+#[tokio::test]
+async fn version() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<watch::Receiver<i16>>().await;
+
+    let (tx, rx) = watch::channel(0);
+    assert_eq!(rx.version(), 0);
+    assert_eq!(tx.borrow().version(), 0);
+
+    println!("Sending values locally");
+    tx.send(1).unwrap();
+    assert_eq!(rx.version(), 1);
+    assert!(!tx.send_if_modified(|_| false));
+    assert_eq!(rx.version(), 1);
+    tx.send_modify(|v| *v += 1);
+    assert_eq!(tx.send_replace(3), 2);
+    let value = rx.borrow().unwrap();
+    assert_eq!((*value, value.version()), (3, 3));
+    drop(value);
+
+    println!("Sending remote watch channel receiver");
+    a_tx.send(rx.clone()).await.unwrap();
+    let mut remote_rx = b_rx.recv().await.unwrap().unwrap();
+    assert_eq!(*remote_rx.borrow().unwrap(), 3);
+    let initial = remote_rx.version();
+
+    println!("Sending value");
+    tx.send(4).unwrap();
+    remote_rx.changed().await.unwrap();
+    let (value, version) = {
+        let value = remote_rx.borrow_and_update().unwrap();
+        (*value, value.version())
+    };
+    assert_eq!(value, 4);
+    assert!(version > initial);
+    assert_eq!(version, remote_rx.version());
+    assert_eq!(rx.version(), 4);
+}
+
+#[tokio::test]
+async fn map() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<watch::Receiver<i16>>().await;
+
+    let (tx, rx) = watch::channel((0i16, 0i16));
+    let rx = rx.map(|(a, _b)| *a);
+
+    println!("Sending mapped watch channel receiver");
+    a_tx.send(rx).await.unwrap();
+    println!("Receiving mapped watch channel receiver");
+    let mut rx = b_rx.recv().await.unwrap().unwrap();
+    assert_eq!(*rx.borrow_and_update().unwrap(), 0);
+
+    println!("Modifying unprojected field");
+    tx.send_modify(|(_a, b)| *b += 1);
+    assert!(!rx.changed_timeout(Duration::from_millis(100)).await.unwrap());
+
+    println!("Modifying projected field");
+    tx.send_modify(|(a, _b)| *a += 1);
+    rx.changed().await.unwrap();
+    assert_eq!(*rx.borrow_and_update().unwrap(), 1);
+
+    drop(tx);
+    assert!(matches!(rx.changed().await, Err(ChangedError::Closed)));
+}
+
 #[tokio::test]
 async fn close() {
     crate::init();
@@ -181,6 +376,92 @@ async fn close() {
     }
 }
 
+#[tokio::test]
+async fn receiver_count() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<watch::Receiver<i16>>().await;
+
+    let (tx, rx) = watch::channel(123);
+    assert_eq!(tx.receiver_count(), 1);
+
+    let rx2 = rx.clone();
+    assert_eq!(tx.receiver_count(), 2);
+
+    println!("Sending remote mpsc channel receiver");
+    a_tx.send(rx).await.unwrap();
+    println!("Receiving remote mpsc channel receiver");
+    let rx = b_rx.recv().await.unwrap().unwrap();
+    assert_eq!(tx.receiver_count(), 2);
+
+    println!("Dropping local receiver");
+    drop(rx2);
+    assert_eq!(tx.receiver_count(), 1);
+
+    println!("Dropping remote receiver");
+    drop(rx);
+    while tx.receiver_count() > 0 {
+        sleep(Duration::from_millis(10)).await;
+    }
+    assert!(tx.is_closed());
+}
+
+#[tokio::test]
+async fn send_no_receivers() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<watch::Receiver<i16>>().await;
+
+    let (tx, rx) = watch::channel(0);
+    let rx2 = rx.clone();
+
+    println!("Sending remote watch channel receiver");
+    a_tx.send(rx).await.unwrap();
+    let mut rx = b_rx.recv().await.unwrap().unwrap();
+
+    println!("Dropping local receiver");
+    drop(rx2);
+    tx.send(1).unwrap();
+    rx.changed().await.unwrap();
+    assert_eq!(*rx.borrow_and_update().unwrap(), 1);
+
+    println!("Dropping remote receiver");
+    drop(rx);
+    tx.closed().await;
+    assert_eq!(tx.receiver_count(), 0);
+
+    println!("Attempting to send");
+    match tx.send(2) {
+        Ok(()) => panic!("send succeeded without receivers"),
+        Err(SendError::Closed) => (),
+        Err(err) => panic!("wrong error without receivers: {err}"),
+    }
+    assert_eq!(*tx.borrow(), 1);
+}
+
+#[tokio::test]
+async fn subscribe() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<watch::Receiver<i16>>().await;
+
+    let (tx, _rx) = watch::channel(0);
+    for value in 1..=5 {
+        tx.send(value).unwrap();
+    }
+
+    println!("Subscribing");
+    let mut rx = tx.subscribe();
+    assert_eq!(tx.receiver_count(), 2);
+    assert_eq!(*rx.borrow_and_update().unwrap(), 5);
+
+    println!("Sending subscribed receiver");
+    a_tx.send(rx).await.unwrap();
+    let mut rx = b_rx.recv().await.unwrap().unwrap();
+    assert_eq!(*rx.borrow_and_update().unwrap(), 5);
+
+    tx.send(6).unwrap();
+    rx.changed().await.unwrap();
+    assert_eq!(*rx.borrow_and_update().unwrap(), 6);
+}
+
 #[tokio::test]
 async fn conn_failure() {
     crate::init();
@@ -213,6 +494,29 @@ async fn conn_failure() {
     }
 }
 
+#[tokio::test]
+async fn stream_conn_failure() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx), conn) = droppable_loop_channel::<watch::Receiver<i16>>().await;
+
+    println!("Sending remote watch channel receiver");
+    let (_tx, rx) = watch::channel(123);
+    a_tx.send(rx).await.unwrap();
+    println!("Receiving remote watch channel receiver");
+    let rx = b_rx.recv().await.unwrap().unwrap();
+    let mut rx = rx.into_stream();
+
+    assert_eq!(rx.next().await.unwrap().unwrap(), 123);
+
+    println!("Dropping connection");
+    drop(conn);
+
+    let err = rx.next().await.unwrap().unwrap_err();
+    println!("Received error: {err}");
+    assert!(err.is_final());
+    assert!(rx.next().await.is_none());
+}
+
 #[tokio::test]
 async fn max_item_size_exceeded() {
     crate::init();
@@ -338,3 +642,272 @@ async fn max_item_size_exceeded_check() {
     println!("Waiting for receive task");
     assert!(matches!(recv_task.await.unwrap(), Err(ChangedError::Closed)));
 }
+
+#[tokio::test]
+async fn max_item_size_exceeded_last_error() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<watch::Receiver<Vec<u8>>>().await;
+
+    println!("Sending remote mpsc channel receiver");
+    let (tx, rx) = watch::channel(Vec::new());
+    a_tx.send(rx).await.unwrap();
+    println!("Receiving remote mpsc channel receiver");
+    let _rx = b_rx.recv().await.unwrap().unwrap();
+
+    assert!(tx.last_error().is_none());
+
+    let elems = tx.max_item_size() * 10;
+    println!("Sending {elems} elements");
+    tx.send(vec![100; elems]).unwrap();
+
+    println!("Waiting for error");
+    let err = loop {
+        if let Some(err) = tx.last_error() {
+            break err;
+        }
+        sleep(Duration::from_millis(10)).await;
+    };
+    println!("Last error: {err:?}");
+    assert!(matches!(err, SendError::RemoteSend(SendErrorKind::MaxItemSizeExceeded)));
+    assert!(matches!(tx.error(), Some(SendError::RemoteSend(SendErrorKind::MaxItemSizeExceeded))));
+}
+
+#[tokio::test]
+async fn custom_max_item_size() {
+    const MAX_ITEM_SIZE: usize = 4 * remoc::rch::DEFAULT_MAX_ITEM_SIZE;
+
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) =
+        loop_channel::<watch::Receiver<Vec<u8>, remoc::codec::Default, MAX_ITEM_SIZE>>().await;
+
+    println!("Sending remote mpsc channel receiver");
+    let (mut tx, rx) = watch::channel(Vec::new()).with_max_item_size::<MAX_ITEM_SIZE>();
+    a_tx.send(rx).await.unwrap();
+    println!("Receiving remote mpsc channel receiver");
+    let mut rx = b_rx.recv().await.unwrap().unwrap();
+
+    assert_eq!(tx.max_item_size(), MAX_ITEM_SIZE);
+    assert_eq!(rx.max_item_size(), MAX_ITEM_SIZE);
+
+    // JSON encoding will result in a transfer size larger than the default limit.
+    let elems = remoc::rch::DEFAULT_MAX_ITEM_SIZE / 2;
+    println!("Sending {elems} elements");
+    let value = vec![100; elems];
+    tx.send(value.clone()).unwrap();
+
+    rx.changed().await.unwrap();
+    assert_eq!(*rx.borrow_and_update().unwrap(), value);
+    tx.check().unwrap();
+}
+
+#[tokio::test]
+async fn cancel_safety() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<watch::Receiver<i16>>().await;
+
+    println!("Sending remote watch channel receiver");
+    let (tx, rx) = watch::channel(0);
+    a_tx.send(rx).await.unwrap();
+    println!("Receiving remote watch channel receiver");
+    let mut rx = b_rx.recv().await.unwrap().unwrap();
+
+    println!("Cancelling pending change notification");
+    assert!(timeout(Duration::from_millis(100), rx.changed()).await.is_err());
+
+    tx.send(1).unwrap();
+    let mut cancelled = 0;
+    loop {
+        tokio::select! {
+            res = rx.changed() => {
+                res.unwrap();
+                break;
+            }
+            () = tokio::task::yield_now() => cancelled += 1,
+        }
+    }
+    println!("Change observed after {cancelled} cancelled notifications");
+    assert_eq!(*rx.borrow_and_update().unwrap(), 1);
+
+    println!("Cancelling pending wait");
+    assert!(timeout(Duration::from_millis(100), rx.wait_for(|v| *v == 2)).await.is_err());
+    tx.send(2).unwrap();
+    assert_eq!(*rx.wait_for(|v| *v == 2).await.unwrap(), 2);
+}
+
+async fn forward_burst(mode: watch::ForwardMode, n: i16) -> Vec<i16> {
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<watch::Receiver<i16>>().await;
+
+    let (mut tx, rx) = watch::channel(0);
+    tx.set_forward_mode(mode);
+    assert_eq!(tx.forward_mode(), mode);
+    a_tx.send(rx).await.unwrap();
+    let mut rx = b_rx.recv().await.unwrap().unwrap();
+
+    let recv_task = tokio::spawn(async move {
+        let mut values = Vec::new();
+        while rx.changed().await.is_ok() {
+            values.push(*rx.borrow_and_update().unwrap());
+        }
+        values
+    });
+
+    println!("Sending {n} values in {mode:?} mode");
+    for value in 1..=n {
+        tx.send(value).unwrap();
+    }
+    sleep(Duration::from_millis(100)).await;
+    drop(tx);
+
+    let values = recv_task.await.unwrap();
+    println!("Received {values:?}");
+    assert_eq!(values.last(), Some(&n));
+    values
+}
+
+#[tokio::test]
+async fn forward_mode() {
+    crate::init();
+
+    let values = forward_burst(watch::ForwardMode::Coalesce, 10).await;
+    assert!(values.len() < 10);
+
+    let values = forward_burst(watch::ForwardMode::Queue(16), 10).await;
+    assert_eq!(values, (1..=10).collect::<Vec<_>>());
+
+    let values = forward_burst(watch::ForwardMode::Queue(4), 100).await;
+    assert!(values.len() < 100);
+}
+
+#[tokio::test]
+async fn forward_mode_sender() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<watch::Sender<i16>>().await;
+
+    let (mut tx, mut rx) = watch::channel(0);
+    tx.set_forward_mode(watch::ForwardMode::Queue(16));
+    println!("Sending remote watch channel sender");
+    a_tx.send(tx).await.unwrap();
+    let tx = b_rx.recv().await.unwrap().unwrap();
+    assert_eq!(tx.forward_mode(), watch::ForwardMode::Queue(16));
+
+    let recv_task = tokio::spawn(async move {
+        let mut values = Vec::new();
+        while rx.changed().await.is_ok() {
+            values.push(*rx.borrow_and_update().unwrap());
+        }
+        values
+    });
+
+    for value in 1..=10 {
+        tx.send(value).unwrap();
+    }
+    sleep(Duration::from_millis(100)).await;
+    drop(tx);
+
+    let values = recv_task.await.unwrap();
+    println!("Received {values:?}");
+    assert_eq!(values, (1..=10).collect::<Vec<_>>());
+}
+
+#[tokio::test]
+async fn from_receiver() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<watch::Receiver<i16>>().await;
+
+    let (local_tx, local_rx) = tokio::sync::watch::channel(1i16);
+    let rx = watch::channel_from_receiver(local_rx);
+
+    println!("Sending adopted watch channel receiver");
+    a_tx.send(rx).await.unwrap();
+    println!("Receiving adopted watch channel receiver");
+    let mut rx = b_rx.recv().await.unwrap().unwrap();
+    assert_eq!(*rx.borrow_and_update().unwrap(), 1);
+
+    for value in 2..=10 {
+        println!("Sending {value} over local channel");
+        local_tx.send(value).unwrap();
+        rx.wait_for(|v| *v == value).await.unwrap();
+    }
+
+    drop(local_tx);
+    while rx.changed().await.is_ok() {}
+    assert!(matches!(rx.changed().await, Err(ChangedError::Closed)));
+    assert_eq!(*rx.borrow().unwrap(), 10);
+}
+
+/// Makes `n` remote receivers report an error and returns the number of errors held by the sender.
+async fn many_errors(n: usize, error_queue_length: Option<usize>) -> usize {
+    const MAX_ITEM_SIZE: usize = 1024;
+
+    let ((mut a_tx, _), (_, mut b_rx)) =
+        loop_channel::<watch::Receiver<Vec<u8>, remoc::codec::Default, MAX_ITEM_SIZE>>().await;
+
+    println!("Sending {n} remote watch channel receivers");
+    let (mut tx, rx) = watch::channel(Vec::new()).with_max_item_size::<MAX_ITEM_SIZE>();
+    assert_eq!(tx.error_queue_length(), watch::DEFAULT_ERROR_QUEUE_LENGTH);
+    if let Some(error_queue_length) = error_queue_length {
+        tx.set_error_queue_length(error_queue_length);
+    }
+    let mut remote_rxs = Vec::new();
+    for _ in 0..n {
+        a_tx.send(rx.clone()).await.unwrap();
+        remote_rxs.push(b_rx.recv().await.unwrap().unwrap());
+    }
+    drop(rx);
+
+    // Each forwarding task reports an error.
+    let elems = tx.max_item_size() * 10;
+    println!("Sending {elems} elements");
+    tx.send(vec![100; elems]).unwrap();
+
+    println!("Wait for sender close");
+    tx.closed().await;
+
+    let mut errors = 0;
+    while let Some(err) = tx.error() {
+        assert!(matches!(err, SendError::RemoteSend(SendErrorKind::MaxItemSizeExceeded)));
+        errors += 1;
+        tx.clear_error();
+    }
+    println!("Received {errors} errors");
+    assert!(matches!(tx.last_error(), Some(SendError::RemoteSend(SendErrorKind::MaxItemSizeExceeded))));
+    errors
+}
+
+#[tokio::test]
+async fn many_errors_discarded() {
+    crate::init();
+    assert_eq!(many_errors(40, None).await, watch::DEFAULT_ERROR_QUEUE_LENGTH);
+}
+
+#[tokio::test]
+async fn many_errors_queued() {
+    crate::init();
+    assert_eq!(many_errors(40, Some(64)).await, 40);
+}
+
+#[tokio::test]
+async fn blocking_changed() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<watch::Receiver<i16>>().await;
+
+    println!("Sending remote watch channel receiver");
+    let (tx, rx) = watch::channel(0);
+    a_tx.send(rx).await.unwrap();
+    let mut rx = b_rx.recv().await.unwrap().unwrap();
+
+    let recv_task = tokio::task::spawn_blocking(move || {
+        rx.blocking_changed().unwrap();
+        let value = *rx.borrow_and_update().unwrap();
+        (rx, value)
+    });
+
+    println!("Sending value");
+    tx.send(123).unwrap();
+    let (mut rx, value) = recv_task.await.unwrap();
+    assert_eq!(value, 123);
+
+    drop(tx);
+    let recv_task = tokio::task::spawn_blocking(move || rx.blocking_changed());
+    assert!(matches!(recv_task.await.unwrap(), Err(ChangedError::Closed)));
+}
@@ -0,0 +1,134 @@
+use futures::{future::try_join, StreamExt};
+use remoc::{
+    chmux::ChMuxError,
+    codec,
+    rch::base,
+    reconnect::{self, Event},
+    Connect, ConnectError,
+};
+use std::time::Duration;
+use tokio::time::sleep;
+
+use crate::loop_transport;
+
+type Error = ConnectError<futures::channel::mpsc::SendError, std::io::Error>;
+
+#[tokio::test]
+async fn reconnect() {
+    crate::init();
+
+    let (servers_tx, mut servers_rx) = tokio::sync::mpsc::unbounded_channel();
+    let (tx, mut rx, mut events) = reconnect::session(4, move || {
+        let servers_tx = servers_tx.clone();
+        async move {
+            loop_transport!(0, a_tx, a_rx, b_tx, b_rx);
+            let ((conn, tx, rx), (server_conn, server_tx, server_rx)) = try_join(
+                Connect::framed::<_, _, u32, u32, codec::Default>(Default::default(), a_tx, a_rx),
+                Connect::framed::<_, _, u32, u32, codec::Default>(Default::default(), b_tx, b_rx),
+            )
+            .await?;
+
+            let shutdown = server_conn.shutdown_handle();
+            tokio::spawn(server_conn);
+            let _ = servers_tx.send((shutdown, server_tx, server_rx));
+
+            Ok::<_, Error>((conn, tx, rx))
+        }
+    });
+
+    for i in 1..=3 {
+        println!("Connection {i}");
+        assert!(matches!(events.recv().await, Some(Event::Connected)));
+        let (shutdown, mut stx, mut srx) = servers_rx.recv().await.unwrap();
+
+        println!("Sending");
+        tx.send(i).await.unwrap();
+        assert_eq!(srx.recv().await.unwrap(), Some(i));
+
+        println!("Receiving");
+        stx.send(i * 10).await.unwrap();
+        assert_eq!(rx.recv().await, Some(i * 10));
+
+        println!("Dropping connection");
+        shutdown.terminate();
+        match events.recv().await {
+            Some(Event::Disconnected(err)) => println!("Disconnected: {err:?}"),
+            other => panic!("unexpected event: {other:?}"),
+        }
+    }
+
+    println!("Dropping session");
+    drop(tx);
+    drop(rx);
+    while let Some(event) = events.recv().await {
+        assert!(matches!(event, Event::Connected), "unexpected event: {event:?}");
+    }
+}
+
+#[tokio::test]
+async fn reconnect_failed() {
+    crate::init();
+
+    type Connection = (Connect<'static, std::io::Error, std::io::Error>, base::Sender<u32>, base::Receiver<u32>);
+    let (tx, mut rx, mut events) = reconnect::session(1, || async {
+        Err::<Connection, _>(ChMuxError::<std::io::Error, std::io::Error>::Timeout)
+    });
+
+    assert!(matches!(events.recv().await, Some(Event::Failed(ChMuxError::Timeout))));
+    assert!(events.recv().await.is_none());
+    assert_eq!(rx.recv().await, None);
+    assert!(tx.is_closed());
+    assert_eq!(tx.send(1).await.unwrap_err().0, 1);
+}
+
+#[tokio::test]
+async fn reconnect_keeps_buffered() {
+    crate::init();
+
+    let (servers_tx, mut servers_rx) = tokio::sync::mpsc::unbounded_channel();
+    let (tx, _rx, mut events) = reconnect::session(4, move || {
+        let servers_tx = servers_tx.clone();
+        async move {
+            loop_transport!(0, a_tx, a_rx, b_tx, b_rx);
+            let ((conn, tx, rx), (server_conn, server_tx, server_rx)) = try_join(
+                Connect::framed::<_, _, u32, u32, codec::Default>(Default::default(), a_tx, a_rx),
+                Connect::framed::<_, _, u32, u32, codec::Default>(Default::default(), b_tx, b_rx),
+            )
+            .await?;
+
+            let shutdown = server_conn.shutdown_handle();
+            tokio::spawn(server_conn);
+            let _ = servers_tx.send((shutdown, server_tx, server_rx));
+
+            Ok::<_, Error>((conn, tx, rx))
+        }
+    });
+
+    println!("First connection");
+    assert!(matches!(events.recv().await, Some(Event::Connected)));
+    let (shutdown, _stx, srx) = servers_rx.recv().await.unwrap();
+
+    println!("Dropping server receiver");
+    drop(srx);
+    sleep(Duration::from_millis(100)).await;
+
+    println!("Sending");
+    for i in 1..=4 {
+        tx.send(i).await.unwrap();
+    }
+    assert!(matches!(events.recv().await, Some(Event::ItemLost)));
+
+    println!("Dropping connection");
+    shutdown.terminate();
+    match events.recv().await {
+        Some(Event::Disconnected(err)) => println!("Disconnected: {err:?}"),
+        other => panic!("unexpected event: {other:?}"),
+    }
+
+    println!("Second connection");
+    assert!(matches!(events.recv().await, Some(Event::Connected)));
+    let (_shutdown, _stx, mut srx) = servers_rx.recv().await.unwrap();
+    for i in 2..=4 {
+        assert_eq!(srx.recv().await.unwrap(), Some(i));
+    }
+}
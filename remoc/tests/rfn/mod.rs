@@ -1,3 +1,6 @@
 mod rfn_const;
 mod rfn_mut;
 mod rfn_once;
+mod rfn_registry;
+mod rfn_state;
+mod rfn_stream;
@@ -1,3 +1,6 @@
+use std::time::Duration;
+use tokio::time::{sleep, timeout};
+
 use remoc::rfn::{CallError, RFn};
 
 use crate::loop_channel;
@@ -26,3 +29,43 @@ async fn simple() {
     println!("rfn({value}) = {result}");
     assert_eq!(result, -value);
 }
+
+struct DropGuard(tokio::sync::mpsc::UnboundedSender<()>);
+
+impl Drop for DropGuard {
+    fn drop(&mut self) {
+        let _ = self.0.send(());
+    }
+}
+
+#[tokio::test]
+async fn call_timeout() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<RFn<_, _>>().await;
+
+    let (dropped_tx, mut dropped_rx) = tokio::sync::mpsc::unbounded_channel();
+    let rfn = RFn::new_1(move |delay: u64| {
+        let guard = DropGuard(dropped_tx.clone());
+        async move {
+            sleep(Duration::from_millis(delay)).await;
+            drop(guard);
+            Ok::<_, CallError>(delay)
+        }
+    });
+
+    println!("Sending remote function");
+    a_tx.send(rfn).await.unwrap();
+    println!("Receiving remote function");
+    let rfn = b_rx.recv().await.unwrap().unwrap();
+
+    println!("calling function within timeout");
+    assert_eq!(rfn.call_timeout(10, Duration::from_secs(10)).await.unwrap(), 10);
+    dropped_rx.recv().await.unwrap();
+
+    println!("calling function exceeding timeout");
+    let res = rfn.call_timeout(100_000, Duration::from_millis(100)).await;
+    assert!(matches!(res, Err(CallError::Timeout)), "unexpected result: {res:?}");
+
+    println!("waiting for cancellation");
+    timeout(Duration::from_secs(10), dropped_rx.recv()).await.expect("function was not cancelled").unwrap();
+}
@@ -0,0 +1,80 @@
+use futures::future::join_all;
+use std::time::Duration;
+use tokio::time::sleep;
+
+use remoc::rfn::{CallError, RFnRegistry, RFnRegistryBuilder};
+
+use crate::loop_channel;
+
+#[tokio::test]
+async fn simple() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<RFnRegistry>().await;
+
+    let registry = RFnRegistryBuilder::new()
+        .register("add", |(a, b): (u32, u32)| async move { Ok::<_, CallError>(a + b) })
+        .register("len", |s: String| async move {
+            sleep(Duration::from_millis(10)).await;
+            Ok::<_, CallError>(s.len())
+        })
+        .build();
+    assert_eq!(registry.names(), ["add", "len"]);
+
+    println!("Sending registry");
+    a_tx.send(registry).await.unwrap();
+    println!("Receiving registry");
+    let registry = b_rx.recv().await.unwrap().unwrap();
+    println!("{registry:?}");
+    assert!(registry.contains("add"));
+    assert!(!registry.contains("sub"));
+
+    println!("Calling functions");
+    assert_eq!(registry.call::<_, u32, CallError>("add", (1u32, 2u32)).await.unwrap(), 3);
+    assert_eq!(registry.call::<_, usize, CallError>("len", "four").await.unwrap(), 4);
+
+    println!("Calling functions from multiple callers");
+    let results = join_all((0..10u32).map(|i| {
+        let registry = registry.clone();
+        async move { registry.call::<_, u32, CallError>("add", (i, i)).await.unwrap() }
+    }))
+    .await;
+    assert_eq!(results, (0..10).map(|i| 2 * i).collect::<Vec<_>>());
+
+    println!("Calling unknown function");
+    let err = registry.try_call::<_, u32>("sub", (1u32, 2u32)).await.unwrap_err();
+    println!("Error: {err}");
+    assert!(matches!(err, CallError::UnknownFunction(name) if name == "sub"));
+
+    // The mismatching types are chosen so that they are detected by all codecs,
+    // including those that are not self-describing.
+    println!("Calling with mismatching argument type");
+    let err = registry.try_call::<_, Result<u32, CallError>>("add", ()).await.unwrap_err();
+    println!("Error: {err}");
+    assert!(matches!(err, CallError::Deserialize(_)));
+
+    println!("Calling with mismatching return type");
+    let err = registry.try_call::<_, Result<(u32, u32), CallError>>("add", (1u32, 2u32)).await.unwrap_err();
+    println!("Error: {err}");
+    assert!(matches!(err, CallError::Deserialize(_)));
+}
+
+#[tokio::test]
+async fn provider() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<RFnRegistry>().await;
+
+    let (registry, provider) = RFnRegistryBuilder::new()
+        .register("double", |x: u32| async move { Ok::<_, CallError>(2 * x) })
+        .build_provided();
+
+    println!("Sending registry");
+    a_tx.send(registry).await.unwrap();
+    let registry = b_rx.recv().await.unwrap().unwrap();
+    assert_eq!(registry.call::<_, u32, CallError>("double", 4u32).await.unwrap(), 8);
+
+    println!("Dropping provider");
+    drop(provider);
+    let err = registry.call::<_, u32, CallError>("double", 4u32).await.unwrap_err();
+    println!("Error: {err:?}");
+    assert!(matches!(err, CallError::Dropped | CallError::RemoteConnect(_)));
+}
@@ -0,0 +1,95 @@
+use futures::future::{join_all, BoxFuture};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::time::sleep;
+
+use remoc::rfn::{CallError, RFnState};
+
+use crate::loop_channel;
+
+#[derive(Debug, Serialize, Deserialize)]
+enum AppendError {
+    Odd(u32),
+    Call(CallError),
+}
+
+impl From<CallError> for AppendError {
+    fn from(err: CallError) -> Self {
+        Self::Call(err)
+    }
+}
+
+#[tokio::test]
+async fn simple() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<RFnState<_, _>>().await;
+
+    let rfn = RFnState::new_1(Vec::new(), |log: &mut Vec<u32>, value: u32| -> BoxFuture<_> {
+        Box::pin(async move {
+            if value % 2 == 1 {
+                return Err(AppendError::Odd(value));
+            }
+            sleep(Duration::from_millis(10)).await;
+            log.push(value);
+            Ok(log.clone())
+        })
+    });
+
+    println!("Sending remote function");
+    a_tx.send(rfn).await.unwrap();
+    println!("Receiving remote function");
+    let rfn = b_rx.recv().await.unwrap().unwrap();
+
+    println!("calling function");
+    assert_eq!(rfn.call(2).await.unwrap(), vec![2]);
+
+    println!("calling function with error");
+    assert!(matches!(rfn.call(3).await, Err(AppendError::Odd(3))));
+
+    println!("calling function from multiple callers");
+    let results = join_all((0..4).map(|i| {
+        let rfn = rfn.clone();
+        async move { rfn.call(10 * (i + 1)).await.unwrap() }
+    }))
+    .await;
+    println!("results: {results:?}");
+
+    let mut lens: Vec<_> = results.iter().map(|log| log.len()).collect();
+    lens.sort();
+    assert_eq!(lens, vec![2, 3, 4, 5], "calls were not serialized");
+
+    let log = rfn.call(4).await.unwrap();
+    println!("log: {log:?}");
+    assert_eq!(log.len(), 6);
+    assert_eq!(log[0], 2);
+    assert_eq!(log[5], 4);
+}
+
+#[tokio::test]
+async fn panic() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<RFnState<_, _>>().await;
+
+    let rfn = RFnState::new_1(0, |count: &mut u32, fail: bool| -> BoxFuture<_> {
+        Box::pin(async move {
+            if fail {
+                panic!("failing as requested");
+            }
+            *count += 1;
+            Ok::<_, CallError>(*count)
+        })
+    });
+
+    println!("Sending remote function");
+    a_tx.send(rfn).await.unwrap();
+    println!("Receiving remote function");
+    let rfn = b_rx.recv().await.unwrap().unwrap();
+
+    assert_eq!(rfn.call(false).await.unwrap(), 1);
+
+    println!("calling panicking function");
+    assert!(matches!(rfn.call(true).await, Err(CallError::Dropped)));
+
+    println!("calling function after panic");
+    assert!(matches!(rfn.call(false).await, Err(CallError::Dropped)));
+}
@@ -0,0 +1,67 @@
+use futures::{stream, StreamExt};
+use std::time::Duration;
+use tokio::time::timeout;
+
+use remoc::rfn::RFnStream;
+
+use crate::loop_channel;
+
+#[tokio::test]
+async fn simple() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<RFnStream<_, _>>().await;
+
+    let rfn = RFnStream::new_2(|start: i16, end: i16| stream::iter(start..end));
+
+    println!("Sending remote function");
+    a_tx.send(rfn).await.unwrap();
+    println!("Receiving remote function");
+    let rfn = b_rx.recv().await.unwrap().unwrap();
+
+    for _ in 0..2 {
+        println!("calling function");
+        let mut rx = rfn.call(10, 20).await.unwrap();
+        for i in 10..20 {
+            assert_eq!(rx.recv().await.unwrap(), Some(i));
+        }
+        assert_eq!(rx.recv().await.unwrap(), None);
+    }
+}
+
+struct DropGuard(tokio::sync::mpsc::UnboundedSender<()>);
+
+impl Drop for DropGuard {
+    fn drop(&mut self) {
+        let _ = self.0.send(());
+    }
+}
+
+#[tokio::test]
+async fn cancel() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<RFnStream<(), u32>>().await;
+
+    let (dropped_tx, mut dropped_rx) = tokio::sync::mpsc::unbounded_channel();
+    let rfn = RFnStream::new_0(move || {
+        let guard = DropGuard(dropped_tx.clone());
+        stream::iter(0..).map(move |i| {
+            let _ = &guard;
+            i
+        })
+    });
+
+    println!("Sending remote function");
+    a_tx.send(rfn).await.unwrap();
+    println!("Receiving remote function");
+    let rfn = b_rx.recv().await.unwrap().unwrap();
+
+    println!("calling function");
+    let mut rx = rfn.call().await.unwrap();
+    for i in 0..10 {
+        assert_eq!(rx.recv().await.unwrap(), Some(i));
+    }
+
+    println!("dropping receiver");
+    drop(rx);
+    timeout(Duration::from_secs(10), dropped_rx.recv()).await.expect("stream was not dropped").unwrap();
+}
@@ -54,3 +54,38 @@ async fn simple() {
     println!("handle value mut: {}", *local_handle.as_mut().await.unwrap());
     println!("handle value: {}", local_handle.into_inner().await.unwrap());
 }
+
+#[tokio::test]
+async fn alive() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<Handle<String>>().await;
+
+    let (local_handle, provider) = Handle::provided("test string".to_string());
+    assert!(local_handle.is_alive());
+
+    println!("Sending handle to remote");
+    a_tx.send(local_handle.clone()).await.unwrap();
+    println!("Receiving handle");
+    let remote_handle = b_rx.recv().await.unwrap().unwrap();
+    assert!(remote_handle.is_alive());
+
+    println!("Dropping provider");
+    drop(provider);
+    drop(local_handle);
+
+    println!("Waiting for remote handle to be closed");
+    remote_handle.closed().await;
+    assert!(!remote_handle.is_alive());
+}
+
+#[tokio::test]
+async fn alive_taken() {
+    crate::init();
+
+    let handle: Handle<_, codec::Default> = Handle::new(123);
+    let other_handle = handle.clone();
+    assert!(other_handle.is_alive());
+
+    assert_eq!(handle.into_inner().await.unwrap(), 123);
+    assert!(!other_handle.is_alive());
+}
@@ -19,3 +19,55 @@ async fn simple() {
     println!("reference: {}", *lazy.get().await.unwrap());
     println!("value: {}", lazy.into_inner().await.unwrap());
 }
+
+#[tokio::test]
+async fn prefetch() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<Lazy<String>>().await;
+
+    let value = "test string data".to_string();
+
+    let (fetched_tx, fetched_rx) = tokio::sync::oneshot::channel();
+    let lazy_value = value.clone();
+    let lazy = Lazy::new_future(async move {
+        let _ = fetched_tx.send(());
+        lazy_value
+    });
+
+    println!("Sending lazy");
+    a_tx.send(lazy).await.unwrap();
+    println!("Receiving lazy");
+    let lazy = b_rx.recv().await.unwrap().unwrap();
+
+    println!("Prefetching lazy");
+    lazy.prefetch();
+    lazy.prefetch();
+    fetched_rx.await.unwrap();
+
+    println!("Getting lazy");
+    assert_eq!(*lazy.get().await.unwrap(), value);
+    lazy.prefetch();
+    assert_eq!(lazy.into_inner().await.unwrap(), value);
+}
+
+#[tokio::test]
+async fn prefetch_dropped() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<Lazy<String>>().await;
+
+    let (lazy, provider) = Lazy::provided("test string data".to_string());
+
+    println!("Sending lazy");
+    a_tx.send(lazy).await.unwrap();
+    println!("Receiving lazy");
+    let lazy = b_rx.recv().await.unwrap().unwrap();
+
+    println!("Dropping provider and prefetching lazy");
+    drop(provider);
+    lazy.prefetch();
+
+    println!("Getting lazy");
+    let res = lazy.get().await;
+    println!("Result: {res:?}");
+    assert!(res.is_err());
+}
@@ -1,4 +1,5 @@
 use crate::loop_channel;
+use futures::StreamExt;
 use rand::{thread_rng, Rng, RngCore};
 use remoc::robj::lazy_blob::LazyBlob;
 
@@ -31,3 +32,39 @@ async fn simple() {
     let fetched = lazy.into_inner().await.unwrap();
     assert_eq!(Vec::from(fetched), data);
 }
+
+#[tokio::test]
+async fn stream() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<LazyBlob>().await;
+
+    let mut rng = thread_rng();
+    let size = rng.gen_range(10_000_000..15_000_000);
+    let mut data = vec![0; size];
+    rng.fill_bytes(&mut data);
+
+    println!("Creating lazy blob of size {} bytes", data.len());
+    let lazy: LazyBlob = LazyBlob::new(data.clone().into());
+
+    println!("Sending lazy blob");
+    a_tx.send(lazy).await.unwrap();
+    println!("Receiving lazy blob");
+    let lazy = b_rx.recv().await.unwrap().unwrap();
+
+    for _ in 0..2 {
+        println!("Streaming");
+        let mut stream = lazy.stream().await.unwrap();
+        assert_eq!(stream.remaining(), size as u64);
+
+        let mut received = Vec::new();
+        let mut chunks = 0;
+        while let Some(chunk) = stream.next().await {
+            received.extend_from_slice(&chunk.unwrap());
+            chunks += 1;
+        }
+        println!("Received {} bytes in {chunks} chunks", received.len());
+        assert!(chunks > 1);
+        assert_eq!(stream.remaining(), 0);
+        assert_eq!(received, data);
+    }
+}
@@ -1,6 +1,8 @@
-use remoc::robj::rw_lock::{Owner, RwLock};
+use remoc::robj::rw_lock::{Access, Fairness, OwnedReadGuard, Owner, RwLock};
+use std::time::Duration;
+use tokio::time::sleep;
 
-use crate::loop_channel;
+use crate::{droppable_loop_channel, loop_channel};
 
 #[tokio::test]
 async fn simple() {
@@ -71,3 +73,323 @@ async fn simple() {
     assert!(!read1.is_invalidated());
     assert!(!read2.is_invalidated());
 }
+
+#[tokio::test]
+async fn upgradable() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<RwLock<String>>().await;
+
+    println!("Creating owner");
+    let owner = Owner::new("initial".to_string());
+
+    println!("Sending RwLocks");
+    a_tx.send(owner.rw_lock()).await.unwrap();
+    a_tx.send(owner.rw_lock()).await.unwrap();
+
+    println!("Receiving RwLocks");
+    let rw_lock1 = b_rx.recv().await.unwrap().unwrap();
+    let rw_lock2 = b_rx.recv().await.unwrap().unwrap();
+
+    println!("Acquiring upgradable read lock");
+    let upgradable = rw_lock1.upgradable_read().await.unwrap();
+    assert_eq!(*upgradable, "initial");
+
+    println!("Reading while upgradable read lock is held");
+    let read = rw_lock2.read().await.unwrap();
+    assert_eq!(*read, "initial");
+
+    println!("Making write request");
+    let writer_lock = rw_lock2.clone();
+    let writer = tokio::spawn(async move {
+        let mut write = writer_lock.write().await.unwrap();
+        assert_eq!(*write, "upgraded");
+        *write = "written".to_string();
+        write.commit().await.unwrap();
+    });
+    sleep(Duration::from_millis(100)).await;
+    assert!(!writer.is_finished());
+    assert!(!read.is_invalidated());
+
+    println!("Upgrading");
+    let upgrade = tokio::spawn(async move { upgradable.upgrade().await.unwrap() });
+    read.invalidated().await;
+    drop(read);
+    let mut write = upgrade.await.unwrap();
+    assert_eq!(*write, "initial");
+    *write = "upgraded".to_string();
+    write.commit().await.unwrap();
+
+    println!("Waiting for writer");
+    writer.await.unwrap();
+    assert_eq!(*rw_lock1.read().await.unwrap(), "written");
+
+    println!("Releasing upgradable read lock without upgrade");
+    let upgradable = rw_lock1.upgradable_read().await.unwrap();
+    assert_eq!(*upgradable, "written");
+    drop(upgradable);
+    let mut write = rw_lock2.write().await.unwrap();
+    *write = "final".to_string();
+    write.commit().await.unwrap();
+    assert_eq!(*rw_lock1.read().await.unwrap(), "final");
+}
+
+#[tokio::test]
+async fn try_lock() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<RwLock<String>>().await;
+
+    println!("Creating owner");
+    let owner = Owner::new("initial".to_string());
+
+    println!("Sending RwLocks");
+    a_tx.send(owner.rw_lock()).await.unwrap();
+    a_tx.send(owner.rw_lock()).await.unwrap();
+
+    println!("Receiving RwLocks");
+    let rw_lock1 = b_rx.recv().await.unwrap().unwrap();
+    let rw_lock2 = b_rx.recv().await.unwrap().unwrap();
+
+    println!("Trying to read");
+    assert_eq!(*rw_lock2.try_read().await.unwrap().unwrap(), "initial");
+
+    println!("Acquiring write lock");
+    let mut write = rw_lock1.write().await.unwrap();
+
+    println!("Trying to read and write while write lock is held");
+    assert!(rw_lock2.try_read().await.unwrap().is_none());
+    assert!(rw_lock2.try_write().await.unwrap().is_none());
+
+    println!("Committing");
+    *write = "written".to_string();
+    write.commit().await.unwrap();
+
+    println!("Trying to read and write after commit");
+    assert_eq!(*rw_lock2.try_read().await.unwrap().unwrap(), "written");
+    let mut write = rw_lock2.try_write().await.unwrap().unwrap();
+    *write = "final".to_string();
+    write.commit().await.unwrap();
+    assert_eq!(*rw_lock1.read().await.unwrap(), "final");
+}
+
+#[tokio::test]
+async fn try_write_while_read() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<RwLock<String>>().await;
+    let ((mut c_tx, _), (_, mut d_rx)) = loop_channel::<OwnedReadGuard<String>>().await;
+
+    println!("Creating owner");
+    let owner = Owner::new("initial".to_string());
+    a_tx.send(owner.rw_lock()).await.unwrap();
+    let rw_lock = b_rx.recv().await.unwrap().unwrap();
+
+    println!("Trying to write while holding read guard");
+    let read = rw_lock.read().await.unwrap();
+    assert!(rw_lock.try_write().await.unwrap().is_none());
+    assert!(read.is_invalidated());
+    drop(read);
+
+    println!("Trying to write while remote owned read guard is held");
+    c_tx.send(rw_lock.read_owned().await.unwrap()).await.unwrap();
+    let remote_guard = d_rx.recv().await.unwrap().unwrap();
+    assert!(rw_lock.try_write().await.unwrap().is_none());
+    assert_eq!(*rw_lock.read().await.unwrap(), "initial");
+    assert!(rw_lock.try_write().await.unwrap().is_none());
+    drop(remote_guard);
+
+    println!("Writing after read guards have been dropped");
+    let mut write = rw_lock.write().await.unwrap();
+    *write = "written".to_string();
+    write.commit().await.unwrap();
+
+    println!("Trying to write without read guards");
+    assert_eq!(*rw_lock.read().await.unwrap(), "written");
+    let mut write = rw_lock.try_write().await.unwrap().unwrap();
+    *write = "final".to_string();
+    write.commit().await.unwrap();
+    assert_eq!(*rw_lock.read().await.unwrap(), "final");
+}
+
+#[tokio::test]
+async fn forward_read() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<OwnedReadGuard<String>>().await;
+
+    println!("Creating owner");
+    let owner = Owner::new("initial".to_string());
+    let rw_lock = owner.rw_lock();
+
+    println!("Forwarding owned read guard");
+    let guard = rw_lock.read_owned().await.unwrap();
+    a_tx.send(guard).await.unwrap();
+    let remote_guard = b_rx.recv().await.unwrap().unwrap();
+    assert_eq!(*remote_guard, "initial");
+    assert!(!remote_guard.is_invalidated());
+
+    println!("Making write request");
+    let writer_lock = rw_lock.clone();
+    let writer = tokio::spawn(async move {
+        let mut write = writer_lock.write().await.unwrap();
+        *write = "written".to_string();
+        write.commit().await.unwrap();
+    });
+
+    println!("Waiting for invalidation");
+    remote_guard.invalidated().await;
+    assert!(remote_guard.is_invalidated());
+    sleep(Duration::from_millis(100)).await;
+    assert!(!writer.is_finished());
+
+    println!("Dropping remote guard");
+    drop(remote_guard);
+    writer.await.unwrap();
+    assert_eq!(*rw_lock.read().await.unwrap(), "written");
+}
+
+#[tokio::test]
+async fn forward_read_disconnect() {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx), drop_rx) = droppable_loop_channel::<OwnedReadGuard<String>>().await;
+
+    println!("Creating owner");
+    let owner = Owner::new("initial".to_string());
+    let rw_lock = owner.rw_lock();
+
+    println!("Forwarding owned read guard");
+    let guard = rw_lock.read().await.unwrap().into_owned();
+    a_tx.send(guard).await.unwrap();
+    let remote_guard = b_rx.recv().await.unwrap().unwrap();
+    assert_eq!(*remote_guard, "initial");
+
+    println!("Making write request");
+    let writer_lock = rw_lock.clone();
+    let writer = tokio::spawn(async move {
+        let mut write = writer_lock.write().await.unwrap();
+        *write = "written".to_string();
+        write.commit().await.unwrap();
+    });
+    sleep(Duration::from_millis(100)).await;
+    assert!(!writer.is_finished());
+
+    println!("Dropping connection");
+    drop(drop_rx);
+    writer.await.unwrap();
+    assert_eq!(*rw_lock.read().await.unwrap(), "written");
+    drop(remote_guard);
+}
+
+async fn wait_for_waiting(owner: &Owner<String>, n: usize) {
+    while owner.waiting() != n {
+        sleep(Duration::from_millis(10)).await;
+    }
+}
+
+async fn fairness(fairness: Fairness, write_first: bool, expected: Vec<Access>) {
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<RwLock<String>>().await;
+
+    println!("Creating owner with {fairness:?}");
+    let owner = Owner::with_fairness("initial".to_string(), fairness);
+    assert_eq!(owner.fairness(), fairness);
+    a_tx.send(owner.rw_lock()).await.unwrap();
+    let rw_lock = b_rx.recv().await.unwrap().unwrap();
+
+    println!("Acquiring write lock");
+    let write = rw_lock.write().await.unwrap();
+    assert_eq!(owner.waiting(), 0);
+
+    let reader_lock = rw_lock.clone();
+    let spawn_reader = move || {
+        tokio::spawn(async move {
+            assert_eq!(*reader_lock.read().await.unwrap(), "initial");
+        })
+    };
+    let writer_lock = rw_lock.clone();
+    let spawn_writer = move || {
+        tokio::spawn(async move {
+            let write = writer_lock.write().await.unwrap();
+            write.commit().await.unwrap();
+        })
+    };
+
+    println!("Making waiting requests");
+    let (first, second) = if write_first {
+        let writer = spawn_writer();
+        wait_for_waiting(&owner, 1).await;
+        let reader = spawn_reader();
+        (writer, reader)
+    } else {
+        let reader = spawn_reader();
+        wait_for_waiting(&owner, 1).await;
+        let writer = spawn_writer();
+        (reader, writer)
+    };
+    wait_for_waiting(&owner, 2).await;
+
+    let queue = owner.queue();
+    println!("Queue: {queue:?}");
+    assert_eq!(queue, expected);
+
+    println!("Releasing write lock");
+    drop(write);
+    first.await.unwrap();
+    second.await.unwrap();
+    assert_eq!(owner.waiting(), 0);
+}
+
+#[tokio::test]
+async fn fairness_writer_preferring() {
+    fairness(Fairness::WriterPreferring, false, vec![Access::Write, Access::Read]).await;
+}
+
+#[tokio::test]
+async fn fairness_reader_preferring() {
+    fairness(Fairness::ReaderPreferring, true, vec![Access::Read, Access::Write]).await;
+}
+
+#[tokio::test]
+async fn fairness_fifo() {
+    fairness(Fairness::Fifo, false, vec![Access::Read, Access::Write]).await;
+}
+
+#[tokio::test]
+async fn fairness_fifo_interleaved() {
+    crate::init();
+
+    println!("Creating owner with FIFO fairness");
+    let owner = Owner::with_fairness("initial".to_string(), Fairness::Fifo);
+    let rw_lock = owner.rw_lock();
+
+    println!("Acquiring write lock");
+    let write = rw_lock.write().await.unwrap();
+
+    println!("Making interleaved requests");
+    let mut tasks = Vec::new();
+    let lock = rw_lock.clone();
+    tasks.push(tokio::spawn(async move {
+        assert_eq!(*lock.read().await.unwrap(), "initial");
+    }));
+    let lock = rw_lock.clone();
+    tasks.push(tokio::spawn(async move {
+        lock.write().await.unwrap().commit().await.unwrap();
+    }));
+    let lock = rw_lock.clone();
+    tasks.push(tokio::spawn(async move {
+        drop(lock.upgradable_read().await.unwrap());
+    }));
+    let lock = rw_lock.clone();
+    tasks.push(tokio::spawn(async move {
+        lock.write().await.unwrap().commit().await.unwrap();
+    }));
+    wait_for_waiting(&owner, 4).await;
+
+    let queue = owner.queue();
+    println!("Queue: {queue:?}");
+    assert_eq!(queue, vec![Access::Read, Access::Write, Access::UpgradableRead, Access::Write]);
+
+    println!("Releasing write lock");
+    drop(write);
+    for task in tasks {
+        task.await.unwrap();
+    }
+    assert_eq!(owner.waiting(), 0);
+}
@@ -0,0 +1,99 @@
+use std::{sync::Arc, time::Duration};
+use tokio::{
+    sync::mpsc,
+    time::{sleep, timeout},
+};
+
+use crate::loop_channel;
+
+#[remoc::rtc::remote]
+pub trait Blocker {
+    async fn block(&self) -> Result<(), remoc::rtc::CallError>;
+}
+
+/// Notifies when dropped.
+struct DropGuard(mpsc::UnboundedSender<&'static str>);
+
+impl Drop for DropGuard {
+    fn drop(&mut self) {
+        let _ = self.0.send("dropped");
+    }
+}
+
+pub struct BlockerObj {
+    event_tx: mpsc::UnboundedSender<&'static str>,
+}
+
+#[remoc::rtc::async_trait]
+impl Blocker for BlockerObj {
+    async fn block(&self) -> Result<(), remoc::rtc::CallError> {
+        let _guard = DropGuard(self.event_tx.clone());
+        let _ = self.event_tx.send("started");
+        sleep(Duration::from_secs(3600)).await;
+        let _ = self.event_tx.send("completed");
+        Ok(())
+    }
+}
+
+async fn setup() -> (BlockerClient, mpsc::UnboundedReceiver<&'static str>) {
+    use remoc::rtc::ServerShared;
+
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<BlockerClient>().await;
+
+    let (event_tx, event_rx) = mpsc::unbounded_channel();
+    let (server, client) = BlockerServerShared::new(Arc::new(BlockerObj { event_tx }), 1);
+    tokio::spawn(server.serve(true));
+
+    a_tx.send(client).await.unwrap();
+    let client = b_rx.recv().await.unwrap().unwrap();
+
+    (client, event_rx)
+}
+
+#[tokio::test]
+async fn drop_call_future() {
+    crate::init();
+    let (client, mut event_rx) = setup().await;
+
+    println!("Calling and dropping call future");
+    let call = client.block();
+    let res = tokio::select! {
+        res = call => Some(res),
+        Some(event) = event_rx.recv() => {
+            assert_eq!(event, "started");
+            None
+        }
+    };
+    assert!(res.is_none());
+
+    println!("Waiting for cancellation on server");
+    let event = timeout(Duration::from_secs(1), event_rx.recv()).await.unwrap().unwrap();
+    assert_eq!(event, "dropped");
+
+    println!("Client is still usable");
+    let call = tokio::spawn(async move { client.block().await });
+    assert_eq!(event_rx.recv().await.unwrap(), "started");
+    call.abort();
+    let event = timeout(Duration::from_secs(1), event_rx.recv()).await.unwrap().unwrap();
+    assert_eq!(event, "dropped");
+}
+
+#[tokio::test]
+async fn drop_client() {
+    crate::init();
+    let (client, mut event_rx) = setup().await;
+
+    println!("Calling from task owning client");
+    let call = tokio::spawn(async move {
+        let _ = client.block().await;
+    });
+    assert_eq!(event_rx.recv().await.unwrap(), "started");
+
+    println!("Dropping client");
+    call.abort();
+
+    println!("Waiting for cancellation on server");
+    let event = timeout(Duration::from_secs(1), event_rx.recv()).await.unwrap().unwrap();
+    assert_eq!(event, "dropped");
+    assert!(event_rx.recv().await.is_none());
+}
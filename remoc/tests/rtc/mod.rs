@@ -1,8 +1,12 @@
+mod cancel;
 mod default;
 mod generics;
+mod pipeline;
 mod readonly;
 mod simple;
 mod simple_clone;
+mod subscribe;
+mod timeout;
 mod value;
 
 // Must result in compile error:
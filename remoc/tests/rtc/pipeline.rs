@@ -0,0 +1,57 @@
+use std::sync::{Arc, Mutex};
+
+use crate::loop_channel;
+
+#[remoc::rtc::remote]
+pub trait Square {
+    async fn square(&self, n: u32) -> Result<u32, remoc::rtc::CallError>;
+    async fn calls(&self) -> Result<Vec<u32>, remoc::rtc::CallError>;
+}
+
+pub struct SquareObj {
+    calls: Mutex<Vec<u32>>,
+}
+
+#[remoc::rtc::async_trait]
+impl Square for SquareObj {
+    async fn square(&self, n: u32) -> Result<u32, remoc::rtc::CallError> {
+        self.calls.lock().unwrap().push(n);
+        Ok(n * n)
+    }
+
+    async fn calls(&self) -> Result<Vec<u32>, remoc::rtc::CallError> {
+        Ok(self.calls.lock().unwrap().clone())
+    }
+}
+
+#[tokio::test]
+async fn pipeline() {
+    use remoc::rtc::ServerShared;
+
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<SquareClient>().await;
+
+    println!("Spawning server");
+    let obj = Arc::new(SquareObj { calls: Mutex::new(Vec::new()) });
+    let (server, client) = SquareServerShared::new(obj, 4);
+    tokio::spawn(server.serve(false));
+
+    println!("Sending client");
+    a_tx.send(client).await.unwrap();
+    let client = b_rx.recv().await.unwrap().unwrap();
+
+    println!("Pipelining calls");
+    let results = remoc::rtc::pipeline((0..20).map(|n| client.square(n))).await;
+    let results: Vec<_> = results.into_iter().map(|r| r.unwrap()).collect();
+    println!("Results: {results:?}");
+    assert_eq!(results, (0..20).map(|n| n * n).collect::<Vec<_>>());
+
+    println!("Pipelining different calls");
+    let (a, b, calls) = futures::join!(client.square(20), client.square(21), client.calls());
+    assert_eq!(a.unwrap(), 400);
+    assert_eq!(b.unwrap(), 441);
+
+    let calls = calls.unwrap();
+    println!("Server calls: {calls:?}");
+    assert_eq!(calls, (0..22).collect::<Vec<_>>());
+}
@@ -159,3 +159,48 @@ async fn simple_spawn() {
     println!("waiting for server to terminate");
     server_task.await.unwrap();
 }
+
+#[tokio::test]
+async fn loopback() {
+    use remoc::rtc::Server;
+
+    crate::init();
+
+    println!("Creating counter server and client over loopback connection");
+    let (mut client, server_task) = <CounterServer<CounterObj>>::loopback(CounterObj::new(), 1).await;
+    let server_task = tokio::spawn(server_task);
+
+    client.increase(20).await.unwrap();
+    client.increase(45).await.unwrap();
+    assert_eq!(client.value().await.unwrap(), 65);
+
+    println!("Dropping client");
+    drop(client);
+    let counter_obj = server_task.await.unwrap().unwrap();
+    println!("Counter obj value: {}", counter_obj.value);
+    assert_eq!(counter_obj.value, 65);
+}
+
+#[tokio::test]
+async fn loopback_shared_mut() {
+    use remoc::rtc::ServerSharedMut;
+
+    crate::init();
+
+    println!("Creating counter server and client over loopback connection");
+    let counter_obj = Arc::new(RwLock::new(CounterObj::new()));
+    let (mut client, server_task) =
+        <CounterServerSharedMut<CounterObj>>::loopback(counter_obj.clone(), 16, true).await;
+    let server_task = tokio::spawn(server_task);
+
+    let mut watch_rx = client.watch().await.unwrap();
+    client.increase(20).await.unwrap();
+    assert_eq!(client.value().await.unwrap(), 20);
+    watch_rx.changed().await.unwrap();
+    assert_eq!(*watch_rx.borrow_and_update().unwrap(), 20);
+
+    println!("Dropping client");
+    drop(client);
+    server_task.await.unwrap();
+    assert_eq!(counter_obj.read().await.value, 20);
+}
@@ -0,0 +1,84 @@
+use remoc::rch::mpsc;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+
+use crate::loop_channel;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Event {
+    Started,
+    Progress(u32),
+}
+
+#[remoc::rtc::remote]
+pub trait EventSource {
+    async fn subscribe(&self) -> Result<mpsc::Receiver<Event>, remoc::rtc::CallError>;
+    async fn publish(&self, event: Event) -> Result<usize, remoc::rtc::CallError>;
+}
+
+#[derive(Default)]
+pub struct EventSourceObj {
+    subscribers: Mutex<Vec<mpsc::Sender<Event>>>,
+}
+
+#[remoc::rtc::async_trait]
+impl EventSource for EventSourceObj {
+    async fn subscribe(&self) -> Result<mpsc::Receiver<Event>, remoc::rtc::CallError> {
+        let (tx, rx) = mpsc::channel(16);
+        self.subscribers.lock().unwrap().push(tx);
+        Ok(rx)
+    }
+
+    async fn publish(&self, event: Event) -> Result<usize, remoc::rtc::CallError> {
+        let subscribers: Vec<_> = {
+            let mut subscribers = self.subscribers.lock().unwrap();
+            subscribers.retain(|tx| !tx.is_closed());
+            subscribers.clone()
+        };
+        for tx in &subscribers {
+            let _ = tx.send(event.clone()).await;
+        }
+        Ok(subscribers.len())
+    }
+}
+
+#[tokio::test]
+async fn subscribe() {
+    use remoc::rtc::ServerShared;
+
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<EventSourceClient>().await;
+
+    println!("Creating server");
+    let (server, client) = EventSourceServerShared::new(Arc::new(EventSourceObj::default()), 1);
+    tokio::spawn(server.serve(true));
+
+    println!("Sending client");
+    a_tx.send(client).await.unwrap();
+    let client = b_rx.recv().await.unwrap().unwrap();
+
+    println!("Subscribing");
+    let mut first = client.subscribe().await.unwrap();
+    let mut second = client.subscribe().await.unwrap();
+
+    let events = vec![Event::Started, Event::Progress(1), Event::Progress(2)];
+    for event in &events {
+        println!("Publishing {event:?}");
+        assert_eq!(client.publish(event.clone()).await.unwrap(), 2);
+    }
+
+    for event in &events {
+        assert_eq!(first.recv().await.unwrap().as_ref(), Some(event));
+        assert_eq!(second.recv().await.unwrap().as_ref(), Some(event));
+    }
+
+    println!("Dropping first subscription");
+    drop(first);
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    assert_eq!(client.publish(Event::Progress(3)).await.unwrap(), 1);
+    assert_eq!(second.recv().await.unwrap(), Some(Event::Progress(3)));
+
+    println!("Dropping client");
+    drop(client);
+    assert_eq!(second.recv().await.unwrap(), None);
+}
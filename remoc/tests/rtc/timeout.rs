@@ -0,0 +1,53 @@
+use std::{sync::Arc, time::Duration};
+
+use crate::loop_channel;
+
+#[remoc::rtc::remote]
+pub trait Sleeper {
+    async fn sleep(&self, ms: u64) -> Result<(), remoc::rtc::CallError>;
+}
+
+pub struct SleeperObj;
+
+#[remoc::rtc::async_trait]
+impl Sleeper for SleeperObj {
+    async fn sleep(&self, ms: u64) -> Result<(), remoc::rtc::CallError> {
+        tokio::time::sleep(Duration::from_millis(ms)).await;
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn timeout() {
+    use remoc::rtc::{CallError, Client, ServerShared};
+
+    crate::init();
+    let ((mut a_tx, _), (_, mut b_rx)) = loop_channel::<SleeperClient>().await;
+
+    println!("Spawning sleeper server");
+    let (server, mut client) = SleeperServerShared::new(Arc::new(SleeperObj), 1);
+    assert_eq!(client.timeout(), None);
+    client.set_timeout(Some(Duration::from_millis(200)));
+    tokio::spawn(server.serve(true));
+
+    println!("Sending sleeper client");
+    a_tx.send(client).await.unwrap();
+
+    println!("Receiving sleeper client");
+    let mut client = b_rx.recv().await.unwrap().unwrap();
+    assert_eq!(client.timeout(), Some(Duration::from_millis(200)));
+
+    println!("Short call");
+    client.sleep(10).await.unwrap();
+
+    println!("Long call");
+    assert!(matches!(client.sleep(1000).await, Err(CallError::Timeout)));
+
+    println!("Long call with timeout overridden");
+    client.with_timeout(Duration::from_secs(10)).sleep(400).await.unwrap();
+    assert_eq!(client.timeout(), Some(Duration::from_millis(200)));
+
+    println!("Short call with timeout overridden");
+    assert!(matches!(client.with_timeout(Duration::from_millis(10)).sleep(400).await, Err(CallError::Timeout)));
+    assert_eq!(client.timeout(), Some(Duration::from_millis(200)));
+}
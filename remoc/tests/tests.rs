@@ -18,6 +18,9 @@ mod codec;
 #[cfg(feature = "rch")]
 mod rch;
 
+#[cfg(feature = "rch")]
+mod reconnect;
+
 #[cfg(feature = "rfn")]
 mod rfn;
 
@@ -264,8 +264,12 @@ impl TraitMethod {
                 reply_tx.set_max_item_size(self.max_reply_size);
                 let req_value = #req_enum :: #req_case { __reply_tx: reply_tx, #entries };
                 let req = ::remoc::rtc::Req::#req_type(req_value);
-                self.req_tx.send(req).await.map_err(::remoc::rtc::CallError::from)?;
-                let reply = reply_rx.await.map_err(::remoc::rtc::CallError::from)?;
+                let req_tx = &self.req_tx;
+                let call = async move {
+                    req_tx.send(req).await.map_err(::remoc::rtc::CallError::from)?;
+                    reply_rx.await.map_err(::remoc::rtc::CallError::from)
+                };
+                let reply = ::remoc::rtc::call_with_timeout(self.timeout, call).await?;
                 reply
             }
         }
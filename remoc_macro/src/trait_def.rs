@@ -699,6 +699,8 @@ impl TraitDef {
                 >,
                 #[serde(default = "::remoc::rtc::missing_max_reply_size", with = "::remoc::rtc::serde_max_reply_size")]
                 max_reply_size: usize,
+                #[serde(default)]
+                timeout: ::std::option::Option<::std::time::Duration>,
                 #[serde(skip)]
                 #[serde(default = "::remoc::rtc::empty_client_drop_tx")]
                 drop_tx: ::remoc::rtc::local_broadcast::Sender<()>,
@@ -713,6 +715,7 @@ impl TraitDef {
                     Self {
                         req_tx,
                         max_reply_size: ::remoc::rch::DEFAULT_MAX_ITEM_SIZE,
+                        timeout: ::std::option::Option::None,
                         drop_tx: ::remoc::rtc::empty_client_drop_tx(),
                     }
                 }
@@ -720,7 +723,7 @@ impl TraitDef {
 
             impl #impl_generics_impl ::remoc::rtc::Client for #client_ident #impl_generics_ty #impl_generics_where {
                 fn capacity(&self) -> usize {
-                    self.req_tx.capacity()
+                    self.req_tx.local_capacity()
                 }
 
                 fn closed(&self) -> ::remoc::rtc::Closed {
@@ -753,6 +756,14 @@ impl TraitDef {
                 fn set_max_reply_size(&mut self, max_reply_size: usize) {
                     self.max_reply_size = max_reply_size
                 }
+
+                fn timeout(&self) -> ::std::option::Option<::std::time::Duration> {
+                    self.timeout
+                }
+
+                fn set_timeout(&mut self, timeout: ::std::option::Option<::std::time::Duration>) {
+                    self.timeout = timeout
+                }
             }
 
             #[::remoc::rtc::async_trait]